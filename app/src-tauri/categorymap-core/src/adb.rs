@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use crate::settings::AppSettings;
+
+/// 通过 adb 连接的设备
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AdbDevice {
+    pub serial: String,
+    /// 设备状态：device-已连接, offline-离线, unauthorized-未授权
+    pub state: String,
+    pub model: Option<String>,
+    pub product: Option<String>,
+}
+
+/// adb 命令封装
+pub struct Adb {
+    program: String,
+}
+
+impl Adb {
+    /// 使用设置中的 adb 路径，未设置时从 PATH 中查找 adb
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let program = match settings.adb_path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => path.to_string(),
+            _ => "adb".to_string(),
+        };
+        Self { program }
+    }
+
+    /// 执行 adb 命令，失败时返回 stderr 中的错误信息
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let mut command = Command::new(&self.program);
+        command.args(args);
+        // Windows 下不弹出控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("无法执行 adb（{}）: {}", self.program, e))?;
+        check_output(output)
+    }
+
+    /// 列出已连接的设备（adb devices -l）
+    pub fn list_devices(&self) -> Result<Vec<AdbDevice>, String> {
+        let stdout = self.run(&["devices", "-l"])?;
+        Ok(parse_devices(&stdout))
+    }
+
+    /// 列出设备上指定目录中的 theme 文件名
+    pub fn list_theme_files(&self, serial: &str, remote_dir: &str) -> Result<Vec<String>, String> {
+        let stdout = self.run(&["-s", serial, "shell", "ls", "-1", &shell_quote(remote_dir)])?;
+        if stdout.contains("No such file or directory") {
+            return Err(format!("设备上不存在目录: {}", remote_dir));
+        }
+        let mut names: Vec<String> = stdout
+            .lines()
+            .map(|line| line.trim().rsplit('/').next().unwrap_or_default().to_string())
+            .filter(|name| crate::is_theme_file_name(name))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// 从设备下载单个文件
+    pub fn pull(&self, serial: &str, remote_path: &str, local_path: &Path) -> Result<(), String> {
+        let local = local_path.to_string_lossy();
+        self.run(&["-s", serial, "pull", remote_path, &local])
+            .map(|_| ())
+            .map_err(|e| format!("下载 {} 失败: {}", remote_path, e))
+    }
+
+    /// 上传单个文件到设备
+    pub fn push(&self, serial: &str, local_path: &Path, remote_path: &str) -> Result<(), String> {
+        let local = local_path.to_string_lossy();
+        self.run(&["-s", serial, "push", &local, remote_path])
+            .map(|_| ())
+            .map_err(|e| format!("上传 {} 失败: {}", local, e))
+    }
+
+    /// 强制停止桌面应用后重新回到桌面，使新的 theme 文件生效
+    pub fn restart_launcher(&self, serial: &str, package: &str) -> Result<(), String> {
+        self.run(&["-s", serial, "shell", "am", "force-stop", package])?;
+        self.run(&[
+            "-s",
+            serial,
+            "shell",
+            "am",
+            "start",
+            "-a",
+            "android.intent.action.MAIN",
+            "-c",
+            "android.intent.category.HOME",
+        ])
+        .map(|_| ())
+        .map_err(|e| format!("重启桌面应用 {} 失败: {}", package, e))
+    }
+}
+
+/// 设备上的文件路径（设备端总是使用 / 分隔）
+pub fn remote_file_path(remote_dir: &str, name: &str) -> String {
+    format!("{}/{}", remote_dir.trim_end_matches('/'), name)
+}
+
+fn check_output(output: Output) -> Result<String, String> {
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() };
+    Err(format!("adb 执行失败: {}", message))
+}
+
+/// 设备端 shell 的参数加单引号，避免路径中的空格被拆开
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// 解析 `adb devices -l` 的输出
+///
+/// 例：`0123456789ABCDEF       device product:p201 model:IP906H transport_id:1`
+fn parse_devices(stdout: &str) -> Vec<AdbDevice> {
+    stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("List of devices"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let serial = parts.next()?.to_string();
+            let state = parts.next()?.to_string();
+            let mut device = AdbDevice {
+                serial,
+                state,
+                model: None,
+                product: None,
+            };
+            for part in parts {
+                if let Some(model) = part.strip_prefix("model:") {
+                    device.model = Some(model.to_string());
+                } else if let Some(product) = part.strip_prefix("product:") {
+                    device.product = Some(product.to_string());
+                }
+            }
+            Some(device)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_device_list() {
+        let stdout = "* daemon started successfully\nList of devices attached\n\
+            0123456789ABCDEF       device product:p201 model:IP906H transport_id:1\n\
+            192.168.1.20:5555      unauthorized transport_id:2\n\n";
+        let devices = parse_devices(stdout);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].serial, "0123456789ABCDEF");
+        assert_eq!(devices[0].model.as_deref(), Some("IP906H"));
+        assert_eq!(devices[0].product.as_deref(), Some("p201"));
+        assert_eq!((devices[1].state.as_str(), devices[1].model.as_deref()), ("unauthorized", None));
+    }
+
+    #[test]
+    fn builds_paths_and_reports_a_missing_adb() {
+        assert_eq!(remote_file_path("/data/theme/", "theme1.json"), "/data/theme/theme1.json");
+        assert_eq!(shell_quote("/sdcard/my theme's"), r"'/sdcard/my theme'\''s'");
+        assert_eq!(Adb::from_settings(&AppSettings::default()).program, "adb");
+
+        let settings = AppSettings {
+            adb_path: Some("/nonexistent/catmap-adb".to_string()),
+            ..AppSettings::default()
+        };
+        let error = Adb::from_settings(&settings).list_devices().unwrap_err();
+        assert!(error.starts_with("无法执行 adb（/nonexistent/catmap-adb）"));
+    }
+}
@@ -0,0 +1,158 @@
+//! zip / apk 部署包中的 theme 文件：直接读取扫描，或解出到工作目录修改后再打包回去
+//!
+//! 重新打包会使 apk 的签名失效，回写后需要重新签名才能安装。
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::is_theme_file_name;
+
+/// 包内文件路径与包路径之间的分隔符，如 `bundle.zip!/assets/theme.json`
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// 解出到工作目录的一个 theme 文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEntry {
+    /// 包内路径
+    pub entry_name: String,
+    /// 工作目录中的文件路径
+    pub file_path: String,
+}
+
+/// 包内文件名（不含目录），目录条目和不安全的路径返回 None
+fn entry_file_name(entry: &zip::read::ZipFile) -> Option<String> {
+    if entry.is_dir() {
+        return None;
+    }
+    entry
+        .enclosed_name()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+fn open(archive_path: &Path) -> Result<zip::ZipArchive<fs::File>, String> {
+    let file = fs::File::open(archive_path).map_err(|e| format!("无法打开压缩包: {}", e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("无法读取压缩包: {}", e))
+}
+
+/// 按包内顺序读取所有 theme 文件（包内路径, 内容），任意目录层级都会读取
+pub fn read_theme_entries(archive_path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive = open(archive_path)?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if !entry_file_name(&entry).is_some_and(|name| is_theme_file_name(&name)) {
+            continue;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+        entries.push((entry.name().to_string(), content));
+    }
+    Ok(entries)
+}
+
+/// 把包内的 theme 文件按文件名平铺解出到工作目录（先清空工作目录）
+///
+/// 工作目录只有一层，包内不同目录下有同名 theme 文件时无法区分，直接报错。
+pub fn extract_to_workspace(archive_path: &Path, workspace_dir: &Path) -> Result<Vec<WorkspaceEntry>, String> {
+    let entries = read_theme_entries(archive_path)?;
+    if entries.is_empty() {
+        return Err("压缩包中没有 theme 文件".into());
+    }
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for (entry_name, _) in &entries {
+        let name = file_name(entry_name);
+        if let Some(other) = seen.insert(name.to_string(), entry_name) {
+            return Err(format!("压缩包中有同名的 theme 文件，无法解出: {} 与 {}", other, entry_name));
+        }
+    }
+
+    if workspace_dir.exists() {
+        fs::remove_dir_all(workspace_dir).map_err(|e| format!("清空工作目录失败: {}", e))?;
+    }
+    fs::create_dir_all(workspace_dir).map_err(|e| e.to_string())?;
+    entries
+        .into_iter()
+        .map(|(entry_name, content)| {
+            let path = workspace_dir.join(file_name(&entry_name));
+            fs::write(&path, content).map_err(|e| e.to_string())?;
+            Ok(WorkspaceEntry {
+                entry_name,
+                file_path: path.to_string_lossy().into_owned(),
+            })
+        })
+        .collect()
+}
+
+/// 包内 theme 条目在工作目录中对应的文件，不存在时返回 None
+fn workspace_file(entry: &zip::read::ZipFile, workspace_dir: &Path) -> Option<PathBuf> {
+    entry_file_name(entry)
+        .filter(|name| is_theme_file_name(name))
+        .map(|name| workspace_dir.join(name))
+        .filter(|path| path.is_file())
+}
+
+/// 工作目录中内容与包内不同的 theme 文件（包内路径）
+pub fn changed_entries(archive_path: &Path, workspace_dir: &Path) -> Result<Vec<String>, String> {
+    let mut archive = open(archive_path)?;
+    let mut changed = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        let Some(path) = workspace_file(&entry, workspace_dir) else {
+            continue;
+        };
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+        if fs::read(&path).map_err(|e| e.to_string())? != content {
+            changed.push(entry.name().to_string());
+        }
+    }
+    Ok(changed)
+}
+
+/// 用工作目录中的 theme 文件替换包内同名条目，其他条目和压缩方式原样保留
+///
+/// 新包先在内存中生成，再原子替换原文件。
+pub fn repack_from_workspace(archive_path: &Path, workspace_dir: &Path) -> Result<(), String> {
+    let mut archive = open(archive_path)?;
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        let options = zip::write::FileOptions::default().compression_method(entry.compression());
+        if entry.is_dir() {
+            writer.add_directory(entry.name(), options).map_err(|e| e.to_string())?;
+            continue;
+        }
+        let content = match workspace_file(&entry, workspace_dir) {
+            Some(path) => fs::read(&path).map_err(|e| e.to_string())?,
+            None => {
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+                content
+            }
+        };
+        writer.start_file(entry.name(), options).map_err(|e| e.to_string())?;
+        writer.write_all(&content).map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.finish().map_err(|e| e.to_string())?.into_inner();
+    crate::file_io::write_atomic(archive_path, bytes).map_err(|e| format!("写入压缩包失败: {}", e))
+}
+
+/// 压缩包对应的默认工作目录（系统临时目录下，按包路径区分）
+pub fn default_workspace(archive_path: &Path) -> PathBuf {
+    let canonical = fs::canonicalize(archive_path).unwrap_or_else(|_| archive_path.to_path_buf());
+    let hash = crate::file_io::content_hash(canonical.to_string_lossy().as_bytes());
+    std::env::temp_dir().join(format!("categorymap-archive-{}", &hash[..12]))
+}
+
+fn file_name(entry_name: &str) -> &str {
+    entry_name.rsplit('/').next().unwrap_or(entry_name)
+}
@@ -0,0 +1,82 @@
+//! 操作完成后的后台任务（git 自动提交、webhook 推送）
+//!
+//! 这些任务要执行外部命令或网络请求，可能耗时数秒，不能阻塞调用操作的线程（界面命令大多直接在主线程执行）。
+//! 任务按提交顺序在同一个后台线程中依次执行，多次操作的 git 提交不会相互争用索引锁。
+//! 命令行工具退出前调用 [`wait_idle`]，等待任务执行完。
+
+use std::sync::{
+    mpsc::{self, Sender},
+    Condvar, Mutex, MutexGuard,
+};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+static QUEUE: Mutex<Option<Sender<Task>>> = Mutex::new(None);
+/// 已提交但尚未执行完的任务数
+static PENDING: Mutex<usize> = Mutex::new(0);
+static IDLE: Condvar = Condvar::new();
+
+fn pending() -> MutexGuard<'static, usize> {
+    PENDING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 把任务放入后台队列，第一次调用时启动后台线程
+pub fn spawn(task: impl FnOnce() + Send + 'static) {
+    *pending() += 1;
+    let mut queue = QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let sender = queue.get_or_insert_with(|| {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        std::thread::spawn(move || {
+            for task in receiver {
+                // 单个任务 panic 不影响后续任务
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(task));
+                task_done();
+            }
+        });
+        sender
+    });
+    if let Err(mpsc::SendError(task)) = sender.send(Box::new(task)) {
+        // 后台线程已退出（不应发生），直接在当前线程执行
+        *queue = None;
+        drop(queue);
+        task();
+        task_done();
+    }
+}
+
+fn task_done() {
+    let mut pending = pending();
+    *pending -= 1;
+    if *pending == 0 {
+        IDLE.notify_all();
+    }
+}
+
+/// 等待已提交的后台任务全部执行完
+pub fn wait_idle() {
+    let mut pending = pending();
+    while *pending > 0 {
+        pending = IDLE.wait(pending).unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn runs_tasks_in_order_off_the_calling_thread() {
+        let caller = std::thread::current().id();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        for index in 0..3 {
+            let seen = Arc::clone(&seen);
+            spawn(move || {
+                assert_ne!(std::thread::current().id(), caller);
+                seen.lock().unwrap().push(index);
+            });
+        }
+        wait_idle();
+        assert_eq!(*seen.lock().unwrap(), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,689 @@
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    file_io::{long_path, retry_io},
+    settings::{AppSettings, BackupFormat, BackupRetention},
+};
+
+/// 备份目录名（也是时间戳）的格式，精确到毫秒
+const BACKUP_NAME_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+/// 旧版本备份名称的时间戳格式，精确到秒
+const LEGACY_BACKUP_NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// 压缩备份的文件名前缀和后缀：backup_<时间戳>.zip
+const ZIP_PREFIX: &str = "backup_";
+const ZIP_SUFFIX: &str = ".zip";
+/// 备份清单的文件名，目录格式放在备份目录中，压缩格式放在压缩包中
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+/// 估算压缩备份大小时每个文件额外预留的字节数（文件头、目录项，以及不可压缩内容经 deflate 后的膨胀）
+const ZIP_ENTRY_OVERHEAD: u64 = 1024;
+
+/// 备份的来源，记录在备份名称的时间戳之后（如 `20240101-120000-123-scheduled`）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    /// 修改文件前自动创建的备份（名称中没有后缀，与旧版本的备份一致）
+    #[default]
+    Operation,
+    /// 用户手动创建的备份
+    Manual,
+    /// 定时自动创建的备份
+    Scheduled,
+}
+
+impl BackupKind {
+    fn suffix(self) -> &'static str {
+        match self {
+            BackupKind::Operation => "",
+            BackupKind::Manual => "-manual",
+            BackupKind::Scheduled => "-scheduled",
+        }
+    }
+
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        [BackupKind::Operation, BackupKind::Manual, BackupKind::Scheduled]
+            .into_iter()
+            .find(|kind| kind.suffix() == suffix)
+    }
+}
+
+/// 单个备份的信息
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    /// 备份名称（时间戳，定时和手动备份带来源后缀）
+    pub name: String,
+    pub path: String,
+    /// 创建时间（YYYY-MM-DD HH:MM:SS）
+    pub created_at: String,
+    pub format: BackupFormat,
+    pub kind: BackupKind,
+}
+
+/// 备份清单：创建备份时记录每个文件的大小和 SHA-256，恢复前据此发现被截断或损坏的备份
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// 创建时间（YYYY-MM-DD HH:MM:SS）
+    pub created_at: String,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl ManifestEntry {
+    fn of(name: &str, content: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            size: content.len() as u64,
+            sha256: crate::file_io::content_hash(content),
+        }
+    }
+}
+
+/// 备份完整性校验结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVerification {
+    pub backup_path: String,
+    /// 旧版本创建的备份没有清单，无法校验
+    pub has_manifest: bool,
+    /// 校验过的文件数
+    pub checked: usize,
+    pub problems: Vec<ManifestProblem>,
+}
+
+impl BackupVerification {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// 与清单不符的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestProblem {
+    pub name: String,
+    pub reason: String,
+}
+
+/// 按保留策略计算出的清理计划
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunePlan {
+    pub kept: Vec<BackupInfo>,
+    pub to_delete: Vec<BackupInfo>,
+}
+
+/// 源目录对应的备份和日志存放目录
+///
+/// 未设置备份根目录时就是源目录本身；设置后为 `<备份根目录>/<目录名>-<路径哈希>`，
+/// 不同源目录的备份互不干扰，也不会随源目录一起被部署。
+pub fn storage_dir(source_dir: &Path, settings: &AppSettings) -> PathBuf {
+    let root = match settings.backup_root.as_deref().map(str::trim) {
+        Some(root) if !root.is_empty() => PathBuf::from(root),
+        _ => return source_dir.to_path_buf(),
+    };
+    let canonical = fs::canonicalize(source_dir).unwrap_or_else(|_| source_dir.to_path_buf());
+    let hash = crate::file_io::content_hash(canonical.to_string_lossy().as_bytes());
+    let name = canonical
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "root".to_string());
+    root.join(format!("{}-{}", name, &hash[..8]))
+}
+
+/// 按设置的格式备份源目录中的文件，完成后按保留策略清理旧备份
+///
+/// 备份存放在 [`storage_dir`] 下的 `backups` 目录：目录格式为 `backups/<时间戳>/`，
+/// 压缩格式为 `backups/backup_<时间戳>.zip`。
+pub fn create_backup<P: AsRef<Path>>(
+    source_dir: &Path,
+    files: &[P],
+    settings: &AppSettings,
+) -> Result<PathBuf, String> {
+    create_backup_of_kind(source_dir, files, settings, BackupKind::Operation)
+}
+
+/// 同 [`create_backup`]，备份名称中记录备份来源
+pub fn create_backup_of_kind<P: AsRef<Path>>(
+    source_dir: &Path,
+    files: &[P],
+    settings: &AppSettings,
+    kind: BackupKind,
+) -> Result<PathBuf, String> {
+    let base_dir = storage_dir(source_dir, settings);
+    let root = base_dir.join("backups");
+    retry_io(&root, || fs::create_dir_all(long_path(&root))).map_err(|err| err.to_string())?;
+    let backup_name = unused_backup_name(&root, kind);
+
+    ensure_space(&root, files, settings.backup_format)?;
+
+    let backup_path = match settings.backup_format {
+        BackupFormat::Folder => {
+            let backup_dir = root.join(&backup_name);
+            // 不用 create_dir_all：目录已存在时报错，不与其他备份混在一起
+            retry_io(&backup_dir, || fs::create_dir(long_path(&backup_dir))).map_err(|err| err.to_string())?;
+            if let Err(err) = copy_files(&backup_dir, files) {
+                // 不留下不完整的备份
+                let _ = fs::remove_dir_all(long_path(&backup_dir));
+                return Err(err);
+            }
+            backup_dir
+        }
+        BackupFormat::Zip => {
+            let zip_path = root.join(format!("{}{}{}", ZIP_PREFIX, backup_name, ZIP_SUFFIX));
+            if let Err(err) = write_zip(&zip_path, files) {
+                let _ = fs::remove_file(long_path(&zip_path));
+                return Err(format!("创建压缩备份失败: {}", err));
+            }
+            zip_path
+        }
+    };
+
+    // 清理失败不影响本次备份，只打印错误
+    if let Err(e) = prune_backups(&base_dir, &settings.backup_retention) {
+        eprintln!("清理旧备份失败: {}", e);
+    }
+    Ok(backup_path)
+}
+
+/// 以当前时间命名的备份名称；同名的目录或压缩备份已存在（同一毫秒内的多次备份）时顺延一毫秒
+fn unused_backup_name(root: &Path, kind: BackupKind) -> String {
+    let mut time = Local::now();
+    loop {
+        let name = format!("{}{}", time.format(BACKUP_NAME_FORMAT), kind.suffix());
+        let zip_name = format!("{}{}{}", ZIP_PREFIX, name, ZIP_SUFFIX);
+        if !root.join(&name).exists() && !root.join(zip_name).exists() {
+            return name;
+        }
+        time += chrono::Duration::milliseconds(1);
+    }
+}
+
+/// 解析备份名称中的时间戳和来源，兼容旧版本精确到秒的名称
+fn parse_backup_name(name: &str) -> Option<(NaiveDateTime, BackupKind)> {
+    // 时间戳为 19 个字符（旧版本为 15 个字符），其后是备份来源的后缀
+    [(19, BACKUP_NAME_FORMAT), (15, LEGACY_BACKUP_NAME_FORMAT)]
+        .into_iter()
+        .find_map(|(len, format)| {
+            let time = NaiveDateTime::parse_from_str(name.get(..len)?, format).ok()?;
+            Some((time, BackupKind::from_suffix(name.get(len..)?)?))
+        })
+}
+
+/// 备份所需的空间（字节），压缩备份按不压缩估算，另为每个文件预留 [`ZIP_ENTRY_OVERHEAD`]
+fn required_space(sizes: &[u64], format: BackupFormat) -> u64 {
+    let total: u64 = sizes.iter().sum();
+    match format {
+        BackupFormat::Folder => total,
+        BackupFormat::Zip => total + ZIP_ENTRY_OVERHEAD * (sizes.len() as u64 + 1),
+    }
+}
+
+/// 备份所需空间和备份目录所在磁盘的剩余空间
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceEstimate {
+    pub backup_root: String,
+    /// 备份需要的空间（字节）
+    pub required: u64,
+    /// 剩余空间（字节），无法获取（如部分网络共享）时为 None
+    pub available: Option<u64>,
+}
+
+impl SpaceEstimate {
+    /// 剩余空间足够，无法获取剩余空间时视为足够
+    pub fn is_sufficient(&self) -> bool {
+        self.available.is_none_or(|available| available >= self.required)
+    }
+}
+
+/// 估算备份源目录中这些文件所需的空间，不创建备份目录
+pub fn estimate_space<P: AsRef<Path>>(
+    source_dir: &Path,
+    files: &[P],
+    settings: &AppSettings,
+) -> Result<SpaceEstimate, String> {
+    let root = storage_dir(source_dir, settings).join("backups");
+    measure_space(&root, files, settings.backup_format)
+}
+
+fn measure_space<P: AsRef<Path>>(root: &Path, files: &[P], format: BackupFormat) -> Result<SpaceEstimate, String> {
+    let sizes = files
+        .iter()
+        .map(|file| {
+            let file = file.as_ref();
+            retry_io(file, || fs::metadata(long_path(file)))
+                .map(|meta| meta.len())
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<u64>, String>>()?;
+    let available = match fs2::available_space(long_path(&existing_ancestor(root))) {
+        Ok(available) => Some(available),
+        Err(e) => {
+            eprintln!("获取 {} 的剩余空间失败: {}", root.display(), e);
+            None
+        }
+    };
+    Ok(SpaceEstimate {
+        backup_root: root.to_string_lossy().into_owned(),
+        required: required_space(&sizes, format),
+        available,
+    })
+}
+
+/// 路径本身或最近的已存在上级目录（目录首次使用前还没有创建）
+pub(crate) fn existing_ancestor(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|dir| !dir.as_os_str().is_empty() && long_path(dir).exists())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+/// 备份目录所在磁盘的剩余空间不足时提前失败，避免复制到一半才因磁盘已满中止
+///
+/// 无法获取剩余空间时不阻止备份。
+fn ensure_space<P: AsRef<Path>>(root: &Path, files: &[P], format: BackupFormat) -> Result<(), String> {
+    let estimate = measure_space(root, files, format)?;
+    match estimate.available {
+        Some(available) if !estimate.is_sufficient() => Err(format!(
+            "备份目录 {} 所在磁盘空间不足：备份需要 {} 字节，剩余 {} 字节",
+            root.display(),
+            estimate.required,
+            available
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn copy_files<P: AsRef<Path>>(backup_dir: &Path, files: &[P]) -> Result<(), String> {
+    let mut entries = Vec::new();
+    for file in files {
+        let file = file.as_ref();
+        if let Some(name) = file.file_name() {
+            let content = retry_io(file, || fs::read(long_path(file))).map_err(|err| err.to_string())?;
+            let target = backup_dir.join(name);
+            retry_io(&target, || fs::write(long_path(&target), &content)).map_err(|err| err.to_string())?;
+            entries.push(ManifestEntry::of(&name.to_string_lossy(), &content));
+        }
+    }
+    let target = backup_dir.join(MANIFEST_FILE_NAME);
+    let manifest = manifest_json(entries)?;
+    retry_io(&target, || fs::write(long_path(&target), &manifest)).map_err(|err| err.to_string())
+}
+
+fn manifest_json(files: Vec<ManifestEntry>) -> Result<String, String> {
+    let manifest = BackupManifest {
+        created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        files,
+    };
+    serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())
+}
+
+fn write_zip<P: AsRef<Path>>(zip_path: &Path, files: &[P]) -> Result<(), String> {
+    // 不覆盖已有的压缩备份
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(long_path(zip_path))
+        .map_err(|e| e.to_string())?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut entries = Vec::new();
+    for file in files {
+        let file = file.as_ref();
+        if let Some(name) = file.file_name() {
+            let content = retry_io(file, || fs::read(long_path(file))).map_err(|e| e.to_string())?;
+            writer
+                .start_file(name.to_string_lossy(), options)
+                .map_err(|e| e.to_string())?;
+            writer.write_all(&content).map_err(|e| e.to_string())?;
+            entries.push(ManifestEntry::of(&name.to_string_lossy(), &content));
+        }
+    }
+    writer
+        .start_file(MANIFEST_FILE_NAME, options)
+        .map_err(|e| e.to_string())?;
+    writer
+        .write_all(manifest_json(entries)?.as_bytes())
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 读取备份中的所有文件（文件名, 内容），自动识别目录和压缩包两种格式；不含备份清单
+pub fn read_backup(backup_path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut files = read_backup_entries(backup_path)?;
+    files.retain(|(name, _)| name != MANIFEST_FILE_NAME);
+    Ok(files)
+}
+
+/// 按清单校验备份：缺少的文件、大小或 SHA-256 不符的文件都列为问题
+pub fn verify_backup(backup_path: &Path) -> Result<BackupVerification, String> {
+    let mut files = read_backup_entries(backup_path)?;
+    let mut verification = BackupVerification {
+        backup_path: backup_path.to_string_lossy().into_owned(),
+        has_manifest: false,
+        checked: 0,
+        problems: Vec::new(),
+    };
+    let Some(index) = files.iter().position(|(name, _)| name == MANIFEST_FILE_NAME) else {
+        return Ok(verification);
+    };
+    let (_, raw) = files.swap_remove(index);
+    let manifest: BackupManifest =
+        serde_json::from_slice(&raw).map_err(|e| format!("备份清单格式错误 {}: {}", backup_path.display(), e))?;
+    verification.has_manifest = true;
+    for entry in &manifest.files {
+        verification.checked += 1;
+        let reason = match files.iter().find(|(name, _)| *name == entry.name) {
+            None => "备份中缺少该文件".to_string(),
+            Some((_, content)) if content.len() as u64 != entry.size => {
+                format!("大小不符：应为 {} 字节，实际 {} 字节", entry.size, content.len())
+            }
+            Some((_, content)) if crate::file_io::content_hash(content) != entry.sha256 => "SHA-256 校验不符".to_string(),
+            Some(_) => continue,
+        };
+        verification.problems.push(ManifestProblem {
+            name: entry.name.clone(),
+            reason,
+        });
+    }
+    Ok(verification)
+}
+
+/// 恢复前校验备份，与清单不符时拒绝恢复
+pub fn ensure_intact(backup_path: &Path) -> Result<(), String> {
+    let verification = verify_backup(backup_path)?;
+    if verification.is_ok() {
+        return Ok(());
+    }
+    let details: Vec<String> = verification
+        .problems
+        .iter()
+        .map(|problem| format!("{}（{}）", problem.name, problem.reason))
+        .collect();
+    Err(format!("备份已损坏，不能用于恢复: {}", details.join("、")))
+}
+
+fn read_backup_entries(backup_path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if backup_path.is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(backup_path).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if let (true, Some(name)) = (path.is_file(), path.file_name()) {
+                let content = fs::read(&path).map_err(|e| e.to_string())?;
+                files.push((name.to_string_lossy().into_owned(), content));
+            }
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        return Ok(files);
+    }
+    if !backup_path.is_file() {
+        return Err(format!("备份不存在: {}", backup_path.display()));
+    }
+
+    let file = fs::File::open(backup_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("无法读取压缩备份: {}", e))?;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        // 只接受不带目录的文件名，避免解压到备份之外的位置
+        let name = match entry.enclosed_name().and_then(|p| p.file_name()) {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+        files.push((name, content));
+    }
+    Ok(files)
+}
+
+/// 读取备份中指定文件名的文本内容，不存在时返回 None
+pub fn read_backup_file(backup_path: &Path, file_name: &str) -> Result<Option<String>, String> {
+    if backup_path.is_dir() {
+        let path = backup_path.join(file_name);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        return crate::file_io::read_text(&path)
+            .map(|(text, _)| Some(text))
+            .map_err(|e| e.to_string());
+    }
+    let content = read_backup(backup_path)?
+        .into_iter()
+        .find(|(name, _)| name == file_name)
+        .map(|(_, content)| {
+            crate::file_io::decode(&content)
+                .map(|(text, _)| text)
+                .map_err(|e| e.to_string())
+        })
+        .transpose()?;
+    Ok(content)
+}
+
+/// 用备份中的同名文件覆盖给定文件，返回恢复的文件数
+pub fn restore_files<P: AsRef<Path>>(backup_path: &Path, files: &[P]) -> Result<usize, String> {
+    ensure_intact(backup_path)?;
+    let contents = read_backup(backup_path)?;
+    let mut restored = 0;
+    for file in files {
+        let file = file.as_ref();
+        let name = match file.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => continue,
+        };
+        let (_, content) = contents
+            .iter()
+            .find(|(backup_name, _)| *backup_name == name)
+            .ok_or_else(|| format!("备份中不存在文件: {}", name))?;
+        crate::file_io::write_atomic(file, content).map_err(|e| format!("恢复 {} 失败: {}", name, e))?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// 定时备份：源目录中的 theme 文件与最近一个备份完全相同时跳过，返回新建的备份路径
+pub fn create_scheduled_backup(source_dir: &Path, settings: &AppSettings) -> Result<Option<PathBuf>, crate::Error> {
+    let files = crate::collect_theme_files(source_dir)?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    if let Some((_, latest)) = list_backups(&storage_dir(source_dir, settings))?.into_iter().next() {
+        let mut backed_up = read_backup(Path::new(&latest.path))?;
+        backed_up.sort();
+        let mut current = files
+            .iter()
+            .map(|file| {
+                let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                retry_io(file, || fs::read(long_path(file)))
+                    .map(|content| (name, content))
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        current.sort();
+        if backed_up == current {
+            return Ok(None);
+        }
+    }
+    Ok(Some(create_backup_of_kind(source_dir, &files, settings, BackupKind::Scheduled)?))
+}
+
+/// 列出源目录的所有备份（最新的在前）
+pub fn list_source_backups(source_dir: &Path, settings: &AppSettings) -> Result<Vec<BackupInfo>, String> {
+    Ok(list_backups(&storage_dir(source_dir, settings))?
+        .into_iter()
+        .map(|(_, info)| info)
+        .collect())
+}
+
+/// 列出 `base_dir/backups` 下的所有备份（最新的在前），忽略名称不是时间戳的条目
+fn list_backups(base_dir: &Path) -> Result<Vec<(NaiveDateTime, BackupInfo)>, String> {
+    let root = base_dir.join("backups");
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&root).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let file_name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let (name, format) = if path.is_dir() {
+            (file_name, BackupFormat::Folder)
+        } else {
+            match file_name
+                .strip_prefix(ZIP_PREFIX)
+                .and_then(|rest| rest.strip_suffix(ZIP_SUFFIX))
+            {
+                Some(name) => (name.to_string(), BackupFormat::Zip),
+                None => continue,
+            }
+        };
+        if let Some((time, kind)) = parse_backup_name(&name) {
+            backups.push((
+                time,
+                BackupInfo {
+                    created_at: time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    name,
+                    path: path.to_string_lossy().into_owned(),
+                    format,
+                    kind,
+                },
+            ));
+        }
+    }
+    backups.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    Ok(backups)
+}
+
+/// 计算清理计划：超出保留个数或超过保留天数的备份会被删除，最新的一个备份始终保留
+///
+/// 各来源的备份分别计数，频繁的定时备份不会挤掉修改前的备份。
+pub fn plan_prune(base_dir: &Path, retention: &BackupRetention) -> Result<PrunePlan, String> {
+    let now = Local::now().naive_local();
+    let mut plan = PrunePlan::default();
+    let mut seen: Vec<BackupKind> = Vec::new();
+    for (time, info) in list_backups(base_dir)? {
+        let index = seen.iter().filter(|kind| **kind == info.kind).count();
+        seen.push(info.kind);
+        let over_count = retention.keep_last.is_some_and(|keep| index >= keep);
+        let too_old = retention
+            .max_age_days
+            .is_some_and(|days| now - time > chrono::Duration::days(i64::from(days)));
+        if index > 0 && (over_count || too_old) {
+            plan.to_delete.push(info);
+        } else {
+            plan.kept.push(info);
+        }
+    }
+    Ok(plan)
+}
+
+/// 按保留策略删除旧备份，返回执行的清理计划
+pub fn prune_backups(base_dir: &Path, retention: &BackupRetention) -> Result<PrunePlan, String> {
+    let plan = plan_prune(base_dir, retention)?;
+    for info in &plan.to_delete {
+        let result = match info.format {
+            BackupFormat::Folder => fs::remove_dir_all(&info.path),
+            BackupFormat::Zip => fs::remove_file(&info.path),
+        };
+        result.map_err(|e| format!("删除备份 {} 失败: {}", info.name, e))?;
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("catmap-backup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("theme1.json"), "{\"version\": 1}\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn zip_backups_restore_and_skip_unchanged_scheduled_runs() {
+        let dir = temp_dir("zip");
+        let settings = AppSettings {
+            backup_format: BackupFormat::Zip,
+            ..AppSettings::default()
+        };
+        let file = dir.join("theme1.json");
+
+        let backup = create_scheduled_backup(&dir, &settings).unwrap().unwrap();
+        assert!(backup.extension().is_some_and(|ext| ext == "zip"));
+        assert!(create_scheduled_backup(&dir, &settings).unwrap().is_none());
+        let verification = verify_backup(&backup).unwrap();
+        assert!(verification.has_manifest && verification.is_ok());
+        assert_eq!(verification.checked, 1);
+
+        fs::write(&file, "{\"version\": 2}\n").unwrap();
+        assert_eq!(restore_files(&backup, &[&file]).unwrap(), 1);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{\"version\": 1}\n");
+        let backups = list_source_backups(&dir, &settings).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].kind, BackupKind::Scheduled);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_to_restore_a_tampered_backup() {
+        let dir = temp_dir("tampered");
+        let settings = AppSettings::default();
+        let file = dir.join("theme1.json");
+
+        let backup = create_backup(&dir, &[&file], &settings).unwrap();
+        assert!(backup.is_dir());
+        fs::write(backup.join("theme1.json"), "{\"version\": 9}\n").unwrap();
+        let verification = verify_backup(&backup).unwrap();
+        assert_eq!(verification.problems.len(), 1);
+        assert!(restore_files(&backup, &[&file]).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{\"version\": 1}\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prunes_each_kind_separately() {
+        let dir = temp_dir("prune");
+        let root = dir.join("backups");
+        for name in ["20240101-120000", "20240102-120000-000", "20240103-120000-000-scheduled", "notes"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+        }
+        assert_eq!(
+            parse_backup_name("20240103-120000-000-scheduled").map(|(_, kind)| kind),
+            Some(BackupKind::Scheduled)
+        );
+
+        let retention = BackupRetention {
+            keep_last: Some(1),
+            max_age_days: None,
+        };
+        let plan = plan_prune(&dir, &retention).unwrap();
+        let names = |infos: &[BackupInfo]| infos.iter().map(|info| info.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&plan.kept), ["20240103-120000-000-scheduled", "20240102-120000-000"]);
+        assert_eq!(names(&plan.to_delete), ["20240101-120000"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
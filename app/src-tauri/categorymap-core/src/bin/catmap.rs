@@ -0,0 +1,235 @@
+//! catmap：栏目映射命令行工具，与桌面端共用同一套核心逻辑，结果以 JSON 输出到标准输出
+//!
+//! ```text
+//! catmap [--config-dir <目录>] scan <目录> [--profile]
+//! catmap [--config-dir <目录>] insert <目录> <本地栏目ID=国网栏目ID>... [--files <文件,...>] [--conflict skip|overwrite|error] [--template <模板名>] [--profile]
+//! catmap [--config-dir <目录>] import <目录> <映射表.csv|json|导出文件> [--mode replace|merge]
+//! catmap [--config-dir <目录>] export <目录> <输出文件> [--problems] [--format csv|json]
+//! catmap [--config-dir <目录>] delete <文件> <本地栏目ID>... [--soft]
+//! catmap [--config-dir <目录>] restore <目录> <备份路径>
+//! catmap verify-backup <备份路径>
+//! ```
+//!
+//! import 的映射表是 export 生成的导出文件时，按文件把映射写回同名文件。
+//! insert、import、delete 默认按设置决定是否递增版本号，可用 `--no-version-bump` 关闭。
+//! delete 加 `--soft` 时把条目注释掉而不是删除。
+//! scan、insert 加 `--profile` 时在结果的 timings 中输出各文件读取、解析、写入的耗时。
+//! export 加 `--problems` 时只导出需要处理的问题条目，`--format` 选择 CSV（默认）或 JSON。
+//! 指定 `--config-dir` 且设置要求校验本地栏目目录时，insert 只接受已加载的本地栏目目录中的本地栏目ID。
+//! 失败时输出 `{"error": "...", "code": "...", "params": {...}}`（错误代码和参数见 `categorymap_core::Error`）并以退出码 1 结束。
+
+use categorymap_core::{
+    background, backup, cancel::CancelToken, export, file_io, ignored_theme_files, master, profile,
+    scan_directory, settings, settings::AppSettings, DeleteMappingRequest, Error, ImportSource, MappingInput,
+    ScanResult,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, path::Path, process::ExitCode};
+
+const USAGE: &str = "用法:
+  catmap [--config-dir <目录>] scan <目录> [--profile]
+  catmap [--config-dir <目录>] insert <目录> <本地栏目ID=国网栏目ID>... [--files <文件,...>] [--conflict skip|overwrite|error] [--template <模板名>] [--no-version-bump] [--profile]
+  catmap [--config-dir <目录>] import <目录> <映射表.csv|json|导出文件> [--mode replace|merge] [--no-version-bump]
+  catmap [--config-dir <目录>] export <目录> <输出文件> [--problems] [--format csv|json]
+  catmap [--config-dir <目录>] delete <文件> <本地栏目ID>... [--soft] [--no-version-bump]
+  catmap [--config-dir <目录>] restore <目录> <备份路径>
+  catmap verify-backup <备份路径>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let result = run(Args::parse(args));
+    // 等待后台任务（webhook 推送、git 自动提交）执行完再退出
+    background::wait_idle();
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            let mut output = serde_json::to_value(&e).unwrap_or_default();
+            output["error"] = e.to_string().into();
+            println!("{}", output);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 拆分后的命令行参数：位置参数和 `--名称 值` 形式的选项
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+/// 不带值的开关
+const FLAGS: &[&str] = &["--no-version-bump", "--soft", "--problems", "--profile"];
+
+impl Args {
+    fn parse(args: Vec<String>) -> Self {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            if FLAGS.contains(&arg.as_str()) {
+                parsed.flags.push(arg);
+            } else if let Some(name) = arg.strip_prefix("--") {
+                parsed.options.insert(name.to_string(), iter.next().unwrap_or_default());
+            } else {
+                parsed.positional.push(arg);
+            }
+        }
+        parsed
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// 第 index 个位置参数（0 是子命令）
+    fn required(&self, index: usize, name: &str) -> Result<String, String> {
+        self.positional
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("缺少参数 <{}>，运行 catmap --help 查看用法", name))
+    }
+
+    /// 按前端使用的小写名称解析枚举选项，如 `--conflict overwrite`
+    fn enum_option<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, String> {
+        self.options
+            .get(name)
+            .map(|value| {
+                serde_json::from_value(serde_json::Value::String(value.clone()))
+                    .map_err(|_| format!("--{} 的值无效: {}", name, value))
+            })
+            .transpose()
+    }
+}
+
+fn run(args: Args) -> Result<String, Error> {
+    let config_dir = args.options.get("config-dir").map(Path::new);
+    let settings = match config_dir {
+        Some(dir) => settings::load_settings(dir)?,
+        None => AppSettings::default(),
+    };
+    let auto_increment_version = settings.auto_increment_version && !args.has_flag("--no-version-bump");
+    let no_progress = |_: &Path, _: usize, _: usize| {};
+
+    match args.required(0, "命令")?.as_str() {
+        "scan" => {
+            let retries = file_io::RetryScope::begin();
+            let profile = profile::ProfileScope::begin(args.has_flag("--profile"));
+            let dir = args.required(1, "目录")?;
+            let files = scan_directory(Path::new(&dir), &settings)?;
+            to_json(&ScanResult {
+                files,
+                ignored_files: ignored_theme_files(Path::new(&dir))?,
+                retries: retries.finish(),
+                timings: profile.map(profile::ProfileScope::finish),
+            })
+        }
+        "insert" => {
+            let target_dir = args.required(1, "目录")?;
+            let entries = args.positional[2..]
+                .iter()
+                .map(|pair| match pair.split_once('=') {
+                    Some((local_id, gw_id)) if !local_id.trim().is_empty() && !gw_id.trim().is_empty() => {
+                        Ok(MappingInput {
+                            local_id: local_id.trim().to_string(),
+                            gw_id: gw_id.trim().to_string(),
+                            template: args.options.get("template").cloned(),
+                            ..Default::default()
+                        })
+                    }
+                    _ => Err(format!("映射格式应为 本地栏目ID=国网栏目ID: {}", pair)),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let target_files = args
+                .options
+                .get("files")
+                .map(|files| files.split(',').map(|f| f.trim().to_string()).collect());
+            to_json(&categorymap_core::run_bulk_insert(
+                &settings,
+                config_dir,
+                target_dir,
+                entries,
+                auto_increment_version,
+                target_files,
+                args.enum_option("conflict")?,
+                None,
+                args.has_flag("--profile"),
+                &CancelToken::default(),
+                &no_progress,
+                &|_| {},
+            )?)
+        }
+        "import" => {
+            let target_dir = args.required(1, "目录")?;
+            let path = args.required(2, "映射表")?;
+            // 导出文件按文件导入，其他文件按映射表读取
+            let source = match export::detect(Path::new(&path))? {
+                Some(export) => ImportSource::Export(export),
+                None => ImportSource::Table(master::load(Path::new(&path))?.into_iter().collect()),
+            };
+            to_json(&categorymap_core::run_import(
+                &settings,
+                target_dir,
+                source,
+                auto_increment_version,
+                args.enum_option("mode")?,
+                None,
+                &CancelToken::default(),
+                &no_progress,
+            )?)
+        }
+        "export" if args.has_flag("--problems") => to_json(&categorymap_core::export_problems(
+            &settings,
+            args.required(1, "目录")?,
+            args.enum_option("format")?,
+            Some(args.required(2, "输出文件")?),
+        )?),
+        "export" => to_json(&categorymap_core::export_mappings(
+            &settings,
+            args.required(1, "目录")?,
+            args.required(2, "输出文件")?,
+        )?),
+        "delete" => {
+            let file_path = args.required(1, "文件")?;
+            args.required(2, "本地栏目ID")?;
+            let requests = args.positional[2..]
+                .iter()
+                .map(|local_id| DeleteMappingRequest {
+                    file_path: file_path.clone(),
+                    local_id: local_id.clone(),
+                    block: None,
+                    prefix: None,
+                })
+                .collect();
+            to_json(&categorymap_core::batch_delete_mappings(
+                &settings,
+                requests,
+                auto_increment_version,
+                None,
+                args.has_flag("--soft"),
+                &|_| {},
+            )?)
+        }
+        "restore" => to_json(&categorymap_core::restore_backup(
+            &settings,
+            args.required(1, "目录")?,
+            args.required(2, "备份路径")?,
+        )?),
+        "verify-backup" => to_json(&backup::verify_backup(Path::new(&args.required(1, "备份路径")?))?),
+        other => Err(format!("未知命令: {}，运行 catmap --help 查看用法", other).into()),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, Error> {
+    serde_json::to_string_pretty(value).map_err(|e| Error::from(e.to_string()))
+}
@@ -0,0 +1,292 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+
+use crate::{file_io, master::split_csv_line, settings::AppSettings, FileMapping};
+
+const CATALOG_CACHE_FILE_NAME: &str = "gw_catalog.json";
+const LOCAL_CATALOG_CACHE_FILE_NAME: &str = "local_catalog.json";
+/// 拉取栏目目录的超时时间
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 国网官方栏目目录（国网栏目ID -> 栏目名称），缓存在应用配置目录下的 gw_catalog.json
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GwCatalog {
+    pub url: String,
+    /// 拉取时间（YYYY-MM-DD HH:MM:SS）
+    pub fetched_at: String,
+    pub entries: BTreeMap<String, String>,
+}
+
+/// 拉取结果摘要（不含完整目录）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSummary {
+    pub url: String,
+    pub fetched_at: String,
+    pub count: usize,
+}
+
+impl GwCatalog {
+    pub fn summary(&self) -> CatalogSummary {
+        CatalogSummary {
+            url: self.url.clone(),
+            fetched_at: self.fetched_at.clone(),
+            count: self.entries.len(),
+        }
+    }
+
+    /// 为扫描结果标注国网栏目名称，并标记不在目录中的国网栏目ID
+    pub fn annotate(&self, files: &mut [FileMapping]) {
+        for entry in files.iter_mut().flat_map(|file| file.mappings.iter_mut()) {
+            let Some(gw_id) = entry.gw_id.as_deref() else {
+                continue;
+            };
+            entry.gw_name = self.entries.get(gw_id).cloned();
+            entry.in_catalog = Some(entry.gw_name.is_some());
+        }
+    }
+}
+
+/// 从 HTTP 接口拉取栏目目录
+pub fn fetch(url: &str) -> Result<GwCatalog, String> {
+    let body = ureq::get(url)
+        .timeout(FETCH_TIMEOUT)
+        .call()
+        .map_err(|e| format!("拉取栏目目录失败: {}", e))?
+        .into_string()
+        .map_err(|e| format!("读取栏目目录失败: {}", e))?;
+    let entries = parse(&body)?;
+    if entries.is_empty() {
+        return Err("栏目目录为空".into());
+    }
+    Ok(GwCatalog {
+        url: url.to_string(),
+        fetched_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        entries,
+    })
+}
+
+/// 解析栏目目录 JSON
+///
+/// 支持 `{"国网栏目ID": "名称"}` 对象，或由 `{id/gwId/tabId, name/title}` 组成的数组；
+/// 数组也可以包在 `data`、`list` 或 `items` 字段中。
+fn parse(body: &str) -> Result<BTreeMap<String, String>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| format!("栏目目录格式错误: {}", e))?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(map) => match ["data", "list", "items"].iter().find_map(|key| map.get(*key)) {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Ok(map
+                    .iter()
+                    .filter_map(|(id, name)| Some((id.clone(), name.as_str()?.to_string())))
+                    .collect())
+            }
+        },
+        _ => return Err("栏目目录必须是 JSON 对象或数组".into()),
+    };
+
+    let mut entries = BTreeMap::new();
+    for item in items {
+        let id = ["gwId", "id", "tabId"].iter().find_map(|key| match item.get(*key) {
+            Some(Value::String(s)) if !s.is_empty() => Some(s.clone()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        });
+        let name = ["name", "title"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(Value::as_str));
+        if let (Some(id), Some(name)) = (id, name) {
+            entries.insert(id, name.to_string());
+        }
+    }
+    Ok(entries)
+}
+
+/// 读取缓存的栏目目录，未缓存时返回 None
+pub fn load_cache(config_dir: &Path) -> Result<Option<GwCatalog>, String> {
+    let path = config_dir.join(CATALOG_CACHE_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取栏目目录缓存失败: {}", e))?;
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| format!("栏目目录缓存格式错误: {}", e))
+}
+
+/// 缓存栏目目录到配置目录
+pub fn save_cache(config_dir: &Path, catalog: &GwCatalog) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(catalog).map_err(|e| e.to_string())?;
+    crate::file_io::write_atomic(&config_dir.join(CATALOG_CACHE_FILE_NAME), content)
+        .map_err(|e| format!("写入栏目目录缓存失败: {}", e))
+}
+
+/// 本地栏目目录（本地栏目ID -> 栏目名称），从运营维护的 CSV 或 JSON 文件加载，缓存在 local_catalog.json
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalCatalog {
+    /// 加载的源文件路径
+    pub path: String,
+    /// 加载时间（YYYY-MM-DD HH:MM:SS）
+    pub loaded_at: String,
+    pub entries: BTreeMap<String, String>,
+}
+
+/// 本地栏目目录的加载结果摘要（不含完整目录）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalCatalogSummary {
+    pub path: String,
+    pub loaded_at: String,
+    pub count: usize,
+}
+
+impl LocalCatalog {
+    pub fn summary(&self) -> LocalCatalogSummary {
+        LocalCatalogSummary {
+            path: self.path.clone(),
+            loaded_at: self.loaded_at.clone(),
+            count: self.entries.len(),
+        }
+    }
+
+    /// 为扫描结果标注本地栏目名称
+    pub fn annotate(&self, files: &mut [FileMapping]) {
+        for entry in files.iter_mut().flat_map(|file| file.mappings.iter_mut()) {
+            entry.local_name = self.entries.get(&entry.local_id).cloned();
+        }
+    }
+}
+
+/// 读取本地栏目目录文件，按扩展名识别 CSV 或 JSON
+///
+/// CSV 取前两列（本地栏目ID, 栏目名称），首行为表头时自动跳过；
+/// JSON 支持 `{"本地栏目ID": "名称"}` 对象或由 `{localId/id, name/title}` 组成的数组。
+pub fn load_local(path: &Path) -> Result<LocalCatalog, String> {
+    let (raw, _) = file_io::read_text(path).map_err(|e| format!("读取本地栏目目录失败: {}", e))?;
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let entries = if is_json { parse_local_json(&raw)? } else { parse_local_csv(&raw)? };
+    if entries.is_empty() {
+        return Err("本地栏目目录为空".into());
+    }
+    Ok(LocalCatalog {
+        path: path.to_string_lossy().into_owned(),
+        loaded_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        entries,
+    })
+}
+
+fn parse_local_json(raw: &str) -> Result<BTreeMap<String, String>, String> {
+    let value: Value = json5::from_str(raw).map_err(|e| format!("本地栏目目录格式错误: {}", e))?;
+    match value {
+        Value::Object(map) => Ok(map
+            .into_iter()
+            .filter_map(|(id, name)| Some((id.trim().to_string(), name.as_str()?.trim().to_string())))
+            .collect()),
+        Value::Array(items) => {
+            let mut entries = BTreeMap::new();
+            for item in &items {
+                let id = ["localId", "id"].iter().find_map(|key| match item.get(*key) {
+                    Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+                    Some(Value::Number(n)) => Some(n.to_string()),
+                    _ => None,
+                });
+                let name = ["name", "title"]
+                    .iter()
+                    .find_map(|key| item.get(*key).and_then(Value::as_str));
+                if let (Some(id), Some(name)) = (id, name) {
+                    entries.insert(id, name.trim().to_string());
+                }
+            }
+            Ok(entries)
+        }
+        _ => Err("本地栏目目录必须是 JSON 对象或数组".into()),
+    }
+}
+
+fn parse_local_csv(raw: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut entries = BTreeMap::new();
+    for (index, line) in raw.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let local_id = fields.first().map(|f| f.trim()).unwrap_or_default();
+        let name = fields.get(1).map(|f| f.trim()).unwrap_or_default();
+        // 首行不是数字ID时视为表头
+        if index == 0 && !local_id.chars().any(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        if local_id.is_empty() || name.is_empty() {
+            return Err(format!("第 {} 行缺少本地栏目ID或栏目名称", index + 1));
+        }
+        entries.insert(local_id.to_string(), name.to_string());
+    }
+    Ok(entries)
+}
+
+/// 读取缓存的本地栏目目录，未加载过时返回 None
+pub fn load_local_cache(config_dir: &Path) -> Result<Option<LocalCatalog>, String> {
+    let path = config_dir.join(LOCAL_CATALOG_CACHE_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取本地栏目目录缓存失败: {}", e))?;
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| format!("本地栏目目录缓存格式错误: {}", e))
+}
+
+/// 缓存本地栏目目录到配置目录
+pub fn save_local_cache(config_dir: &Path, catalog: &LocalCatalog) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(catalog).map_err(|e| e.to_string())?;
+    crate::file_io::write_atomic(&config_dir.join(LOCAL_CATALOG_CACHE_FILE_NAME), content)
+        .map_err(|e| format!("写入本地栏目目录缓存失败: {}", e))
+}
+
+/// 设置要求时检查新增的本地栏目ID（按ID规范规则规范后）都在已加载的本地栏目目录中
+pub fn ensure_local_ids_known<'a>(
+    settings: &AppSettings,
+    config_dir: &Path,
+    local_ids: impl IntoIterator<Item = &'a str>,
+) -> Result<(), String> {
+    if !settings.require_local_catalog {
+        return Ok(());
+    }
+    let catalog = load_local_cache(config_dir)?.ok_or("设置要求校验本地栏目目录，但尚未加载本地栏目目录")?;
+    let unknown: Vec<&str> = local_ids
+        .into_iter()
+        .filter(|local_id| !catalog.entries.contains_key(&settings.id_normalization.local_id(local_id.trim())))
+        .collect();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("以下本地栏目ID不在本地栏目目录中：{}", unknown.join("、")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_catalog() {
+        let csv = parse_local_csv("本地栏目ID,栏目名称\n101,新闻\n\"102\",\"体育, 赛事\"\n").unwrap();
+        assert_eq!(csv["101"], "新闻");
+        assert_eq!(csv["102"], "体育, 赛事");
+        assert!(parse_local_csv("101,\n").is_err());
+
+        let json = parse_local_json(r#"[{"localId": 101, "name": "新闻"}, {"id": "102", "title": "体育"}]"#).unwrap();
+        assert_eq!(json.len(), 2);
+        assert_eq!(parse_local_json(r#"{"103": "电影"}"#).unwrap()["103"], "电影");
+    }
+}
@@ -0,0 +1,509 @@
+//! JSON5 对象的无损语法树
+//!
+//! 只展开对象的一层成员，记录每个成员的 key、值和逗号在原文中的位置，成员之间的空白和注释原样保留。
+//! 增删成员时只改动必要的字符，注释、缩进、成员顺序和末尾逗号的写法都保持不变，
+//! 修改后会重新解析一遍，保证输出的对象语法正确。
+
+use crate::editor::EditError;
+
+/// 对象中的一个成员
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// 去掉引号后的 key
+    pub key: String,
+    /// key 的起始位置（含引号）
+    pub key_start: usize,
+    /// 值的起止位置（字符串含引号）
+    pub value_start: usize,
+    pub value_end: usize,
+    /// 值后面逗号的位置
+    pub comma: Option<usize>,
+}
+
+impl Member {
+    /// 成员最后一个字符（逗号或值）之后的位置
+    fn terminator_end(&self) -> usize {
+        self.comma.map(|comma| comma + 1).unwrap_or(self.value_end)
+    }
+}
+
+/// 一个对象及其成员
+#[derive(Debug, Clone)]
+pub struct ObjectCst {
+    /// 左右花括号的位置
+    pub open: usize,
+    pub close: usize,
+    pub members: Vec<Member>,
+}
+
+impl ObjectCst {
+    /// 解析从 open（左花括号）开始的对象
+    pub fn parse(source: &str, open: usize) -> Result<Self, EditError> {
+        let bytes = source.as_bytes();
+        if bytes.get(open) != Some(&b'{') {
+            return Err(EditError::ExtOptionsNotObject);
+        }
+        let mut members = Vec::new();
+        let mut i = skip_trivia(source, open + 1);
+        loop {
+            match bytes.get(i) {
+                None => return Err(EditError::UnterminatedExtOptions),
+                Some(b'}') => return Ok(Self { open, close: i, members }),
+                _ => {}
+            }
+
+            let key_start = i;
+            let (key, key_end) = parse_key(source, i)?;
+            i = skip_trivia(source, key_end);
+            if bytes.get(i) != Some(&b':') {
+                return Err(EditError::MalformedExtOptions);
+            }
+            let value_start = skip_trivia(source, i + 1);
+            let value_end = skip_value(source, value_start)?;
+            i = skip_trivia(source, value_end);
+            let comma = if bytes.get(i) == Some(&b',') {
+                let comma = i;
+                i = skip_trivia(source, i + 1);
+                Some(comma)
+            } else {
+                None
+            };
+            members.push(Member {
+                key,
+                key_start,
+                value_start,
+                value_end,
+                comma,
+            });
+
+            if comma.is_none() {
+                return match bytes.get(i) {
+                    Some(b'}') => Ok(Self { open, close: i, members }),
+                    None => Err(EditError::UnterminatedExtOptions),
+                    Some(_) => Err(EditError::MalformedExtOptions),
+                };
+            }
+        }
+    }
+
+    /// 最后一个成员后面是否带逗号
+    pub fn trailing_comma(&self) -> bool {
+        self.members.last().is_some_and(|member| member.comma.is_some())
+    }
+
+    /// 整个对象是否写在同一行
+    pub fn is_single_line(&self, source: &str) -> bool {
+        !source[self.open..self.close].contains('\n')
+    }
+
+    /// 删除指定下标的成员，返回修改后的全文
+    pub fn remove_members(&self, source: &str, indices: &[usize]) -> Result<String, EditError> {
+        self.take_out_members(source, indices, false)
+    }
+
+    /// 把指定下标的成员注释掉（独占一行的写成 `// "key": "value"`，同一行内的写成块注释），
+    /// 逗号的处理与删除相同，返回修改后的全文
+    pub fn comment_out_members(&self, source: &str, indices: &[usize]) -> Result<String, EditError> {
+        self.take_out_members(source, indices, true)
+    }
+
+    fn take_out_members(&self, source: &str, indices: &[usize], comment_out: bool) -> Result<String, EditError> {
+        let removed = |idx: usize| indices.contains(&idx);
+        let mut edits: Vec<(usize, usize, String)> = (0..self.members.len())
+            .filter(|idx| removed(*idx))
+            .map(|idx| {
+                let (start, end) = self.removal_range(source, idx);
+                let replacement = if comment_out {
+                    self.commented_member(source, idx, start, end)
+                } else {
+                    String::new()
+                };
+                (start, end, replacement)
+            })
+            .collect();
+
+        // 原本最后一个成员后面没有逗号时，删掉末尾的成员后要去掉新的最后一个成员的逗号
+        if !self.trailing_comma() {
+            if let Some(last_kept) = (0..self.members.len()).rev().find(|idx| !removed(*idx)) {
+                if last_kept + 1 < self.members.len() {
+                    let comma = self.members[last_kept].comma.unwrap_or(self.members[last_kept].value_end);
+                    let next_key = self.members[last_kept + 1].key_start;
+                    // 同一行内的成员：连同逗号和下一个成员之间的空格一起删掉
+                    let end = if source[comma + 1..next_key].chars().all(|ch| ch == ' ' || ch == '\t') {
+                        next_key
+                    } else {
+                        comma + 1
+                    };
+                    edits.push((comma, end, String::new()));
+                }
+            }
+        }
+
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        let mut result = source.to_string();
+        for (start, end, replacement) in edits {
+            result.replace_range(start..end, &replacement);
+        }
+        self.verified(result)
+    }
+
+    /// 注释掉成员时用来替换删除范围的文本，行尾注释保留在注释行末尾
+    fn commented_member(&self, source: &str, idx: usize, start: usize, end: usize) -> String {
+        let member = &self.members[idx];
+        let text = &source[member.key_start..member.value_end];
+        if start == member.key_start {
+            let gap = if member.comma.is_some() { " " } else { "" };
+            return format!("/* {} */{}", text, gap);
+        }
+        let indent = &source[start..member.key_start];
+        let line_end = self.member_line_end(source, member).unwrap_or(end);
+        let trailing = source[member.terminator_end()..line_end].trim();
+        let line_ending = &source[line_end..end];
+        if trailing.is_empty() {
+            format!("{}// {}{}", indent, text, line_ending)
+        } else {
+            format!("{}// {} {}{}", indent, text, trailing, line_ending)
+        }
+    }
+
+    /// 在最后一个成员之后追加已格式化好的成员（`"key":"value"`），沿用原有的缩进和末尾逗号写法
+    ///
+    /// 对象写在同一行且已有成员时，新成员也追加在同一行。
+    pub fn append_members(&self, source: &str, lines: &[String]) -> Result<String, EditError> {
+        if lines.is_empty() {
+            return Ok(source.to_string());
+        }
+        if !self.members.is_empty() && self.is_single_line(source) {
+            return self.append_members_inline(source, lines);
+        }
+        let line_ending = if source.contains("\r\n") { "\r\n" } else { "\n" };
+        let mut result = source.to_string();
+
+        let Some(last) = self.members.last() else {
+            // 空对象：去掉右花括号前的空白，写成每行一个成员
+            let base_indent = line_indent(source, self.open);
+            let mut ws_start = self.close;
+            while ws_start > self.open + 1 && source.as_bytes()[ws_start - 1].is_ascii_whitespace() {
+                ws_start -= 1;
+            }
+            let entry_indent = format!("{base_indent}  ");
+            let mut insertion = String::new();
+            for (idx, line) in lines.iter().enumerate() {
+                insertion.push_str(line_ending);
+                insertion.push_str(&entry_indent);
+                insertion.push_str(line);
+                if idx + 1 < lines.len() {
+                    insertion.push(',');
+                }
+            }
+            insertion.push_str(line_ending);
+            insertion.push_str(&base_indent);
+            result.replace_range(ws_start..self.close, &insertion);
+            return self.verified(result);
+        };
+
+        let entry_indent = if self.starts_line(source, last.key_start) {
+            source[line_start(source, last.key_start)..last.key_start].to_string()
+        } else {
+            format!("{}  ", line_indent(source, self.open))
+        };
+        // 插在最后一个成员所在行的末尾（行尾注释之后）
+        let position = self.member_line_end(source, last).unwrap_or(last.terminator_end());
+        let mut insertion = String::new();
+        for (idx, line) in lines.iter().enumerate() {
+            insertion.push_str(line_ending);
+            insertion.push_str(&entry_indent);
+            insertion.push_str(line);
+            if idx + 1 < lines.len() || last.comma.is_some() {
+                insertion.push(',');
+            }
+        }
+        result.insert_str(position, &insertion);
+        if last.comma.is_none() {
+            result.insert(last.value_end, ',');
+        }
+        self.verified(result)
+    }
+
+    /// 在同一行内追加成员，成员之间的空格沿用对象中已有的写法
+    pub fn append_members_inline(&self, source: &str, lines: &[String]) -> Result<String, EditError> {
+        if lines.is_empty() {
+            return Ok(source.to_string());
+        }
+        let gap = self.inline_gap(source);
+        let mut result = source.to_string();
+        match self.members.last() {
+            None => {
+                let pad = if source[self.open + 1..self.close].is_empty() { "" } else { " " };
+                let joined = lines.join(&format!(",{gap}"));
+                result.replace_range(self.open + 1..self.close, &format!("{pad}{joined}{pad}"));
+            }
+            Some(last) if last.comma.is_some() => {
+                let insertion: String = lines.iter().map(|line| format!("{gap}{line},")).collect();
+                result.insert_str(last.terminator_end(), &insertion);
+            }
+            Some(last) => {
+                let insertion: String = lines.iter().map(|line| format!(",{gap}{line}")).collect();
+                result.insert_str(last.value_end, &insertion);
+            }
+        }
+        self.verified(result)
+    }
+
+    /// 同一行内成员之间逗号后的空白，没有可参考的成员时按左花括号后的空白推断
+    fn inline_gap<'a>(&self, source: &'a str) -> &'a str {
+        let is_gap = |text: &str| text.chars().all(|ch| ch == ' ' || ch == '\t');
+        for pair in self.members.windows(2) {
+            if let Some(comma) = pair[0].comma {
+                let between = &source[comma + 1..pair[1].key_start];
+                if is_gap(between) {
+                    return between;
+                }
+            }
+        }
+        match self.members.first() {
+            Some(first) if is_gap(&source[self.open + 1..first.key_start]) => &source[self.open + 1..first.key_start],
+            _ => " ",
+        }
+    }
+
+    /// 删除一个成员时需要移除的文本范围
+    fn removal_range(&self, source: &str, idx: usize) -> (usize, usize) {
+        let member = &self.members[idx];
+        // 成员独占一行时连同缩进、行尾注释和换行一起删除
+        if self.starts_line(source, member.key_start) {
+            if let Some(line_end) = self.member_line_end(source, member) {
+                let next_line = if source[line_end..].starts_with("\r\n") {
+                    line_end + 2
+                } else {
+                    line_end + 1
+                };
+                return (line_start(source, member.key_start), next_line);
+            }
+        }
+        let mut end = member.terminator_end();
+        if member.comma.is_some() {
+            while end < self.close && matches!(source.as_bytes()[end], b' ' | b'\t') {
+                end += 1;
+            }
+        }
+        (member.key_start, end)
+    }
+
+    /// 成员所在行在对象内结束时，返回换行符的位置（跳过行尾空白和注释）；同一行还有其他内容时返回 None
+    fn member_line_end(&self, source: &str, member: &Member) -> Option<usize> {
+        let start = member.terminator_end();
+        let line_end = source[start..].find('\n').map(|pos| start + pos)?;
+        if line_end > self.close {
+            return None;
+        }
+        let rest = source[start..line_end].trim();
+        if rest.is_empty() || rest.starts_with("//") {
+            Some(if source[..line_end].ends_with('\r') { line_end - 1 } else { line_end })
+        } else {
+            None
+        }
+    }
+
+    /// 位置之前同一行是否只有空白（且不在左花括号所在行）
+    fn starts_line(&self, source: &str, pos: usize) -> bool {
+        let start = line_start(source, pos);
+        start > self.open && source[start..pos].trim().is_empty()
+    }
+
+    /// 重新解析修改后的对象，确认语法正确
+    fn verified(&self, result: String) -> Result<String, EditError> {
+        Self::parse(&result, self.open)?;
+        Ok(result)
+    }
+}
+
+/// 位置所在行的起始位置
+fn line_start(source: &str, pos: usize) -> usize {
+    source[..pos].rfind('\n').map(|idx| idx + 1).unwrap_or(0)
+}
+
+/// 位置所在行的缩进
+pub fn line_indent(source: &str, pos: usize) -> String {
+    source[line_start(source, pos)..pos]
+        .chars()
+        .take_while(|ch| ch.is_whitespace())
+        .collect()
+}
+
+/// 跳过空白和注释
+pub(crate) fn skip_trivia(source: &str, mut i: usize) -> usize {
+    let bytes = source.as_bytes();
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if source[i..].starts_with("//") {
+            i = source[i..].find('\n').map(|pos| i + pos + 1).unwrap_or(bytes.len());
+        } else if source[i..].starts_with("/*") {
+            i = source[i + 2..].find("*/").map(|pos| i + pos + 4).unwrap_or(bytes.len());
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// 字符串（单引号或双引号）结束引号的位置
+fn string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let quote = bytes[start];
+    let mut escaped = false;
+    for (offset, &ch) in bytes[start + 1..].iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if ch == b'\\' {
+            escaped = true;
+        } else if ch == quote {
+            return Some(start + 1 + offset);
+        }
+    }
+    None
+}
+
+/// 解析成员的 key（带引号的字符串或 JSON5 标识符），返回 key 和其后的位置
+pub(crate) fn parse_key(source: &str, start: usize) -> Result<(String, usize), EditError> {
+    let bytes = source.as_bytes();
+    match bytes[start] {
+        b'"' | b'\'' => {
+            let end = string_end(bytes, start).ok_or(EditError::UnterminatedExtOptions)?;
+            Ok((source[start + 1..end].to_string(), end + 1))
+        }
+        _ => {
+            let end = source[start..]
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '$'))
+                .map(|pos| start + pos)
+                .unwrap_or(source.len());
+            if end == start {
+                return Err(EditError::MalformedExtOptions);
+            }
+            Ok((source[start..end].to_string(), end))
+        }
+    }
+}
+
+/// 跳过一个值（字符串、对象、数组或其他字面量），返回值之后的位置
+pub(crate) fn skip_value(source: &str, start: usize) -> Result<usize, EditError> {
+    let bytes = source.as_bytes();
+    match bytes.get(start) {
+        None => Err(EditError::UnterminatedExtOptions),
+        Some(b'"' | b'\'') => string_end(bytes, start)
+            .map(|end| end + 1)
+            .ok_or(EditError::UnterminatedExtOptions),
+        Some(b'{' | b'[') => {
+            let mut depth = 0usize;
+            let mut i = start;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'"' | b'\'' => {
+                        i = string_end(bytes, i).ok_or(EditError::UnterminatedExtOptions)?;
+                    }
+                    b'/' if source[i..].starts_with("//") || source[i..].starts_with("/*") => {
+                        i = skip_trivia(source, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Ok(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            Err(EditError::UnterminatedExtOptions)
+        }
+        Some(_) => {
+            let end = source[start..]
+                .find(|ch: char| matches!(ch, ',' | '}' | ']' | '/') || ch.is_whitespace())
+                .map(|pos| start + pos)
+                .unwrap_or(source.len());
+            if end == start {
+                Err(EditError::MalformedExtOptions)
+            } else {
+                Ok(end)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remove(source: &str, keys: &[&str]) -> String {
+        let object = ObjectCst::parse(source, source.find('{').unwrap()).unwrap();
+        let indices: Vec<usize> = (0..object.members.len())
+            .filter(|idx| keys.contains(&object.members[*idx].key.as_str()))
+            .collect();
+        object.remove_members(source, &indices).unwrap()
+    }
+
+    fn append(source: &str, lines: &[&str]) -> String {
+        let object = ObjectCst::parse(source, source.find('{').unwrap()).unwrap();
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        object.append_members(source, &lines).unwrap()
+    }
+
+    #[test]
+    fn parses_json5_members() {
+        let source = "{ a: 1, 'b': [1, {\"x\": \"}\"}], /* c, d */ \"e\": \"f,g\", }";
+        let object = ObjectCst::parse(source, 0).unwrap();
+        let keys: Vec<&str> = object.members.iter().map(|m| m.key.as_str()).collect();
+        assert_eq!(keys, ["a", "b", "e"]);
+        assert!(object.trailing_comma());
+        assert!(object.is_single_line(source));
+    }
+
+    #[test]
+    fn rejects_broken_objects() {
+        assert_eq!(ObjectCst::parse("{\"a\" 1}", 0).unwrap_err(), EditError::MalformedExtOptions);
+        assert_eq!(ObjectCst::parse("{\"a\": 1 \"b\": 2}", 0).unwrap_err(), EditError::MalformedExtOptions);
+        assert_eq!(ObjectCst::parse("{\"a\": \"1", 0).unwrap_err(), EditError::UnterminatedExtOptions);
+    }
+
+    #[test]
+    fn removes_single_line_members() {
+        let source = "{ \"a\": 1, \"b\": 2, \"c\": 3 }";
+        assert_eq!(remove(source, &["a"]), "{ \"b\": 2, \"c\": 3 }");
+        assert_eq!(remove(source, &["b"]), "{ \"a\": 1, \"c\": 3 }");
+        assert_eq!(remove(source, &["c"]), "{ \"a\": 1, \"b\": 2 }");
+        assert_eq!(remove(source, &["a", "b", "c"]), "{  }");
+    }
+
+    #[test]
+    fn removes_lines_and_keeps_comments() {
+        let source = "{\n  // 首页, 推荐\n  \"a\": 1, // 行尾注释\n  \"b\": 2\n}";
+        assert_eq!(remove(source, &["a"]), "{\n  // 首页, 推荐\n  \"b\": 2\n}");
+        assert_eq!(remove(source, &["b"]), "{\n  // 首页, 推荐\n  \"a\": 1 // 行尾注释\n}");
+    }
+
+    #[test]
+    fn keeps_trailing_comma_style() {
+        let source = "{\n  \"a\": 1,\n  \"b\": 2,\n}";
+        assert_eq!(remove(source, &["b"]), "{\n  \"a\": 1,\n}");
+        assert_eq!(append(source, &["\"c\": 3"]), "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3,\n}");
+    }
+
+    #[test]
+    fn appends_in_compact_style() {
+        assert_eq!(append("{\"a\":1, \"b\":2}", &["\"c\":3", "\"d\":4"]), "{\"a\":1, \"b\":2, \"c\":3, \"d\":4}");
+        assert_eq!(append("{\"a\":1,\"b\":2,}", &["\"c\":3"]), "{\"a\":1,\"b\":2,\"c\":3,}");
+        assert_eq!(append("{ \"a\":1 }", &["\"c\":3"]), "{ \"a\":1, \"c\":3 }");
+        let object = ObjectCst::parse("{ }", 0).unwrap();
+        let lines = vec!["\"a\":1".to_string(), "\"b\":2".to_string()];
+        assert_eq!(object.append_members_inline("{ }", &lines).unwrap(), "{ \"a\":1, \"b\":2 }");
+    }
+
+    #[test]
+    fn appends_after_trailing_comment() {
+        let source = "{\r\n    \"a\": 1 // 注释\r\n}";
+        assert_eq!(append(source, &["\"b\": 2"]), "{\r\n    \"a\": 1, // 注释\r\n    \"b\": 2\r\n}");
+        assert_eq!(append("{\n  // 空\n}", &["\"b\": 2"]), "{\n  // 空\n  \"b\": 2\n}");
+    }
+}
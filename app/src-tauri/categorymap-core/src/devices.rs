@@ -0,0 +1,157 @@
+//! 设备登记：为每种机顶盒型号记录 adb 序列号、设备上的 theme 目录、本地对应的目录和使用的设置配置，
+//! 扫描、批量新增、同步时选中设备即可使用它的目录和设置。设备保存在配置目录下的 devices.json。
+
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::settings::{self, AppSettings};
+
+const DEVICES_FILE_NAME: &str = "devices.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProfile {
+    /// 设备名称，如机顶盒型号
+    pub name: String,
+    /// adb 序列号
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// 设备上的 theme 目录，用于 adb 下载和上传
+    #[serde(default)]
+    pub remote_path: Option<String>,
+    /// 本地对应的 theme 目录
+    pub dir: String,
+    /// 使用的设置配置名称，None 表示使用当前设置
+    #[serde(default)]
+    pub settings_profile: Option<String>,
+}
+
+impl DeviceProfile {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("设备名称不能为空".into());
+        }
+        if self.dir.trim().is_empty() {
+            return Err("设备目录不能为空".into());
+        }
+        let has_serial = self.serial.as_deref().is_some_and(|serial| !serial.trim().is_empty());
+        let has_remote_path = self.remote_path.as_deref().is_some_and(|path| !path.trim().is_empty());
+        if has_serial && !has_remote_path {
+            return Err("填写了 adb 序列号时必须填写设备上的 theme 目录".into());
+        }
+        Ok(())
+    }
+
+    /// 操作这台设备时使用的设置：设置配置中的设置，未指定配置时为 current
+    pub fn settings(&self, config_dir: &Path, current: &AppSettings) -> Result<AppSettings, String> {
+        let Some(name) = self.settings_profile.as_deref().filter(|name| !name.trim().is_empty()) else {
+            return Ok(current.clone());
+        };
+        settings::load_profiles(config_dir)?
+            .profiles
+            .remove(name)
+            .ok_or_else(|| format!("设备 {} 使用的设置配置不存在: {}", self.name, name))
+    }
+
+    /// 本地目录，不存在时返回错误
+    pub fn local_dir(&self) -> Result<&Path, String> {
+        let dir = Path::new(&self.dir);
+        if !dir.is_dir() {
+            return Err(format!("设备 {} 的目录不存在: {}", self.name, self.dir));
+        }
+        Ok(dir)
+    }
+}
+
+pub fn load_devices(config_dir: &Path) -> Result<Vec<DeviceProfile>, String> {
+    let path = config_dir.join(DEVICES_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取设备列表失败: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("设备列表文件格式错误: {}", e))
+}
+
+pub fn find_device(config_dir: &Path, name: &str) -> Result<DeviceProfile, String> {
+    load_devices(config_dir)?
+        .into_iter()
+        .find(|device| device.name == name)
+        .ok_or_else(|| format!("设备不存在: {}", name))
+}
+
+/// 保存（新增或覆盖同名）设备，返回所有设备
+pub fn save_device(config_dir: &Path, device: DeviceProfile) -> Result<Vec<DeviceProfile>, String> {
+    device.validate()?;
+    if let Some(name) = device.settings_profile.as_deref().filter(|name| !name.trim().is_empty()) {
+        if !settings::load_profiles(config_dir)?.profiles.contains_key(name) {
+            return Err(format!("设置配置不存在: {}", name));
+        }
+    }
+    let mut devices = load_devices(config_dir)?;
+    match devices.iter_mut().find(|existing| existing.name == device.name) {
+        Some(existing) => *existing = device,
+        None => devices.push(device),
+    }
+    write_devices(config_dir, &devices)?;
+    Ok(devices)
+}
+
+pub fn delete_device(config_dir: &Path, name: &str) -> Result<Vec<DeviceProfile>, String> {
+    let mut devices = load_devices(config_dir)?;
+    let count = devices.len();
+    devices.retain(|device| device.name != name);
+    if devices.len() == count {
+        return Err(format!("设备不存在: {}", name));
+    }
+    write_devices(config_dir, &devices)?;
+    Ok(devices)
+}
+
+fn write_devices(config_dir: &Path, devices: &[DeviceProfile]) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(devices).map_err(|e| e.to_string())?;
+    fs::write(config_dir.join(DEVICES_FILE_NAME), content).map_err(|e| format!("写入设备列表失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_devices_and_resolves_settings_profile() {
+        let config_dir = std::env::temp_dir().join(format!("catmap-devices-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&config_dir);
+        let device = |name: &str, profile: Option<&str>| DeviceProfile {
+            name: name.to_string(),
+            serial: None,
+            remote_path: None,
+            dir: format!("/deploy/{}", name),
+            settings_profile: profile.map(str::to_string),
+        };
+
+        assert!(save_device(&config_dir, device("A", Some("四川"))).is_err());
+        let profile = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..Default::default()
+        };
+        settings::save_profile(&config_dir, "四川", &profile).unwrap();
+        save_device(&config_dir, device("A", Some("四川"))).unwrap();
+        assert_eq!(save_device(&config_dir, device("B", None)).unwrap().len(), 2);
+
+        let current = AppSettings::default();
+        let a = find_device(&config_dir, "A").unwrap();
+        assert_eq!(a.settings(&config_dir, &current).unwrap().extra_prefixes, ["vod_frag_"]);
+        let b = find_device(&config_dir, "B").unwrap();
+        assert!(b.settings(&config_dir, &current).unwrap().extra_prefixes.is_empty());
+
+        let serial_only = DeviceProfile {
+            serial: Some("emulator-5554".to_string()),
+            ..device("C", None)
+        };
+        assert!(save_device(&config_dir, serial_only).is_err());
+
+        assert_eq!(delete_device(&config_dir, "A").unwrap().len(), 1);
+        assert!(delete_device(&config_dir, "A").is_err());
+        let _ = fs::remove_dir_all(&config_dir);
+    }
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FileMapping, MappingEntry};
+
+/// 只存在于一侧的映射
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffEntry {
+    pub local_id: String,
+    pub gw_id: Option<String>,
+    pub raw_value: String,
+}
+
+/// 两侧都存在但映射值不同的映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedMapping {
+    pub local_id: String,
+    pub gw_id_a: Option<String>,
+    pub gw_id_b: Option<String>,
+    pub raw_value_a: String,
+    pub raw_value_b: String,
+}
+
+/// 两组映射之间的差异
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingDiff {
+    pub only_in_a: Vec<DiffEntry>,
+    pub only_in_b: Vec<DiffEntry>,
+    pub changed: Vec<ChangedMapping>,
+}
+
+impl MappingDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 按文件名对比的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub file_name: String,
+    /// 文件存在情况：both-两侧都有, only_a-仅A有, only_b-仅B有
+    pub presence: String,
+    pub diff: MappingDiff,
+}
+
+/// 对比两组映射（本地栏目ID重复时以第一条为准）
+pub fn diff_mappings(a: &[MappingEntry], b: &[MappingEntry]) -> MappingDiff {
+    diff_entries(&active_entries(a), &active_entries(b))
+}
+
+/// 对比两组已提取的映射（本地栏目ID重复时以第一条为准）
+pub fn diff_entries(a: &[DiffEntry], b: &[DiffEntry]) -> MappingDiff {
+    let map_a = first_by_local_id(a);
+    let map_b = first_by_local_id(b);
+    let mut diff = MappingDiff::default();
+
+    for (local_id, entry_a) in &map_a {
+        match map_b.get(local_id) {
+            None => diff.only_in_a.push((*entry_a).clone()),
+            Some(entry_b) if entry_a.raw_value != entry_b.raw_value => {
+                diff.changed.push(ChangedMapping {
+                    local_id: local_id.to_string(),
+                    gw_id_a: entry_a.gw_id.clone(),
+                    gw_id_b: entry_b.gw_id.clone(),
+                    raw_value_a: entry_a.raw_value.clone(),
+                    raw_value_b: entry_b.raw_value.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for (local_id, entry_b) in &map_b {
+        if !map_a.contains_key(local_id) {
+            diff.only_in_b.push((*entry_b).clone());
+        }
+    }
+    diff
+}
+
+/// 会被设备读取的映射（在 sExtOptions 中且未被注释）
+pub fn active_entries(entries: &[MappingEntry]) -> Vec<DiffEntry> {
+    entries
+        .iter()
+        .filter(|entry| entry.is_active())
+        .map(to_diff_entry)
+        .collect()
+}
+
+/// 按文件名对比两组扫描结果
+pub fn diff_file_sets(a: &[FileMapping], b: &[FileMapping]) -> Vec<FileDiff> {
+    diff_named_sets(&by_file_name(a), &by_file_name(b))
+}
+
+/// 按文件名对比两组「文件名 -> 映射」
+pub fn diff_named_sets(a: &BTreeMap<String, Vec<DiffEntry>>, b: &BTreeMap<String, Vec<DiffEntry>>) -> Vec<FileDiff> {
+    let mut names: Vec<&String> = a.keys().chain(b.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let (presence, diff) = match (a.get(name), b.get(name)) {
+                (Some(entries_a), Some(entries_b)) => ("both", diff_entries(entries_a, entries_b)),
+                (Some(entries_a), None) => ("only_a", diff_entries(entries_a, &[])),
+                (None, Some(entries_b)) => ("only_b", diff_entries(&[], entries_b)),
+                (None, None) => unreachable!(),
+            };
+            FileDiff {
+                file_name: name.clone(),
+                presence: presence.to_string(),
+                diff,
+            }
+        })
+        .collect()
+}
+
+fn first_by_local_id(entries: &[DiffEntry]) -> BTreeMap<&str, &DiffEntry> {
+    let mut map = BTreeMap::new();
+    for entry in entries {
+        map.entry(entry.local_id.as_str()).or_insert(entry);
+    }
+    map
+}
+
+/// 以文件名（不含目录）为键的有效映射
+pub fn by_file_name(files: &[FileMapping]) -> BTreeMap<String, Vec<DiffEntry>> {
+    files
+        .iter()
+        .map(|file| {
+            let name = std::path::Path::new(&file.file_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.file_path.clone());
+            (name, active_entries(&file.mappings))
+        })
+        .collect()
+}
+
+fn to_diff_entry(entry: &MappingEntry) -> DiffEntry {
+    DiffEntry {
+        local_id: entry.local_id.clone(),
+        gw_id: entry.gw_id.clone(),
+        raw_value: entry.raw_value.clone(),
+    }
+}
+
+/// 单行差异操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// LCS 表格的最大单元数，超过时把中间不同的部分整体视为删除+新增
+const MAX_LCS_CELLS: usize = 4_000_000;
+/// 统一格式差异中每个变更块保留的上下文行数
+const CONTEXT_LINES: usize = 3;
+
+/// 生成统一格式（unified diff）的文本差异
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|(op, _, _)| *op == LineOp::Equal) {
+        return String::new();
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_label, new_label);
+
+    // 找出所有变更的位置，并按上下文行数合并成若干变更块
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _, _))| *op != LineOp::Equal)
+        .map(|(idx, _)| idx)
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changed {
+        let start = idx.saturating_sub(CONTEXT_LINES);
+        let end = (idx + CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let slice = &ops[start..end];
+        let old_start = slice.first().map(|(_, o, _)| *o).unwrap_or(0);
+        let new_start = slice.first().map(|(_, _, n)| *n).unwrap_or(0);
+        let old_count = slice.iter().filter(|(op, _, _)| *op != LineOp::Insert).count();
+        let new_count = slice.iter().filter(|(op, _, _)| *op != LineOp::Delete).count();
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk_start(old_start, old_count),
+            old_count,
+            hunk_start(new_start, new_count),
+            new_count
+        ));
+        for (op, old_idx, new_idx) in slice {
+            match op {
+                LineOp::Equal => output.push_str(&format!(" {}\n", old_lines[*old_idx])),
+                LineOp::Delete => output.push_str(&format!("-{}\n", old_lines[*old_idx])),
+                LineOp::Insert => output.push_str(&format!("+{}\n", new_lines[*new_idx])),
+            }
+        }
+    }
+    output
+}
+
+/// 统一格式中的起始行号（从 1 开始，空块使用前一行的行号）
+fn hunk_start(index: usize, count: usize) -> usize {
+    if count == 0 {
+        index
+    } else {
+        index + 1
+    }
+}
+
+/// 逐行对比，返回（操作, 旧行号, 新行号）列表；行号指向操作发生时两侧的当前位置
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<(LineOp, usize, usize)> {
+    // 先去掉相同的首尾部分，缩小 LCS 计算范围
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    for i in 0..prefix {
+        ops.push((LineOp::Equal, i, i));
+    }
+
+    let n = old_mid.len();
+    let m = new_mid.len();
+    if (n + 1) * (m + 1) > MAX_LCS_CELLS {
+        for i in 0..n {
+            ops.push((LineOp::Delete, prefix + i, prefix));
+        }
+        for j in 0..m {
+            ops.push((LineOp::Insert, prefix + n, prefix + j));
+        }
+    } else {
+        // lcs[i][j] 表示 old_mid[i..] 与 new_mid[j..] 的最长公共子序列长度
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((LineOp::Equal, prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                ops.push((LineOp::Delete, prefix + i, prefix + j));
+                i += 1;
+            } else {
+                ops.push((LineOp::Insert, prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    for k in 0..suffix {
+        ops.push((LineOp::Equal, old.len() - suffix + k, new.len() - suffix + k));
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(local_id: &str, gw_id: &str) -> DiffEntry {
+        DiffEntry {
+            local_id: local_id.to_string(),
+            gw_id: Some(gw_id.to_string()),
+            raw_value: format!("intent://?es_tabId={}", gw_id),
+        }
+    }
+
+    #[test]
+    fn diffs_named_sets() {
+        let a = BTreeMap::from([
+            ("theme1.json".to_string(), vec![entry("101", "1001"), entry("102", "1002")]),
+            ("theme2.json".to_string(), vec![entry("101", "1001")]),
+        ]);
+        let b = BTreeMap::from([("theme1.json".to_string(), vec![entry("101", "2001"), entry("103", "1003")])]);
+        let files = diff_named_sets(&a, &b);
+        assert_eq!(files.len(), 2);
+        let diff = &files[0].diff;
+        assert_eq!(files[0].presence, "both");
+        assert_eq!(diff.only_in_a[0].local_id, "102");
+        assert_eq!(diff.only_in_b[0].local_id, "103");
+        assert_eq!(diff.changed[0].gw_id_b.as_deref(), Some("2001"));
+        assert_eq!((files[1].presence.as_str(), files[1].diff.only_in_a.len()), ("only_a", 1));
+    }
+}
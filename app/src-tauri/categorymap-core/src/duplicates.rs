@@ -0,0 +1,392 @@
+//! 国网栏目ID重复（duplicate_gw）的分析和处理建议
+//!
+//! 同一 sExtOptions 段落中同一前缀的多个本地栏目ID指向同一个国网栏目ID时，按（文件, 段落, 前缀, 国网栏目ID）分组，
+//! 给出可直接交给 `batch_delete_mappings` 或 `batch_update_mappings` 执行的处理建议：
+//! 保留其中一条并删除其余条目，或把某个本地栏目ID改指向它在其他文件中使用的国网栏目ID。
+//!
+//! 也可以在整个目录中一次清理重复条目：同一段落中本地栏目ID（或国网栏目ID）相同的条目只保留第一条或最后一条，
+//! 所有文件作为一个整体写入，任一文件失败时不保留部分修改。
+//!
+//! 手工录入不规范造成的疑似重复（suspicious_duplicate）也在这里定义：两个ID只差前导零、两侧空白
+//! 或全角/半角数字时，[`canonical_id`] 得到相同的结果，可交给 `normalize_ids` 统一为规范形式。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use crate::{
+    extract_gw_id, i18n::Locale, remove_matching_mappings, scan_directory, settings::AppSettings,
+    DeleteMappingRequest, EntrySpan, Error, FileMapping, IntentParams, KeepPolicy, MappingUpdate, OperationType,
+    RemovalRequest, RemoveMappingsResult,
+};
+
+/// 一组指向同一国网栏目ID的映射
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGwGroup {
+    pub file_path: String,
+    pub block: Option<usize>,
+    /// 映射的前缀
+    pub prefix: String,
+    pub gw_id: String,
+    pub local_ids: Vec<String>,
+    pub resolutions: Vec<DuplicateResolution>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionKind {
+    /// 保留一条，删除其余条目
+    KeepOne,
+    /// 把本地栏目ID改指向其他国网栏目ID
+    Retarget,
+}
+
+/// 一条处理建议，delete 交给 batch_delete_mappings，updates 交给 batch_update_mappings
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResolution {
+    pub kind: ResolutionKind,
+    pub description: String,
+    /// 是否为推荐方案
+    pub recommended: bool,
+    pub delete: Vec<DeleteMappingRequest>,
+    pub updates: Vec<MappingUpdate>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateAnalysis {
+    pub groups: Vec<DuplicateGwGroup>,
+    /// 涉及的映射条数
+    pub entry_count: usize,
+}
+
+/// 把全角字母、数字、符号和全角空格转为半角
+pub fn half_width(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+/// ID 的规范形式：全角字符转为半角、去掉两侧空白，纯数字ID去掉前导零（全为零时保留一个 0）
+pub fn canonical_id(id: &str) -> String {
+    let converted = half_width(id);
+    let trimmed = converted.trim();
+    if !trimmed.is_empty() && trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        let digits = trimmed.trim_start_matches('0');
+        if digits.is_empty() { "0" } else { digits }.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// 扫描目录并分析国网栏目ID重复的映射
+pub fn analyze(settings: &AppSettings, dir: &Path) -> Result<DuplicateAnalysis, Error> {
+    if !dir.is_dir() {
+        return Err(Error::DirNotFound { path: dir.to_string_lossy().into_owned() });
+    }
+    let files = scan_directory(dir, settings)?;
+    let groups = analyze_files(&files);
+    Ok(DuplicateAnalysis {
+        entry_count: groups.iter().map(|group| group.local_ids.len()).sum(),
+        groups,
+    })
+}
+
+/// 按（文件, 段落, 前缀, 国网栏目ID）分组并生成处理建议
+pub fn analyze_files(files: &[FileMapping]) -> Vec<DuplicateGwGroup> {
+    // 每个映射（前缀, 本地栏目ID）在所有文件中未重复时使用的国网栏目ID及次数，用于建议改指向
+    let mut usual_targets: HashMap<(&str, &str), BTreeMap<&str, usize>> = HashMap::new();
+    for entry in files.iter().flat_map(|file| &file.mappings) {
+        if let (Some(gw_id), "normal") = (entry.gw_id.as_deref(), entry.status.as_str()) {
+            *usual_targets
+                .entry((&entry.prefix, &entry.local_id))
+                .or_default()
+                .entry(gw_id)
+                .or_default() += 1;
+        }
+    }
+
+    let mut groups = Vec::new();
+    for file in files {
+        let mut by_gw: BTreeMap<(Option<usize>, &str, &str), Vec<&crate::MappingEntry>> = BTreeMap::new();
+        for entry in file.mappings.iter().filter(|entry| entry.status == "duplicate_gw") {
+            if let Some(gw_id) = entry.gw_id.as_deref() {
+                by_gw.entry((entry.block_index, &entry.prefix, gw_id)).or_default().push(entry);
+            }
+        }
+        for ((block, prefix, gw_id), entries) in by_gw {
+            let local_ids: Vec<String> = entries.iter().map(|entry| entry.local_id.clone()).collect();
+            // 优先保留本地栏目ID与国网栏目ID相同的条目，其次保留第一条
+            let preferred = entries
+                .iter()
+                .position(|entry| entry.same_id)
+                .unwrap_or(0);
+            let mut resolutions: Vec<DuplicateResolution> = local_ids
+                .iter()
+                .enumerate()
+                .map(|(index, keep)| DuplicateResolution {
+                    kind: ResolutionKind::KeepOne,
+                    description: format!("保留 {}，删除其余 {} 条", keep, local_ids.len() - 1),
+                    recommended: index == preferred,
+                    delete: local_ids
+                        .iter()
+                        .filter(|local_id| *local_id != keep)
+                        .map(|local_id| DeleteMappingRequest {
+                            file_path: file.file_path.clone(),
+                            local_id: local_id.clone(),
+                            block,
+                            prefix: Some(prefix.to_string()),
+                        })
+                        .collect(),
+                    updates: Vec::new(),
+                })
+                .collect();
+            for local_id in &local_ids {
+                let Some(target) = usual_targets.get(&(prefix, local_id.as_str())).and_then(|targets| {
+                    targets
+                        .iter()
+                        .filter(|(target, _)| **target != gw_id)
+                        .max_by_key(|(_, count)| **count)
+                }) else {
+                    continue;
+                };
+                resolutions.push(DuplicateResolution {
+                    kind: ResolutionKind::Retarget,
+                    description: format!(
+                        "将 {} 改为其他文件中使用的国网栏目ID {}（{} 处）",
+                        local_id, target.0, target.1
+                    ),
+                    recommended: false,
+                    delete: Vec::new(),
+                    updates: vec![MappingUpdate {
+                        local_id: local_id.clone(),
+                        new_gw_id: target.0.to_string(),
+                        params: IntentParams::default(),
+                        block,
+                        expected_gw_id: Some(gw_id.to_string()),
+                        prefix: Some(prefix.to_string()),
+                    }],
+                });
+            }
+            groups.push(DuplicateGwGroup {
+                file_path: file.file_path.clone(),
+                block,
+                prefix: prefix.to_string(),
+                gw_id: gw_id.to_string(),
+                local_ids,
+                resolutions,
+            });
+        }
+    }
+    groups
+}
+
+/// 按哪种ID判断重复
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateScope {
+    /// 本地栏目ID重复（duplicate_local）
+    #[default]
+    Local,
+    /// 国网栏目ID重复（duplicate_gw）
+    Gw,
+}
+
+/// 找出同一段落中重复的多余条目，返回它们在 spans 中的序号；每组按 keep 保留一条
+pub fn redundant_entries(raw: &str, spans: &[EntrySpan], scope: DuplicateScope, keep: KeepPolicy) -> Vec<usize> {
+    let mut kept: HashMap<(usize, &str, String), usize> = HashMap::new();
+    let mut keys = Vec::with_capacity(spans.len());
+    for (idx, span) in spans.iter().enumerate() {
+        let key = match scope {
+            DuplicateScope::Local => Some(span.local_id.clone()),
+            DuplicateScope::Gw => extract_gw_id(&raw[span.value_start..span.value_end]),
+        }
+        .map(|key| (span.block, span.prefix.as_str(), key));
+        if let Some(key) = &key {
+            match keep {
+                KeepPolicy::First => {
+                    kept.entry(key.clone()).or_insert(idx);
+                }
+                KeepPolicy::Last => {
+                    kept.insert(key.clone(), idx);
+                }
+            }
+        }
+        keys.push(key);
+    }
+    keys.iter()
+        .enumerate()
+        .filter(|(idx, key)| key.as_ref().is_some_and(|key| kept.get(key) != Some(idx)))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// 清理目录中所有文件的重复条目，dry_run 为 true 时只返回每个文件将删除的条目
+pub fn cleanup(
+    settings: &AppSettings,
+    target_dir: String,
+    scope: Option<DuplicateScope>,
+    keep: Option<KeepPolicy>,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, Error> {
+    if !Path::new(&target_dir).is_dir() {
+        return Err(Error::DirNotFound { path: target_dir });
+    }
+    let scope = scope.unwrap_or_default();
+    let keep = keep.unwrap_or_default();
+    let locale = Locale::of(settings);
+    let scope_label = match scope {
+        DuplicateScope::Local => locale.pick("本地栏目ID", "local ID"),
+        DuplicateScope::Gw => locale.pick("国网栏目ID", "GW ID"),
+    };
+    let summary = match locale {
+        Locale::ZhCn => format!("清理{}重复的映射（{}）", scope_label, keep.label_in(locale)),
+        Locale::EnUs => format!("Clean up mappings with duplicate {} ({})", scope_label, keep.label_in(locale)),
+    };
+    let request = RemovalRequest {
+        operation_type: OperationType::CleanupDuplicates,
+        summary,
+        soft: false,
+        transactional: true,
+        files: None,
+        prefixes: settings.prefixes().into_iter().map(str::to_string).collect(),
+        auto_increment_version,
+        dry_run: dry_run.unwrap_or(false),
+        expected_hashes,
+    };
+    remove_matching_mappings(settings, &target_dir, request, |raw, spans| {
+        redundant_entries(raw, spans, scope, keep)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::TextFormat;
+
+    fn theme(entries: &[(&str, &str)]) -> String {
+        let body: Vec<String> = entries
+            .iter()
+            .map(|(local_id, gw_id)| {
+                format!(
+                    "    \"portal_frag_{}\": \"com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=\"",
+                    local_id, gw_id
+                )
+            })
+            .collect();
+        format!("{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", body.join(",\n"))
+    }
+
+    fn file(path: &str, entries: &[(&str, &str)]) -> FileMapping {
+        crate::file_mapping_from_text(path.to_string(), &theme(entries), TextFormat::default(), &AppSettings::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_redundant_entries_per_scope() {
+        let raw = theme(&[("101", "9001"), ("102", "9001"), ("101", "9003"), ("103", "9004")]);
+        let spans = crate::locate_portal_entries(&raw, "portal_frag_").unwrap();
+        assert_eq!(redundant_entries(&raw, &spans, DuplicateScope::Local, KeepPolicy::First), [2]);
+        assert_eq!(redundant_entries(&raw, &spans, DuplicateScope::Local, KeepPolicy::Last), [0]);
+        assert_eq!(redundant_entries(&raw, &spans, DuplicateScope::Gw, KeepPolicy::First), [1]);
+        assert_eq!(redundant_entries(&raw, &spans, DuplicateScope::Gw, KeepPolicy::Last), [0]);
+    }
+
+    #[test]
+    fn dedupe_keeps_one_entry_per_block() {
+        let entry = |local_id: &str, gw_id: &str| {
+            format!(
+                "\"portal_frag_{}\": \"com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=\"",
+                local_id, gw_id
+            )
+        };
+        let raw = format!(
+            "{{\n  \"version\": 3,\n  \"themes\": [\n    {{ \"sExtOptions\": {{\n      {},\n      {}\n    }} }},\n    {{ \"sExtOptions\": {{\n      {}\n    }} }}\n  ]\n}}\n",
+            entry("101", "9001"),
+            entry("101", "9002"),
+            entry("101", "9003")
+        );
+        let dir = std::env::temp_dir().join(format!("catmap-dedupe-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("theme.json");
+        std::fs::write(&path, &raw).unwrap();
+
+        let result = crate::dedupe_mappings(
+            &AppSettings::default(),
+            path.to_string_lossy().into_owned(),
+            Some(KeepPolicy::First),
+            false,
+            None,
+        )
+        .unwrap();
+        let removed: Vec<(&str, Option<&str>)> = result
+            .removed
+            .iter()
+            .map(|item| (item.local_id.as_str(), item.gw_id.as_deref()))
+            .collect();
+        assert_eq!(removed, [("101", Some("9002"))]);
+
+        let updated = std::fs::read_to_string(&path).unwrap();
+        let kept: Vec<(usize, Option<String>)> = crate::locate_portal_entries(&updated, "portal_frag_")
+            .unwrap()
+            .into_iter()
+            .map(|span| (span.block, extract_gw_id(&updated[span.value_start..span.value_end])))
+            .collect();
+        assert_eq!(kept, [(0, Some("9001".to_string())), (1, Some("9003".to_string()))]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn canonicalizes_sloppy_ids() {
+        assert_eq!(canonical_id("00101"), "101");
+        assert_eq!(canonical_id(" １０１\u{3000}"), "101");
+        assert_eq!(canonical_id("000"), "0");
+        assert_eq!(canonical_id("0a01"), "0a01");
+    }
+
+    #[test]
+    fn groups_duplicates_and_suggests_resolutions() {
+        let files = [
+            file("theme1.json", &[("101", "9001"), ("102", "9001"), ("9001", "9001")]),
+            file("theme2.json", &[("102", "9002"), ("9001", "9001")]),
+        ];
+        let groups = analyze_files(&files);
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!((group.file_path.as_str(), group.gw_id.as_str()), ("theme1.json", "9001"));
+        assert_eq!(group.local_ids, ["101", "102", "9001"]);
+
+        let keep: Vec<_> = group
+            .resolutions
+            .iter()
+            .filter(|resolution| resolution.kind == ResolutionKind::KeepOne)
+            .collect();
+        assert_eq!(keep.len(), 3);
+        // 本地栏目ID与国网栏目ID相同的条目为推荐保留项
+        assert!(keep[2].recommended && !keep[0].recommended);
+        let deleted: Vec<&str> = keep[2].delete.iter().map(|request| request.local_id.as_str()).collect();
+        assert_eq!(deleted, ["101", "102"]);
+
+        let retarget: Vec<_> = group
+            .resolutions
+            .iter()
+            .filter(|resolution| resolution.kind == ResolutionKind::Retarget)
+            .collect();
+        assert_eq!(retarget.len(), 1);
+        assert_eq!(
+            (retarget[0].updates[0].local_id.as_str(), retarget[0].updates[0].new_gw_id.as_str()),
+            ("102", "9002")
+        );
+    }
+}
@@ -0,0 +1,2338 @@
+//! sExtOptions 段落的解析和文本级修改
+//!
+//! 所有修改都直接在原文上进行，保留注释、缩进和其他字段，不会重新序列化整个文件；
+//! 增删条目基于 [`crate::cst`] 的对象语法树。
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    cst::ObjectCst, duplicates::canonical_id, settings::AppSettings, ImportFileStats, MappingEntry, MappingInput,
+    MappingKey, SortOrder, SyncFilePlan,
+};
+
+/// 解析或修改 sExtOptions 时的错误，序列化为 `{"code": ..., "params": {...}}`（见 [`crate::Error`]）
+#[derive(Debug, Error, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "code", content = "params", rename_all = "snake_case")]
+pub enum EditError {
+    #[error("未找到 sExtOptions 段落")]
+    ExtOptionsNotFound,
+    #[error("sExtOptions 定义格式不正确")]
+    MalformedExtOptions,
+    #[error("sExtOptions 不是对象类型")]
+    ExtOptionsNotObject,
+    #[error("未能定位 sExtOptions 的结束位置")]
+    UnterminatedExtOptions,
+    #[error("未找到本地栏目ID: {local_id}")]
+    #[serde(rename_all = "camelCase")]
+    MappingNotFound { local_id: String },
+    #[error("未找到映射值模板: {name}")]
+    UnknownTemplate { name: String },
+    #[error("未找到序号为 {index} 的 sExtOptions 段落")]
+    BlockNotFound { index: usize },
+    #[error("映射值格式不正确: {reason}")]
+    InvalidRawValue { reason: String },
+}
+
+/// 尚未改用 [`crate::Error`] 的内部函数仍使用字符串错误
+impl From<EditError> for String {
+    fn from(err: EditError) -> Self {
+        err.to_string()
+    }
+}
+
+/// JSON5 语法错误
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSyntaxError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl std::fmt::Display for JsonSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "第 {} 行第 {} 列: {}", line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// 使用允许注释的 JSON5 解析器校验内容，返回带行列号的语法错误
+pub fn validate_json_content(content: &str) -> Result<(), JsonSyntaxError> {
+    match json5::from_str::<serde_json::Value>(content) {
+        Ok(_) => Ok(()),
+        Err(json5::Error::Message { msg, location }) => {
+            // pest 的错误信息包含多行源码示意，只保留最后的说明
+            let message = msg
+                .lines()
+                .rev()
+                .find_map(|line| line.trim_start().strip_prefix("= "))
+                .unwrap_or(&msg)
+                .to_string();
+            Err(JsonSyntaxError {
+                message,
+                line: location.as_ref().map(|l| l.line),
+                column: location.as_ref().map(|l| l.column),
+            })
+        }
+    }
+}
+
+/// 提取 JSON 文件中的版本号
+pub fn extract_version(content: &str) -> Option<u32> {
+    let lines: Vec<&str> = content.lines().collect();
+    
+    for line in lines {
+        let trimmed = line.trim();
+        
+        // 查找包含 "version": 的行
+        if trimmed.starts_with("\"version\"") && trimmed.contains(':') {
+            // 提取版本号
+            if let Some(colon_pos) = trimmed.find(':') {
+                let value_part = &trimmed[colon_pos + 1..];
+                let value_part = value_part.trim().trim_end_matches(',');
+                
+                if let Ok(version) = value_part.parse::<u32>() {
+                    return Some(version);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// 递增 JSON 文件中的版本号
+pub fn increment_version(content: &str) -> Result<String, EditError> {
+    // 查找 "version": 数字 的模式
+    let lines: Vec<&str> = content.lines().collect();
+    let mut updated_lines = Vec::new();
+    let mut version_updated = false;
+    
+    for line in lines {
+        let trimmed = line.trim();
+        
+        // 查找包含 "version": 的行
+        if trimmed.starts_with("\"version\"") && trimmed.contains(':') {
+            // 提取版本号
+            if let Some(colon_pos) = trimmed.find(':') {
+                let value_part = &trimmed[colon_pos + 1..];
+                let value_part = value_part.trim().trim_end_matches(',');
+                
+                if let Ok(current_version) = value_part.parse::<u32>() {
+                    let new_version = current_version + 1;
+                    
+                    // 保持原有的缩进和格式
+                    let indent = line.len() - line.trim_start().len();
+                    let indent_str = " ".repeat(indent);
+                    let comma = if trimmed.ends_with(',') { "," } else { "" };
+                    
+                    let new_line = format!("{}\"version\": {}{}", indent_str, new_version, comma);
+                    updated_lines.push(new_line);
+                    version_updated = true;
+                } else {
+                    updated_lines.push(line.to_string());
+                }
+            } else {
+                updated_lines.push(line.to_string());
+            }
+        } else {
+            updated_lines.push(line.to_string());
+        }
+    }
+    
+    if version_updated {
+        Ok(updated_lines.join("\n"))
+    } else {
+        // 如果没有找到版本号，返回原内容
+        Ok(content.to_string())
+    }
+}
+
+/// 解析 sExtOptions 段落中 key 以任一前缀开头的映射，多个前缀都匹配时归入最长的前缀
+pub fn parse_mappings(raw: &str, prefixes: &[&str]) -> Result<Vec<MappingEntry>, EditError> {
+    // 直接从文本中查找所有 portal_frag_* 条目，而不是从JSON对象中获取
+    // 因为JSON解析时重复的key会被覆盖，无法检测到重复
+    let mut result = Vec::new();
+    parse_portal_frag_from_text(raw, prefixes, &mut result)?;
+    
+    // 检查重复：本地ID必须唯一，国网ID可以重复但需要提示（按 sExtOptions 段落和前缀分别统计）
+    let mut local_id_counts: std::collections::HashMap<(Option<usize>, String, String), usize> = std::collections::HashMap::new();
+    let mut gw_id_counts: std::collections::HashMap<(Option<usize>, String, String), usize> = std::collections::HashMap::new();
+    
+    // 统计出现次数
+    for entry in &result {
+        *local_id_counts.entry((entry.block_index, entry.prefix.clone(), entry.local_id.clone())).or_insert(0) += 1;
+        if let Some(ref gw_id) = entry.gw_id {
+            *gw_id_counts.entry((entry.block_index, entry.prefix.clone(), gw_id.clone())).or_insert(0) += 1;
+        }
+    }
+    
+    // 规范形式（见 duplicates::canonical_id）的出现次数，多于原值的出现次数时为疑似重复
+    let mut canonical_local_counts: std::collections::HashMap<(Option<usize>, String, String), usize> = std::collections::HashMap::new();
+    let mut canonical_gw_counts: std::collections::HashMap<(Option<usize>, String, String), usize> = std::collections::HashMap::new();
+    for entry in &result {
+        *canonical_local_counts
+            .entry((entry.block_index, entry.prefix.clone(), canonical_id(&entry.local_id)))
+            .or_insert(0) += 1;
+        if let Some(ref gw_id) = entry.gw_id {
+            *canonical_gw_counts.entry((entry.block_index, entry.prefix.clone(), canonical_id(gw_id))).or_insert(0) += 1;
+        }
+    }
+
+    // 标记状态
+    for entry in &mut result {
+        let local_count = local_id_counts
+            .get(&(entry.block_index, entry.prefix.clone(), entry.local_id.clone()))
+            .copied()
+            .unwrap_or(0);
+        let gw_count = entry.gw_id.as_ref()
+            .and_then(|gw| gw_id_counts.get(&(entry.block_index, entry.prefix.clone(), gw.clone())).copied())
+            .unwrap_or(0);
+        
+        let canonical_local_count = canonical_local_counts
+            .get(&(entry.block_index, entry.prefix.clone(), canonical_id(&entry.local_id)))
+            .copied()
+            .unwrap_or(0);
+        let canonical_gw_count = entry.gw_id.as_ref()
+            .and_then(|gw| canonical_gw_counts.get(&(entry.block_index, entry.prefix.clone(), canonical_id(gw))).copied())
+            .unwrap_or(0);
+        
+        if local_count > 1 {
+            entry.status = "duplicate_local".to_string();
+        } else if gw_count > 1 {
+            entry.status = "duplicate_gw".to_string();
+        } else if canonical_local_count > local_count || canonical_gw_count > gw_count {
+            entry.status = "suspicious_duplicate".to_string();
+        } else {
+            entry.status = "normal".to_string();
+        }
+    }
+    
+    Ok(result)
+}
+
+/// 从原始文本中直接解析所有 portal_frag_* 条目，支持检测重复的key
+fn parse_portal_frag_from_text(raw: &str, prefixes: &[&str], acc: &mut Vec<MappingEntry>) -> Result<(), EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    // 条目按位置排序，行号从上一个条目处接着数
+    let (mut line, mut counted) = (1, 0);
+    for span in locate_entries(raw, prefixes)? {
+        line += raw[counted..span.key_start].matches('\n').count();
+        counted = span.key_start;
+        let raw_value = &raw[span.value_start..span.value_end];
+        let gw_id = extract_gw_id(raw_value);
+        let same_id = gw_id.as_ref().map(|gw| gw == &span.local_id).unwrap_or(false);
+
+        acc.push(MappingEntry {
+            local_id: span.local_id,
+            prefix: span.prefix,
+            gw_id,
+            params: IntentParams::from_value(raw_value),
+            es_params: extract_es_params(raw_value),
+            fragment_class: fragment_class(raw_value),
+            raw_value: raw_value.to_string(),
+            same_id,
+            non_standard: crate::intent::is_non_standard(raw_value),
+            status: "normal".to_string(),
+            gw_name: None,
+            local_name: None,
+            in_catalog: None,
+            block_index: Some(span.block),
+            block_path: blocks[span.block].path.clone(),
+            line,
+            offset: span.key_start,
+        });
+    }
+    Ok(())
+}
+
+/// 字节位置所在的行号（从 1 开始）
+fn line_at(raw: &str, offset: usize) -> usize {
+    raw[..offset].matches('\n').count() + 1
+}
+
+/// 找出 sExtOptions 段落以外的 portal_frag_* 条目（顶层或其他对象中，设备不会读取），状态为 misplaced
+///
+/// 段落内的内容由 [`parse_mappings`] 负责，这里整体跳过。文件结构无法解析时返回已找到的部分。
+pub fn find_misplaced_mappings(raw: &str, prefixes: &[&str]) -> Vec<MappingEntry> {
+    let block_starts: Vec<usize> = find_ext_options_blocks(raw)
+        .map(|blocks| blocks.iter().map(|block| block.start).collect())
+        .unwrap_or_default();
+    let mut result = Vec::new();
+    collect_misplaced(raw, crate::cst::skip_trivia(raw, 0), "$", prefixes, &block_starts, &mut result);
+    result
+}
+
+fn collect_misplaced(
+    raw: &str,
+    start: usize,
+    path: &str,
+    prefixes: &[&str],
+    block_starts: &[usize],
+    acc: &mut Vec<MappingEntry>,
+) {
+    match raw.as_bytes().get(start) {
+        Some(b'{') => {
+            if block_starts.contains(&start) {
+                return;
+            }
+            let Ok(object) = ObjectCst::parse(raw, start) else {
+                return;
+            };
+            for member in &object.members {
+                if let Some(prefix) = matching_prefix(&member.key, prefixes) {
+                    let local_id = &member.key[prefix.len()..];
+                    let value = &raw[member.value_start..member.value_end];
+                    let raw_value = value
+                        .strip_prefix(['"', '\''])
+                        .and_then(|v| v.strip_suffix(['"', '\'']))
+                        .unwrap_or(value);
+                    let gw_id = extract_gw_id(raw_value);
+                    acc.push(MappingEntry {
+                        local_id: local_id.to_string(),
+                        prefix: prefix.to_string(),
+                        same_id: gw_id.as_deref() == Some(local_id),
+                        non_standard: crate::intent::is_non_standard(raw_value),
+                        gw_id,
+                        params: IntentParams::from_value(raw_value),
+                        es_params: extract_es_params(raw_value),
+                        fragment_class: fragment_class(raw_value),
+                        raw_value: raw_value.to_string(),
+                        status: "misplaced".to_string(),
+                        gw_name: None,
+                        local_name: None,
+                        in_catalog: None,
+                        block_index: None,
+                        block_path: path.to_string(),
+                        line: line_at(raw, member.key_start),
+                        offset: member.key_start,
+                    });
+                }
+                let child = format!("{}.{}", path, member.key);
+                collect_misplaced(raw, member.value_start, &child, prefixes, block_starts, acc);
+            }
+        }
+        Some(b'[') => {
+            let mut i = crate::cst::skip_trivia(raw, start + 1);
+            let mut index = 0;
+            while raw.as_bytes().get(i).is_some_and(|ch| *ch != b']') {
+                collect_misplaced(raw, i, &format!("{}[{}]", path, index), prefixes, block_starts, acc);
+                let Ok(end) = crate::cst::skip_value(raw, i) else {
+                    return;
+                };
+                i = crate::cst::skip_trivia(raw, end);
+                if raw.as_bytes().get(i) != Some(&b',') {
+                    break;
+                }
+                i = crate::cst::skip_trivia(raw, i + 1);
+                index += 1;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// sExtOptions 段落中被整行注释掉的 portal_frag_ 条目，如 `// "portal_frag_101": "..."`
+#[derive(Debug, Clone)]
+pub struct CommentedSpan {
+    pub local_id: String,
+    /// 条目的映射前缀
+    pub prefix: String,
+    /// 所在 sExtOptions 段落的序号
+    pub block: usize,
+    /// 注释行（含缩进和换行符）的起止位置
+    pub line_start: usize,
+    pub line_end: usize,
+    /// 去掉注释符号和末尾逗号后的条目文本 `"key": "value"`
+    pub entry_text: String,
+    /// 值内容（不含引号）
+    pub raw_value: String,
+}
+
+/// 定位所有 sExtOptions 段落中被行注释掉的 portal_frag_* 条目
+///
+/// 只识别整行都是注释、且注释内容恰好是一个条目（可带末尾逗号）的行；key 匹配多个前缀时归入最长的前缀。
+pub fn locate_commented_entries(raw: &str, prefixes: &[&str]) -> Result<Vec<CommentedSpan>, EditError> {
+    let mut spans = Vec::new();
+    for block in find_ext_options_blocks(raw)? {
+        // 从左花括号之后的第一个整行开始
+        let Some(mut line_start) = raw[block.start..block.end].find('\n').map(|pos| block.start + pos + 1) else {
+            continue;
+        };
+        while line_start < block.end {
+            let line_end = raw[line_start..].find('\n').map(|pos| line_start + pos + 1).unwrap_or(raw.len());
+            if line_end > block.end {
+                break;
+            }
+            if let Some(body) = raw[line_start..line_end].trim().strip_prefix("//") {
+                if let Some((span, entry_text, raw_value)) = parse_commented_entry(body.trim(), prefixes) {
+                    spans.push(CommentedSpan {
+                        local_id: span.local_id,
+                        prefix: span.prefix,
+                        block: block.index,
+                        line_start,
+                        line_end,
+                        entry_text,
+                        raw_value,
+                    });
+                }
+            }
+            line_start = line_end;
+        }
+    }
+    Ok(spans)
+}
+
+/// 解析注释内容，返回（条目在注释内容中的位置, 条目文本, 值内容）
+fn parse_commented_entry(body: &str, prefixes: &[&str]) -> Option<(EntrySpan, String, String)> {
+    if !body.starts_with('"') {
+        return None;
+    }
+    let key_end = find_string_end(body, 1, body.as_bytes())?;
+    let prefix = matching_prefix(body.get(1..key_end)?, prefixes)?;
+    let span = parse_portal_entry_at(body, 0, body.as_bytes(), prefix)?;
+    // 条目之后只允许逗号和行尾注释
+    let rest = body[span.value_end + 1..].trim_start().trim_start_matches(',').trim_start();
+    if !(rest.is_empty() || rest.starts_with("//")) {
+        return None;
+    }
+    let entry_text = body[..=span.value_end].to_string();
+    let raw_value = body[span.value_start..span.value_end].to_string();
+    Some((span, entry_text, raw_value))
+}
+
+/// 找出所有被注释掉的映射，状态为 commented；文件结构无法解析时返回空列表
+pub fn find_commented_mappings(raw: &str, prefixes: &[&str]) -> Vec<MappingEntry> {
+    let Ok(blocks) = find_ext_options_blocks(raw) else {
+        return Vec::new();
+    };
+    locate_commented_entries(raw, prefixes)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|span| {
+            let gw_id = extract_gw_id(&span.raw_value);
+            // 注释内容以条目的 key 开头，行中第一个引号即 key 的开始引号
+            let offset = span.line_start + raw[span.line_start..span.line_end].find('"').unwrap_or(0);
+            MappingEntry {
+                same_id: gw_id.as_deref() == Some(span.local_id.as_str()),
+                non_standard: crate::intent::is_non_standard(&span.raw_value),
+                prefix: span.prefix,
+                gw_id,
+                params: IntentParams::from_value(&span.raw_value),
+                es_params: extract_es_params(&span.raw_value),
+                fragment_class: fragment_class(&span.raw_value),
+                status: "commented".to_string(),
+                gw_name: None,
+                local_name: None,
+                in_catalog: None,
+                block_index: Some(span.block),
+                block_path: blocks[span.block].path.clone(),
+                line: line_at(raw, offset),
+                offset,
+                local_id: span.local_id,
+                raw_value: span.raw_value,
+            }
+        })
+        .collect()
+}
+
+/// 删除指定的注释行
+pub fn remove_commented_spans(raw: &str, spans: &[&CommentedSpan]) -> String {
+    let mut sorted: Vec<&&CommentedSpan> = spans.iter().collect();
+    sorted.sort_by_key(|span| std::cmp::Reverse(span.line_start));
+    let mut content = raw.to_string();
+    for span in sorted {
+        content.replace_range(span.line_start..span.line_end, "");
+    }
+    content
+}
+
+/// 取消注释：删除注释行，并把条目追加到原所在 sExtOptions 段落的末尾
+pub fn uncomment_spans(raw: &str, spans: &[&CommentedSpan]) -> Result<String, EditError> {
+    let mut content = remove_commented_spans(raw, spans);
+    // 注释行都在段落内部，删除后各段落的起始位置只受前面段落的影响，重新定位即可
+    for block in find_ext_options_blocks(&content)?.iter().rev() {
+        let lines: Vec<String> = spans
+            .iter()
+            .filter(|span| span.block == block.index)
+            .map(|span| span.entry_text.clone())
+            .collect();
+        if !lines.is_empty() {
+            content = insert_lines_into_block(&content, block, &lines)?;
+        }
+    }
+    Ok(content)
+}
+
+/// portal_frag_ 条目在原文中的位置
+#[derive(Debug, Clone)]
+pub struct EntrySpan {
+    pub local_id: String,
+    /// 条目的映射前缀
+    pub prefix: String,
+    /// 所在 sExtOptions 段落的序号
+    pub block: usize,
+    /// key 开始引号的位置
+    pub key_start: usize,
+    /// 值内容（不含引号）的起止位置
+    pub value_start: usize,
+    pub value_end: usize,
+}
+
+/// 定位所有 sExtOptions 段落中 key 以任一前缀开头的条目，按位置排序；多个前缀都匹配时归入最长的前缀
+pub fn locate_entries(raw: &str, prefixes: &[&str]) -> Result<Vec<EntrySpan>, EditError> {
+    let mut spans = Vec::new();
+    for prefix in prefixes {
+        spans.extend(locate_portal_entries(raw, prefix)?);
+    }
+    spans.sort_by_key(|span| (span.key_start, std::cmp::Reverse(span.prefix.len())));
+    spans.dedup_by_key(|span| span.key_start);
+    Ok(spans)
+}
+
+/// key 匹配的最长前缀，没有匹配的前缀时返回 None
+pub fn matching_prefix<'a>(key: &str, prefixes: &[&'a str]) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .filter(|prefix| key.starts_with(**prefix))
+        .max_by_key(|prefix| prefix.len())
+        .copied()
+}
+
+/// 定位所有 sExtOptions 段落中 portal_frag_* 条目的位置（跳过注释）
+pub fn locate_portal_entries(raw: &str, portal_prefix: &str) -> Result<Vec<EntrySpan>, EditError> {
+    let mut spans = Vec::new();
+    for block in find_ext_options_blocks(raw)? {
+        locate_entries_in_block(raw, &block, portal_prefix, &mut spans);
+    }
+    Ok(spans)
+}
+
+fn locate_entries_in_block(raw: &str, block: &ExtOptionsBlock, portal_prefix: &str, spans: &mut Vec<EntrySpan>) {
+    let block_start = block.start;
+    let block_content = &raw[block.start..=block.end];
+    
+    // 使用逐字符解析，查找所有 "portal_frag_xxx":"value" 的模式，同时跳过注释
+    let prefix = format!("\"{portal_prefix}");
+    let bytes = block_content.as_bytes();
+    let mut i = 0;
+    let mut in_string = false;
+    let mut escape = false;
+    let mut in_line_comment = false;
+    let mut in_block_comment = false;
+    
+    while i < bytes.len() {
+        let ch = bytes[i];
+        
+        // 处理注释
+        if in_line_comment {
+            if ch == b'\n' {
+                in_line_comment = false;
+            }
+            i += 1;
+            continue;
+        }
+        
+        if in_block_comment {
+            if ch == b'*' && i + 1 < bytes.len() && bytes[i + 1] == b'/' {
+                in_block_comment = false;
+                i += 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        
+        // 处理字符串
+        if in_string {
+            if escape {
+                escape = false;
+            } else if ch == b'\\' {
+                escape = true;
+            } else if ch == b'"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        
+        // 检查注释开始
+        if ch == b'/' && i + 1 < bytes.len() {
+            if bytes[i + 1] == b'/' {
+                in_line_comment = true;
+                i += 2;
+                continue;
+            }
+            if bytes[i + 1] == b'*' {
+                in_block_comment = true;
+                i += 2;
+                continue;
+            }
+        }
+        
+        // 检查字符串开始
+        if ch == b'"' {
+            in_string = true;
+            // 检查是否是 portal_frag_ 开头的key
+            if i + prefix.len() <= bytes.len() {
+                // 使用 get() 方法安全地获取字符串切片，避免字符边界问题
+                if let Some(candidate) = block_content.get(i..i + prefix.len()) {
+                    if candidate == prefix {
+                        // 找到了一个可能的 portal_frag_ 条目
+                        if let Some(mut span) = parse_portal_entry_at(block_content, i, bytes, portal_prefix) {
+                            span.block = block.index;
+                            span.key_start += block_start;
+                            span.value_start += block_start;
+                            span.value_end += block_start;
+                            spans.push(span);
+                        }
+                    }
+                }
+            }
+        }
+        
+        i += 1;
+    }
+}
+
+/// 在指定位置解析一个 portal_frag_ 条目
+fn parse_portal_entry_at(
+    content: &str,
+    start: usize,
+    bytes: &[u8],
+    portal_prefix: &str,
+) -> Option<EntrySpan> {
+    // key 的开始引号在 start，跳过它
+    let key_start = start + 1;
+    let key_end = find_string_end(content, key_start, bytes)?;
+    
+    // 使用 get() 方法安全地获取字符串切片
+    let full_key = content.get(key_start..key_end)?;
+    
+    if !full_key.starts_with(portal_prefix) {
+        return None;
+    }
+    
+    // 跳过冒号和空白
+    let mut value_start = key_end + 1;
+    while value_start < bytes.len()
+        && (bytes[value_start] == b':'
+            || bytes[value_start].is_ascii_whitespace())
+    {
+        value_start += 1;
+    }
+    
+    // 查找值的开始引号
+    if value_start >= bytes.len() || bytes[value_start] != b'"' {
+        return None;
+    }
+    
+    value_start += 1; // 跳过引号
+    let value_end = find_string_end(content, value_start, bytes)?;
+    
+    // 确认值的范围落在字符边界上
+    content.get(value_start..value_end)?;
+    
+    Some(EntrySpan {
+        local_id: full_key[portal_prefix.len()..].to_string(),
+        prefix: portal_prefix.to_string(),
+        block: 0,
+        key_start: start,
+        value_start,
+        value_end,
+    })
+}
+
+/// 查找字符串的结束位置（考虑转义）
+fn find_string_end(_content: &str, start: usize, bytes: &[u8]) -> Option<usize> {
+    let mut i = start;
+    let mut escaped = false;
+    
+    while i < bytes.len() {
+        let ch = bytes[i];
+        if escaped {
+            escaped = false;
+        } else if ch == b'\\' {
+            escaped = true;
+        } else if ch == b'"' {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 映射值中 `|` 之前的栏目类名（如 com.ipanel.join.gw_ui_sdk.GwPortalFragment），没有类名时返回 None
+pub fn fragment_class(raw_value: &str) -> Option<String> {
+    let (class, _) = raw_value.split_once('|')?;
+    let class = class.trim();
+    if class.is_empty() || class.contains("://") {
+        None
+    } else {
+        Some(class.to_string())
+    }
+}
+
+pub fn extract_gw_id(raw_value: &str) -> Option<String> {
+    extract_intent_param(raw_value, "es_tabId")
+}
+
+/// 映射值中 intent 携带的附加参数（es_title、es_focusStartColor、es_focusEndColor、es_focusImg）
+///
+/// 解析时参数缺失或为空都记为 None；写入时只改动取值为 Some 的参数。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntentParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_start_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_end_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_img: Option<String>,
+}
+
+impl IntentParams {
+    /// 从映射值中解析附加参数
+    pub fn from_value(raw_value: &str) -> Self {
+        Self {
+            title: extract_intent_param(raw_value, "es_title"),
+            focus_start_color: extract_intent_param(raw_value, "es_focusStartColor"),
+            focus_end_color: extract_intent_param(raw_value, "es_focusEndColor"),
+            focus_img: extract_intent_param(raw_value, "es_focusImg"),
+        }
+    }
+
+    /// 是否没有设置任何参数
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.is_none())
+    }
+
+    /// 把取值为 Some 的参数写入映射值，值中没有该参数时追加到末尾
+    pub fn apply_to(&self, raw_value: &str) -> String {
+        let mut value = raw_value.to_string();
+        for (key, param) in self.fields() {
+            let Some(param) = param else { continue };
+            value = replace_intent_param(&value, key, param).unwrap_or_else(|| format!("{}&{}={}", value, key, param));
+        }
+        value
+    }
+
+    /// 映射值中的参数是否已经与这里设置的参数一致
+    pub fn matches(&self, raw_value: &str) -> bool {
+        self.fields().iter().all(|(key, param)| match param {
+            Some(param) => extract_intent_param(raw_value, key).unwrap_or_default() == param.trim(),
+            None => true,
+        })
+    }
+
+    fn fields(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("es_title", self.title.as_deref()),
+            ("es_focusStartColor", self.focus_start_color.as_deref()),
+            ("es_focusEndColor", self.focus_end_color.as_deref()),
+            ("es_focusImg", self.focus_img.as_deref()),
+        ]
+    }
+}
+
+/// 映射值中 es_tabId 以外所有非空的 es_* 参数，同名参数取第一个
+pub fn extract_es_params(raw_value: &str) -> std::collections::BTreeMap<String, String> {
+    let mut params = std::collections::BTreeMap::new();
+    let Some((_, query)) = raw_value.split_once('?') else {
+        return params;
+    };
+    let query = query.split(['|', '"']).next().unwrap_or_default();
+    for (key, value) in query.split('&').filter_map(|param| param.split_once('=')) {
+        let (key, value) = (key.trim(), value.trim());
+        if key.starts_with("es_") && key != "es_tabId" && !value.is_empty() {
+            params.entry(key.to_string()).or_insert_with(|| value.to_string());
+        }
+    }
+    params
+}
+
+/// 读取映射值中 intent 参数 key 的值，参数不存在或为空时返回 None
+pub fn extract_intent_param(raw_value: &str, key: &str) -> Option<String> {
+    let (start, end) = intent_param_range(raw_value, key)?;
+    let value = raw_value[start..end].trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// 替换映射值中 intent 参数 key 的值，值中没有该参数时返回 None
+pub fn replace_intent_param(raw_value: &str, key: &str, new_value: &str) -> Option<String> {
+    let (start, end) = intent_param_range(raw_value, key)?;
+    Some(format!("{}{}{}", &raw_value[..start], new_value, &raw_value[end..]))
+}
+
+/// intent 参数值在映射值中的范围，参数名需位于 `?` 或 `&` 之后
+fn intent_param_range(raw_value: &str, key: &str) -> Option<(usize, usize)> {
+    let marker = format!("{}=", key);
+    let mut search_from = 0;
+    while let Some(pos) = raw_value[search_from..].find(&marker) {
+        let key_start = search_from + pos;
+        search_from = key_start + marker.len();
+        if key_start > 0 && !matches!(raw_value.as_bytes()[key_start - 1], b'?' | b'&') {
+            continue;
+        }
+        let remainder = &raw_value[search_from..];
+        let end = remainder.find(['&', '|', '"']).unwrap_or(remainder.len());
+        return Some((search_from, search_from + end));
+    }
+    None
+}
+
+/// 追加映射条目，条目指定了段落序号时只写入该段落，否则写入文件中的每个 sExtOptions 段落
+pub fn insert_entries(raw: &str, entries: &[MappingInput], settings: &AppSettings) -> Result<String, EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    if let Some(missing) = entries.iter().filter_map(|entry| entry.block).find(|block| *block >= blocks.len()) {
+        return Err(EditError::BlockNotFound { index: missing });
+    }
+    let mut updated = raw.to_string();
+    // 从后往前处理各段落，前面段落的位置不受影响
+    for block in blocks.iter().rev() {
+        let lines = entries
+            .iter()
+            .filter(|entry| entry.block.is_none() || entry.block == Some(block.index))
+            .map(|entry| format_entry(entry, settings))
+            .collect::<Result<Vec<String>, EditError>>()?;
+        if !lines.is_empty() {
+            updated = insert_lines_into_block(&updated, block, &lines)?;
+        }
+    }
+    Ok(updated)
+}
+
+/// 在每个 sExtOptions 段落末尾追加已格式化好的条目（`"key":"value"`）
+pub fn insert_entry_lines(raw: &str, lines: &[String]) -> Result<String, EditError> {
+    let mut updated = raw.to_string();
+    for block in find_ext_options_blocks(raw)?.iter().rev() {
+        updated = insert_lines_into_block(&updated, block, lines)?;
+    }
+    Ok(updated)
+}
+
+/// 把已格式化好的条目追加到指定序号的 sExtOptions 段落，lines 为（段落序号, `"key":"value"`）
+pub fn insert_lines_into_blocks(raw: &str, lines: &[(usize, String)]) -> Result<String, EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    if let Some((missing, _)) = lines.iter().find(|(block, _)| *block >= blocks.len()) {
+        return Err(EditError::BlockNotFound { index: *missing });
+    }
+    let mut updated = raw.to_string();
+    for block in blocks.iter().rev() {
+        let block_lines: Vec<String> = lines
+            .iter()
+            .filter(|(index, _)| *index == block.index)
+            .map(|(_, line)| line.clone())
+            .collect();
+        if !block_lines.is_empty() {
+            updated = insert_lines_into_block(&updated, block, &block_lines)?;
+        }
+    }
+    Ok(updated)
+}
+
+fn insert_lines_into_block(raw: &str, block: &ExtOptionsBlock, lines: &[String]) -> Result<String, EditError> {
+    ObjectCst::parse(raw, block.start)?.append_members(raw, lines)
+}
+
+fn format_entry(entry: &MappingInput, settings: &AppSettings) -> Result<String, EditError> {
+    let value = entry.params.apply_to(&template_value(entry, settings)?);
+    let prefix = entry.prefix.as_deref().unwrap_or(&settings.portal_prefix);
+    Ok(format!("\"{prefix}{key}\":\"{value}\"", key = entry.local_id))
+}
+
+/// 用条目选择的模板生成映射值
+fn template_value(entry: &MappingInput, settings: &AppSettings) -> Result<String, EditError> {
+    let template = settings
+        .template(entry.template.as_deref())
+        .ok_or_else(|| EditError::UnknownTemplate { name: entry.template.clone().unwrap_or_default() })?;
+    Ok(template.replace("{id}", &entry.gw_id))
+}
+
+/// 模板试渲染的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplatePreview {
+    /// 生成的映射值
+    pub value: String,
+    /// 写入 sExtOptions 的条目文本
+    pub entry_line: String,
+    /// 解析器读回的条目，无法读回时为 None
+    pub parsed: Option<MappingEntry>,
+    /// 读回结果与示例条目不一致等问题，为空表示模板可用
+    pub problems: Vec<String>,
+}
+
+/// 用尚未保存的模板为示例条目生成映射值，并按扫描时的解析逻辑读回，检查模板能否被正确识别
+pub fn render_template(template: &str, entry: &MappingInput, settings: &AppSettings) -> TemplatePreview {
+    let mut problems = Vec::new();
+    if !template.contains("{id}") {
+        problems.push("映射值模板必须包含 {id} 占位符".to_string());
+    }
+    let prefix = match settings.key_prefix(entry.prefix.as_deref()) {
+        Ok(prefix) => prefix,
+        Err(e) => {
+            problems.push(e);
+            &settings.portal_prefix
+        }
+    };
+    let value = entry.params.apply_to(&template.replace("{id}", &entry.gw_id));
+    let entry_line = format!("\"{prefix}{key}\":\"{value}\"", key = entry.local_id);
+    if let Err(EditError::InvalidRawValue { reason }) = check_raw_value(&value) {
+        problems.push(format!("映射值格式不正确：{}", reason));
+    }
+
+    let document = format!("{{\n  \"sExtOptions\": {{\n    {}\n  }}\n}}\n", entry_line);
+    if let Err(err) = validate_json_content(&document) {
+        problems.push(format!("生成的条目不是合法的 JSON：{}", err.message));
+    }
+    let parsed = parse_mappings(&document, &settings.prefixes()).ok().and_then(|mut entries| entries.pop());
+    match &parsed {
+        None => problems.push("解析器无法读回生成的条目".to_string()),
+        Some(parsed) => {
+            if parsed.local_id != entry.local_id || parsed.prefix != prefix {
+                problems.push(format!("读回的 key 为 {}{}", parsed.prefix, parsed.local_id));
+            }
+            match parsed.gw_id.as_deref() {
+                Some(gw_id) if gw_id == entry.gw_id => {}
+                Some(gw_id) => problems.push(format!("读回的国网栏目ID为 {}，应为 {}", gw_id, entry.gw_id)),
+                None => problems.push("读回的映射值中没有 es_tabId，无法识别国网栏目ID".to_string()),
+            }
+            if parsed.fragment_class.is_none() {
+                problems.push("映射值中缺少栏目类名".to_string());
+            }
+        }
+    }
+    TemplatePreview {
+        value,
+        entry_line,
+        parsed,
+        problems,
+    }
+}
+
+/// 替换文件中 prefixes 下的所有映射项（导入模式），文件中有多个 sExtOptions 段落时每个段落都替换
+pub fn replace_mappings_in_file(
+    raw: &str,
+    mappings: &std::collections::HashMap<MappingKey, String>,
+    prefixes: &[&str],
+) -> Result<String, EditError> {
+    let mut updated = raw.to_string();
+    for block in find_ext_options_blocks(raw)?.iter().rev() {
+        updated = replace_mappings_in_block(&updated, block, mappings, prefixes)?;
+    }
+    Ok(updated)
+}
+
+fn replace_mappings_in_block(
+    raw: &str,
+    block: &ExtOptionsBlock,
+    mappings: &std::collections::HashMap<MappingKey, String>,
+    prefixes: &[&str],
+) -> Result<String, EditError> {
+    // 先移除所有前缀的映射条目，其他成员和注释保持不变
+    let object = ObjectCst::parse(raw, block.start)?;
+    let compact = !object.members.is_empty() && object.is_single_line(raw);
+    let portal_members: Vec<usize> = (0..object.members.len())
+        .filter(|idx| matching_prefix(&object.members[*idx].key, prefixes).is_some())
+        .collect();
+    let trimmed = object.remove_members(raw, &portal_members)?;
+
+    // 再按（前缀, 本地栏目ID）排序追加新的映射项，原本写在同一行的段落继续写在同一行
+    let mut mapping_vec: Vec<_> = mappings.iter().collect();
+    mapping_vec.sort_by_key(|(k, _)| *k);
+    let lines: Vec<String> = mapping_vec
+        .iter()
+        .map(|(key, raw_value)| format!("\"{}{}\":\"{}\"", key.prefix, key.local_id, raw_value))
+        .collect();
+    let object = ObjectCst::parse(&trimmed, block.start)?;
+    if compact {
+        object.append_members_inline(&trimmed, &lines)
+    } else {
+        object.append_members(&trimmed, &lines)
+    }
+}
+
+/// 计算让目标文件中 prefixes 下的映射与源映射保持一致所需的变更
+pub fn plan_sync(
+    raw: &str,
+    source: &std::collections::HashMap<MappingKey, String>,
+    prefixes: &[&str],
+) -> Result<SyncFilePlan, EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    let spans = locate_entries(raw, prefixes)?;
+    let mut plan = SyncFilePlan::default();
+    // 各段落分别与源映射比较，同一映射在计划中只记一次
+    let mut seen = std::collections::HashSet::new();
+
+    for span in &spans {
+        let key = MappingKey::of(span);
+        if !seen.insert((span.block, key.clone())) {
+            continue;
+        }
+        let list = match source.get(&key) {
+            None => &mut plan.removed,
+            Some(value) if &raw[span.value_start..span.value_end] != value.as_str() => &mut plan.changed,
+            Some(_) => continue,
+        };
+        if !list.contains(&key) {
+            list.push(key);
+        }
+    }
+
+    let mut added: Vec<MappingKey> = source
+        .keys()
+        .filter(|key| blocks.iter().any(|block| !seen.contains(&(block.index, (*key).clone()))))
+        .cloned()
+        .collect();
+    added.sort();
+    plan.added = added;
+    Ok(plan)
+}
+
+/// 让目标文件中 prefixes 下的映射集合与源映射完全一致：删除多余映射、更新不同的值、追加缺失映射
+pub fn apply_sync(
+    raw: &str,
+    source: &std::collections::HashMap<MappingKey, String>,
+    prefixes: &[&str],
+) -> Result<String, EditError> {
+    let spans = locate_entries(raw, prefixes)?;
+    let mut seen = std::collections::HashSet::new();
+    // 删除源中不存在的映射以及同一段落中重复出现的映射
+    let to_remove: Vec<&EntrySpan> = spans
+        .iter()
+        .filter(|span| {
+            let key = MappingKey::of(span);
+            !source.contains_key(&key) || !seen.insert((span.block, key))
+        })
+        .collect();
+    let trimmed = remove_entry_spans(raw, &to_remove)?;
+    let (updated, _) = merge_mappings_in_file(&trimmed, source, prefixes)?;
+    Ok(updated)
+}
+
+/// 将已存在映射的国网栏目ID替换为新值（覆盖策略），保留值中的其他参数
+pub fn set_gw_ids_in_file(raw: &str, updates: &[MappingInput], settings: &AppSettings) -> Result<String, EditError> {
+    if updates.is_empty() {
+        return Ok(raw.to_string());
+    }
+    let spans = locate_entries(raw, &settings.prefixes())?;
+    let mut updated = raw.to_string();
+    // 从后往前替换，避免位置偏移
+    for span in spans.iter().rev() {
+        if let Some(entry) = updates.iter().find(|e| {
+            e.local_id == span.local_id
+                && e.prefix.as_deref().unwrap_or(&settings.portal_prefix) == span.prefix
+                && (e.block.is_none() || e.block == Some(span.block))
+        }) {
+            let old_value = &raw[span.value_start..span.value_end];
+            let new_value = match replace_gw_id_in_value(old_value, &entry.gw_id) {
+                Some(new_value) => new_value,
+                None => template_value(entry, settings)?,
+            };
+            let new_value = entry.params.apply_to(&new_value);
+            updated.replace_range(span.value_start..span.value_end, &new_value);
+        }
+    }
+    Ok(updated)
+}
+
+/// 把 es_tabId 等于 old_gw_id 的映射全部改为 new_gw_id，返回修改后的内容和被修改的映射
+pub fn replace_gw_id_in_file(
+    raw: &str,
+    old_gw_id: &str,
+    new_gw_id: &str,
+    prefixes: &[&str],
+) -> Result<(String, Vec<MappingKey>), EditError> {
+    let spans = locate_entries(raw, prefixes)?;
+    let mut updated = raw.to_string();
+    let mut keys = Vec::new();
+    // 从后往前替换，避免位置偏移
+    for span in spans.iter().rev() {
+        let old_value = &raw[span.value_start..span.value_end];
+        if extract_gw_id(old_value).as_deref() != Some(old_gw_id) {
+            continue;
+        }
+        if let Some(new_value) = replace_gw_id_in_value(old_value, new_gw_id) {
+            updated.replace_range(span.value_start..span.value_end, &new_value);
+            keys.push(MappingKey::of(span));
+        }
+    }
+    keys.reverse();
+    Ok((updated, keys))
+}
+
+/// 检查手工填写的映射值：可选的 `栏目类名|` 加上 `intent://` 地址，地址中的参数为 `key=value` 且不重复
+pub fn check_raw_value(raw_value: &str) -> Result<(), EditError> {
+    let invalid = |reason: &str| Err(EditError::InvalidRawValue { reason: reason.to_string() });
+    if raw_value.trim().is_empty() {
+        return invalid("映射值不能为空");
+    }
+    if raw_value.chars().any(|ch| ch == '"' || ch == '\\' || ch.is_control()) {
+        return invalid("不能包含引号、反斜杠或换行");
+    }
+    if raw_value.chars().any(char::is_whitespace) {
+        return invalid("不能包含空白");
+    }
+    crate::intent::IntentValue::parse(raw_value)
+        .map(|_| ())
+        .map_err(|reason| EditError::InvalidRawValue { reason })
+}
+
+/// 把映射的整个值替换为 new_value，返回修改后的内容和各段落中原来的值；block 为 None 时修改所有段落中的该映射
+pub fn set_raw_value_in_file(
+    raw: &str,
+    local_id: &str,
+    prefix: &str,
+    block: Option<usize>,
+    new_value: &str,
+) -> Result<(String, Vec<String>), EditError> {
+    check_raw_value(new_value)?;
+    let mut updated = raw.to_string();
+    let mut old_values = Vec::new();
+    // 从后往前替换，避免位置偏移
+    for span in locate_portal_entries(raw, prefix)?.iter().rev() {
+        if span.local_id != local_id || block.is_some_and(|block| block != span.block) {
+            continue;
+        }
+        old_values.push(raw[span.value_start..span.value_end].to_string());
+        updated.replace_range(span.value_start..span.value_end, new_value);
+    }
+    if old_values.is_empty() {
+        return Err(EditError::MappingNotFound { local_id: local_id.to_string() });
+    }
+    old_values.reverse();
+    Ok((updated, old_values))
+}
+
+/// 把能解析但不是规范形式的映射值改写为规范形式，返回修改后的内容和被修改的映射
+pub fn normalize_values_in_file(raw: &str, prefixes: &[&str]) -> Result<(String, Vec<MappingKey>), EditError> {
+    let spans = locate_entries(raw, prefixes)?;
+    let mut updated = raw.to_string();
+    let mut keys = Vec::new();
+    // 从后往前替换，避免位置偏移
+    for span in spans.iter().rev() {
+        let old_value = &raw[span.value_start..span.value_end];
+        match crate::intent::normalize_value(old_value) {
+            Some(normalized) if normalized != old_value => {
+                updated.replace_range(span.value_start..span.value_end, &normalized);
+                keys.push(MappingKey::of(span));
+            }
+            _ => {}
+        }
+    }
+    keys.reverse();
+    Ok((updated, keys))
+}
+
+/// 被规范的ID
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdField {
+    /// 本地栏目ID（映射 key）
+    Local,
+    /// 国网栏目ID（es_tabId）
+    Gw,
+}
+
+/// 一处ID规范
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IdChange {
+    /// 修改前的本地栏目ID
+    pub local_id: String,
+    /// 条目的映射前缀
+    pub prefix: String,
+    pub field: IdField,
+    pub from: String,
+    pub to: String,
+}
+
+/// 规范疑似重复ID的结果
+#[derive(Debug)]
+pub struct IdNormalization {
+    pub content: String,
+    pub changes: Vec<IdChange>,
+    /// 规范后会与同一段落中已存在的 key 重名、因此未修改 key 的映射
+    pub collisions: Vec<MappingKey>,
+}
+
+/// 把状态为 suspicious_duplicate 的映射中的本地栏目ID和国网栏目ID改为规范形式
+///
+/// 国网栏目ID规范后与其他条目相同时成为 duplicate_gw，可再按重复分析处理；
+/// 本地栏目ID规范后与已存在的 key 重名时不修改 key，记入 collisions。
+pub fn normalize_ids_in_file(raw: &str, prefixes: &[&str]) -> Result<IdNormalization, EditError> {
+    let spans = locate_entries(raw, prefixes)?;
+    // 同一段落、同一前缀中规范形式相同的不同ID
+    type VariantKey<'a> = (usize, &'a str, String);
+    let mut local_variants: std::collections::HashMap<VariantKey, std::collections::HashSet<&str>> =
+        std::collections::HashMap::new();
+    let mut gw_variants: std::collections::HashMap<VariantKey, std::collections::HashSet<String>> =
+        std::collections::HashMap::new();
+    for span in &spans {
+        local_variants
+            .entry((span.block, &span.prefix, canonical_id(&span.local_id)))
+            .or_default()
+            .insert(&span.local_id);
+        if let Some(gw_id) = extract_gw_id(&raw[span.value_start..span.value_end]) {
+            gw_variants.entry((span.block, &span.prefix, canonical_id(&gw_id))).or_default().insert(gw_id);
+        }
+    }
+    let suspicious = |variants: Option<usize>| variants.is_some_and(|count| count > 1);
+    let mut existing: std::collections::HashSet<(usize, &str, String)> =
+        spans.iter().map(|span| (span.block, span.prefix.as_str(), span.local_id.clone())).collect();
+
+    let mut content = raw.to_string();
+    let mut changes = Vec::new();
+    let mut collisions = Vec::new();
+    // 从后往前替换，避免位置偏移；同一条目先改值再改 key
+    for span in spans.iter().rev() {
+        let old_value = &raw[span.value_start..span.value_end];
+        if let Some(gw_id) = extract_gw_id(old_value) {
+            let canonical = canonical_id(&gw_id);
+            let variants = gw_variants.get(&(span.block, span.prefix.as_str(), canonical.clone()));
+            if canonical != gw_id && suspicious(variants.map(|v| v.len())) {
+                if let Some(new_value) = replace_gw_id_in_value(old_value, &canonical) {
+                    content.replace_range(span.value_start..span.value_end, &new_value);
+                    changes.push(IdChange {
+                        local_id: span.local_id.clone(),
+                        prefix: span.prefix.clone(),
+                        field: IdField::Gw,
+                        from: gw_id,
+                        to: canonical,
+                    });
+                }
+            }
+        }
+        let canonical = canonical_id(&span.local_id);
+        if canonical == span.local_id
+            || !suspicious(
+                local_variants
+                    .get(&(span.block, span.prefix.as_str(), canonical.clone()))
+                    .map(|v| v.len()),
+            )
+        {
+            continue;
+        }
+        if !existing.insert((span.block, span.prefix.as_str(), canonical.clone())) {
+            collisions.push(MappingKey::of(span));
+            continue;
+        }
+        let id_start = span.key_start + 1 + span.prefix.len();
+        content.replace_range(id_start..id_start + span.local_id.len(), &canonical);
+        changes.push(IdChange {
+            local_id: span.local_id.clone(),
+            prefix: span.prefix.clone(),
+            field: IdField::Local,
+            from: span.local_id.clone(),
+            to: canonical,
+        });
+    }
+    changes.reverse();
+    collisions.reverse();
+    Ok(IdNormalization {
+        content,
+        changes,
+        collisions,
+    })
+}
+
+/// 修改映射 key 前缀的结果
+#[derive(Debug)]
+pub struct PrefixRename {
+    pub content: String,
+    /// 被修改 key 的本地栏目ID
+    pub local_ids: Vec<String>,
+    /// 修改后会与同一段落中已存在的 key 重名的本地栏目ID
+    pub collisions: Vec<String>,
+}
+
+/// 把 sExtOptions 段落中 key 前缀为 old_prefix 的映射改为 new_prefix，本地栏目ID和值保持不变
+///
+/// 被注释和不在 sExtOptions 中的条目不修改。new_prefix 以 old_prefix 开头时，已是新前缀的 key 视为已迁移。
+pub fn rename_prefix_in_file(raw: &str, old_prefix: &str, new_prefix: &str) -> Result<PrefixRename, EditError> {
+    let spans: Vec<EntrySpan> = locate_portal_entries(raw, old_prefix)?
+        .into_iter()
+        .filter(|span| !format!("{}{}", old_prefix, span.local_id).starts_with(new_prefix))
+        .collect();
+    let renamed: std::collections::HashSet<(usize, String)> = spans
+        .iter()
+        .map(|span| (span.block, format!("{}{}", old_prefix, span.local_id)))
+        .collect();
+    // 同一段落中已存在的新前缀 key（不含本次要改名的 key）
+    let existing: std::collections::HashSet<(usize, String)> = locate_portal_entries(raw, new_prefix)?
+        .into_iter()
+        .map(|span| (span.block, format!("{}{}", new_prefix, span.local_id)))
+        .filter(|key| !renamed.contains(key))
+        .collect();
+
+    let mut content = raw.to_string();
+    let mut local_ids = Vec::new();
+    let mut collisions = Vec::new();
+    // 从后往前替换，避免位置偏移
+    for span in spans.iter().rev() {
+        if existing.contains(&(span.block, format!("{}{}", new_prefix, span.local_id))) {
+            collisions.push(span.local_id.clone());
+            continue;
+        }
+        let key_start = span.key_start + 1;
+        content.replace_range(key_start..key_start + old_prefix.len(), new_prefix);
+        local_ids.push(span.local_id.clone());
+    }
+    local_ids.reverse();
+    collisions.reverse();
+    Ok(PrefixRename {
+        content,
+        local_ids,
+        collisions,
+    })
+}
+
+/// 替换映射值中 es_tabId 参数的值，值中没有 es_tabId 时返回 None
+pub fn replace_gw_id_in_value(raw_value: &str, new_gw_id: &str) -> Option<String> {
+    replace_intent_param(raw_value, "es_tabId", new_gw_id)
+}
+
+/// 合并导入的映射（合并模式）：覆盖同名映射的值、追加缺失的映射，其余内容保持不变
+///
+/// mappings 按（前缀, 本地栏目ID）给出，已有条目按 prefixes 中的最长前缀识别。
+/// 文件中有多个 sExtOptions 段落时逐个段落合并，统计数按段落累计。
+pub fn merge_mappings_in_file(
+    raw: &str,
+    mappings: &std::collections::HashMap<MappingKey, String>,
+    prefixes: &[&str],
+) -> Result<(String, ImportFileStats), EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    let spans = locate_entries(raw, prefixes)?;
+    let mut stats = ImportFileStats::default();
+    let mut updated = raw.to_string();
+
+    let mut mapping_vec: Vec<_> = mappings.iter().collect();
+    mapping_vec.sort_by_key(|(k, _)| *k);
+
+    // 从后往前处理各段落，前面段落的位置不受影响
+    for block in blocks.iter().rev() {
+        let mut replacements: Vec<(usize, usize, &str)> = Vec::new();
+        let mut additions = Vec::new();
+
+        for (key, raw_value) in &mapping_vec {
+            let occurrences: Vec<&EntrySpan> = spans
+                .iter()
+                .filter(|span| span.block == block.index && span.prefix == key.prefix && span.local_id == key.local_id)
+                .collect();
+            if occurrences.is_empty() {
+                additions.push(format!("\"{}{}\":\"{}\"", key.prefix, key.local_id, raw_value));
+                stats.added += 1;
+                continue;
+            }
+
+            let mut changed = false;
+            for span in occurrences {
+                if &raw[span.value_start..span.value_end] != raw_value.as_str() {
+                    replacements.push((span.value_start, span.value_end, raw_value.as_str()));
+                    changed = true;
+                }
+            }
+            if changed {
+                stats.updated += 1;
+            } else {
+                stats.unchanged += 1;
+            }
+        }
+
+        // 先在段落末尾追加，再从后往前替换，两者都不会影响前面的位置
+        if !additions.is_empty() {
+            updated = insert_lines_into_block(&updated, block, &additions)?;
+        }
+        replacements.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        for (start, end, value) in replacements {
+            updated.replace_range(start..end, value);
+        }
+    }
+
+    Ok((updated, stats))
+}
+
+/// 文件中的一个 sExtOptions 段落
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtOptionsBlock {
+    /// 段落在文件中的序号（从 0 开始）
+    pub index: usize,
+    /// 段落所属对象的路径，如 `$`、`$.themes[1]`
+    pub path: String,
+    /// 左右花括号的位置
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 扫描时所在的容器
+enum Container {
+    /// 对象：当前成员的 key，以及该对象是否为 sExtOptions 段落（段落序号）
+    Object { key: Option<String>, ext_block: Option<usize> },
+    Array { index: usize },
+}
+
+/// 返回第一个 sExtOptions 段落左右花括号的位置
+pub fn find_ext_options_block(content: &str) -> Result<(usize, usize), EditError> {
+    let blocks = find_ext_options_blocks(content)?;
+    Ok((blocks[0].start, blocks[0].end))
+}
+
+/// 按出现顺序找出文件中所有的 sExtOptions 段落（跳过字符串和注释）
+pub fn find_ext_options_blocks(content: &str) -> Result<Vec<ExtOptionsBlock>, EditError> {
+    let bytes = content.as_bytes();
+    let mut blocks: Vec<ExtOptionsBlock> = Vec::new();
+    let mut stack: Vec<Container> = Vec::new();
+    // 最近读到的字符串，遇到冒号时作为对象成员的 key
+    let mut last_string: Option<(usize, usize)> = None;
+    let mut expect_ext_value = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let ch = bytes[i];
+        if ch == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'/' {
+            i = content[i..].find('\n').map(|pos| i + pos + 1).unwrap_or(bytes.len());
+            continue;
+        }
+        if ch == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'*' {
+            i = content[i + 2..].find("*/").map(|pos| i + pos + 4).unwrap_or(bytes.len());
+            continue;
+        }
+        if ch.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if expect_ext_value {
+            expect_ext_value = false;
+            if ch != b'{' {
+                return Err(EditError::ExtOptionsNotObject);
+            }
+            blocks.push(ExtOptionsBlock {
+                index: blocks.len(),
+                path: container_path(&stack),
+                start: i,
+                end: i,
+            });
+            stack.push(Container::Object {
+                key: None,
+                ext_block: Some(blocks.len() - 1),
+            });
+            i += 1;
+            continue;
+        }
+        if ch == b'"' {
+            let Some(end) = find_string_end(content, i + 1, bytes) else {
+                break;
+            };
+            last_string = Some((i + 1, end));
+            i = end + 1;
+            continue;
+        }
+
+        let key = last_string.take();
+        match ch {
+            b':' => {
+                if let Some(Container::Object { key: current, .. }) = stack.last_mut() {
+                    *current = key.map(|(start, end)| content[start..end].to_string());
+                    expect_ext_value = current.as_deref() == Some("sExtOptions");
+                }
+            }
+            b'{' => stack.push(Container::Object {
+                key: None,
+                ext_block: None,
+            }),
+            b'[' => stack.push(Container::Array { index: 0 }),
+            b'}' | b']' => {
+                if let Some(Container::Object {
+                    ext_block: Some(idx), ..
+                }) = stack.pop()
+                {
+                    blocks[idx].end = i;
+                }
+            }
+            b',' => match stack.last_mut() {
+                Some(Container::Object { key: current, .. }) => *current = None,
+                Some(Container::Array { index }) => *index += 1,
+                None => {}
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if expect_ext_value {
+        return Err(EditError::ExtOptionsNotObject);
+    }
+    if stack
+        .iter()
+        .any(|container| matches!(container, Container::Object { ext_block: Some(_), .. }))
+    {
+        return Err(EditError::UnterminatedExtOptions);
+    }
+    if blocks.is_empty() {
+        return Err(EditError::ExtOptionsNotFound);
+    }
+    Ok(blocks)
+}
+
+/// 栈顶对象（sExtOptions 所在对象）的路径
+fn container_path(stack: &[Container]) -> String {
+    let mut path = String::from("$");
+    for container in &stack[..stack.len().saturating_sub(1)] {
+        match container {
+            Container::Object { key, .. } => {
+                path.push('.');
+                path.push_str(key.as_deref().unwrap_or_default());
+            }
+            Container::Array { index } => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// 按指定方式重排每个 sExtOptions 段落中的映射条目，不同前缀的条目按 prefixes 中的顺序分组排列
+///
+/// 条目之间的逗号、空白、注释以及其他 key 都留在原位，只交换条目本身的文本。
+pub fn sort_entries(raw: &str, prefixes: &[&str], order: SortOrder) -> Result<String, EditError> {
+    let mut content = raw.to_string();
+    // 从后往前处理各段落，前面段落的位置不受影响
+    for block in find_ext_options_blocks(raw)?.iter().rev() {
+        let object = ObjectCst::parse(&content, block.start)?;
+        // 每个条目及其前缀在 prefixes 中的序号
+        let slots: Vec<(&crate::cst::Member, usize, &str)> = object
+            .members
+            .iter()
+            .filter_map(|member| {
+                let prefix = matching_prefix(&member.key, prefixes)?;
+                let rank = prefixes.iter().position(|other| *other == prefix)?;
+                Some((member, rank, &member.key[prefix.len()..]))
+            })
+            .collect();
+        let mut sorted = slots.clone();
+        sorted.sort_by(|(a, a_rank, a_id), (b, b_rank, b_id)| {
+            a_rank.cmp(b_rank).then_with(|| match order {
+                SortOrder::ByLocalId => compare_ids(a_id, b_id),
+                SortOrder::ByGwId => {
+                    let a_gw = extract_gw_id(content[a.value_start..a.value_end].trim_matches(['"', '\'']));
+                    let b_gw = extract_gw_id(content[b.value_start..b.value_end].trim_matches(['"', '\'']));
+                    match (a_gw, b_gw) {
+                        (Some(a_gw), Some(b_gw)) => compare_ids(&a_gw, &b_gw),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
+                    }
+                    .then_with(|| compare_ids(a_id, b_id))
+                }
+            })
+        });
+
+        // 从后往前把每个位置替换成排序后对应条目的文本
+        let mut updated = content.clone();
+        for ((slot, _, _), (member, _, _)) in slots.iter().zip(&sorted).rev() {
+            updated.replace_range(slot.key_start..slot.value_end, &content[member.key_start..member.value_end]);
+        }
+        content = updated;
+    }
+    Ok(content)
+}
+
+/// 比较两个ID：都是数字时按数值比较，数字排在非数字之前，其余按字符串比较
+fn compare_ids(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u128>(), b.parse::<u128>()) {
+        (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num).then_with(|| a.cmp(b)),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// 按位置删除 sExtOptions 中的指定条目，相邻的逗号、缩进和行尾注释一并处理
+pub fn remove_entry_spans(raw: &str, spans: &[&EntrySpan]) -> Result<String, EditError> {
+    take_out_entry_spans(raw, spans, false)
+}
+
+/// 按位置注释掉 sExtOptions 中的指定条目（软删除），逗号的处理同 [`remove_entry_spans`]
+pub fn comment_out_entry_spans(raw: &str, spans: &[&EntrySpan]) -> Result<String, EditError> {
+    take_out_entry_spans(raw, spans, true)
+}
+
+fn take_out_entry_spans(raw: &str, spans: &[&EntrySpan], soft: bool) -> Result<String, EditError> {
+    let mut content = raw.to_string();
+    // 从后往前处理各段落，前面段落的位置不受影响
+    for block in find_ext_options_blocks(raw)?.iter().rev() {
+        let object = ObjectCst::parse(&content, block.start)?;
+        let indices: Vec<usize> = (0..object.members.len())
+            .filter(|idx| spans.iter().any(|span| span.key_start == object.members[*idx].key_start))
+            .collect();
+        if indices.is_empty() {
+            continue;
+        }
+        content = if soft {
+            object.comment_out_members(&content, &indices)?
+        } else {
+            object.remove_members(&content, &indices)?
+        };
+    }
+    Ok(content)
+}
+
+/// 从文件中删除指定的映射项（所有 sExtOptions 段落）
+pub fn remove_mapping_from_file(raw: &str, local_id: &str, prefix: &str) -> Result<String, EditError> {
+    remove_mapping_from_block(raw, local_id, prefix, None)
+}
+
+/// 从指定序号的 sExtOptions 段落中删除映射项，block 为 None 时从所有段落中删除
+pub fn remove_mapping_from_block(
+    raw: &str,
+    local_id: &str,
+    prefix: &str,
+    block: Option<usize>,
+) -> Result<String, EditError> {
+    take_out_mapping(raw, local_id, prefix, block, false)
+}
+
+/// 软删除：把映射项所在行注释掉而不是删除，便于在设备上手动恢复；段落的选择同 [`remove_mapping_from_block`]
+pub fn comment_out_mapping_from_block(
+    raw: &str,
+    local_id: &str,
+    prefix: &str,
+    block: Option<usize>,
+) -> Result<String, EditError> {
+    take_out_mapping(raw, local_id, prefix, block, true)
+}
+
+fn take_out_mapping(
+    raw: &str,
+    local_id: &str,
+    prefix: &str,
+    block: Option<usize>,
+    soft: bool,
+) -> Result<String, EditError> {
+    let blocks = find_ext_options_blocks(raw)?;
+    if let Some(missing) = block.filter(|block| *block >= blocks.len()) {
+        return Err(EditError::BlockNotFound { index: missing });
+    }
+    let mut updated = raw.to_string();
+    let mut found_target = false;
+    for target in blocks.iter().rev() {
+        if block.is_some_and(|block| block != target.index) {
+            continue;
+        }
+        if let Some(result) = remove_mapping_in_block(&updated, target, local_id, prefix, soft)? {
+            updated = result;
+            found_target = true;
+        }
+    }
+    if !found_target {
+        return Err(EditError::MappingNotFound { local_id: local_id.to_string() });
+    }
+    Ok(updated)
+}
+
+/// 删除单个段落中的映射项，段落中没有该映射时返回 None；soft 为 true 时注释掉而不是删除
+fn remove_mapping_in_block(
+    raw: &str,
+    block: &ExtOptionsBlock,
+    local_id: &str,
+    prefix: &str,
+    soft: bool,
+) -> Result<Option<String>, EditError> {
+    let object = ObjectCst::parse(raw, block.start)?;
+    let target_key = format!("{prefix}{local_id}");
+    let indices: Vec<usize> = (0..object.members.len())
+        .filter(|idx| object.members[*idx].key == target_key)
+        .collect();
+    if indices.is_empty() {
+        return Ok(None);
+    }
+    if soft {
+        object.comment_out_members(raw, &indices).map(Some)
+    } else {
+        object.remove_members(raw, &indices).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const PREFIX: &str = "portal_frag_";
+
+    fn value(gw_id: &str) -> String {
+        format!("com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=", gw_id)
+    }
+
+    fn theme(entries: &[(&str, &str)]) -> String {
+        let body: Vec<String> = entries
+            .iter()
+            .map(|(local_id, gw_id)| format!("    \"{}{}\": \"{}\"", PREFIX, local_id, value(gw_id)))
+            .collect();
+        format!(
+            "{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }},\n  \"other\": 1\n}}\n",
+            body.join(",\n")
+        )
+    }
+
+    fn ids(raw: &str) -> Vec<(String, Option<String>)> {
+        parse_mappings(raw, &[PREFIX])
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.local_id, entry.gw_id))
+            .collect()
+    }
+
+    fn pair(local_id: &str, gw_id: &str) -> (String, Option<String>) {
+        (local_id.to_string(), Some(gw_id.to_string()))
+    }
+
+    fn keyed(mappings: &HashMap<String, String>) -> HashMap<MappingKey, String> {
+        mappings
+            .iter()
+            .map(|(local_id, value)| {
+                let key = MappingKey {
+                    prefix: PREFIX.to_string(),
+                    local_id: local_id.clone(),
+                };
+                (key, value.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_marks_duplicates_and_same_id() {
+        let raw = theme(&[("101", "1001"), ("102", "102"), ("101", "1003"), ("104", "1001")]);
+        let entries = parse_mappings(&raw, &[PREFIX]).unwrap();
+        let statuses: Vec<&str> = entries.iter().map(|e| e.status.as_str()).collect();
+        assert_eq!(statuses, ["duplicate_local", "normal", "duplicate_local", "duplicate_gw"]);
+        assert!(entries[1].same_id);
+        assert!(!entries[0].same_id);
+    }
+
+    #[test]
+    fn parse_skips_commented_entries() {
+        let raw = format!(
+            "{{\"sExtOptions\": {{\n  // \"{p}900\": \"x\",\n  /* \"{p}901\": \"y\", */\n  \"{p}101\": \"{v}\"\n}}}}",
+            p = PREFIX,
+            v = value("1001")
+        );
+        assert_eq!(ids(&raw), [pair("101", "1001")]);
+    }
+
+    #[test]
+    fn soft_delete_comments_out_entries() {
+        let raw = theme(&[("101", "1001"), ("102", "1002"), ("103", "1003")]);
+        let middle = comment_out_mapping_from_block(&raw, "102", PREFIX, None).unwrap();
+        assert_eq!(ids(&middle), [pair("101", "1001"), pair("103", "1003")]);
+        assert!(middle.contains(&format!("    // \"{}102\": \"{}\"\n", PREFIX, value("1002"))));
+
+        // 注释掉最后一条时去掉前一条的逗号，结果仍是合法的 JSON
+        let last = comment_out_mapping_from_block(&raw, "103", PREFIX, None).unwrap();
+        assert!(validate_json_content(&last).is_ok());
+        assert!(last.contains(&format!("\"{}\"\n    // \"{}103\"", value("1002"), PREFIX)));
+
+        // 注释掉的条目可以被识别并重新启用
+        let commented = find_commented_mappings(&last, &[PREFIX]);
+        assert_eq!(commented.len(), 1);
+        assert_eq!(commented[0].local_id, "103");
+        let spans = locate_commented_entries(&last, &[PREFIX]).unwrap();
+        let restored = uncomment_spans(&last, &spans.iter().collect::<Vec<_>>()).unwrap();
+        assert_eq!(ids(&restored), ids(&raw));
+    }
+
+    #[test]
+    fn commented_entries_can_be_found_uncommented_and_purged() {
+        let raw = format!(
+            "{{\n  \"sExtOptions\": {{\n    \"{p}101\": \"{v1}\",\n    // \"{p}102\": \"{v2}\",\n    // 普通注释\n    \"{p}103\": \"{v3}\"\n  }}\n}}\n",
+            p = PREFIX,
+            v1 = value("1001"),
+            v2 = value("1002"),
+            v3 = value("1003")
+        );
+        let commented = find_commented_mappings(&raw, &[PREFIX]);
+        assert_eq!(commented.len(), 1);
+        assert_eq!(commented[0].local_id, "102");
+        assert_eq!(commented[0].gw_id.as_deref(), Some("1002"));
+        assert_eq!(commented[0].status, "commented");
+
+        let spans = locate_commented_entries(&raw, &[PREFIX]).unwrap();
+        let targets: Vec<&CommentedSpan> = spans.iter().collect();
+        let enabled = uncomment_spans(&raw, &targets).unwrap();
+        assert_eq!(ids(&enabled), [pair("101", "1001"), pair("103", "1003"), pair("102", "1002")]);
+        assert!(validate_json_content(&enabled).is_ok());
+        assert!(find_commented_mappings(&enabled, &[PREFIX]).is_empty());
+
+        let purged = remove_commented_spans(&raw, &targets);
+        assert_eq!(ids(&purged), [pair("101", "1001"), pair("103", "1003")]);
+        assert!(!purged.contains("1002"));
+        assert!(purged.contains("// 普通注释"));
+    }
+
+    #[test]
+    fn parse_reports_missing_block() {
+        assert_eq!(parse_mappings("{\"version\": 1}", &[PREFIX]).unwrap_err(), EditError::ExtOptionsNotFound);
+        assert_eq!(
+            parse_mappings("{\"sExtOptions\": []}", &[PREFIX]).unwrap_err(),
+            EditError::ExtOptionsNotObject
+        );
+        assert_eq!(
+            parse_mappings("{\"sExtOptions\": {\"a\": 1", &[PREFIX]).unwrap_err(),
+            EditError::UnterminatedExtOptions
+        );
+    }
+
+    #[test]
+    fn extract_gw_id_stops_at_delimiters() {
+        assert_eq!(extract_gw_id(&value("1001")), Some("1001".to_string()));
+        assert_eq!(extract_gw_id("a|intent://?es_tabId=77|b"), Some("77".to_string()));
+        assert_eq!(extract_gw_id("a|intent://?es_tabId=&x=1"), None);
+        assert_eq!(extract_gw_id("no id here"), None);
+    }
+
+    #[test]
+    fn insert_appends_entries_and_keeps_json_valid() {
+        let raw = theme(&[("101", "1001")]);
+        let entries = vec![
+            MappingInput { local_id: "102".into(), gw_id: "1002".into(), ..Default::default() },
+            MappingInput { local_id: "103".into(), gw_id: "1003".into(), ..Default::default() },
+        ];
+        let updated = insert_entries(&raw, &entries, &AppSettings::default()).unwrap();
+        assert_eq!(ids(&updated), [pair("101", "1001"), pair("102", "1002"), pair("103", "1003")]);
+        assert!(validate_json_content(&updated).is_ok());
+        assert!(updated.contains("\"other\": 1"));
+    }
+
+    #[test]
+    fn insert_into_empty_block_and_keeps_crlf() {
+        let raw = "{\r\n  \"sExtOptions\": {\r\n  }\r\n}\r\n";
+        let entries = vec![MappingInput { local_id: "101".into(), gw_id: "1001".into(), ..Default::default() }];
+        let updated = insert_entries(raw, &entries, &AppSettings::default()).unwrap();
+        assert_eq!(ids(&updated), [pair("101", "1001")]);
+        assert!(validate_json_content(&updated).is_ok());
+        assert!(!updated.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn replace_mappings_drops_existing_entries() {
+        let raw = theme(&[("101", "1001"), ("102", "1002")]);
+        let mappings = HashMap::from([("201".to_string(), value("2001")), ("200".to_string(), value("2000"))]);
+        let updated = replace_mappings_in_file(&raw, &keyed(&mappings), &[PREFIX]).unwrap();
+        assert_eq!(ids(&updated), [pair("200", "2000"), pair("201", "2001")]);
+        assert!(validate_json_content(&updated).is_ok());
+    }
+
+    #[test]
+    fn merge_updates_and_appends() {
+        let raw = theme(&[("101", "1001"), ("102", "1002")]);
+        let mappings = HashMap::from([
+            ("101".to_string(), value("1001")),
+            ("102".to_string(), value("9002")),
+            ("103".to_string(), value("1003")),
+        ]);
+        let (updated, stats) = merge_mappings_in_file(&raw, &keyed(&mappings), &[PREFIX]).unwrap();
+        assert_eq!(ids(&updated), [pair("101", "1001"), pair("102", "9002"), pair("103", "1003")]);
+        assert_eq!((stats.added, stats.updated, stats.unchanged), (1, 1, 1));
+        assert!(validate_json_content(&updated).is_ok());
+    }
+
+    #[test]
+    fn set_gw_ids_keeps_other_parameters() {
+        let raw = theme(&[("101", "1001")]);
+        let updates = vec![MappingInput { local_id: "101".into(), gw_id: "5555".into(), ..Default::default() }];
+        let updated = set_gw_ids_in_file(&raw, &updates, &AppSettings::default()).unwrap();
+        assert_eq!(ids(&updated), [pair("101", "5555")]);
+        assert!(updated.contains("es_tabId=5555&es_title="));
+    }
+
+    #[test]
+    fn replace_gw_id_only_touches_exact_matches() {
+        let raw = theme(&[("101", "1001"), ("102", "10011"), ("103", "1001")]);
+        let (updated, local_ids) = replace_gw_id_in_file(&raw, "1001", "2002", &[PREFIX]).unwrap();
+        let local_ids: Vec<&str> = local_ids.iter().map(|key| key.local_id.as_str()).collect();
+        assert_eq!(local_ids, ["101", "103"]);
+        assert_eq!(ids(&updated), [pair("101", "2002"), pair("102", "10011"), pair("103", "2002")]);
+    }
+
+    #[test]
+    fn intent_params_round_trip() {
+        let params = IntentParams::from_value(&value("1001"));
+        assert!(params.is_empty());
+
+        let params = IntentParams {
+            title: Some("首页".into()),
+            focus_img: Some("http://img/a.png".into()),
+            ..Default::default()
+        };
+        let updated = params.apply_to(&value("1001"));
+        assert_eq!(extract_gw_id(&updated), Some("1001".to_string()));
+        assert_eq!(IntentParams::from_value(&updated), params);
+        assert!(params.matches(&updated));
+        assert!(!params.matches(&value("1001")));
+        assert_eq!(params.apply_to("a|intent://?es_tabId=1"), "a|intent://?es_tabId=1&es_title=首页&es_focusImg=http://img/a.png");
+    }
+
+    #[test]
+    fn intent_param_requires_boundary() {
+        assert_eq!(extract_intent_param("a|intent://?xes_title=1&es_title=2", "es_title"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn insert_uses_selected_template() {
+        let mut settings = AppSettings::default();
+        settings.value_templates.push(crate::settings::ValueTemplate {
+            name: "web".into(),
+            value: "com.example.WebFragment|intent://?url=http://host/{id}".into(),
+        });
+        let raw = theme(&[("101", "1001")]);
+        let entries = vec![MappingInput {
+            local_id: "102".into(),
+            gw_id: "77".into(),
+            template: Some("web".into()),
+            ..Default::default()
+        }];
+        let updated = insert_entries(&raw, &entries, &settings).unwrap();
+        let parsed = parse_mappings(&updated, &[PREFIX]).unwrap();
+        assert_eq!(parsed[0].fragment_class.as_deref(), Some("com.ipanel.join.gw_ui_sdk.GwPortalFragment"));
+        assert_eq!(parsed[1].fragment_class.as_deref(), Some("com.example.WebFragment"));
+        assert_eq!(parsed[1].raw_value, "com.example.WebFragment|intent://?url=http://host/77");
+
+        let entries = vec![MappingInput { template: Some("missing".into()), ..entries[0].clone() }];
+        assert_eq!(
+            insert_entries(&raw, &entries, &settings).unwrap_err(),
+            EditError::UnknownTemplate { name: "missing".into() }
+        );
+    }
+
+    fn combined(first: &[(&str, &str)], second: &[(&str, &str)]) -> String {
+        let block = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(local_id, gw_id)| format!("        \"{}{}\": \"{}\"", PREFIX, local_id, value(gw_id)))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        };
+        format!(
+            "{{\n  \"version\": 1,\n  \"themes\": [\n    {{\n      \"name\": \"sExtOptions\",\n      \"sExtOptions\": {{\n{}\n      }}\n    }},\n    {{\n      \"sExtOptions\": {{\n{}\n      }}\n    }}\n  ]\n}}\n",
+            block(first),
+            block(second)
+        )
+    }
+
+    #[test]
+    fn finds_every_block_with_owner_path() {
+        let raw = combined(&[("101", "1001")], &[("101", "2001")]);
+        let blocks = find_ext_options_blocks(&raw).unwrap();
+        let paths: Vec<&str> = blocks.iter().map(|block| block.path.as_str()).collect();
+        assert_eq!(paths, ["$.themes[0]", "$.themes[1]"]);
+
+        // 同一本地栏目ID出现在不同段落中不算重复
+        let entries = parse_mappings(&raw, &[PREFIX]).unwrap();
+        assert_eq!(entries.iter().map(|e| e.block_index).collect::<Vec<_>>(), [Some(0), Some(1)]);
+        assert!(entries.iter().all(|e| e.status == "normal"));
+        assert_eq!(find_ext_options_blocks(&theme(&[])).unwrap()[0].path, "$");
+    }
+
+    #[test]
+    fn edits_target_selected_block() {
+        let raw = combined(&[("101", "1001")], &[("201", "2001")]);
+        let entries = vec![
+            MappingInput { local_id: "102".into(), gw_id: "1002".into(), block: Some(1), ..Default::default() },
+            MappingInput { local_id: "103".into(), gw_id: "1003".into(), ..Default::default() },
+        ];
+        let updated = insert_entries(&raw, &entries, &AppSettings::default()).unwrap();
+        assert!(validate_json_content(&updated).is_ok());
+        let located: Vec<(usize, String)> = locate_portal_entries(&updated, PREFIX)
+            .unwrap()
+            .into_iter()
+            .map(|span| (span.block, span.local_id))
+            .collect();
+        assert_eq!(
+            located,
+            [(0, "101".into()), (0, "103".into()), (1, "201".into()), (1, "102".into()), (1, "103".into())]
+        );
+
+        let removed = remove_mapping_from_block(&updated, "103", PREFIX, Some(1)).unwrap();
+        assert!(validate_json_content(&removed).is_ok());
+        assert_eq!(locate_portal_entries(&removed, PREFIX).unwrap().len(), 4);
+        let removed = remove_mapping_from_file(&updated, "103", PREFIX).unwrap();
+        assert_eq!(locate_portal_entries(&removed, PREFIX).unwrap().len(), 3);
+        assert_eq!(
+            remove_mapping_from_block(&updated, "103", PREFIX, Some(5)).unwrap_err(),
+            EditError::BlockNotFound { index: 5 }
+        );
+    }
+
+    #[test]
+    fn inserts_lines_into_chosen_blocks() {
+        let raw = combined(&[("101", "1001")], &[("201", "2001")]);
+        let line = format!("\"{}202\":\"{}\"", PREFIX, value("2002"));
+        let updated = insert_lines_into_blocks(&raw, &[(1, line.clone())]).unwrap();
+        assert!(validate_json_content(&updated).is_ok());
+        let located: Vec<(usize, String)> = locate_portal_entries(&updated, PREFIX)
+            .unwrap()
+            .into_iter()
+            .map(|span| (span.block, span.local_id))
+            .collect();
+        assert_eq!(located, [(0, "101".into()), (1, "201".into()), (1, "202".into())]);
+        assert_eq!(
+            insert_lines_into_blocks(&raw, &[(2, line)]).unwrap_err(),
+            EditError::BlockNotFound { index: 2 }
+        );
+    }
+
+    #[test]
+    fn merge_and_replace_apply_to_each_block() {
+        let raw = combined(&[("101", "1001")], &[("201", "2001")]);
+        let mappings = HashMap::from([("101".to_string(), value("9001"))]);
+        let (merged, stats) = merge_mappings_in_file(&raw, &keyed(&mappings), &[PREFIX]).unwrap();
+        assert_eq!((stats.added, stats.updated), (1, 1));
+        assert_eq!(ids(&merged), [pair("101", "9001"), pair("201", "2001"), pair("101", "9001")]);
+        assert!(validate_json_content(&merged).is_ok());
+
+        let replaced = replace_mappings_in_file(&raw, &keyed(&mappings), &[PREFIX]).unwrap();
+        assert_eq!(ids(&replaced), [pair("101", "9001"), pair("101", "9001")]);
+        assert!(validate_json_content(&replaced).is_ok());
+    }
+
+    #[test]
+    fn compact_blocks_stay_on_one_line() {
+        let raw = format!(
+            "{{\"sExtOptions\": {{\"{p}1\":\"{a}\", \"{p}2\":\"{b}\"}}, \"v\": 1}}",
+            p = PREFIX,
+            a = value("11"),
+            b = value("22")
+        );
+        let entries = vec![MappingInput { local_id: "3".into(), gw_id: "33".into(), ..Default::default() }];
+        let inserted = insert_entries(&raw, &entries, &AppSettings::default()).unwrap();
+        assert!(!inserted.contains('\n'));
+        assert_eq!(ids(&inserted), [pair("1", "11"), pair("2", "22"), pair("3", "33")]);
+
+        let removed = remove_mapping_from_file(&inserted, "2", PREFIX).unwrap();
+        assert!(!removed.contains('\n'));
+        assert!(validate_json_content(&removed).is_ok());
+
+        let mappings = HashMap::from([("9".to_string(), value("99"))]);
+        let replaced = replace_mappings_in_file(&raw, &keyed(&mappings), &[PREFIX]).unwrap();
+        assert!(!replaced.contains('\n'));
+        assert_eq!(ids(&replaced), [pair("9", "99")]);
+    }
+
+    #[test]
+    fn sort_entries_keeps_other_keys_in_place() {
+        let raw = format!(
+            "{{\"sExtOptions\": {{\n  \"{p}10\": \"{a}\",\n  // 分隔\n  \"other\": 1,\n  \"{p}9\": \"{b}\",\n  \"{p}x\": \"{c}\"\n}}}}",
+            p = PREFIX,
+            a = value("300"),
+            b = value("200"),
+            c = value("100")
+        );
+        let by_local = sort_entries(&raw, &[PREFIX], SortOrder::ByLocalId).unwrap();
+        assert_eq!(ids(&by_local), [pair("9", "200"), pair("10", "300"), pair("x", "100")]);
+        assert!(by_local.contains("  // 分隔\n  \"other\": 1,\n"));
+        assert!(validate_json_content(&by_local).is_ok());
+
+        let by_gw = sort_entries(&raw, &[PREFIX], SortOrder::ByGwId).unwrap();
+        assert_eq!(ids(&by_gw), [pair("x", "100"), pair("9", "200"), pair("10", "300")]);
+        assert_eq!(sort_entries(&by_gw, &[PREFIX], SortOrder::ByGwId).unwrap(), by_gw);
+    }
+
+    #[test]
+    fn remove_mapping_keeps_json_valid() {
+        for target in ["101", "102", "103"] {
+            let raw = theme(&[("101", "1001"), ("102", "1002"), ("103", "1003")]);
+            let updated = remove_mapping_from_file(&raw, target, PREFIX).unwrap();
+            assert!(ids(&updated).iter().all(|(local_id, _)| local_id != target));
+            assert_eq!(ids(&updated).len(), 2);
+            assert!(validate_json_content(&updated).is_ok(), "删除 {} 后: {}", target, updated);
+        }
+    }
+
+    #[test]
+    fn remove_missing_mapping_is_typed_error() {
+        let raw = theme(&[("101", "1001")]);
+        assert_eq!(
+            remove_mapping_from_file(&raw, "999", PREFIX).unwrap_err(),
+            EditError::MappingNotFound { local_id: "999".to_string() }
+        );
+    }
+
+    #[test]
+    fn remove_entry_spans_handles_inline_entries() {
+        let raw = format!(
+            "{{\"sExtOptions\": {{\"{p}1\": \"a\", \"{p}2\": \"b\", \"{p}3\": \"c\"}}}}",
+            p = PREFIX
+        );
+        let spans = locate_portal_entries(&raw, PREFIX).unwrap();
+        let updated = remove_entry_spans(&raw, &[&spans[1], &spans[2]]).unwrap();
+        assert_eq!(updated, format!("{{\"sExtOptions\": {{\"{}1\": \"a\"}}}}", PREFIX));
+    }
+
+    #[test]
+    fn comment_out_entry_spans_keeps_json_valid() {
+        let raw = theme(&[("101", "101"), ("102", "1002"), ("103", "103")]);
+        let spans = locate_portal_entries(&raw, PREFIX).unwrap();
+        let updated = comment_out_entry_spans(&raw, &[&spans[0], &spans[2]]).unwrap();
+        assert!(validate_json_content(&updated).is_ok());
+        assert_eq!(ids(&updated), [pair("102", "1002")]);
+        let commented: Vec<String> = find_commented_mappings(&updated, &[PREFIX]).into_iter().map(|e| e.local_id).collect();
+        assert_eq!(commented, ["101", "103"]);
+    }
+
+    #[test]
+    fn apply_sync_matches_source_exactly() {
+        let raw = theme(&[("101", "1001"), ("102", "1002"), ("102", "1002")]);
+        let source = HashMap::from([("101".to_string(), value("9001")), ("103".to_string(), value("1003"))]);
+        let source = keyed(&source);
+        let plan = plan_sync(&raw, &source, &[PREFIX]).unwrap();
+        let local_ids = |keys: Vec<MappingKey>| keys.into_iter().map(|key| key.local_id).collect::<Vec<_>>();
+        assert_eq!(
+            (local_ids(plan.added), local_ids(plan.removed), local_ids(plan.changed)),
+            (vec!["103".to_string()], vec!["102".to_string()], vec!["101".to_string()])
+        );
+        let updated = apply_sync(&raw, &source, &[PREFIX]).unwrap();
+        assert_eq!(ids(&updated), [pair("101", "9001"), pair("103", "1003")]);
+        assert!(validate_json_content(&updated).is_ok());
+    }
+
+    #[test]
+    fn increment_version_preserves_formatting() {
+        let raw = theme(&[("101", "1001")]);
+        assert_eq!(extract_version(&raw), Some(3));
+        let updated = increment_version(&raw).unwrap();
+        assert_eq!(extract_version(&updated), Some(4));
+        assert!(updated.contains("  \"version\": 4,"));
+        assert_eq!(increment_version("{}").unwrap(), "{}");
+    }
+
+    #[test]
+    fn validate_reports_position() {
+        let err = validate_json_content("{\n  \"a\": 1,,\n}").unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(err.column.is_some());
+    }
+
+    #[test]
+    fn finds_entries_outside_ext_options() {
+        let raw = concat!(
+            "{\n",
+            "  \"portal_frag_1\": \"a.B|intent://?es_tabId=11\",\n",
+            "  \"sExtOptions\": {\n",
+            "    \"portal_frag_2\": \"a.B|intent://?es_tabId=22\"\n",
+            "  },\n",
+            "  \"other\": {\"portal_frag_3\": \"a.B|intent://?es_tabId=3\"},\n",
+            "  \"themes\": [{\"portal_frag_4\": 4}]\n",
+            "}\n"
+        );
+        let misplaced = find_misplaced_mappings(raw, &[PREFIX]);
+        let found: Vec<(&str, &str, Option<&str>)> = misplaced
+            .iter()
+            .map(|e| (e.local_id.as_str(), e.block_path.as_str(), e.gw_id.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [("1", "$", Some("11")), ("3", "$.other", Some("3")), ("4", "$.themes[0]", None)]
+        );
+        assert!(misplaced.iter().all(|e| e.status == "misplaced" && e.block_index.is_none()));
+        assert_eq!(parse_mappings(raw, &[PREFIX]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn records_entry_lines_and_offsets() {
+        let raw = concat!(
+            "{\n",
+            "  \"portal_frag_1\": \"a.B|intent://?es_tabId=11\",\n",
+            "  \"sExtOptions\": {\n",
+            "    \"portal_frag_2\": \"a.B|intent://?es_tabId=22\",\n",
+            "    // \"portal_frag_3\": \"a.B|intent://?es_tabId=33\",\n",
+            "    \"portal_frag_4\": \"a.B|intent://?es_tabId=44\"\n",
+            "  }\n",
+            "}\n"
+        );
+        let mut entries = parse_mappings(raw, &[PREFIX]).unwrap();
+        entries.extend(find_commented_mappings(raw, &[PREFIX]));
+        entries.extend(find_misplaced_mappings(raw, &[PREFIX]));
+        let found: Vec<(&str, usize)> = entries.iter().map(|e| (e.local_id.as_str(), e.line)).collect();
+        assert_eq!(found, [("2", 4), ("4", 6), ("3", 5), ("1", 2)]);
+        for entry in &entries {
+            assert!(raw[entry.offset..].starts_with(&format!("\"{}{}\"", PREFIX, entry.local_id)));
+        }
+    }
+
+    #[test]
+    fn renames_prefix_and_reports_collisions() {
+        let raw = theme(&[("101", "1001"), ("102", "1002")]).replace(
+            &format!("\"{}102\"", PREFIX),
+            &format!("\"portal_v2_101\": \"{}\",\n    \"{}102\"", value("9999"), PREFIX),
+        );
+        let renamed = rename_prefix_in_file(&raw, PREFIX, "portal_v2_").unwrap();
+        assert_eq!(renamed.local_ids, ["102"]);
+        assert_eq!(renamed.collisions, ["101"]);
+        assert!(renamed.content.contains(&format!("\"portal_v2_102\": \"{}\"", value("1002"))));
+        assert!(validate_json_content(&renamed.content).is_ok());
+
+        // 新前缀以旧前缀开头时，已迁移的 key 不会被再次修改
+        let migrated = rename_prefix_in_file(&theme(&[("101", "1001")]), PREFIX, "portal_frag_v2_").unwrap();
+        assert_eq!(migrated.local_ids, ["101"]);
+        let again = rename_prefix_in_file(&migrated.content, PREFIX, "portal_frag_v2_").unwrap();
+        assert!(again.local_ids.is_empty() && again.content == migrated.content);
+    }
+
+    #[test]
+    fn renders_and_reads_back_templates() {
+        let settings = AppSettings::default();
+        let entry = MappingInput {
+            local_id: "101".to_string(),
+            gw_id: "1001".to_string(),
+            ..Default::default()
+        };
+        let preview = render_template(&settings.template_value, &entry, &settings);
+        assert!(preview.problems.is_empty(), "{:?}", preview.problems);
+        assert_eq!(preview.parsed.unwrap().gw_id.as_deref(), Some("1001"));
+
+        let preview = render_template("com.a.B|intent://?tabId={id}", &entry, &settings);
+        assert_eq!(preview.problems, ["读回的映射值中没有 es_tabId，无法识别国网栏目ID"]);
+    }
+
+    #[test]
+    fn flags_and_normalizes_suspicious_duplicates() {
+        let raw = theme(&[("101", "1001"), ("0101", "2001"), ("102", "１００１"), ("103", "3001")]);
+        let statuses: Vec<String> = parse_mappings(&raw, &[PREFIX]).unwrap().into_iter().map(|e| e.status).collect();
+        assert_eq!(statuses, ["suspicious_duplicate", "suspicious_duplicate", "suspicious_duplicate", "normal"]);
+
+        let normalized = normalize_ids_in_file(&raw, &[PREFIX]).unwrap();
+        // 0101 规范后与 101 重名，不修改 key；全角国网栏目ID改为半角后成为国网ID重复
+        assert_eq!(
+            normalized.collisions,
+            [MappingKey {
+                prefix: PREFIX.to_string(),
+                local_id: "0101".to_string()
+            }]
+        );
+        let changes: Vec<(&str, IdField, &str)> = normalized
+            .changes
+            .iter()
+            .map(|change| (change.local_id.as_str(), change.field, change.to.as_str()))
+            .collect();
+        assert_eq!(changes, [("102", IdField::Gw, "1001")]);
+        let statuses: Vec<String> = parse_mappings(&normalized.content, &[PREFIX])
+            .unwrap()
+            .into_iter()
+            .map(|e| e.status)
+            .collect();
+        assert_eq!(statuses, ["duplicate_gw", "suspicious_duplicate", "duplicate_gw", "normal"]);
+    }
+
+    #[test]
+    fn parses_multiple_prefixes() {
+        let raw = theme(&[("101", "1001"), ("x_101", "2001"), ("102", "1002")]);
+        let entries = parse_mappings(&raw, &[PREFIX, "portal_frag_x_"]).unwrap();
+        let found: Vec<(&str, &str, &str)> = entries
+            .iter()
+            .map(|e| (e.prefix.as_str(), e.local_id.as_str(), e.status.as_str()))
+            .collect();
+        // 同时匹配两个前缀的 key 归入更长的前缀，不同前缀下的同名本地栏目ID不算重复
+        assert_eq!(
+            found,
+            [
+                (PREFIX, "101", "normal"),
+                ("portal_frag_x_", "101", "normal"),
+                (PREFIX, "102", "normal"),
+            ]
+        );
+
+        let input = MappingInput {
+            local_id: "103".to_string(),
+            gw_id: "2003".to_string(),
+            prefix: Some("portal_frag_x_".to_string()),
+            ..Default::default()
+        };
+        let inserted = insert_entries(&raw, &[input], &AppSettings::default()).unwrap();
+        let added = parse_mappings(&inserted, &[PREFIX, "portal_frag_x_"]).unwrap().pop().unwrap();
+        assert_eq!(
+            (added.prefix.as_str(), added.local_id.as_str(), added.gw_id.as_deref()),
+            ("portal_frag_x_", "103", Some("2003"))
+        );
+    }
+
+    #[test]
+    fn entry_edits_assign_overlapping_keys_to_the_longest_prefix() {
+        const EXTRA: &str = "portal_frag_x_";
+        let key = |prefix: &str, local_id: &str| MappingKey {
+            prefix: prefix.to_string(),
+            local_id: local_id.to_string(),
+        };
+        let raw = theme(&[("x_102", "1001"), ("102", "1002"), ("x_101", "1003"), ("101", "1001")]);
+        let spans = locate_entries(&raw, &[PREFIX, EXTRA]).unwrap();
+        let located: Vec<(&str, &str)> = spans.iter().map(|s| (s.prefix.as_str(), s.local_id.as_str())).collect();
+        assert_eq!(located, [(EXTRA, "102"), (PREFIX, "102"), (EXTRA, "101"), (PREFIX, "101")]);
+
+        let (_, replaced) = replace_gw_id_in_file(&raw, "1001", "2002", &[PREFIX, EXTRA]).unwrap();
+        assert_eq!(replaced, [key(EXTRA, "102"), key(PREFIX, "101")]);
+
+        // 按前缀在设置中的顺序分组，组内按本地栏目ID排序
+        let sorted = sort_entries(&raw, &[PREFIX, EXTRA], SortOrder::ByLocalId).unwrap();
+        let keys: Vec<(String, String)> = parse_mappings(&sorted, &[PREFIX, EXTRA])
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.prefix, entry.local_id))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (PREFIX.to_string(), "101".to_string()),
+                (PREFIX.to_string(), "102".to_string()),
+                (EXTRA.to_string(), "101".to_string()),
+                (EXTRA.to_string(), "102".to_string()),
+            ]
+        );
+
+        let commented = raw.replace("    \"portal_frag_x_101\"", "    // \"portal_frag_x_101\"");
+        let found = find_commented_mappings(&commented, &[PREFIX, EXTRA]);
+        let found: Vec<(&str, &str)> = found.iter().map(|e| (e.prefix.as_str(), e.local_id.as_str())).collect();
+        assert_eq!(found, [(EXTRA, "101")]);
+    }
+
+    #[test]
+    fn checks_and_sets_raw_values() {
+        assert!(check_raw_value(&value("1001")).is_ok());
+        assert!(check_raw_value("intent://page?es_tabId=1&custom=a=b").is_ok());
+        for bad in [
+            "",
+            "com.a.B|http://x?es_tabId=1",
+            "com.a.B|intent://",
+            "com.a.B|intent://?es_tabId=1&&x=1",
+            "com.a.B|intent://?es_tabId=1&es_tabId=2",
+            "com.a.B|intent://?es_tabId=1\"",
+            "com a.B|intent://?es_tabId=1",
+        ] {
+            assert!(check_raw_value(bad).is_err(), "{}", bad);
+        }
+
+        let raw = theme(&[("101", "1001"), ("102", "1002")]);
+        let custom = "com.a.B|intent://page?es_tabId=2002&mode=full";
+        let (updated, old) = set_raw_value_in_file(&raw, "102", PREFIX, None, custom).unwrap();
+        assert_eq!(old, [value("1002")]);
+        assert!(updated.contains(&format!("\"{}102\": \"{}\"", PREFIX, custom)));
+        assert_eq!(ids(&updated), [pair("101", "1001"), pair("102", "2002")]);
+        assert_eq!(
+            set_raw_value_in_file(&raw, "103", PREFIX, None, custom).unwrap_err(),
+            EditError::MappingNotFound { local_id: "103".to_string() }
+        );
+    }
+
+    #[test]
+    fn extracts_all_es_params() {
+        let params = extract_es_params("a.B|intent://?es_tabId=1&es_title=新闻&es_focusImg=&es_custom=x&mode=1&es_title=y");
+        let found: Vec<(&str, &str)> = params.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        assert_eq!(found, [("es_custom", "x"), ("es_title", "新闻")]);
+        assert!(extract_es_params("a.B|intent://").is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! 核心操作返回的错误
+//!
+//! 序列化为 `{"code": ..., "params": {...}}`，前端和脚本按 `code` 分支，不必匹配错误文本；
+//! 中文文本见各变体的 `#[error]`，[`Error::render`] 按语言渲染。尚未细分的错误代码为 `other`，原文放在 `params.text` 中。
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::{editor::EditError, i18n::Locale};
+
+#[derive(Debug, thiserror::Error, Serialize, Clone, PartialEq)]
+#[serde(tag = "code", content = "params", rename_all = "snake_case")]
+pub enum Error {
+    #[error("文件不存在: {file}")]
+    FileNotFound { file: String },
+    #[error("目录不存在: {path}")]
+    DirNotFound { path: String },
+    #[error("备份不存在: {path}")]
+    BackupNotFound { path: String },
+    #[error("压缩包不存在: {path}")]
+    ArchiveNotFound { path: String },
+    #[error("当前目录下未找到 theme*.json 文件")]
+    NoThemeFiles,
+    #[error("请至少输入一条映射关系。")]
+    NoEntries,
+    #[error("请至少选择一个目标文件")]
+    NoTargetFiles,
+    #[error("未匹配到任何选中的 theme 文件")]
+    NoSelectedFiles,
+    #[error("导入的映射为空")]
+    EmptyImport,
+    #[error("conflict: 文件在扫描后已被修改，请重新扫描后再操作：{file}")]
+    StaleConflict { file: String },
+    #[error("以下文件或目录无法写入，未修改任何文件：{paths}")]
+    NotWritable { paths: String },
+    #[error("以下本地栏目ID已存在，已取消本次写入：{ids}")]
+    LocalIdsExist { ids: String },
+    #[error("所有映射的国网栏目ID均不符合校验规则：{ids}")]
+    InvalidGwIds { ids: String },
+    #[error("以下国网栏目ID已被其他本地栏目ID使用，已取消本次写入：{ids}")]
+    GwIdsReused { ids: String },
+    #[error("读取文件失败: {file}: {io}")]
+    ReadFailed { file: String, io: String },
+    #[error("写入文件失败: {file}: {io}")]
+    WriteFailed { file: String, io: String },
+    #[error("修改后的内容校验失败，已保留原文件：{error}")]
+    ValidationFailed { error: String },
+    #[error("安全模式：文件已有结构问题，未做修改：{issues}")]
+    UnsafeToModify { issues: String },
+    #[error("{file} 中未找到 sExtOptions 段落")]
+    ExtOptionsMissing { file: String },
+    #[error("{text}")]
+    Other { text: String },
+    /// sExtOptions 的解析或修改错误，代码和参数与 [`EditError`] 相同
+    #[error(transparent)]
+    #[serde(untagged)]
+    Edit(#[from] EditError),
+}
+
+impl Error {
+    /// 按语言渲染错误文本
+    pub fn render(&self, locale: Locale) -> String {
+        match locale {
+            Locale::ZhCn => self.to_string(),
+            Locale::EnUs => self.english(),
+        }
+    }
+
+    fn english(&self) -> String {
+        match self {
+            Error::FileNotFound { file } => format!("File not found: {}", file),
+            Error::DirNotFound { path } => format!("Directory not found: {}", path),
+            Error::BackupNotFound { path } => format!("Backup not found: {}", path),
+            Error::ArchiveNotFound { path } => format!("Archive not found: {}", path),
+            Error::NoThemeFiles => "No theme*.json files found in this directory".into(),
+            Error::NoEntries => "Enter at least one mapping.".into(),
+            Error::NoTargetFiles => "Select at least one target file".into(),
+            Error::NoSelectedFiles => "None of the selected theme files were found".into(),
+            Error::EmptyImport => "The imported mapping table is empty".into(),
+            Error::StaleConflict { file } => format!("conflict: file changed after scanning, rescan and try again: {}", file),
+            Error::NotWritable { paths } => {
+                format!("These files or directories are not writable, nothing was changed: {}", paths)
+            }
+            Error::LocalIdsExist { ids } => format!("These local IDs already exist, nothing was written: {}", ids),
+            Error::InvalidGwIds { ids } => format!("No mapping has a valid GW ID: {}", ids),
+            Error::GwIdsReused { ids } => {
+                format!("These GW IDs are already used by other local IDs, nothing was written: {}", ids)
+            }
+            Error::ReadFailed { file, io } => format!("Failed to read file: {}: {}", file, io),
+            Error::WriteFailed { file, io } => format!("Failed to write file: {}: {}", file, io),
+            Error::ValidationFailed { error } => format!("Edited content failed validation, original file kept: {}", error),
+            Error::UnsafeToModify { issues } => {
+                format!("Safe mode: file already has structural problems and was left unchanged: {}", issues)
+            }
+            Error::ExtOptionsMissing { file } => format!("No sExtOptions block found in {}", file),
+            Error::Edit(err) => match err {
+                EditError::ExtOptionsNotFound => "No sExtOptions block found".into(),
+                EditError::MalformedExtOptions => "Malformed sExtOptions definition".into(),
+                EditError::ExtOptionsNotObject => "sExtOptions is not an object".into(),
+                EditError::UnterminatedExtOptions => "Could not find the end of sExtOptions".into(),
+                EditError::MappingNotFound { local_id } => format!("Local ID not found: {}", local_id),
+                EditError::UnknownTemplate { name } => format!("Value template not found: {}", name),
+                EditError::BlockNotFound { index } => format!("No sExtOptions block with index {}", index),
+                EditError::InvalidRawValue { reason } => format!("Invalid mapping value: {}", reason),
+            },
+            // 尚未细分的错误没有英文文本，保留原文
+            Error::Other { text } => text.clone(),
+        }
+    }
+
+    /// 单个文件的解析错误：未找到 sExtOptions 时带上文件路径，其余按 [`EditError`] 返回
+    pub fn in_file(file: &str) -> impl FnOnce(EditError) -> Error + '_ {
+        move |err| match err {
+            EditError::ExtOptionsNotFound => Error::ExtOptionsMissing { file: file.to_string() },
+            err => Error::Edit(err),
+        }
+    }
+
+    pub(crate) fn read_failed(path: &Path, io: impl ToString) -> Self {
+        Error::ReadFailed {
+            file: path.to_string_lossy().into_owned(),
+            io: io.to_string(),
+        }
+    }
+
+    pub(crate) fn write_failed(path: &Path, io: impl ToString) -> Self {
+        Error::WriteFailed {
+            file: path.to_string_lossy().into_owned(),
+            io: io.to_string(),
+        }
+    }
+}
+
+/// 尚未细分的字符串错误，便于在核心逻辑中直接用 `?` 传递
+impl From<String> for Error {
+    fn from(text: String) -> Self {
+        Error::Other { text }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_codes_and_params() {
+        let error = Error::WriteFailed {
+            file: "theme1.json".into(),
+            io: "拒绝访问".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({"code": "write_failed", "params": {"file": "theme1.json", "io": "拒绝访问"}})
+        );
+        assert_eq!(error.render(Locale::EnUs), "Failed to write file: theme1.json: 拒绝访问");
+        assert_eq!(serde_json::to_value(Error::NoThemeFiles).unwrap(), serde_json::json!({"code": "no_theme_files"}));
+
+        let error = Error::from(EditError::BlockNotFound { index: 2 });
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({"code": "block_not_found", "params": {"index": 2}})
+        );
+        assert_eq!(error.to_string(), "未找到序号为 2 的 sExtOptions 段落");
+        let error = Error::in_file("theme1.json")(EditError::ExtOptionsNotFound);
+        assert_eq!(error, Error::ExtOptionsMissing { file: "theme1.json".into() });
+
+        let error = Error::from("源文件不存在");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({"code": "other", "params": {"text": "源文件不存在"}})
+        );
+        assert_eq!(error.render(Locale::EnUs), "源文件不存在");
+    }
+}
@@ -0,0 +1,356 @@
+//! 自描述的映射导出文件
+//!
+//! 导出文件按 theme 文件分别记录映射，并带有格式版本、工具版本、导出时间、源目录和文件哈希，
+//! 导入时据此检查兼容性，并把映射写回同名文件，不会像普通映射表那样丢失按文件的结构。
+//!
+//! 另外可以只导出需要处理的问题条目（CSV 或 JSON），交给运营方核对，不用导出整张表再筛选。
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_theme_files, editor, file_io, file_name_lossy, settings::AppSettings, Error, FileMapping, MappingKey,
+};
+
+/// 导出文件的格式标识
+pub const EXPORT_FORMAT: &str = "categorymap-export";
+/// 当前的导出格式版本，格式不兼容地变化时递增
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingExport {
+    pub format: String,
+    pub version: u32,
+    /// 导出时的工具版本
+    pub tool_version: String,
+    /// 导出时间（RFC 3339）
+    pub exported_at: String,
+    pub source_dir: String,
+    /// 导出时设置的映射前缀
+    pub portal_prefix: String,
+    pub files: Vec<ExportedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedFile {
+    pub file_name: String,
+    /// 导出时文件内容的 SHA-256
+    pub file_hash: String,
+    /// 导出时文件的版本号
+    #[serde(default)]
+    pub version: Option<u32>,
+    pub mappings: Vec<ExportedMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMapping {
+    pub local_id: String,
+    pub gw_id: String,
+    /// 映射的前缀，None 表示映射前缀（portalPrefix）；旧版本的导出文件中没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+impl MappingExport {
+    /// 导出目录中所有 theme 文件的映射（被注释的映射不导出）
+    pub fn build(settings: &AppSettings, dir: &Path) -> Result<Self, Error> {
+        let mut files = Vec::new();
+        for path in collect_theme_files(dir)? {
+            let (raw, _) = file_io::read_text(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+            let mappings = editor::parse_mappings(&raw, &settings.prefixes())
+                .map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?
+                .into_iter()
+                .filter(|entry| entry.is_active())
+                .filter_map(|entry| {
+                    Some(ExportedMapping {
+                        gw_id: entry.gw_id?,
+                        local_id: entry.local_id,
+                        prefix: (entry.prefix != settings.portal_prefix).then_some(entry.prefix),
+                    })
+                })
+                .collect();
+            files.push(ExportedFile {
+                file_name: file_name_lossy(&path),
+                file_hash: file_io::content_hash(raw.as_bytes()),
+                version: editor::extract_version(&raw),
+                mappings,
+            });
+        }
+        if files.is_empty() {
+            return Err("当前目录下未找到 theme*.json 文件".into());
+        }
+        Ok(Self {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Local::now().to_rfc3339(),
+            source_dir: dir.to_string_lossy().into_owned(),
+            portal_prefix: settings.portal_prefix.clone(),
+            files,
+        })
+    }
+
+    pub fn mapping_count(&self) -> usize {
+        self.files.iter().map(|file| file.mappings.len()).sum()
+    }
+
+    /// 按文件名（小写）分组的映射表；没有前缀的映射按当前设置的映射前缀写入，当前未配置的前缀下的映射不导入
+    pub fn tables(&self, settings: &AppSettings) -> HashMap<String, HashMap<MappingKey, String>> {
+        let prefixes = settings.prefixes();
+        self.files
+            .iter()
+            .map(|file| {
+                let table = file
+                    .mappings
+                    .iter()
+                    .filter_map(|mapping| {
+                        let prefix = mapping.prefix.as_deref().unwrap_or(&settings.portal_prefix);
+                        prefixes.contains(&prefix).then(|| {
+                            let key = MappingKey {
+                                prefix: prefix.to_string(),
+                                local_id: mapping.local_id.clone(),
+                            };
+                            (key, mapping.gw_id.clone())
+                        })
+                    })
+                    .collect();
+                (file.file_name.to_lowercase(), table)
+            })
+            .collect()
+    }
+
+    /// 导入到目标目录前的兼容性提示：映射前缀不同、目标目录缺少文件、导回源目录时文件已在导出后被修改
+    pub fn compatibility_warnings(&self, settings: &AppSettings, dir: &Path, files: &[PathBuf]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.portal_prefix != settings.portal_prefix {
+            warnings.push(format!(
+                "导出时的映射前缀 {} 与当前设置 {} 不同，按当前设置写入",
+                self.portal_prefix, settings.portal_prefix
+            ));
+        }
+        let prefixes = settings.prefixes();
+        let mut unknown: Vec<&str> = self
+            .files
+            .iter()
+            .flat_map(|file| &file.mappings)
+            .filter_map(|mapping| mapping.prefix.as_deref())
+            .filter(|prefix| !prefixes.contains(prefix))
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        for prefix in unknown {
+            warnings.push(format!("导出文件中的映射前缀 {} 未在当前设置中配置，这些映射不会导入", prefix));
+        }
+        let same_dir = fs::canonicalize(dir).ok().is_some_and(|dir| {
+            fs::canonicalize(&self.source_dir).is_ok_and(|source| source == dir)
+        });
+        for exported in &self.files {
+            let target = files
+                .iter()
+                .find(|file| file_name_lossy(file).eq_ignore_ascii_case(&exported.file_name));
+            match target {
+                None => warnings.push(format!("导出文件中的 {} 在目标目录中不存在", exported.file_name)),
+                Some(file) if same_dir => {
+                    let changed = file_io::read_text(file)
+                        .map(|(raw, _)| file_io::content_hash(raw.as_bytes()) != exported.file_hash)
+                        .unwrap_or(false);
+                    if changed {
+                        warnings.push(format!("{} 在导出后已被修改", exported.file_name));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        warnings
+    }
+}
+
+/// 写入导出文件
+pub fn write(export: &MappingExport, output_path: &Path) -> Result<(), Error> {
+    let content = serde_json::to_string_pretty(export).map_err(|e| e.to_string())?;
+    fs::write(file_io::long_path(output_path), content).map_err(|e| Error::write_failed(output_path, e))
+}
+
+/// 问题条目清单的格式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ProblemFormat {
+    /// 清单文件的默认扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            ProblemFormat::Csv => "csv",
+            ProblemFormat::Json => "json",
+        }
+    }
+}
+
+/// 需要处理的映射条目：状态不是 normal、本地栏目ID与国网栏目ID相同或映射值不规范
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemEntry {
+    pub file_name: String,
+    pub local_id: String,
+    pub gw_id: Option<String>,
+    /// 问题列表：条目的状态（如 duplicate_local、misplaced），以及 same_id、non_standard
+    pub problems: Vec<String>,
+    pub raw_value: String,
+}
+
+/// 按文件顺序列出所有问题条目
+pub fn problem_entries(files: &[FileMapping]) -> Vec<ProblemEntry> {
+    files
+        .iter()
+        .flat_map(|file| {
+            file.mappings.iter().filter_map(|entry| {
+                let mut problems = Vec::new();
+                if entry.status != "normal" {
+                    problems.push(entry.status.clone());
+                }
+                if entry.same_id {
+                    problems.push("same_id".to_string());
+                }
+                if entry.non_standard {
+                    problems.push("non_standard".to_string());
+                }
+                if problems.is_empty() {
+                    return None;
+                }
+                Some(ProblemEntry {
+                    file_name: file_name_lossy(Path::new(&file.file_path)),
+                    local_id: entry.local_id.clone(),
+                    gw_id: entry.gw_id.clone(),
+                    problems,
+                    raw_value: entry.raw_value.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
+/// 渲染问题条目清单；CSV 带 BOM，便于直接用 Excel 打开
+pub fn render_problems(entries: &[ProblemEntry], format: ProblemFormat) -> Result<String, Error> {
+    match format {
+        ProblemFormat::Json => serde_json::to_string_pretty(entries).map_err(|e| Error::from(e.to_string())),
+        ProblemFormat::Csv => {
+            let mut content = String::from("\u{feff}文件,本地栏目ID,国网栏目ID,问题,映射值\n");
+            for entry in entries {
+                let fields = [
+                    entry.file_name.as_str(),
+                    entry.local_id.as_str(),
+                    entry.gw_id.as_deref().unwrap_or_default(),
+                    &entry.problems.join(";"),
+                    entry.raw_value.as_str(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                content.push_str(&fields.join(","));
+                content.push('\n');
+            }
+            Ok(content)
+        }
+    }
+}
+
+/// 包含逗号、引号或换行的字段用双引号包裹，引号写作 ""
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 读取导出文件，不是导出格式时返回 None（按普通映射表处理）
+pub fn detect(path: &Path) -> Result<Option<MappingExport>, Error> {
+    let (raw, _) = file_io::read_text(path).map_err(|e| Error::read_failed(path, e))?;
+    Ok(parse(&raw)?)
+}
+
+/// 解析导出文件并检查格式版本
+fn parse(raw: &str) -> Result<Option<MappingExport>, String> {
+    let Ok(value) = serde_json::from_str::<Value>(raw) else {
+        return Ok(None);
+    };
+    if value.get("format").and_then(Value::as_str) != Some(EXPORT_FORMAT) {
+        return Ok(None);
+    }
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version == 0 || version > u64::from(EXPORT_VERSION) {
+        return Err(format!(
+            "导出文件的格式版本 {} 不受支持（当前支持 {}），请升级工具后再导入",
+            version, EXPORT_VERSION
+        ));
+    }
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(|e| format!("导出文件格式错误: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::TextFormat;
+
+    #[test]
+    fn parse_checks_format_and_version() {
+        let export = r#"{
+            "format": "categorymap-export", "version": 1, "toolVersion": "0.1.0",
+            "exportedAt": "2024-01-01T00:00:00+08:00", "sourceDir": "D:/themes", "portalPrefix": "portal_",
+            "files": [{"fileName": "theme1.json", "fileHash": "abc",
+                       "mappings": [{"localId": "101", "gwId": "9001"}]}]
+        }"#;
+        let parsed = parse(export).unwrap().unwrap();
+        assert_eq!(parsed.mapping_count(), 1);
+        let key = MappingKey {
+            prefix: "portal_frag_".to_string(),
+            local_id: "101".to_string(),
+        };
+        assert_eq!(parsed.tables(&AppSettings::default())["theme1.json"][&key], "9001");
+
+        assert!(parse(r#"{"101": "9001"}"#).unwrap().is_none());
+        assert!(parse("101,9001").unwrap().is_none());
+        assert!(parse(&export.replace(r#""version": 1"#, r#""version": 2"#)).is_err());
+    }
+
+    #[test]
+    fn lists_only_problem_entries() {
+        let raw = r#"{
+  "sExtOptions": {
+    "portal_frag_101": "a.B|intent://?es_tabId=9001&es_title=",
+    "portal_frag_102": "a.B|intent://?es_tabId=102&es_title=",
+    "portal_frag_103": "a.B|intent://?es_tabId=9001&es_title="
+  }
+}"#;
+        let file =
+            crate::file_mapping_from_text("theme1.json".to_string(), raw, TextFormat::default(), &AppSettings::default())
+                .unwrap();
+        let entries = problem_entries(&[file]);
+        let summary: Vec<(&str, Vec<&str>)> = entries
+            .iter()
+            .map(|entry| (entry.local_id.as_str(), entry.problems.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            [("101", vec!["duplicate_gw"]), ("102", vec!["same_id"]), ("103", vec!["duplicate_gw"])]
+        );
+        let csv = render_problems(&entries[1..2], ProblemFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "\u{feff}文件,本地栏目ID,国网栏目ID,问题,映射值\ntheme1.json,102,102,same_id,a.B|intent://?es_tabId=102&es_title=\n"
+        );
+    }
+}
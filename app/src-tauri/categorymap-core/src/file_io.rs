@@ -0,0 +1,452 @@
+use encoding_rs::{GB18030, UTF_8};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::profile;
+
+/// 计算内容的 SHA-256（小写十六进制）
+pub fn content_hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// theme 文件的文本编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    #[serde(rename = "UTF-8")]
+    Utf8,
+    /// 部分旧版配置工具导出的 GBK 文件按 GB18030（GBK 的超集）处理
+    #[serde(rename = "GB18030")]
+    Gb18030,
+}
+
+/// 文本文件的编码信息，写回时原样保留
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextFormat {
+    pub encoding: TextEncoding,
+    /// 是否以 UTF-8 BOM 开头
+    pub has_bom: bool,
+}
+
+/// 换行符风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// 同时存在 LF 和 CRLF
+    Mixed,
+    /// 只有一行
+    None,
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 识别编码并解码：合法的 UTF-8 按 UTF-8 处理（去掉 BOM），否则尝试 GB18030
+pub fn decode(bytes: &[u8]) -> io::Result<(String, TextFormat)> {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        if let Some(text) = UTF_8.decode_without_bom_handling_and_without_replacement(rest) {
+            let format = TextFormat {
+                encoding: TextEncoding::Utf8,
+                has_bom: true,
+            };
+            return Ok((text.into_owned(), format));
+        }
+    }
+    if let Some(text) = UTF_8.decode_without_bom_handling_and_without_replacement(bytes) {
+        return Ok((text.into_owned(), TextFormat::default()));
+    }
+    if let Some(text) = GB18030.decode_without_bom_handling_and_without_replacement(bytes) {
+        let format = TextFormat {
+            encoding: TextEncoding::Gb18030,
+            has_bom: false,
+        };
+        return Ok((text.into_owned(), format));
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "无法识别文件编码（仅支持 UTF-8 和 GBK/GB18030）",
+    ))
+}
+
+/// 按指定格式编码文本
+pub fn encode(text: &str, format: TextFormat) -> Vec<u8> {
+    match format.encoding {
+        TextEncoding::Utf8 if format.has_bom => [UTF8_BOM, text.as_bytes()].concat(),
+        TextEncoding::Utf8 => text.as_bytes().to_vec(),
+        TextEncoding::Gb18030 => GB18030.encode(text).0.into_owned(),
+    }
+}
+
+/// 识别文本的换行符风格
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (lf, crlf) {
+        (0, 0) => LineEnding::None,
+        (_, 0) => LineEnding::Lf,
+        (0, _) => LineEnding::Crlf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// 读取文本文件并识别编码
+pub fn read_text(path: &Path) -> io::Result<(String, TextFormat)> {
+    let long = long_path(path);
+    let content = profile::measure(path, profile::Stage::Read, || retry_io(path, || fs::read(&long)))?;
+    decode(&content)
+}
+
+/// 按原格式（编码、BOM）原子写入文本文件
+pub fn write_text_atomic(path: &Path, text: &str, format: TextFormat) -> io::Result<()> {
+    write_atomic(path, encode(text, format))
+}
+
+/// 原子写入文件：先写入同目录下的临时文件并落盘，再重命名覆盖原文件
+///
+/// 写入过程中崩溃或磁盘写满时，原文件保持不变。
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let long = long_path(path);
+    profile::measure(path, profile::Stage::Write, || {
+        retry_io(path, || write_atomic_once(&long, content.as_ref()))
+    })
+}
+
+fn write_atomic_once(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "无效的文件路径"))?;
+    let tmp_path = dir.join(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write_and_replace(&tmp_path, path, content);
+    if result.is_err() {
+        // 清理残留的临时文件，忽略清理失败
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn write_and_replace(tmp_path: &Path, path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    // 保留原文件的权限
+    #[cfg(unix)]
+    if let Ok(meta) = fs::metadata(path) {
+        fs::set_permissions(tmp_path, meta.permissions())?;
+    }
+
+    fs::rename(tmp_path, path)?;
+
+    // 同步目录项，确保重命名本身也已落盘（尽力而为）
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir_file) = fs::File::open(dir) {
+            let _ = dir_file.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// Windows 下超过此长度（UTF-16 字符数）的路径改用扩展长度形式；创建目录的上限比文件路径的 260 更小
+#[cfg(any(windows, test))]
+const LONG_PATH_THRESHOLD: usize = 248;
+
+/// 转成系统可以访问的路径：Windows 下过长的路径转成扩展长度形式
+/// （`\\?\C:\...`、`\\?\UNC\server\share\...`），其他平台原样返回
+///
+/// 只用于实际的文件系统调用，返回给前端和写入日志的仍是原路径。
+pub fn long_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            match std::env::current_dir() {
+                Ok(cwd) => cwd.join(path),
+                Err(_) => return path.to_path_buf(),
+            }
+        };
+        match extended_length_form(&absolute.to_string_lossy()) {
+            Some(long) => PathBuf::from(long),
+            None => path.to_path_buf(),
+        }
+    }
+    #[cfg(not(windows))]
+    path.to_path_buf()
+}
+
+/// 绝对路径的扩展长度形式，路径不够长、已是扩展形式或不是绝对路径时返回 None
+///
+/// 扩展长度形式不会再由系统处理 `/`、`.` 和 `..`，这里先自行规范化。
+#[cfg(any(windows, test))]
+fn extended_length_form(path: &str) -> Option<String> {
+    if path.encode_utf16().count() < LONG_PATH_THRESHOLD || path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    let (prefix, root_parts, rest) = if let Some(unc) = path.strip_prefix(r"\\") {
+        // \\server\share 是根，不能被 .. 越过
+        let mut parts = unc.splitn(3, '\\');
+        let server = parts.next().filter(|p| !p.is_empty())?;
+        let share = parts.next().filter(|p| !p.is_empty())?;
+        (r"\\?\UNC\", format!("{}\\{}", server, share), parts.next().unwrap_or_default().to_string())
+    } else {
+        let bytes = path.as_bytes();
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' || bytes[2] != b'\\' {
+            return None;
+        }
+        (r"\\?\", path[..2].to_string(), path[3..].to_string())
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            part => components.push(part),
+        }
+    }
+    let mut long = format!("{}{}", prefix, root_parts);
+    for part in components {
+        long.push('\\');
+        long.push_str(part);
+    }
+    if long.ends_with(':') {
+        long.push('\\');
+    }
+    Some(long)
+}
+
+/// 网络共享上的临时错误最多重试的次数
+const MAX_RETRIES: u32 = 3;
+/// 第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Windows 下网络共享（SMB）的临时错误码：共享冲突、锁冲突、网络忙、意外的网络错误、
+/// 网络名不再可用、信号灯超时、网络不可达、连接被中止
+#[cfg(windows)]
+const TRANSIENT_WINDOWS_ERRORS: &[i32] = &[32, 33, 54, 59, 64, 121, 1231, 1236];
+
+/// 一次因临时错误而进行的重试
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IoRetry {
+    pub path: String,
+    /// 第几次重试（从 1 开始）
+    pub attempt: u32,
+    pub error: String,
+}
+
+/// 一次操作的重试记录，由 [`RetryScope`] 创建；并行任务中用 [`RetryContext::enter`] 记入同一次操作
+#[derive(Clone, Default)]
+pub struct RetryContext(Option<Arc<Mutex<Vec<IoRetry>>>>);
+
+thread_local! {
+    /// 当前线程正在执行的操作的重试记录
+    static CURRENT_RETRIES: RefCell<RetryContext> = RefCell::new(RetryContext::default());
+}
+
+impl RetryContext {
+    /// 当前线程所在操作的重试记录，没有操作在收集时为空
+    pub fn current() -> Self {
+        CURRENT_RETRIES.with(|current| current.borrow().clone())
+    }
+
+    /// 之后当前线程上的重试记入这次操作，直到返回值被丢弃时恢复原来的记录
+    ///
+    /// 并行处理时在 rayon 的 `map_init` 中调用，使工作线程上的重试也记入发起操作的记录。
+    pub fn enter(&self) -> EnteredRetries {
+        EnteredRetries(CURRENT_RETRIES.with(|current| current.replace(self.clone())))
+    }
+
+    fn push(&self, retry: IoRetry) {
+        if let Some(records) = &self.0 {
+            records.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(retry);
+        }
+    }
+}
+
+/// 被丢弃时恢复线程原来的重试记录，见 [`RetryContext::enter`]
+pub struct EnteredRetries(RetryContext);
+
+impl Drop for EnteredRetries {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.0);
+        CURRENT_RETRIES.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// 收集一次操作期间（包括其中的并行任务）发生的重试，用于在操作结果中报告
+///
+/// 记录只属于开始收集的线程上的这次操作，同时进行的其他操作互不可见。
+pub struct RetryScope {
+    context: RetryContext,
+    _previous: EnteredRetries,
+}
+
+impl RetryScope {
+    pub fn begin() -> Self {
+        let context = RetryContext(Some(Arc::default()));
+        Self {
+            _previous: context.enter(),
+            context,
+        }
+    }
+
+    /// 结束收集，返回开始以来的重试记录
+    pub fn finish(self) -> Vec<IoRetry> {
+        self.context
+            .0
+            .as_ref()
+            .map(|records| records.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone())
+            .unwrap_or_default()
+    }
+}
+
+/// 是否为可以重试的临时错误（超时、连接中断，以及 Windows 网络共享的临时错误）
+fn is_transient(err: &io::Error) -> bool {
+    if matches!(
+        err.kind(),
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    ) {
+        return true;
+    }
+    #[cfg(windows)]
+    if let Some(code) = err.raw_os_error() {
+        return TRANSIENT_WINDOWS_ERRORS.contains(&code);
+    }
+    false
+}
+
+/// 执行文件操作，遇到临时错误时按指数退避重试，重试记录到当前的 [`RetryScope`]
+pub fn retry_io<T>(path: &Path, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(err) if attempt < MAX_RETRIES && is_transient(&err) => {
+                attempt += 1;
+                CURRENT_RETRIES.with(|current| {
+                    current.borrow().push(IoRetry {
+                        path: path.to_string_lossy().into_owned(),
+                        attempt,
+                        error: err.to_string(),
+                    })
+                });
+                thread::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_paths_use_extended_form() {
+        let dir = "a".repeat(250);
+        assert_eq!(extended_length_form(r"C:\short\theme.json"), None);
+        assert_eq!(
+            extended_length_form(&format!(r"C:\x\..\{}/.\theme.json", dir)),
+            Some(format!(r"\\?\C:\{}\theme.json", dir))
+        );
+        assert_eq!(
+            extended_length_form(&format!(r"\\server\share\{}\..\..\theme.json", dir)),
+            Some(r"\\?\UNC\server\share\theme.json".to_string())
+        );
+        assert_eq!(extended_length_form(&format!(r"\\?\C:\{}", dir)), None);
+        assert_eq!(extended_length_form(&format!(r"relative\{}", dir)), None);
+    }
+
+    #[test]
+    fn retries_transient_errors_only() {
+        let scope = RetryScope::begin();
+        let mut calls = 0;
+        let result = retry_io(Path::new("theme.json"), || {
+            calls += 1;
+            if calls < 3 {
+                Err(io::Error::new(io::ErrorKind::Interrupted, "busy"))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        let retries = scope.finish();
+        assert_eq!(retries.iter().map(|r| r.attempt).collect::<Vec<_>>(), [1, 2]);
+
+        let mut calls = 0;
+        let result: io::Result<()> = retry_io(Path::new("theme.json"), || {
+            calls += 1;
+            Err(io::Error::new(io::ErrorKind::NotFound, "missing"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retries_belong_to_their_own_operation() {
+        let interrupted_once = |path: &'static str| {
+            let mut calls = 0;
+            retry_io(Path::new(path), || {
+                calls += 1;
+                if calls < 2 {
+                    Err(io::Error::new(io::ErrorKind::Interrupted, "busy"))
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap();
+        };
+        let scope = RetryScope::begin();
+        interrupted_once("a.json");
+        // 同时进行的另一次操作看不到本次操作的重试
+        let other = thread::spawn(move || {
+            let scope = RetryScope::begin();
+            interrupted_once("b.json");
+            scope.finish()
+        });
+        // 并行任务进入本次操作的记录后，重试记入本次操作
+        let context = RetryContext::current();
+        thread::spawn(move || {
+            let _entered = context.enter();
+            interrupted_once("c.json");
+        })
+        .join()
+        .unwrap();
+        let paths = |retries: Vec<IoRetry>| retries.into_iter().map(|retry| retry.path).collect::<Vec<_>>();
+        assert_eq!(paths(other.join().unwrap()), ["b.json"]);
+        assert_eq!(paths(scope.finish()), ["a.json", "c.json"]);
+        interrupted_once("d.json");
+        assert!(RetryContext::current().0.is_none());
+    }
+}
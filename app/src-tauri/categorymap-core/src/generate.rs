@@ -0,0 +1,200 @@
+//! 按ID范围或模式批量生成待新增的映射，结果交给批量新增的预览和 `run_bulk_insert`
+//!
+//! 范围写法：逗号分隔的多项，每项为单个ID或 `起始-结束` 范围，如 `1001-1050,1060`；
+//! 起始值带前导零时按起始值的位数补零（`0001-0050`）。
+//! 模式写法：`模式:范围`，模式中用 printf 风格的 `%d`、`%05d` 表示序号，如 `col_%03d:1-20`。
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{master, MappingInput};
+
+/// 一次最多生成的映射条数
+const MAX_GENERATED: usize = 10_000;
+
+/// 生成国网栏目ID的规则
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum GwIdRule {
+    /// 与本地栏目ID相同（占位，之后按 same_id 修正）
+    Same,
+    /// 序号加上偏移量，可用 printf 风格的模式格式化，如 `90%06d`
+    #[serde(rename_all = "camelCase")]
+    Offset { offset: i64, pattern: Option<String> },
+    /// 按对照表（CSV 或 JSON，格式同主映射表）查找
+    #[serde(rename_all = "camelCase")]
+    Lookup { path: String },
+}
+
+/// 展开范围写法，返回（序号, 本地栏目ID）
+pub fn expand_range(range_spec: &str) -> Result<Vec<(i64, String)>, String> {
+    let mut ids = Vec::new();
+    for item in range_spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let (pattern, range) = match item.rsplit_once(':') {
+            Some((pattern, range)) => (Some(Pattern::parse(pattern)?), range.trim()),
+            None => (None, item),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (range, range),
+        };
+        let first = parse_number(start, item)?;
+        let last = parse_number(end, item)?;
+        if last < first {
+            return Err(format!("范围的结束值小于起始值: {}", item));
+        }
+        if ids.len() as i64 + (last - first) >= MAX_GENERATED as i64 {
+            return Err(format!("一次最多生成 {} 条映射", MAX_GENERATED));
+        }
+        // 起始值带前导零时保持位数
+        let width = if start.len() > 1 && start.starts_with('0') { start.len() } else { 0 };
+        for number in first..=last {
+            let local_id = match &pattern {
+                Some(pattern) => pattern.format(number),
+                None => format!("{:0>width$}", number),
+            };
+            ids.push((number, local_id));
+        }
+    }
+    if ids.is_empty() {
+        return Err("请输入ID范围，如 1001-1050".into());
+    }
+    Ok(ids)
+}
+
+/// 按范围和国网栏目ID规则生成映射，重复的本地栏目ID只保留第一次出现
+pub fn generate(range_spec: &str, gw_id_rule: &GwIdRule) -> Result<Vec<MappingInput>, String> {
+    let ids = expand_range(range_spec)?;
+    let lookup = match gw_id_rule {
+        GwIdRule::Lookup { path } => Some(master::load(Path::new(path))?),
+        _ => None,
+    };
+    let gw_pattern = match gw_id_rule {
+        GwIdRule::Offset { pattern: Some(pattern), .. } => Some(Pattern::parse(pattern)?),
+        _ => None,
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    let mut missing = Vec::new();
+    let mut entries = Vec::new();
+    for (number, local_id) in ids {
+        if !seen.insert(local_id.clone()) {
+            continue;
+        }
+        let gw_id = match gw_id_rule {
+            GwIdRule::Same => local_id.clone(),
+            GwIdRule::Offset { offset, .. } => {
+                let value = number
+                    .checked_add(*offset)
+                    .filter(|value| *value >= 0)
+                    .ok_or_else(|| format!("{} 加上偏移量后超出范围", local_id))?;
+                match &gw_pattern {
+                    Some(pattern) => pattern.format(value),
+                    None => value.to_string(),
+                }
+            }
+            GwIdRule::Lookup { .. } => match lookup.as_ref().and_then(|table| table.get(&local_id)) {
+                Some(gw_id) => gw_id.clone(),
+                None => {
+                    missing.push(local_id);
+                    continue;
+                }
+            },
+        };
+        entries.push(MappingInput {
+            local_id,
+            gw_id,
+            ..Default::default()
+        });
+    }
+    if !missing.is_empty() {
+        return Err(format!("以下本地栏目ID在对照表中不存在：{}", missing.join("、")));
+    }
+    Ok(entries)
+}
+
+fn parse_number(text: &str, item: &str) -> Result<i64, String> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("ID范围格式错误: {}", item));
+    }
+    text.parse().map_err(|_| format!("ID范围中的数字过大: {}", item))
+}
+
+/// printf 风格的模式，只含一个 `%d`（可带宽度和补零），`%%` 表示百分号
+#[derive(Debug)]
+struct Pattern {
+    prefix: String,
+    suffix: String,
+    width: usize,
+    zero_pad: bool,
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        let invalid = || format!("模式中应有且只有一个 %d: {}", pattern);
+        let (mut prefix, mut suffix) = (String::new(), String::new());
+        let mut spec: Option<(usize, bool)> = None;
+        let mut chars = pattern.chars().peekable();
+        while let Some(ch) = chars.next() {
+            let text = if spec.is_some() { &mut suffix } else { &mut prefix };
+            if ch != '%' {
+                text.push(ch);
+                continue;
+            }
+            if chars.next_if_eq(&'%').is_some() {
+                text.push('%');
+                continue;
+            }
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            if chars.next() != Some('d') || spec.is_some() {
+                return Err(invalid());
+            }
+            spec = Some((digits.parse().unwrap_or(0), digits.starts_with('0')));
+        }
+        let (width, zero_pad) = spec.ok_or_else(invalid)?;
+        Ok(Self {
+            prefix,
+            suffix,
+            width,
+            zero_pad,
+        })
+    }
+
+    fn format(&self, number: i64) -> String {
+        let width = self.width;
+        let number = if self.zero_pad {
+            format!("{:0>width$}", number)
+        } else {
+            format!("{:>width$}", number)
+        };
+        format!("{}{}{}", self.prefix, number, self.suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_ranges_and_patterns() {
+        let ids: Vec<String> = expand_range("1001-1003, 1010").unwrap().into_iter().map(|(_, id)| id).collect();
+        assert_eq!(ids, ["1001", "1002", "1003", "1010"]);
+        let ids: Vec<String> = expand_range("0008-0010").unwrap().into_iter().map(|(_, id)| id).collect();
+        assert_eq!(ids, ["0008", "0009", "0010"]);
+        let ids: Vec<String> = expand_range("col_%03d:9-10").unwrap().into_iter().map(|(_, id)| id).collect();
+        assert_eq!(ids, ["col_009", "col_010"]);
+        assert!(expand_range("1050-1001").is_err());
+        assert!(expand_range("a_%d_%d:1-2").is_err());
+
+        let rule = GwIdRule::Offset {
+            offset: 100,
+            pattern: Some("90%04d".to_string()),
+        };
+        let entries = generate("1-2,1", &rule).unwrap();
+        let pairs: Vec<(&str, &str)> = entries.iter().map(|e| (e.local_id.as_str(), e.gw_id.as_str())).collect();
+        assert_eq!(pairs, [("1", "900101"), ("2", "900102")]);
+    }
+}
@@ -0,0 +1,215 @@
+//! 目标目录是 git 工作区时的版本管理集成
+//!
+//! 开启设置中的 git 自动提交后，每次修改操作完成后在后台把修改过的 theme 文件暂存并提交，
+//! 提交说明由操作类型、映射条数和操作人自动生成。只提交本次修改的文件，不带上工作区里其他已暂存的改动。
+
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::OperationLogRecord;
+
+/// 工作区中有改动的文件
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitChange {
+    pub file_path: String,
+    /// git status --porcelain 的两位状态码，如 `M`、`??`、`A`
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// 目录是否位于 git 工作区中
+    pub is_work_tree: bool,
+    /// 工作区根目录
+    pub root: Option<String>,
+    /// 当前分支，游离 HEAD 时为 None
+    pub branch: Option<String>,
+    /// 目录下有改动的文件
+    pub changes: Vec<GitChange>,
+}
+
+/// 自动提交生成的一次提交
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommit {
+    pub root: String,
+    pub commit_id: String,
+    pub files: Vec<String>,
+}
+
+/// 在 dir 中执行 git 命令，失败时返回 stderr 中的错误信息
+fn run(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    // Windows 下不弹出控制台窗口
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output().map_err(|e| format!("无法执行 git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(if stderr.is_empty() {
+            format!("git 退出码 {}", output.status.code().unwrap_or(-1))
+        } else {
+            stderr
+        })
+    }
+}
+
+/// 目录所在 git 工作区的根目录，不在工作区中（或未安装 git）时返回 None
+fn work_tree_root(dir: &Path) -> Option<PathBuf> {
+    let stdout = run(dir, &["rev-parse", "--show-toplevel"]).ok()?;
+    let root = stdout.trim();
+    (!root.is_empty()).then(|| PathBuf::from(root))
+}
+
+/// 查询目标目录的 git 状态，不是 git 工作区时 is_work_tree 为 false
+pub fn status(target_dir: &Path) -> Result<GitStatus, String> {
+    if !target_dir.is_dir() {
+        return Err(format!("目录不存在: {}", target_dir.display()));
+    }
+    let Some(root) = work_tree_root(target_dir) else {
+        return Ok(GitStatus::default());
+    };
+    let stdout = run(target_dir, &["status", "--porcelain=v1", "--branch", "-z", "--", "."])?;
+    let (branch, changes) = parse_status(&stdout);
+    Ok(GitStatus {
+        is_work_tree: true,
+        root: Some(root.to_string_lossy().into_owned()),
+        branch,
+        changes: changes
+            .into_iter()
+            .map(|change| GitChange {
+                file_path: root.join(&change.file_path).to_string_lossy().into_owned(),
+                ..change
+            })
+            .collect(),
+    })
+}
+
+/// 解析 `git status --porcelain=v1 --branch -z` 的输出，文件路径相对于工作区根目录
+fn parse_status(stdout: &str) -> (Option<String>, Vec<GitChange>) {
+    let mut branch = None;
+    let mut changes = Vec::new();
+    let mut records = stdout.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("## ") {
+            let name = header.strip_prefix("No commits yet on ").unwrap_or(header);
+            let name = name.split("...").next().unwrap_or_default();
+            let name = name.split_whitespace().next().unwrap_or_default();
+            branch = (!name.is_empty() && name != "HEAD").then(|| name.to_string());
+            continue;
+        }
+        let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+            continue;
+        };
+        // 重命名和复制后面跟着原路径，跳过
+        if code.starts_with('R') || code.starts_with('C') {
+            records.next();
+        }
+        changes.push(GitChange {
+            file_path: path.to_string(),
+            status: code.trim().to_string(),
+        });
+    }
+    (branch, changes)
+}
+
+/// 按操作日志生成提交说明：首行为操作类型和条数，正文为操作人和附加信息
+pub fn commit_message(record: &OperationLogRecord) -> (String, String) {
+    let mut counts = Vec::new();
+    if !record.added.is_empty() {
+        counts.push(format!("新增 {} 条映射", record.added.len()));
+    }
+    if !record.deleted.is_empty() {
+        counts.push(format!("删除 {} 条映射", record.deleted.len()));
+    }
+    counts.push(format!("修改 {} 个文件", record.updated_files.len()));
+    let subject = format!("{}：{}", record.operation_label, counts.join("，"));
+
+    let mut body = format!("操作人: {}\n时间: {}\n", record.operator, record.timestamp);
+    if let Some(info) = &record.additional_info {
+        body.push_str(&format!("附加信息: {}\n", info));
+    }
+    body.push_str(&format!("操作日志: operation_{}.log", record.id));
+    (subject, body)
+}
+
+/// 暂存并提交操作修改过的本地文件，按所在工作区分别提交；不在 git 工作区中的文件忽略
+pub fn commit_operation(record: &OperationLogRecord) -> Result<Vec<GitCommit>, String> {
+    // 远程、设备上的路径和压缩包内的条目不是本地文件，不参与提交
+    let mut repos: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let mut roots: BTreeMap<PathBuf, Option<PathBuf>> = BTreeMap::new();
+    for file in &record.updated_files {
+        let path = Path::new(file);
+        if !path.is_absolute() || !path.is_file() {
+            continue;
+        }
+        let Some(dir) = path.parent() else { continue };
+        let root = roots
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| work_tree_root(dir))
+            .clone();
+        if let Some(root) = root {
+            repos.entry(root).or_default().push(file.clone());
+        }
+    }
+
+    let (subject, body) = commit_message(record);
+    let mut commits = Vec::new();
+    for (root, files) in repos {
+        let paths: Vec<&str> = files.iter().map(String::as_str).collect();
+        run(&root, &[&["add", "--"][..], &paths].concat())?;
+        // 内容与已提交版本相同时没有可提交的内容
+        let staged = run(&root, &[&["diff", "--cached", "--name-only", "--"][..], &paths].concat())?;
+        if staged.trim().is_empty() {
+            continue;
+        }
+        run(
+            &root,
+            &[&["commit", "-m", &subject, "-m", &body, "--only", "--"][..], &paths].concat(),
+        )
+        .map_err(|e| format!("提交到 {} 失败: {}", root.display(), e))?;
+        let commit_id = run(&root, &["rev-parse", "--short", "HEAD"])?.trim().to_string();
+        commits.push(GitCommit {
+            root: root.to_string_lossy().into_owned(),
+            commit_id,
+            files,
+        });
+    }
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_porcelain_status() {
+        let stdout = "## master...origin/master [ahead 1]\0 M theme/theme1.json\0?? theme2.json\0R  new.json\0old.json\0";
+        let (branch, changes) = parse_status(stdout);
+        assert_eq!(branch.as_deref(), Some("master"));
+        let codes: Vec<(&str, &str)> = changes
+            .iter()
+            .map(|change| (change.status.as_str(), change.file_path.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![("M", "theme/theme1.json"), ("??", "theme2.json"), ("R", "new.json")]
+        );
+
+        assert_eq!(parse_status("## No commits yet on main\0").0.as_deref(), Some("main"));
+        assert_eq!(parse_status("## HEAD (no branch)\0").0, None);
+    }
+}
@@ -0,0 +1,321 @@
+//! 操作日志的存放、轮转和读取
+//!
+//! 日志放在备份和日志存放目录（见 [`crate::backup::storage_dir`]）下的日志目录中（默认 `logs`），
+//! 不再与 theme 文件混在一起被部署到设备。旧版本直接放在存放目录中的日志在首次使用时移入日志目录。
+
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    settings::{AppSettings, LogRetention},
+    OperationLogRecord, OperationType,
+};
+
+/// 默认的操作日志目录名
+pub const DEFAULT_LOG_DIR_NAME: &str = "logs";
+const LOG_FILE_PREFIX: &str = "operation_";
+
+/// 存放目录对应的日志目录
+pub fn log_dir(storage_dir: &Path, settings: &AppSettings) -> PathBuf {
+    let name = settings.log_dir_name.trim();
+    storage_dir.join(if name.is_empty() { DEFAULT_LOG_DIR_NAME } else { name })
+}
+
+/// 创建日志目录，并把存放目录中旧版本留下的日志移入其中，返回日志目录
+pub fn prepare_log_dir(storage_dir: &Path, settings: &AppSettings) -> Result<PathBuf, String> {
+    let dir = log_dir(storage_dir, settings);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    if storage_dir.is_dir() {
+        for ext in ["json", "log"] {
+            for (_, path) in list_log_files(storage_dir, ext)? {
+                let Some(name) = path.file_name() else {
+                    continue;
+                };
+                // 移动失败（如文件被占用）时保留原处，下次再试
+                if let Err(e) = fs::rename(&path, dir.join(name)) {
+                    eprintln!("移动操作日志 {} 失败: {}", path.display(), e);
+                }
+            }
+        }
+    }
+    Ok(dir)
+}
+
+/// 新建一次操作的文本日志文件，返回文件和实际使用的日志ID
+///
+/// 同一 ID 的日志（文本或 JSON）已存在时（同一毫秒内的多次操作）在 ID 后追加序号，不覆盖已有日志。
+pub fn create_log_file(dir: &Path, id: &str) -> Result<(fs::File, String), String> {
+    for attempt in 1u32.. {
+        let candidate = if attempt == 1 { id.to_string() } else { format!("{}-{}", id, attempt) };
+        if dir.join(format!("{}{}.json", LOG_FILE_PREFIX, candidate)).exists() {
+            continue;
+        }
+        let path = dir.join(format!("{}{}.log", LOG_FILE_PREFIX, candidate));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, candidate)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("创建日志文件失败: {}", e)),
+        }
+    }
+    Err("创建日志文件失败: 没有可用的日志ID".into())
+}
+
+/// 按轮转策略删除最旧的日志（同一次操作的文本和 JSON 日志一起删除），返回删除的日志ID
+pub fn rotate_logs(dir: &Path, retention: &LogRetention) -> Result<Vec<String>, String> {
+    let mut operations: BTreeMap<String, (u64, Vec<PathBuf>)> = BTreeMap::new();
+    for ext in ["json", "log"] {
+        for (id, path) in list_log_files(dir, ext)? {
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            let operation = operations.entry(id).or_default();
+            operation.0 += size;
+            operation.1.push(path);
+        }
+    }
+    let sizes: Vec<(String, u64)> = operations.iter().map(|(id, (size, _))| (id.clone(), *size)).collect();
+    let expired = plan_rotation(&sizes, retention);
+    for id in &expired {
+        for path in &operations[id].1 {
+            fs::remove_file(path).map_err(|e| format!("删除旧日志 {} 失败: {}", path.display(), e))?;
+        }
+    }
+    Ok(expired)
+}
+
+/// 计算需要删除的日志：从最新的开始保留，超出数量或总大小上限后的都删除；最新的一次操作总是保留
+fn plan_rotation(operations: &[(String, u64)], retention: &LogRetention) -> Vec<String> {
+    let mut newest_first: Vec<&(String, u64)> = operations.iter().collect();
+    newest_first.sort_by(|a, b| b.0.cmp(&a.0));
+    let max_bytes = retention.max_total_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let mut total = 0u64;
+    let mut expired = Vec::new();
+    for (index, (id, size)) in newest_first.into_iter().enumerate() {
+        total = total.saturating_add(*size);
+        let over_count = retention.keep_last.is_some_and(|keep| index >= keep);
+        let over_size = max_bytes.is_some_and(|max| total > max);
+        if index > 0 && (over_count || over_size) {
+            expired.push(id.clone());
+        }
+    }
+    expired
+}
+
+/// 操作历史中的一条记录
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// 日志ID（日志文件名中的时间戳，如 20240101-120000-123）
+    pub id: String,
+    /// 操作类型，旧版文本日志无法识别时为 None
+    pub operation_type: Option<OperationType>,
+    pub operation_label: String,
+    /// 操作时间
+    pub timestamp: String,
+    pub operator: Option<String>,
+    pub affected_files: Vec<String>,
+    pub added_count: usize,
+    pub deleted_count: usize,
+    pub skipped_count: usize,
+    pub backup_dir: Option<String>,
+    /// 日志文件路径
+    pub log_file: String,
+    /// 是否来自结构化 JSON 日志（可用于重放）
+    pub structured: bool,
+}
+
+/// 读取目录下的操作日志，按时间倒序返回最近 `limit` 条
+///
+/// 同一次操作同时存在 JSON 和文本日志时优先使用 JSON 日志。
+pub fn load_history(dir: &Path, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    let mut entries = Vec::new();
+    for (_, path) in list_log_files(dir, "json")? {
+        match load_record(&path) {
+            Ok(record) => entries.push(from_record(record, &path)),
+            Err(e) => eprintln!("解析操作日志失败 {}: {}", path.display(), e),
+        }
+    }
+    for (id, path) in list_log_files(dir, "log")? {
+        if entries.iter().any(|entry| entry.id == id) {
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(raw) => entries.push(parse_text_log(&id, &raw, &path)),
+            Err(e) => eprintln!("读取操作日志失败 {}: {}", path.display(), e),
+        }
+    }
+
+    entries.sort_by(|a, b| b.id.cmp(&a.id));
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+    Ok(entries)
+}
+
+/// 读取指定ID的结构化操作日志
+pub fn find_record(dir: &Path, id: &str) -> Result<OperationLogRecord, String> {
+    let path = dir.join(format!("{}{}.json", LOG_FILE_PREFIX, id));
+    if !path.is_file() {
+        return Err(format!("未找到操作 {} 的结构化日志，仅支持重放带 JSON 日志的操作", id));
+    }
+    load_record(&path)
+}
+
+fn load_record(path: &Path) -> Result<OperationLogRecord, String> {
+    let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&raw).map_err(|e| e.to_string())
+}
+
+/// 列出目录下 operation_<id>.<ext> 格式的日志文件
+fn list_log_files(dir: &Path, ext: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    if !dir.is_dir() {
+        return Err(format!("目录不存在: {}", dir.display()));
+    }
+    let suffix = format!(".{}", ext);
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some(id) = name
+            .strip_prefix(LOG_FILE_PREFIX)
+            .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+        {
+            files.push((id.to_string(), path.clone()));
+        }
+    }
+    Ok(files)
+}
+
+fn from_record(record: OperationLogRecord, path: &Path) -> HistoryEntry {
+    HistoryEntry {
+        id: record.id,
+        operation_type: Some(record.operation_type),
+        operation_label: record.operation_label,
+        timestamp: record.timestamp,
+        operator: Some(record.operator),
+        affected_files: record.updated_files,
+        added_count: record.added.len(),
+        deleted_count: record.deleted.len(),
+        skipped_count: record.skipped_files.len(),
+        backup_dir: record.backup_dir,
+        log_file: path.to_string_lossy().into_owned(),
+        structured: true,
+    }
+}
+
+/// 解析旧版文本日志（仅有 operation_*.log 时使用）
+fn parse_text_log(id: &str, raw: &str, path: &Path) -> HistoryEntry {
+    let mut entry = HistoryEntry {
+        id: id.to_string(),
+        operation_type: None,
+        operation_label: String::new(),
+        timestamp: String::new(),
+        operator: None,
+        affected_files: Vec::new(),
+        added_count: 0,
+        deleted_count: 0,
+        skipped_count: 0,
+        backup_dir: None,
+        log_file: path.to_string_lossy().into_owned(),
+        structured: false,
+    };
+
+    // 当前所在的段落，用于收集文件列表
+    let mut in_updated_files = false;
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(time) = trimmed.strip_prefix("操作日志 - ") {
+            entry.timestamp = time.to_string();
+        } else if let Some(label) = trimmed.strip_prefix("操作类型: ") {
+            entry.operation_label = label.to_string();
+            entry.operation_type = OperationType::from_label(label);
+        } else if let Some(backup) = trimmed.strip_prefix("备份路径: ") {
+            if !backup.starts_with("无") {
+                entry.backup_dir = Some(backup.to_string());
+            }
+        } else if trimmed.starts_with("新增的映射详情") {
+            entry.added_count = section_count(trimmed);
+        } else if trimmed.starts_with("删除的映射详情") {
+            entry.deleted_count = section_count(trimmed);
+        } else if trimmed.starts_with("跳过的文件") {
+            entry.skipped_count = section_count(trimmed);
+        }
+
+        if trimmed.starts_with("成功处理的文件") {
+            in_updated_files = true;
+            continue;
+        }
+        if in_updated_files {
+            match trimmed.strip_prefix("- ") {
+                Some(file) => entry.affected_files.push(file.to_string()),
+                None => in_updated_files = false,
+            }
+        }
+    }
+    entry
+}
+
+/// 从形如 "新增的映射详情 (3 条):" 的标题中取出数量
+fn section_count(line: &str) -> usize {
+    line.split_once('(')
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_keeps_newest_within_limits() {
+        let mb = 1024 * 1024;
+        let operations: Vec<(String, u64)> = ["20240103-000000", "20240101-000000", "20240102-000000", "20240104-000000"]
+            .iter()
+            .map(|id| (id.to_string(), mb))
+            .collect();
+        let by_count = LogRetention {
+            keep_last: Some(2),
+            max_total_mb: None,
+        };
+        assert_eq!(plan_rotation(&operations, &by_count), ["20240102-000000", "20240101-000000"]);
+        let by_size = LogRetention {
+            keep_last: None,
+            max_total_mb: Some(3),
+        };
+        assert_eq!(plan_rotation(&operations, &by_size), ["20240101-000000"]);
+        // 单次操作的日志超过总大小上限时仍保留
+        let tiny = LogRetention {
+            keep_last: None,
+            max_total_mb: Some(1),
+        };
+        assert_eq!(plan_rotation(&operations[..1], &tiny), Vec::<String>::new());
+    }
+
+    #[test]
+    fn log_ids_never_overwrite_existing_logs() {
+        let dir = std::env::temp_dir().join(format!("catmap-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let id = "20240101-120000-123";
+        let (_, first) = create_log_file(&dir, id).unwrap();
+        let (_, second) = create_log_file(&dir, id).unwrap();
+        fs::write(dir.join("operation_20240101-120000-456.json"), "{}").unwrap();
+        let (_, third) = create_log_file(&dir, "20240101-120000-456").unwrap();
+        assert_eq!([first.as_str(), second.as_str(), third.as_str()], [id, "20240101-120000-123-2", "20240101-120000-456-2"]);
+        // 旧版本精确到秒的ID排在同一秒内的新ID之前
+        let mut ids = vec![second, "20240101-120000".to_string(), first, third];
+        ids.sort();
+        assert_eq!(ids, ["20240101-120000", id, "20240101-120000-123-2", "20240101-120000-456-2"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
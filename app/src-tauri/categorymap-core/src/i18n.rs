@@ -0,0 +1,221 @@
+//! 后端消息的多语言支持
+//!
+//! 跳过原因用消息代码加参数表示（[`Message`]），按设置中的 `locale` 渲染为中文或英文；
+//! 代码和参数随结果一起返回，前端也可以自行翻译。
+//!
+//! 命令的错误统一包装为 [`CommandError`]，代码和参数来自 [`Error`]，前端和脚本按 `code` 分支，不必匹配错误文本。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{settings::AppSettings, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    ZhCn,
+    EnUs,
+}
+
+impl Locale {
+    /// 按语言标签识别，`en` 开头的为英文，其余按中文处理
+    pub fn from_tag(tag: &str) -> Self {
+        if tag.trim().to_ascii_lowercase().starts_with("en") {
+            Locale::EnUs
+        } else {
+            Locale::ZhCn
+        }
+    }
+
+    pub fn of(settings: &AppSettings) -> Self {
+        Self::from_tag(&settings.locale)
+    }
+
+    /// 按语言选择文本
+    pub fn pick(self, zh: &'static str, en: &'static str) -> &'static str {
+        match self {
+            Locale::ZhCn => zh,
+            Locale::EnUs => en,
+        }
+    }
+}
+
+/// 消息代码，前端按代码翻译
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageCode {
+    /// 未收录的消息，原文放在 text 参数中
+    Other,
+    FileNotFound,
+    ReadFailed,
+    WriteFailed,
+    StaleSkipped,
+    UnsafeToModify,
+    RemoteModified,
+    AllMappingsExist,
+    DuplicateIds,
+    PartialDuplicate,
+    NothingChanged,
+    AlreadyInSync,
+    ValidationFailed,
+    DeleteValidationFailed,
+    SyncValidationFailed,
+    ReplaceValidationFailed,
+    PartialDeleteFailed,
+    DeleteFailed,
+    PartiallyProcessed,
+    SameAsDevice,
+    NotInExport,
+}
+
+impl MessageCode {
+    /// 消息模板，参数写作 `{名称}`；中文模板与改造前的原文一致
+    pub fn template(self, locale: Locale) -> &'static str {
+        match self {
+            MessageCode::Other => "{text}",
+            MessageCode::FileNotFound => locale.pick("文件不存在", "File not found"),
+            MessageCode::ReadFailed => locale.pick("读取文件失败: {error}", "Failed to read file: {error}"),
+            MessageCode::WriteFailed => locale.pick("写入文件失败: {error}", "Failed to write file: {error}"),
+            MessageCode::StaleSkipped => locale.pick(
+                "conflict: 文件在扫描后已被修改，已拒绝写入，请重新扫描",
+                "conflict: file changed after scanning, write refused; please rescan",
+            ),
+            MessageCode::UnsafeToModify => locale.pick(
+                "安全模式：文件已有结构问题，未做修改：{issues}",
+                "Safe mode: file already has structural problems and was left unchanged: {issues}",
+            ),
+            MessageCode::RemoteModified => locale.pick(
+                "远程文件在扫描后已被修改，请重新扫描后再操作",
+                "Remote file changed after scanning, rescan and try again",
+            ),
+            MessageCode::AllMappingsExist => locale.pick("所有映射已存在", "All mappings already exist"),
+            MessageCode::DuplicateIds => locale.pick("本地栏目ID重复：{ids}", "Duplicate local IDs: {ids}"),
+            MessageCode::PartialDuplicate => locale.pick(
+                "部分ID重复（已跳过）：{ids}",
+                "Some IDs already exist (skipped): {ids}",
+            ),
+            MessageCode::NothingChanged => locale.pick("所有映射均未变化", "No mapping changed"),
+            MessageCode::AlreadyInSync => locale.pick("映射已与源文件一致", "Mappings already match the source file"),
+            MessageCode::ValidationFailed => locale.pick(
+                "修改后的内容校验失败，已保留原文件：{error}",
+                "Edited content failed validation, original file kept: {error}",
+            ),
+            MessageCode::DeleteValidationFailed => locale.pick(
+                "删除后的内容校验失败，已保留原文件：{error}",
+                "Content failed validation after deleting, original file kept: {error}",
+            ),
+            MessageCode::SyncValidationFailed => locale.pick(
+                "同步后的内容校验失败，已保留原文件：{error}",
+                "Content failed validation after syncing, original file kept: {error}",
+            ),
+            MessageCode::ReplaceValidationFailed => locale.pick(
+                "替换后的内容校验失败，已保留原文件：{error}",
+                "Content failed validation after replacing, original file kept: {error}",
+            ),
+            MessageCode::PartialDeleteFailed => locale.pick(
+                "部分ID未找到或删除失败：{ids}",
+                "Some IDs were not found or could not be deleted: {ids}",
+            ),
+            MessageCode::DeleteFailed => locale.pick("所有ID删除失败：{ids}", "Failed to delete all IDs: {ids}"),
+            MessageCode::PartiallyProcessed => locale.pick("部分映射未处理", "Some mappings were not processed"),
+            MessageCode::SameAsDevice => locale.pick("与设备上的文件相同", "Identical to the file on the device"),
+            MessageCode::NotInExport => locale.pick("导出文件中没有该文件的映射", "The export has no mappings for this file"),
+        }
+    }
+}
+
+/// 消息代码和参数
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub code: MessageCode,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+impl Message {
+    pub fn new(code: MessageCode) -> Self {
+        Self {
+            code,
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// 未收录的原文
+    pub fn other(text: impl ToString) -> Self {
+        Self::new(MessageCode::Other).with("text", text)
+    }
+
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn render(&self, locale: Locale) -> String {
+        let mut text = self.code.template(locale).to_string();
+        for (name, value) in &self.params {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+/// 命令返回的结构化错误，序列化为 `{"code": ..., "params": {...}, "message": ...}`
+///
+/// 代码和参数来自核心逻辑返回的 [`Error`]，message 是按语言渲染好的文本，可直接展示。
+/// 没有默认语言的转换，命令需按当前设置的语言调用 [`CommandError::new`]。
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CommandError {
+    #[serde(flatten)]
+    pub error: Error,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(error: impl Into<Error>, locale: Locale) -> Self {
+        let error = error.into();
+        let message = error.render(locale);
+        Self { error, message }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::EditError;
+
+    #[test]
+    fn renders_messages() {
+        let message = Message::new(MessageCode::ReadFailed).with("error", "拒绝访问");
+        assert_eq!(message.render(Locale::ZhCn), "读取文件失败: 拒绝访问");
+        assert_eq!(message.render(Locale::EnUs), "Failed to read file: 拒绝访问");
+        assert_eq!(Message::other("源文件不存在").render(Locale::EnUs), "源文件不存在");
+        assert_eq!(Locale::from_tag("en-US"), Locale::EnUs);
+        assert_eq!(Locale::from_tag("zh-CN"), Locale::ZhCn);
+    }
+
+    #[test]
+    fn wraps_errors_with_codes() {
+        let error = CommandError::new(EditError::BlockNotFound { index: 2 }, Locale::EnUs);
+        assert_eq!(error.message, "No sExtOptions block with index 2");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "block_not_found",
+                "params": {"index": 2},
+                "message": "No sExtOptions block with index 2",
+            })
+        );
+        let error = CommandError::new(Error::DirNotFound { path: "/data/themes".into() }, Locale::EnUs);
+        assert_eq!(error.message, "Directory not found: /data/themes");
+        let error = CommandError::new("源文件不存在", Locale::EnUs);
+        assert_eq!(error.error, Error::Other { text: "源文件不存在".into() });
+        assert_eq!(error.message, "源文件不存在");
+    }
+}
@@ -0,0 +1,137 @@
+//! 目标目录中的 .catmapignore：列出不参与扫描和修改的 theme 文件
+//!
+//! 每行一个文件名通配符（`*` 匹配任意个字符，`?` 匹配单个字符），`#` 开头的行为注释，
+//! `!` 开头表示重新包含之前被排除的文件。多条规则匹配同一文件时以最后一条为准。
+//! 只按文件名匹配，不区分大小写（与 Windows 文件系统一致）。
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::file_io;
+
+pub const IGNORE_FILE_NAME: &str = ".catmapignore";
+
+/// 被忽略规则排除的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoredFile {
+    pub file_path: String,
+    /// 命中的规则
+    pub pattern: String,
+    /// 规则所在行号（从 1 开始）
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    line: usize,
+    pattern: String,
+    negated: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// 读取目录中的 .catmapignore，文件不存在时返回空规则
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(IGNORE_FILE_NAME);
+        if !file_io::long_path(&path).is_file() {
+            return Ok(Self::default());
+        }
+        let (text, _) = file_io::read_text(&path).map_err(|e| format!("读取 {} 失败: {}", IGNORE_FILE_NAME, e))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line_text = line.trim();
+                if line_text.is_empty() || line_text.starts_with('#') {
+                    return None;
+                }
+                let (negated, pattern) = match line_text.strip_prefix('!') {
+                    Some(rest) => (true, rest.trim()),
+                    None => (false, line_text),
+                };
+                (!pattern.is_empty()).then(|| IgnoreRule {
+                    line: index + 1,
+                    pattern: pattern.to_string(),
+                    negated,
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 文件被排除时返回命中的规则（规则, 行号）
+    pub fn matched(&self, file_name: &str) -> Option<(&str, usize)> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| glob_match(&rule.pattern, file_name))
+            .filter(|rule| !rule.negated)
+            .map(|rule| (rule.pattern.as_str(), rule.line))
+    }
+}
+
+/// 不区分大小写的通配符匹配，支持 `*` 和 `?`
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // 最近一个 `*` 的位置及其匹配到的名称位置，失配时回溯
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_wildcards_case_insensitively() {
+        assert!(glob_match("theme_test*.json", "theme_test1.json"));
+        assert!(glob_match("theme_test*.json", "THEME_TEST.json"));
+        assert!(glob_match("theme?.json", "theme2.json"));
+        assert!(!glob_match("theme?.json", "theme.json"));
+        assert!(glob_match("*sample*", "theme_sample_a.json"));
+        assert!(!glob_match("theme_test*.json", "theme1.json"));
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let rules = IgnoreRules::parse("# 测试文件\ntheme_test*.json\n\n!theme_test_keep.json\n");
+        assert_eq!(rules.matched("theme_test1.json"), Some(("theme_test*.json", 2)));
+        assert_eq!(rules.matched("theme_test_keep.json"), None);
+        assert_eq!(rules.matched("theme1.json"), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! 映射值的结构：`栏目类名|intent://目标?key=value&...`
+//!
+//! 解析出栏目类名和 intent 参数，并按默认模板的布局生成规范形式：标准参数（es_tabId、es_title、
+//! es_focusStartColor、es_focusEndColor、es_focusImg）按模板顺序排在前面，其余参数保持原顺序排在后面，
+//! 去掉参数两侧的空白，百分号编码统一为大写。规范化不增删参数，也不改动参数的取值。
+
+/// 默认模板中标准参数的顺序
+pub const STANDARD_PARAMS: [&str; 5] = ["es_tabId", "es_title", "es_focusStartColor", "es_focusEndColor", "es_focusImg"];
+
+/// 解析后的映射值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentValue {
+    /// `|` 之前的栏目类名，值中没有 `|` 时为 None
+    pub fragment_class: Option<String>,
+    /// `intent://` 与 `?` 之间的部分，默认模板中为空
+    pub target: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl IntentValue {
+    pub fn parse(raw_value: &str) -> Result<Self, String> {
+        let (fragment_class, uri) = match raw_value.split_once('|') {
+            Some((class, uri)) => {
+                let class = class.trim();
+                if class.is_empty() || class.chars().any(char::is_whitespace) {
+                    return Err("| 之前的栏目类名不能为空或包含空白".into());
+                }
+                (Some(class.to_string()), uri.trim())
+            }
+            None => (None, raw_value.trim()),
+        };
+        let Some(rest) = uri.strip_prefix("intent://") else {
+            return Err("缺少 intent:// 地址".into());
+        };
+        if rest.contains('|') {
+            return Err("intent 地址中不能包含 |".into());
+        }
+        let Some((target, query)) = rest.split_once('?') else {
+            return Err("intent 地址中缺少 ? 参数".into());
+        };
+        let mut params: Vec<(String, String)> = Vec::new();
+        for param in query.split('&') {
+            let Some((key, value)) = param.split_once('=').filter(|(key, _)| !key.is_empty()) else {
+                return Err(format!("参数 {} 应为 key=value 格式", param));
+            };
+            if params.iter().any(|(existing, _)| existing == key) {
+                return Err(format!("参数 {} 重复", key));
+            }
+            params.push((key.to_string(), value.to_string()));
+        }
+        Ok(Self {
+            fragment_class,
+            target: target.to_string(),
+            params,
+        })
+    }
+
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 规范形式：标准参数按模板顺序在前，百分号编码大写
+    pub fn canonical(&self) -> Self {
+        let rank = |key: &str| {
+            STANDARD_PARAMS
+                .iter()
+                .position(|standard| *standard == key)
+                .unwrap_or(STANDARD_PARAMS.len())
+        };
+        let mut params: Vec<(String, String)> = self
+            .params
+            .iter()
+            .map(|(key, value)| (key.trim().to_string(), canonical_encoding(value.trim())))
+            .collect();
+        // 稳定排序，非标准参数保持原顺序
+        params.sort_by_key(|(key, _)| rank(key));
+        Self {
+            fragment_class: self.fragment_class.clone(),
+            target: canonical_encoding(&self.target),
+            params,
+        }
+    }
+
+    pub fn to_value(&self) -> String {
+        let params: Vec<String> = self.params.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        let uri = format!("intent://{}?{}", self.target, params.join("&"));
+        match &self.fragment_class {
+            Some(class) => format!("{}|{}", class, uri),
+            None => uri,
+        }
+    }
+}
+
+/// 映射值的规范形式，无法解析时返回 None
+pub fn normalize_value(raw_value: &str) -> Option<String> {
+    IntentValue::parse(raw_value).ok().map(|value| value.canonical().to_value())
+}
+
+/// 映射值是否偏离模板布局：无法解析、缺少栏目类名或 es_tabId、不是规范形式
+pub fn is_non_standard(raw_value: &str) -> bool {
+    let Ok(value) = IntentValue::parse(raw_value) else {
+        return true;
+    };
+    value.fragment_class.is_none()
+        || value.param("es_tabId").map(str::trim).unwrap_or_default().is_empty()
+        || value.canonical().to_value() != raw_value
+}
+
+/// 把百分号编码中的十六进制数字改为大写
+fn canonical_encoding(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < text.len() {
+        let escaped = bytes[index] == b'%'
+            && bytes
+                .get(index + 1..index + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+        if escaped {
+            result.push('%');
+            result.push_str(&text[index + 1..index + 3].to_ascii_uppercase());
+            index += 3;
+        } else {
+            let ch = text[index..].chars().next().unwrap_or_default();
+            result.push(ch);
+            index += ch.len_utf8();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_to_template_layout() {
+        let standard = "com.a.B|intent://?es_tabId=1001&es_title=%E6%96%B0&es_focusImg=";
+        assert!(!is_non_standard(standard));
+        assert_eq!(normalize_value(standard).as_deref(), Some(standard));
+
+        let messy = "com.a.B|intent://?es_title=%e6%96%b0&mode=full&es_tabId= 1001";
+        assert!(is_non_standard(messy));
+        assert_eq!(
+            normalize_value(messy).as_deref(),
+            Some("com.a.B|intent://?es_tabId=1001&es_title=%E6%96%B0&mode=full")
+        );
+
+        assert!(is_non_standard("intent://?es_tabId=1001"));
+        assert!(is_non_standard("com.a.B|intent://?es_title=x"));
+        assert_eq!(normalize_value("com.a.B|intent://?es_tabId=1&es_tabId=2"), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! categorymap-core：栏目映射的核心逻辑，包括扫描 theme 文件、修改 sExtOptions 映射、备份和操作日志
+//!
+//! 不依赖 Tauri，桌面端和命令行工具（bin/catmap.rs）共用；sExtOptions 的文本级编辑见 [`editor`]。
+//! 扫描和各项修改操作按操作分模块放在 ops 下，公共类型在 types，操作日志的写入在 oplog，都从 crate 根导出。
+
+mod oplog;
+mod ops;
+mod types;
+
+pub mod adb;
+pub mod archive;
+pub mod backup;
+pub mod background;
+pub mod cancel;
+pub mod catalog;
+pub mod cst;
+pub mod devices;
+pub mod diff;
+pub mod duplicates;
+pub mod editor;
+pub mod error;
+pub mod export;
+pub mod file_io;
+pub mod generate;
+pub mod git;
+pub mod history;
+pub mod i18n;
+pub mod ignore;
+pub mod intent;
+pub mod lint;
+pub mod master;
+pub mod paging;
+pub mod parse_cache;
+pub mod preflight;
+pub mod profile;
+pub mod remote;
+pub mod repair;
+pub mod report;
+pub mod same_id;
+pub mod search;
+pub mod settings;
+pub mod snapshot;
+pub mod stats;
+pub mod validation;
+pub mod webdav;
+pub mod webhook;
+pub mod workspace;
+
+pub use editor::{
+    apply_sync, extract_gw_id, extract_intent_param, extract_version, find_commented_mappings,
+    find_ext_options_block, find_ext_options_blocks, find_misplaced_mappings, fragment_class, increment_version,
+    insert_entries, insert_entry_lines, locate_entries, locate_portal_entries, matching_prefix, merge_mappings_in_file, parse_mappings, plan_sync,
+    comment_out_entry_spans, remove_entry_spans, remove_mapping_from_block, remove_mapping_from_file, replace_gw_id_in_file,
+    replace_gw_id_in_value, replace_intent_param, replace_mappings_in_file, set_gw_ids_in_file, sort_entries,
+    validate_json_content, EditError, EntrySpan, ExtOptionsBlock, IntentParams, JsonSyntaxError,
+};
+pub use error::Error;
+pub use oplog::*;
+pub use ops::*;
+pub use types::*;
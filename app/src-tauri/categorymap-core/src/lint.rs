@@ -0,0 +1,404 @@
+//! theme 文件的结构检查：找出解析时被容忍或忽略的问题，并给出行列号和严重程度
+//!
+//! 手工编辑的文件有语法错误时，扫描只能报“未找到 sExtOptions 段落”之类的错误，
+//! [`validate_syntax`] 给出出错的行列号和附近的源码，便于定位。
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cst::{self, ObjectCst},
+    editor::{extract_gw_id, find_ext_options_blocks, validate_json_content},
+};
+
+/// 上下文片段中出错行前后各显示的行数
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
+/// 问题的严重程度
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// 会导致映射失效或被覆盖
+    Error,
+    /// 可以工作，但部分解析器或设备上可能出问题
+    Warning,
+}
+
+/// 检查发现的一个问题
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    pub line: usize,
+    pub column: usize,
+    pub severity: LintSeverity,
+    /// 问题类型：syntax_error、duplicate_key、trailing_comma、non_string_value、missing_tab_id、empty_gw_id、invalid_intent
+    pub code: String,
+    pub message: String,
+}
+
+/// 检查文件内容，问题按出现位置排序；prefixes 为所有映射前缀
+pub fn lint(raw: &str, prefixes: &[&str]) -> Vec<LintIssue> {
+    let mut linter = Linter { raw, issues: Vec::new() };
+    if let Err(err) = validate_json_content(raw) {
+        linter.issues.push(LintIssue {
+            line: err.line.unwrap_or(1),
+            column: err.column.unwrap_or(1),
+            severity: LintSeverity::Error,
+            code: "syntax_error".to_string(),
+            message: err.message,
+        });
+        return linter.issues;
+    }
+
+    let root = cst::skip_trivia(raw, 0);
+    linter.walk_value(root, prefixes);
+    if let Ok(blocks) = find_ext_options_blocks(raw) {
+        for block in blocks {
+            if let Ok(object) = ObjectCst::parse(raw, block.start) {
+                linter.check_portal_entries(&object, prefixes);
+            }
+        }
+    }
+
+    let mut issues = linter.issues;
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+/// 语法检查发现的问题，带出错位置附近的源码
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxIssue {
+    #[serde(flatten)]
+    pub issue: LintIssue,
+    /// 出错行及前后几行，带行号，出错列下方用 ^ 标出
+    pub snippet: String,
+}
+
+/// 检查文件语法，问题类型：
+/// - syntax_error：JSON5 解析失败，扫描和写入都会失败
+/// - strict_syntax：JSON5 可以解析，但严格的 JSON（只额外允许注释）不接受，如末尾逗号、单引号、不带引号的 key，
+///   部分设备上的解析器可能无法读取
+/// - missing_ext_options：语法正确，但没有可用的 sExtOptions 段落
+pub fn validate_syntax(raw: &str) -> Vec<SyntaxIssue> {
+    let issue = |line: usize, column: usize, severity: LintSeverity, code: &str, message: String| SyntaxIssue {
+        snippet: snippet(raw, line, column),
+        issue: LintIssue {
+            line,
+            column,
+            severity,
+            code: code.to_string(),
+            message,
+        },
+    };
+    if let Err(err) = validate_json_content(raw) {
+        let (line, column) = (err.line.unwrap_or(1), err.column.unwrap_or(1));
+        return vec![issue(line, column, LintSeverity::Error, "syntax_error", err.message)];
+    }
+    let mut issues = Vec::new();
+    if let Err(err) = serde_json::from_str::<serde_json::Value>(&blank_comments(raw)) {
+        let line = err.line().max(1);
+        let column = byte_column_to_char(raw, line, err.column());
+        let message = format!("严格的 JSON 解析器不接受：{}", err);
+        issues.push(issue(line, column, LintSeverity::Warning, "strict_syntax", message));
+    }
+    match find_ext_options_blocks(raw) {
+        Ok(blocks) if !blocks.is_empty() => {}
+        Ok(_) => issues.push(issue(1, 1, LintSeverity::Error, "missing_ext_options", "未找到 sExtOptions 段落".into())),
+        Err(err) => issues.push(issue(1, 1, LintSeverity::Error, "missing_ext_options", err.to_string())),
+    }
+    issues
+}
+
+/// 把注释替换为空格（保留换行），使严格的 JSON 解析器报告的行列号与原文一致
+fn blank_comments(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'/' if matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')) => {
+                let end = if bytes[i + 1] == b'/' {
+                    raw[i..].find('\n').map(|pos| i + pos).unwrap_or(bytes.len())
+                } else {
+                    raw[i + 2..].find("*/").map(|pos| i + pos + 4).unwrap_or(bytes.len())
+                };
+                for byte in &mut out[i..end] {
+                    if !byte.is_ascii_whitespace() {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    // 注释中的多字节字符被整体替换为空格，结果仍是合法的 UTF-8
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// 把 serde_json 报告的字节列号（从 1 开始）换算为字符列号
+fn byte_column_to_char(raw: &str, line: usize, column: usize) -> usize {
+    let text = raw.lines().nth(line - 1).unwrap_or_default();
+    let mut end = column.saturating_sub(1).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].chars().count() + 1
+}
+
+/// 出错行及前后几行源码，出错列下方用 ^ 标出（行号、列号从 1 开始）
+fn snippet(raw: &str, line: usize, column: usize) -> String {
+    let lines: Vec<&str> = raw.lines().collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let line = line.clamp(1, lines.len());
+    let first = line.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+    let last = (line + SNIPPET_CONTEXT_LINES).min(lines.len());
+    let width = last.to_string().len();
+    let mut out = String::new();
+    for number in first..=last {
+        let text = lines[number - 1];
+        out.push_str(&format!("{:>width$} | {}\n", number, text, width = width));
+        if number == line {
+            // 制表符原样保留，使 ^ 与出错字符对齐
+            let pad: String = text
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+            out.push_str(&format!("{:>width$} | {}^\n", "", pad, width = width));
+        }
+    }
+    out
+}
+
+struct Linter<'a> {
+    raw: &'a str,
+    issues: Vec<LintIssue>,
+}
+
+impl Linter<'_> {
+    fn push(&mut self, offset: usize, severity: LintSeverity, code: &str, message: String) {
+        let (line, column) = position(self.raw, offset);
+        self.issues.push(LintIssue {
+            line,
+            column,
+            severity,
+            code: code.to_string(),
+            message,
+        });
+    }
+
+    /// 递归检查对象和数组：重复的 key（portal_frag_ 的重复由扫描结果提示）和末尾逗号
+    fn walk_value(&mut self, start: usize, prefixes: &[&str]) {
+        match self.raw.as_bytes().get(start) {
+            Some(b'{') => {
+                let Ok(object) = ObjectCst::parse(self.raw, start) else {
+                    return;
+                };
+                for (idx, member) in object.members.iter().enumerate() {
+                    if !prefixes.iter().any(|prefix| member.key.starts_with(prefix))
+                        && object.members[..idx].iter().any(|other| other.key == member.key)
+                    {
+                        self.push(
+                            member.key_start,
+                            LintSeverity::Error,
+                            "duplicate_key",
+                            format!("key \"{}\" 重复出现，只有最后一个会生效", member.key),
+                        );
+                    }
+                    self.walk_value(member.value_start, prefixes);
+                }
+                if let Some(comma) = object.members.last().and_then(|member| member.comma) {
+                    self.push(comma, LintSeverity::Warning, "trailing_comma", "对象末尾有多余的逗号".to_string());
+                }
+            }
+            Some(b'[') => {
+                let mut i = cst::skip_trivia(self.raw, start + 1);
+                while self.raw.as_bytes().get(i).is_some_and(|ch| *ch != b']') {
+                    self.walk_value(i, prefixes);
+                    let Ok(end) = cst::skip_value(self.raw, i) else {
+                        return;
+                    };
+                    i = cst::skip_trivia(self.raw, end);
+                    if self.raw.as_bytes().get(i) != Some(&b',') {
+                        break;
+                    }
+                    let comma = i;
+                    i = cst::skip_trivia(self.raw, i + 1);
+                    if self.raw.as_bytes().get(i) == Some(&b']') {
+                        self.push(comma, LintSeverity::Warning, "trailing_comma", "数组末尾有多余的逗号".to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 检查 portal_frag_ 条目的值：是否为字符串、是否带 es_tabId、intent 是否可解析
+    fn check_portal_entries(&mut self, object: &ObjectCst, prefixes: &[&str]) {
+        let is_mapping = |key: &str| prefixes.iter().any(|prefix| key.starts_with(prefix));
+        for member in object.members.iter().filter(|member| is_mapping(&member.key)) {
+            let value = &self.raw[member.value_start..member.value_end];
+            let Some(value) = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            else {
+                self.push(
+                    member.value_start,
+                    LintSeverity::Error,
+                    "non_string_value",
+                    format!("{} 的值不是字符串", member.key),
+                );
+                continue;
+            };
+            // 值内容从引号之后开始
+            let value_offset = member.value_start + 1;
+
+            if !value.contains("es_tabId=") {
+                self.push(
+                    value_offset,
+                    LintSeverity::Warning,
+                    "missing_tab_id",
+                    format!("{} 的值中没有 es_tabId 参数", member.key),
+                );
+            } else if extract_gw_id(value).is_none() {
+                self.push(
+                    value_offset + value.find("es_tabId=").unwrap_or(0),
+                    LintSeverity::Error,
+                    "empty_gw_id",
+                    format!("{} 的国网栏目ID为空", member.key),
+                );
+            }
+
+            if let Some((offset, reason)) = intent_problem(value) {
+                self.push(
+                    value_offset + offset,
+                    LintSeverity::Warning,
+                    "invalid_intent",
+                    format!("{} 的 intent 无法解析：{}", member.key, reason),
+                );
+            }
+        }
+    }
+}
+
+/// 检查 `类名|intent://?k=v&...` 形式的映射值，返回问题在值中的位置和原因
+fn intent_problem(value: &str) -> Option<(usize, &'static str)> {
+    let Some((class, uri)) = value.split_once('|') else {
+        return Some((0, "缺少 `类名|intent` 分隔符"));
+    };
+    if class.trim().is_empty() {
+        return Some((0, "缺少栏目类名"));
+    }
+    let uri_offset = class.len() + 1;
+    let Some(query) = uri.strip_prefix("intent://") else {
+        return Some((uri_offset, "不是 intent:// 开头"));
+    };
+    let query_offset = uri_offset + "intent://".len();
+    let Some(params) = query.split_once('?').map(|(_, params)| params) else {
+        return Some((query_offset, "缺少 ? 参数部分"));
+    };
+    let params_offset = query_offset + query.find('?').unwrap_or(0) + 1;
+    let mut offset = params_offset;
+    for param in params.split('&') {
+        if !param.is_empty() && !param.contains('=') {
+            return Some((offset, "参数缺少 ="));
+        }
+        if param.starts_with('=') {
+            return Some((offset, "参数名为空"));
+        }
+        offset += param.len() + 1;
+    }
+    None
+}
+
+/// 字节位置对应的行号和列号（从 1 开始，列按字符计）
+fn position(raw: &str, offset: usize) -> (usize, usize) {
+    let before = &raw[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(raw: &str) -> Vec<(usize, String)> {
+        lint(raw, &["portal_frag_"]).into_iter().map(|issue| (issue.line, issue.code)).collect()
+    }
+
+    #[test]
+    fn reports_structural_problems() {
+        let raw = concat!(
+            "{\n",
+            "  \"version\": 1,\n",
+            "  \"version\": 2,\n",
+            "  \"list\": [1, 2,],\n",
+            "  \"sExtOptions\": {\n",
+            "    \"portal_frag_1\": \"a.B|intent://?es_tabId=&es_title=\",\n",
+            "    \"portal_frag_2\": \"a.B|intent://?es_title=x\",\n",
+            "    \"portal_frag_3\": 5,\n",
+            "    \"portal_frag_4\": \"a.B|http://x?es_tabId=4\",\n",
+            "  },\n",
+            "}\n"
+        );
+        let expected = [
+            (3, "duplicate_key"),
+            (4, "trailing_comma"),
+            (6, "empty_gw_id"),
+            (7, "missing_tab_id"),
+            (8, "non_string_value"),
+            (9, "invalid_intent"),
+            (9, "trailing_comma"),
+            (10, "trailing_comma"),
+        ];
+        let expected: Vec<(usize, String)> = expected.iter().map(|(line, code)| (*line, code.to_string())).collect();
+        assert_eq!(codes(raw), expected);
+    }
+
+    #[test]
+    fn reports_syntax_error_only() {
+        let issues = lint("{\n  \"a\": 1,,\n}", &["portal_frag_"]);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].line, issues[0].code.as_str()), (2, "syntax_error"));
+    }
+
+    #[test]
+    fn validates_syntax_with_snippet() {
+        let issues = validate_syntax("{\n  // 注释\n  \"a\": 1\n  \"b\": 2\n}");
+        assert_eq!(issues.len(), 1);
+        let issue = &issues[0];
+        // 缺少逗号时 JSON5 解析器在上一个值的末尾报错
+        assert_eq!((issue.issue.line, issue.issue.column, issue.issue.code.as_str()), (3, 8, "syntax_error"));
+        assert!(issue.snippet.contains("3 |   \"a\": 1\n  |        ^\n4 |"));
+
+        let raw = "{\n  /* 说明 */ \"sExtOptions\": {\n    \"portal_frag_1\": \"x\",\n  }\n}";
+        let issues = validate_syntax(raw);
+        let found: Vec<(usize, usize, &str)> = issues
+            .iter()
+            .map(|issue| (issue.issue.line, issue.issue.column, issue.issue.code.as_str()))
+            .collect();
+        assert_eq!(found, [(4, 3, "strict_syntax")]);
+        assert!(issues[0].snippet.ends_with("4 |   }\n  |   ^\n5 | }\n"));
+
+        let issues = validate_syntax("{\"a\": 1}");
+        assert_eq!(issues[0].issue.code, "missing_ext_options");
+    }
+
+    #[test]
+    fn clean_file_has_no_issues() {
+        let raw = "{\"sExtOptions\": {\"portal_frag_1\": \"a.B|intent://?es_tabId=1&es_title=\"}}";
+        assert!(lint(raw, &["portal_frag_"]).is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{file_io, FileMapping};
+
+/// 主映射表：本地栏目ID -> 国网栏目ID
+pub type MasterTable = BTreeMap<String, String>;
+
+/// 只在一侧存在的映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterEntry {
+    pub local_id: String,
+    pub gw_id: Option<String>,
+}
+
+/// 国网栏目ID与主映射表不一致的映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterMismatch {
+    pub local_id: String,
+    pub expected_gw_id: String,
+    pub actual_gw_id: Option<String>,
+}
+
+/// 单个 theme 文件与主映射表的对比结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterFileReport {
+    pub file_path: String,
+    /// 主映射表中有、文件中缺失的映射
+    pub missing: Vec<MasterEntry>,
+    /// 文件中有、主映射表中没有的映射
+    pub extra: Vec<MasterEntry>,
+    pub mismatched: Vec<MasterMismatch>,
+    pub passed: bool,
+}
+
+/// 读取主映射表，按扩展名识别 CSV 或 JSON
+///
+/// CSV 取前两列（本地栏目ID, 国网栏目ID），首行为表头时自动跳过；
+/// JSON 支持 `{"本地栏目ID": "国网栏目ID"}` 对象或 `[{"localId": .., "gwId": ..}]` 数组。
+pub fn load(path: &Path) -> Result<MasterTable, String> {
+    let (raw, _) = file_io::read_text(path).map_err(|e| format!("读取主映射表失败: {}", e))?;
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let table = if is_json { parse_json(&raw)? } else { parse_csv(&raw)? };
+    if table.is_empty() {
+        return Err("主映射表中没有任何映射".into());
+    }
+    Ok(table)
+}
+
+fn parse_json(raw: &str) -> Result<MasterTable, String> {
+    let value: Value = json5::from_str(raw).map_err(|e| format!("主映射表 JSON 格式错误: {}", e))?;
+    let mut table = MasterTable::new();
+    match value {
+        Value::Object(map) => {
+            for (local_id, gw_id) in map {
+                let gw_id = json_id(&gw_id).ok_or_else(|| format!("本地栏目ID {} 的国网栏目ID无效", local_id))?;
+                insert(&mut table, local_id, gw_id)?;
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let local_id = item.get("localId").and_then(json_id);
+                let gw_id = item.get("gwId").and_then(json_id);
+                match (local_id, gw_id) {
+                    (Some(local_id), Some(gw_id)) => insert(&mut table, local_id, gw_id)?,
+                    _ => return Err(format!("第 {} 项缺少 localId 或 gwId", index + 1)),
+                }
+            }
+        }
+        _ => return Err("主映射表 JSON 必须是对象或数组".into()),
+    }
+    Ok(table)
+}
+
+/// 取出字符串或数字形式的ID
+fn json_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn parse_csv(raw: &str) -> Result<MasterTable, String> {
+    let mut table = MasterTable::new();
+    for (index, line) in raw.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let local_id = fields.first().map(|f| f.trim()).unwrap_or_default();
+        let gw_id = fields.get(1).map(|f| f.trim()).unwrap_or_default();
+        // 首行不是数字ID时视为表头
+        if index == 0 && !local_id.chars().any(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        if local_id.is_empty() || gw_id.is_empty() {
+            return Err(format!("第 {} 行缺少本地栏目ID或国网栏目ID", index + 1));
+        }
+        insert(&mut table, local_id.to_string(), gw_id.to_string())?;
+    }
+    Ok(table)
+}
+
+/// 拆分一行 CSV，支持双引号包裹的字段和 "" 转义
+pub(crate) fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// 加入一条映射，同一本地栏目ID对应不同国网栏目ID时报错
+fn insert(table: &mut MasterTable, local_id: String, gw_id: String) -> Result<(), String> {
+    match table.get(&local_id) {
+        Some(existing) if existing != &gw_id => Err(format!(
+            "主映射表中本地栏目ID {} 对应多个国网栏目ID：{}、{}",
+            local_id, existing, gw_id
+        )),
+        _ => {
+            table.insert(local_id, gw_id);
+            Ok(())
+        }
+    }
+}
+
+/// 逐个文件对比主映射表（文件中本地栏目ID重复时以第一条为准）
+pub fn validate(files: &[FileMapping], master: &MasterTable) -> Vec<MasterFileReport> {
+    files
+        .iter()
+        .map(|file| {
+            let mut actual: BTreeMap<&str, Option<&String>> = BTreeMap::new();
+            for entry in file.mappings.iter().filter(|entry| entry.is_active()) {
+                actual.entry(entry.local_id.as_str()).or_insert(entry.gw_id.as_ref());
+            }
+
+            let mut missing = Vec::new();
+            let mut mismatched = Vec::new();
+            for (local_id, expected) in master {
+                match actual.get(local_id.as_str()) {
+                    None => missing.push(MasterEntry {
+                        local_id: local_id.clone(),
+                        gw_id: Some(expected.clone()),
+                    }),
+                    Some(gw_id) if *gw_id != Some(expected) => mismatched.push(MasterMismatch {
+                        local_id: local_id.clone(),
+                        expected_gw_id: expected.clone(),
+                        actual_gw_id: gw_id.cloned(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            let extra: Vec<MasterEntry> = actual
+                .iter()
+                .filter(|(local_id, _)| !master.contains_key(**local_id))
+                .map(|(local_id, gw_id)| MasterEntry {
+                    local_id: local_id.to_string(),
+                    gw_id: gw_id.cloned(),
+                })
+                .collect();
+
+            MasterFileReport {
+                file_path: file.file_path.clone(),
+                passed: missing.is_empty() && extra.is_empty() && mismatched.is_empty(),
+                missing,
+                extra,
+                mismatched,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scan_directory, settings::AppSettings};
+
+    #[test]
+    fn parses_csv_and_json_tables() {
+        let table = parse_csv("本地栏目ID,国网栏目ID\n101,900\n\n\"102\",\"9,01\"\n").unwrap();
+        assert_eq!(table.get("102").map(String::as_str), Some("9,01"));
+        assert_eq!(table.len(), 2);
+        assert!(parse_csv("101,900\n101,901\n").is_err());
+        assert!(parse_csv("101,900\n102,\n").is_err());
+
+        assert_eq!(parse_json("{\"101\": 900, \"102\": \"901\"}").unwrap(), parse_csv("101,900\n102,901").unwrap());
+        let table = parse_json("[{localId: 101, gwId: '900'}]").unwrap();
+        assert_eq!(table.get("101").map(String::as_str), Some("900"));
+        assert!(parse_json("[{\"localId\": \"101\"}]").is_err());
+        assert_eq!(split_csv_line("a,\"b \"\"c\"\"\",d"), ["a", "b \"c\"", "d"]);
+    }
+
+    #[test]
+    fn reports_missing_extra_and_mismatched_mappings() {
+        let dir = std::env::temp_dir().join(format!("catmap-master-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let entries = [("101", "900"), ("102", "999"), ("104", "903")]
+            .map(|(local_id, gw_id)| {
+                format!("    \"portal_frag_{}\": \"a.B|intent://?es_tabId={}&es_title=\"", local_id, gw_id)
+            })
+            .join(",\n");
+        std::fs::write(dir.join("theme1.json"), format!("{{\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", entries)).unwrap();
+        let files = scan_directory(&dir, &AppSettings::default()).unwrap();
+        let master = parse_csv("101,900\n102,901\n103,902\n").unwrap();
+
+        let reports = validate(&files, &master);
+        assert!(!reports[0].passed);
+        let ids = |entries: &[MasterEntry]| entries.iter().map(|entry| entry.local_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&reports[0].missing), ["103"]);
+        assert_eq!(ids(&reports[0].extra), ["104"]);
+        assert_eq!(reports[0].mismatched[0].actual_gw_id.as_deref(), Some("999"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,371 @@
+//! 操作日志的记录、写入，以及操作完成后的自动提交和通知
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::Write, path::Path};
+
+use crate::{
+    background, git, history, i18n::Locale, settings::AppSettings, webhook, AddedMapping, ConflictStrategy,
+    DeletedMapping, EntryFileResult, ModifiedMapping, OperationType, SkippedFile, SkippedMapping, VersionChange,
+};
+
+/// 日志ID格式，精确到毫秒，按字符串排序即按时间排序
+const LOG_ID_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+/// 结构化操作日志（operation_YYYYMMDD-HHMMSS-mmm.json），与文本日志同时写入
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationLogRecord {
+    /// 日志ID，与日志文件名中的时间戳一致
+    pub id: String,
+    /// 操作时间（RFC 3339）
+    pub timestamp: String,
+    pub operation_type: OperationType,
+    pub operation_label: String,
+    /// 操作人（取自系统用户名）
+    pub operator: String,
+    pub backup_dir: Option<String>,
+    pub additional_info: Option<String>,
+    pub updated_files: Vec<String>,
+    pub skipped_files: Vec<SkippedFile>,
+    pub added: Vec<AddedMapping>,
+    pub deleted: Vec<DeletedMapping>,
+    /// 映射值被修改的映射
+    #[serde(default)]
+    pub modified: Vec<ModifiedMapping>,
+    pub skipped: Vec<SkippedMapping>,
+    pub version_changes: Vec<VersionChange>,
+    /// 批量新增时每条映射在每个文件中的处理结果
+    #[serde(default)]
+    pub entry_results: Vec<EntryFileResult>,
+    /// 批量新增遇到已存在映射时的处理策略，用于重放；旧版本的日志中没有
+    #[serde(default)]
+    pub conflict_strategy: Option<ConflictStrategy>,
+    /// 批量新增时被覆盖国网栏目ID的映射，记录内容与 `added` 相同
+    #[serde(default)]
+    pub overwritten: Vec<AddedMapping>,
+}
+
+/// 当前操作人，取自系统用户名
+pub(crate) fn current_operator() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// 一次修改操作要写入日志的内容，用 [`OperationLog::new`] 创建后填写用到的字段，其余明细默认为空
+#[derive(Debug, Clone, Copy)]
+pub struct OperationLog<'a> {
+    pub operation_type: OperationType,
+    pub updated_files: &'a [String],
+    pub skipped_files: &'a [SkippedFile],
+    pub backup_dir: Option<&'a String>,
+    pub additional_info: Option<&'a str>,
+    pub deleted: &'a [DeletedMapping],
+    pub added: &'a [AddedMapping],
+    pub modified: &'a [ModifiedMapping],
+    pub version_changes: &'a [VersionChange],
+    /// 批量新增时每条映射在每个文件中的处理结果
+    pub entry_results: &'a [EntryFileResult],
+    /// 批量新增遇到已存在映射时的处理策略
+    pub conflict_strategy: Option<ConflictStrategy>,
+    /// 批量新增时被覆盖国网栏目ID的映射
+    pub overwritten: &'a [AddedMapping],
+}
+
+impl OperationLog<'_> {
+    pub fn new(operation_type: OperationType) -> Self {
+        Self {
+            operation_type,
+            updated_files: &[],
+            skipped_files: &[],
+            backup_dir: None,
+            additional_info: None,
+            deleted: &[],
+            added: &[],
+            modified: &[],
+            version_changes: &[],
+            entry_results: &[],
+            conflict_strategy: None,
+            overwritten: &[],
+        }
+    }
+
+    /// 日志记录，ID 和时间取当前时间；ID 写入日志时可能追加序号，见 [`history::create_log_file`]
+    pub fn record(&self, settings: &AppSettings) -> OperationLogRecord {
+        let timestamp = Local::now();
+        let locale = Locale::of(settings);
+        let OperationLog {
+            operation_type,
+            updated_files,
+            skipped_files,
+            backup_dir,
+            additional_info,
+            deleted,
+            added,
+            modified,
+            version_changes,
+            entry_results,
+            conflict_strategy,
+            overwritten,
+        } = *self;
+        OperationLogRecord {
+            id: timestamp.format(LOG_ID_FORMAT).to_string(),
+            timestamp: timestamp.to_rfc3339(),
+            operation_type,
+            operation_label: operation_type.label_in(locale).to_string(),
+            operator: current_operator(),
+            backup_dir: backup_dir.cloned(),
+            additional_info: additional_info.map(str::to_string),
+            updated_files: updated_files.to_vec(),
+            skipped_files: skipped_files.to_vec(),
+            added: added.to_vec(),
+            deleted: deleted.to_vec(),
+            modified: modified.to_vec(),
+            skipped: skipped_files
+                .iter()
+                .flat_map(|skipped| {
+                    skipped.duplicate_ids.iter().map(|local_id| SkippedMapping {
+                        file_path: skipped.file_path.clone(),
+                        local_id: local_id.clone(),
+                        reason: skipped.reason.clone(),
+                    })
+                })
+                .collect(),
+            version_changes: version_changes.to_vec(),
+            entry_results: entry_results.to_vec(),
+            conflict_strategy,
+            overwritten: overwritten.to_vec(),
+        }
+    }
+}
+
+/// 操作完成后记录日志，再在后台执行 git 自动提交和 webhook 推送；日志写入失败不影响主操作和后台任务，只打印错误
+pub fn finish_operation(settings: &AppSettings, target_dir: &Path, log: OperationLog) {
+    let mut record = log.record(settings);
+    if let Err(e) = write_operation_log(settings, target_dir, &mut record) {
+        eprintln!("写入操作日志失败: {}", e);
+    }
+    after_operation(settings, target_dir, record);
+}
+
+/// 开启 git 自动提交时提交本次修改的文件，设置了 webhook 时推送操作摘要，都在后台线程中执行
+pub fn after_operation(settings: &AppSettings, target_dir: &Path, record: OperationLogRecord) {
+    webhook::notify(settings, webhook::OperationSummary::from_record(&record, target_dir));
+    if settings.git_auto_commit {
+        background::spawn(move || {
+            // 提交失败不影响主操作，只打印错误
+            if let Err(e) = git::commit_operation(&record) {
+                eprintln!("自动提交到 git 失败: {}", e);
+            }
+        });
+    }
+}
+
+/// 写入操作日志（文本日志 + JSON 日志，放在日志目录中并按轮转策略清理旧日志）
+///
+/// 同一 ID 的日志已存在时 `record.id` 会改为实际写入的日志ID。
+pub fn write_operation_log(
+    settings: &AppSettings,
+    target_dir: &Path,
+    record: &mut OperationLogRecord,
+) -> Result<(), String> {
+    let log_dir = history::prepare_log_dir(target_dir, settings)?;
+    let (mut file, log_id) = history::create_log_file(&log_dir, &record.id)?;
+    record.id = log_id;
+    let OperationLogRecord {
+        timestamp,
+        operation_type,
+        backup_dir,
+        additional_info,
+        updated_files,
+        skipped_files,
+        added,
+        deleted,
+        modified,
+        version_changes,
+        entry_results,
+        ..
+    } = record;
+    let timestamp = chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| timestamp.clone());
+
+    let locale = Locale::of(settings);
+    let local_id_label = locale.pick("本地栏目ID", "Local ID");
+    let gw_id_label = locale.pick("国网栏目ID", "GW ID");
+    let mut log_content = String::new();
+    
+    // 写入日志头
+    let separator = "=".repeat(80);
+    log_content.push_str(&separator);
+    log_content.push_str(&format!("\n{} - {}\n", locale.pick("操作日志", "Operation log"), timestamp));
+    log_content.push_str(&format!("{}\n", separator));
+    
+    // 操作类型
+    log_content.push_str(&format!("\n{}: {}\n", locale.pick("操作类型", "Operation"), operation_type.label_in(locale)));
+    
+    // 备份路径
+    if let Some(backup_path) = backup_dir {
+        log_content.push_str(&format!("{}: {}\n", locale.pick("备份路径", "Backup"), backup_path));
+    } else {
+        log_content.push_str(locale.pick("备份路径: 无（未创建备份）\n", "Backup: none (no backup created)\n"));
+    }
+    
+    // 附加信息
+    if let Some(info) = additional_info {
+        log_content.push_str(&format!("{}: {}\n", locale.pick("附加信息", "Details"), info));
+    }
+    
+    // 版本变化信息
+    if !version_changes.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("版本变化: {} 个文件\n", version_changes.len()),
+            Locale::EnUs => format!("Version changes: {} files\n", version_changes.len()),
+        });
+        for change in version_changes {
+            log_content.push_str(&format!("  {} : {} → {}\n", 
+                change.file_path, change.old_version, change.new_version));
+        }
+    }
+    
+    // 新增的映射详情（仅对新增操作）
+    if !added.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("\n新增的映射详情 ({} 条):\n", added.len()),
+            Locale::EnUs => format!("\nAdded mappings ({}):\n", added.len()),
+        });
+        // 按文件路径分组显示
+        let mut file_groups: std::collections::HashMap<String, Vec<&AddedMapping>> = std::collections::HashMap::new();
+        for mapping in added {
+            file_groups.entry(mapping.file_path.clone()).or_default().push(mapping);
+        }
+        
+        for (file_path, mappings) in file_groups {
+            log_content.push_str(&format!("  {}\n", file_path));
+            for mapping in mappings {
+                log_content.push_str(&format!("    - {}: {} | {}: {}\n", local_id_label, mapping.local_id, gw_id_label, mapping.gw_id));
+            }
+        }
+    }
+    
+    // 删除的映射详情（仅对删除操作）
+    if !deleted.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("\n删除的映射详情 ({} 条):\n", deleted.len()),
+            Locale::EnUs => format!("\nDeleted mappings ({}):\n", deleted.len()),
+        });
+        // 按文件路径分组显示
+        let mut file_groups: std::collections::HashMap<String, Vec<&DeletedMapping>> = std::collections::HashMap::new();
+        for mapping in deleted {
+            file_groups.entry(mapping.file_path.clone()).or_default().push(mapping);
+        }
+        
+        for (file_path, mappings) in file_groups {
+            log_content.push_str(&format!("  {}\n", file_path));
+            for mapping in mappings {
+                if let Some(gw_id) = &mapping.gw_id {
+                    log_content.push_str(&format!("    - {}: {} | {}: {}\n", local_id_label, mapping.local_id, gw_id_label, gw_id));
+                } else {
+                    log_content.push_str(&format!("    - {}: {} | {}: {}\n", local_id_label, mapping.local_id, gw_id_label, locale.pick("(无)", "(none)")));
+                }
+            }
+        }
+    }
+    
+    // 修改的映射详情：修改前后的完整映射值
+    if !modified.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("\n修改的映射详情 ({} 条):\n", modified.len()),
+            Locale::EnUs => format!("\nModified mappings ({}):\n", modified.len()),
+        });
+        let mut current_file: Option<&str> = None;
+        for mapping in modified {
+            if current_file != Some(mapping.file_path.as_str()) {
+                log_content.push_str(&format!("  {}\n", mapping.file_path));
+                current_file = Some(&mapping.file_path);
+            }
+            log_content.push_str(&format!("    - {}: {}\n", local_id_label, mapping.local_id));
+            log_content.push_str(&format!("      {}: {}\n", locale.pick("原值", "Before"), mapping.old_value));
+            log_content.push_str(&format!("      {}: {}\n", locale.pick("新值", "After"), mapping.new_value));
+        }
+    }
+
+    // 每条映射在每个文件中的处理结果（仅对批量新增）
+    if !entry_results.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("\n映射处理结果 ({} 项):\n", entry_results.len()),
+            Locale::EnUs => format!("\nMapping results ({}):\n", entry_results.len()),
+        });
+        let mut current_file: Option<&str> = None;
+        for result in entry_results {
+            if current_file != Some(result.file_path.as_str()) {
+                log_content.push_str(&format!("  {}\n", result.file_path));
+                current_file = Some(&result.file_path);
+            }
+            match &result.message {
+                Some(message) => log_content.push_str(&format!(
+                    "    - {}: {} | {}: {}\n",
+                    local_id_label,
+                    result.local_id,
+                    result.status.label_in(locale),
+                    message
+                )),
+                None => log_content.push_str(&format!(
+                    "    - {}: {} | {}\n",
+                    local_id_label,
+                    result.local_id,
+                    result.status.label_in(locale)
+                )),
+            }
+        }
+    }
+
+    // 成功处理的文件
+    log_content.push_str(&match locale {
+        Locale::ZhCn => format!("\n成功处理的文件 ({} 个):\n", updated_files.len()),
+        Locale::EnUs => format!("\nUpdated files ({}):\n", updated_files.len()),
+    });
+    if updated_files.is_empty() {
+        log_content.push_str(locale.pick("  无\n", "  none\n"));
+    } else {
+        for file in updated_files {
+            log_content.push_str(&format!("  - {}\n", file));
+        }
+    }
+    
+    // 跳过的文件
+    if !skipped_files.is_empty() {
+        log_content.push_str(&match locale {
+            Locale::ZhCn => format!("\n跳过的文件 ({} 个):\n", skipped_files.len()),
+            Locale::EnUs => format!("\nSkipped files ({}):\n", skipped_files.len()),
+        });
+        for skipped in skipped_files {
+            log_content.push_str(&format!("  - {}\n", skipped.file_path));
+            log_content.push_str(&format!("    {}: {}\n", locale.pick("原因", "Reason"), skipped.reason));
+            if !skipped.duplicate_ids.is_empty() {
+                log_content.push_str(&format!("    {}: {}\n", locale.pick("相关ID", "Related IDs"), skipped.duplicate_ids.join(locale.pick("、", ", "))));
+            }
+        }
+    }
+    
+    // 日志尾
+    let separator = "=".repeat(80);
+    log_content.push_str(&format!("\n{}\n", separator));
+    log_content.push('\n');
+    
+    // 写入文件
+    file.write_all(log_content.as_bytes())
+        .map_err(|e| format!("写入日志文件失败: {}", e))?;
+
+    // 结构化日志，供审计工具解析
+    let json_content = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+    fs::write(log_dir.join(format!("operation_{}.json", record.id)), json_content)
+        .map_err(|e| format!("写入JSON日志文件失败: {}", e))?;
+    // 轮转失败不影响本次日志，只打印错误
+    if let Err(e) = history::rotate_logs(&log_dir, &settings.log_retention) {
+        eprintln!("清理旧日志失败: {}", e);
+    }
+
+    Ok(())
+}
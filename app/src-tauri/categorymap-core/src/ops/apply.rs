@@ -0,0 +1,521 @@
+//! 修改文件的公共流程：校验、递增版本号、备份、写入和跳过记录
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup, cancel, collect_theme_files, diff,
+    editor::{extract_version, increment_version, parse_mappings, validate_json_content, JsonSyntaxError},
+    file_io,
+    i18n::{Locale, Message, MessageCode},
+    lint, preflight,
+    settings::AppSettings,
+    AddedMapping, BulkInsertResult, DeletedMapping, EntryFileResult, Error, FileValidationError, ModifiedMapping, SkippedFile,
+    VersionChange,
+};
+
+/// 批量操作中单个文件处理完成时推送给前端的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileResult {
+    pub file_path: String,
+    /// updated / skipped / stale / validation_error
+    pub status: String,
+    /// 本文件中新增、删除或修改（如覆盖国网栏目ID）的映射条数
+    pub entries_changed: usize,
+    /// 未写入时的原因
+    pub reason: Option<String>,
+}
+
+/// 单个文件的处理结果；各文件并行处理后按原顺序合并
+#[derive(Default)]
+pub struct FileOutcome {
+    pub updated_files: Vec<String>,
+    pub skipped_files: Vec<SkippedFile>,
+    pub stale_files: Vec<String>,
+    pub validation_errors: Vec<FileValidationError>,
+    pub version_changes: Vec<VersionChange>,
+    pub added_mappings: Vec<AddedMapping>,
+    pub deleted_mappings: Vec<DeletedMapping>,
+    pub modified_mappings: Vec<ModifiedMapping>,
+    /// 批量新增时被覆盖国网栏目ID的映射
+    pub overwritten_mappings: Vec<AddedMapping>,
+    pub entry_results: Vec<EntryFileResult>,
+}
+
+impl FileOutcome {
+    /// 转换为推送给前端的单文件结果；文件未被处理（操作已取消）时返回 None
+    pub(crate) fn file_result(&self) -> Option<FileResult> {
+        let file_path = self
+            .updated_files
+            .first()
+            .or_else(|| self.skipped_files.first().map(|skipped| &skipped.file_path))?;
+        let status = if !self.stale_files.is_empty() {
+            "stale"
+        } else if !self.validation_errors.is_empty() {
+            "validation_error"
+        } else if !self.updated_files.is_empty() {
+            "updated"
+        } else {
+            "skipped"
+        };
+        Some(FileResult {
+            file_path: file_path.clone(),
+            status: status.to_string(),
+            entries_changed: self.added_mappings.len() + self.deleted_mappings.len() + self.modified_mappings.len(),
+            reason: self.skipped_files.first().map(|skipped| skipped.reason.clone()),
+        })
+    }
+
+    pub(crate) fn merge(outcomes: Vec<FileOutcome>) -> FileOutcome {
+        let mut merged = FileOutcome::default();
+        for outcome in outcomes {
+            merged.updated_files.extend(outcome.updated_files);
+            merged.skipped_files.extend(outcome.skipped_files);
+            merged.stale_files.extend(outcome.stale_files);
+            merged.validation_errors.extend(outcome.validation_errors);
+            merged.version_changes.extend(outcome.version_changes);
+            merged.added_mappings.extend(outcome.added_mappings);
+            merged.deleted_mappings.extend(outcome.deleted_mappings);
+            merged.modified_mappings.extend(outcome.modified_mappings);
+            merged.overwritten_mappings.extend(outcome.overwritten_mappings);
+            merged.entry_results.extend(outcome.entry_results);
+        }
+        merged
+    }
+}
+
+/// 对比写入前后的文件内容，找出映射值被修改的映射（不含新增和删除的映射），记入操作日志
+fn modified_between(settings: &AppSettings, file_path: &str, before: &str, after: &str) -> Vec<ModifiedMapping> {
+    let prefixes = settings.prefixes();
+    let (Ok(before), Ok(after)) = (parse_mappings(before, &prefixes), parse_mappings(after, &prefixes)) else {
+        return Vec::new();
+    };
+    diff::diff_mappings(&before, &after)
+        .changed
+        .into_iter()
+        .map(|changed| ModifiedMapping {
+            file_path: file_path.to_string(),
+            local_id: changed.local_id,
+            old_value: changed.raw_value_a,
+            new_value: changed.raw_value_b,
+        })
+        .collect()
+}
+
+/// 一个文件修改后待写入的内容
+pub(crate) struct EditedFile<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) file_path: &'a str,
+    /// 修改前的内容，用于对比版本号和被修改的映射
+    pub(crate) raw: &'a str,
+    pub(crate) format: file_io::TextFormat,
+    pub(crate) updated: String,
+}
+
+/// 修改后的内容未能写入的原因，两种情况下文件都保留原样
+pub(crate) enum WriteRefused {
+    /// 修改后的内容未通过校验
+    Invalid(JsonSyntaxError),
+    /// 递增版本号或写入文件失败
+    Failed(Error),
+}
+
+/// 校验修改后的内容，按需递增版本号，返回版本号变化
+fn prepare_edit(edit: &mut EditedFile, auto_increment_version: bool) -> Result<Option<VersionChange>, WriteRefused> {
+    validate_json_content(&edit.updated).map_err(WriteRefused::Invalid)?;
+    if !auto_increment_version {
+        return Ok(None);
+    }
+    edit.updated = increment_version(&edit.updated).map_err(|err| WriteRefused::Failed(err.into()))?;
+    Ok(match (extract_version(edit.raw), extract_version(&edit.updated)) {
+        (Some(old_version), Some(new_version)) => Some(VersionChange {
+            file_path: edit.file_path.to_string(),
+            old_version,
+            new_version,
+        }),
+        _ => None,
+    })
+}
+
+/// 原子写入已准备好的内容，成功时把该文件、版本号变化和被修改的映射记入 outcome
+fn store_edit(
+    settings: &AppSettings,
+    edit: EditedFile,
+    version_change: Option<VersionChange>,
+    outcome: &mut FileOutcome,
+) -> Result<(), Error> {
+    file_io::write_text_atomic(edit.path, &edit.updated, edit.format)
+        .map_err(|err| Error::write_failed(edit.path, err))?;
+    outcome.updated_files.push(edit.file_path.to_string());
+    outcome.version_changes.extend(version_change);
+    outcome
+        .modified_mappings
+        .extend(modified_between(settings, edit.file_path, edit.raw, &edit.updated));
+    Ok(())
+}
+
+/// 写入一个文件修改后的内容：写入前校验，按需递增版本号后原子写入，
+/// 成功时把该文件、版本号变化和被修改的映射记入 outcome。调用前需已备份该文件。
+pub(crate) fn write_edited_file(
+    settings: &AppSettings,
+    mut edit: EditedFile,
+    auto_increment_version: bool,
+    outcome: &mut FileOutcome,
+) -> Result<(), WriteRefused> {
+    let version_change = prepare_edit(&mut edit, auto_increment_version)?;
+    store_edit(settings, edit, version_change, outcome).map_err(WriteRefused::Failed)
+}
+
+/// 写入单个文件的修改：校验和递增版本号都成功后才备份该文件再写入，返回备份目录
+pub(crate) fn write_single_file(
+    settings: &AppSettings,
+    dir: &Path,
+    mut edit: EditedFile,
+    auto_increment_version: bool,
+    outcome: &mut FileOutcome,
+) -> Result<PathBuf, Error> {
+    let version_change = prepare_edit(&mut edit, auto_increment_version).map_err(|refused| match refused {
+        WriteRefused::Invalid(err) => Error::ValidationFailed { error: err.to_string() },
+        WriteRefused::Failed(err) => err,
+    })?;
+    preflight::ensure_writable(dir, &[edit.path], settings)?;
+    let backup_dir = backup::create_backup(dir, &[edit.path], settings)?;
+    store_edit(settings, edit, version_change, outcome)?;
+    Ok(backup_dir)
+}
+
+impl FileOutcome {
+    /// 记录未能写入的文件：校验失败时使用 validation_code 并记入 validation_errors，其他情况记为写入失败
+    pub(crate) fn refuse(
+        &mut self,
+        settings: &AppSettings,
+        file_path: &str,
+        refused: WriteRefused,
+        validation_code: MessageCode,
+        local_ids: Vec<String>,
+    ) {
+        let reason = match &refused {
+            WriteRefused::Invalid(err) => Message::new(validation_code).with("error", err),
+            WriteRefused::Failed(err) => Message::new(MessageCode::WriteFailed).with("error", err),
+        };
+        self.skipped_files
+            .push(SkippedFile::new(file_path.to_string(), reason, local_ids, Locale::of(settings)));
+        if let WriteRefused::Invalid(err) = refused {
+            self.validation_errors.push(FileValidationError::new(file_path.to_string(), err));
+        }
+    }
+}
+
+/// 目录范围修改中单个文件的变更计划
+pub(crate) trait FilePlan {
+    fn file_path(&self) -> &str;
+
+    /// 文件未能写入时记在跳过记录中的本地栏目ID
+    fn local_ids(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// 为单个文件生成的计划；updated 为 None 时只列入预览，不写入该文件
+pub(crate) struct PlannedFile<P> {
+    pub plan: P,
+    pub updated: Option<String>,
+}
+
+/// 写入前对所有计划的检查
+pub(crate) type PlanCheck<'a, P> = &'a dyn Fn(&[P]) -> Result<(), Error>;
+
+/// 目录范围修改的选项
+pub(crate) struct ApplyOptions<'a, P> {
+    /// 只处理这些文件，None 表示目录下所有 theme 文件
+    pub files: Option<Vec<PathBuf>>,
+    pub auto_increment_version: bool,
+    pub dry_run: bool,
+    /// 修改后内容校验失败时跳过记录使用的消息代码
+    pub validation_code: MessageCode,
+    /// 任一文件无法处理时不修改任何文件，写入中途失败时从备份恢复已写入的文件
+    pub transactional: bool,
+    /// 备份前对所有计划的检查，返回错误时不修改任何文件；预览时不检查
+    pub check: Option<PlanCheck<'a, P>>,
+}
+
+impl<P> ApplyOptions<'_, P> {
+    pub fn new(auto_increment_version: bool, dry_run: bool) -> Self {
+        Self {
+            files: None,
+            auto_increment_version,
+            dry_run,
+            validation_code: MessageCode::ValidationFailed,
+            transactional: false,
+            check: None,
+        }
+    }
+}
+
+/// [`plan_and_apply`] 的结果
+pub(crate) struct AppliedPlans<P> {
+    pub plans: Vec<P>,
+    pub outcome: FileOutcome,
+    /// 本次备份目录，未写入任何文件（预览或没有需要修改的文件）时为 None
+    pub backup_dir: Option<String>,
+}
+
+impl<P: FilePlan> AppliedPlans<P> {
+    /// 已写入文件的计划
+    pub fn written(&self) -> impl Iterator<Item = &P> {
+        self.plans
+            .iter()
+            .filter(|plan| self.outcome.updated_files.iter().any(|file| file == plan.file_path()))
+    }
+
+    /// 转换为返回给前端的结果
+    pub fn into_result(self, retries: file_io::RetryScope) -> (BulkInsertResult, Vec<P>) {
+        let result = BulkInsertResult {
+            updated_files: self.outcome.updated_files,
+            skipped_files: self.outcome.skipped_files,
+            validation_errors: self.outcome.validation_errors,
+            stale_files: self.outcome.stale_files,
+            backup_dir: self.backup_dir,
+            retries: retries.finish(),
+            ..Default::default()
+        };
+        (result, self.plans)
+    }
+}
+
+/// 等待写入的文件
+struct PendingFile {
+    path: PathBuf,
+    raw: String,
+    format: file_io::TextFormat,
+    updated: String,
+    /// 对应计划在 plans 中的序号
+    plan: usize,
+}
+
+/// 目录范围修改的公共流程：逐个读取文件，跳过已被他人修改（expected_hashes 不一致）和安全模式下
+/// 有结构问题的文件，由 plan_fn 按文件路径和内容生成计划；预览时到此返回，否则只备份需要写入的
+/// 文件，逐个校验、写入，未能写入的文件记入跳过记录。
+///
+/// plan_fn 返回 None 表示该文件无需处理，返回错误时跳过该文件。调用方按需根据已写入的计划
+/// （[`AppliedPlans::written`]）记录日志。
+pub(crate) fn plan_and_apply<P: FilePlan>(
+    settings: &AppSettings,
+    dir: &Path,
+    expected_hashes: Option<&std::collections::HashMap<String, String>>,
+    options: ApplyOptions<P>,
+    mut plan_fn: impl FnMut(&str, &str) -> Result<Option<PlannedFile<P>>, Error>,
+) -> Result<AppliedPlans<P>, Error> {
+    let files = match options.files {
+        Some(files) => files,
+        None => collect_theme_files(dir)?,
+    };
+    let locale = Locale::of(settings);
+    let mut outcome = FileOutcome::default();
+    let mut plans = Vec::new();
+    let mut pending = Vec::new();
+
+    // 先在每个文件中生成计划
+    for path in files {
+        let file_path = path.to_string_lossy().into_owned();
+        let (raw, format) = match file_io::read_text(&path) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                outcome.skipped_files.push(SkippedFile::new(
+                    file_path,
+                    Message::new(MessageCode::ReadFailed).with("error", e),
+                    Vec::new(),
+                    locale,
+                ));
+                continue;
+            }
+        };
+        if is_stale(expected_hashes, &file_path, &raw) {
+            outcome.skipped_files.push(stale_skipped_file(&file_path, locale));
+            outcome.stale_files.push(file_path);
+            continue;
+        }
+        if let Some(skipped) = safe_mode_skipped_file(settings, &file_path, &raw) {
+            outcome.skipped_files.push(skipped);
+            continue;
+        }
+        match plan_fn(&file_path, &raw) {
+            Ok(Some(planned)) => {
+                if let Some(updated) = planned.updated {
+                    pending.push(PendingFile {
+                        path,
+                        raw,
+                        format,
+                        updated,
+                        plan: plans.len(),
+                    });
+                }
+                plans.push(planned.plan);
+            }
+            Ok(None) => {}
+            Err(e) => outcome
+                .skipped_files
+                .push(SkippedFile::new(file_path, Message::other(e), Vec::new(), locale)),
+        }
+    }
+
+    if options.dry_run || pending.is_empty() {
+        return Ok(AppliedPlans {
+            plans,
+            outcome,
+            backup_dir: None,
+        });
+    }
+    if options.transactional {
+        // 写入前先校验所有文件，任一文件无法处理时整个操作不执行
+        let mut failures: Vec<String> = outcome
+            .skipped_files
+            .iter()
+            .map(|skipped| format!("{}（{}）", skipped.file_path, skipped.reason))
+            .collect();
+        for file in &pending {
+            if let Err(err) = validate_json_content(&file.updated) {
+                failures.push(format!("{}（{}）", plans[file.plan].file_path(), err));
+            }
+        }
+        if !failures.is_empty() {
+            return Err(format!("以下文件无法处理，未修改任何文件：{}", failures.join("；")).into());
+        }
+    }
+    if let Some(check) = options.check {
+        check(&plans)?;
+    }
+
+    // 只备份需要写入的文件
+    let files_to_backup: Vec<&PathBuf> = pending.iter().map(|file| &file.path).collect();
+    preflight::ensure_writable(dir, &files_to_backup, settings)?;
+    let backup_dir = backup::create_backup(dir, &files_to_backup, settings)?
+        .to_string_lossy()
+        .into_owned();
+
+    for file in pending {
+        let plan = &plans[file.plan];
+        let edit = EditedFile {
+            path: &file.path,
+            file_path: plan.file_path(),
+            raw: &file.raw,
+            format: file.format,
+            updated: file.updated,
+        };
+        match write_edited_file(settings, edit, options.auto_increment_version, &mut outcome) {
+            Ok(()) => {}
+            Err(WriteRefused::Failed(err)) if options.transactional => {
+                return Err(rollback_failed_write(&backup_dir, &outcome.updated_files, plan.file_path(), &err).into());
+            }
+            Err(refused) => {
+                outcome.refuse(settings, plan.file_path(), refused, options.validation_code, plan.local_ids())
+            }
+        }
+    }
+
+    Ok(AppliedPlans {
+        plans,
+        outcome,
+        backup_dir: Some(backup_dir),
+    })
+}
+
+/// 事务方式修改时写入失败，用本次备份恢复已写入的文件，返回给前端的说明
+fn rollback_failed_write(backup_dir: &str, written_files: &[String], failed_file: &str, error: &Error) -> String {
+    match backup::restore_files(Path::new(backup_dir), written_files) {
+        Ok(count) => format!("写入 {} 失败: {}，已从备份恢复 {} 个已写入的文件", failed_file, error, count),
+        Err(e) => format!(
+            "写入 {} 失败: {}，从备份恢复已写入的文件也失败: {}（备份位置: {}）",
+            failed_file, error, e, backup_dir
+        ),
+    }
+}
+
+/// 操作被取消后，用本次备份恢复已写入的文件，返回给前端的说明
+pub(crate) fn rollback_cancelled(backup_dir: Option<&str>, written_files: &[String]) -> String {
+    if written_files.is_empty() {
+        return format!("{}，未修改任何文件", cancel::CANCELLED_MESSAGE);
+    }
+    let backup_dir = match backup_dir {
+        Some(dir) => dir,
+        None => return format!("{}，但找不到本次备份，已写入的文件未能恢复", cancel::CANCELLED_MESSAGE),
+    };
+    match backup::restore_files(Path::new(backup_dir), written_files) {
+        Ok(count) => format!("{}，已从备份恢复 {} 个已写入的文件", cancel::CANCELLED_MESSAGE, count),
+        Err(e) => format!("{}，但从备份恢复已写入的文件失败: {}（备份位置: {}）", cancel::CANCELLED_MESSAGE, e, backup_dir),
+    }
+}
+
+/// 检查文件内容是否与扫描时不同；未提供该文件的期望哈希时视为未修改
+pub(crate) fn is_stale(expected_hashes: Option<&std::collections::HashMap<String, String>>, file_path: &str, raw: &str) -> bool {
+    expected_hashes
+        .and_then(|hashes| hashes.get(file_path))
+        .map(|expected| expected != &file_io::content_hash(raw.as_bytes()))
+        .unwrap_or(false)
+}
+
+pub(crate) fn stale_skipped_file(file_path: &str, locale: Locale) -> SkippedFile {
+    SkippedFile::new(file_path.to_string(), Message::new(MessageCode::StaleSkipped), Vec::new(), locale)
+}
+
+/// 安全模式下文件已有的结构问题（lint 的 error 级别问题）及其摘要，没有问题或未开启安全模式时返回 None
+fn safe_mode_findings(settings: &AppSettings, raw: &str) -> Option<(String, Vec<lint::LintIssue>)> {
+    if !settings.safe_mode {
+        return None;
+    }
+    let issues: Vec<lint::LintIssue> = lint::lint(raw, &settings.prefixes())
+        .into_iter()
+        .filter(|issue| issue.severity == lint::LintSeverity::Error)
+        .collect();
+    if issues.is_empty() {
+        return None;
+    }
+    let summary: Vec<String> = issues
+        .iter()
+        .map(|issue| format!("第 {} 行 {}", issue.line, issue.message))
+        .collect();
+    Some((summary.join("；"), issues))
+}
+
+/// 安全模式下跳过已有结构问题的文件，跳过记录中带上检查发现的问题
+pub(crate) fn safe_mode_skipped_file(settings: &AppSettings, file_path: &str, raw: &str) -> Option<SkippedFile> {
+    let (summary, issues) = safe_mode_findings(settings, raw)?;
+    let message = Message::new(MessageCode::UnsafeToModify).with("issues", summary);
+    let mut skipped = SkippedFile::new(file_path.to_string(), message, Vec::new(), Locale::of(settings));
+    skipped.lint_issues = issues;
+    Some(skipped)
+}
+
+/// 修改单个文件前的安全模式检查，文件已有结构问题时拒绝修改
+pub(crate) fn ensure_safe_to_modify(settings: &AppSettings, raw: &str) -> Result<(), Error> {
+    match safe_mode_findings(settings, raw) {
+        Some((issues, _)) => Err(Error::UnsafeToModify { issues }),
+        None => Ok(()),
+    }
+}
+
+/// 按选中列表拆分文件，返回（选中的文件, 未选中的文件路径）
+///
+/// 选中项既可以是完整路径，也可以只是文件名。
+pub(crate) fn select_target_files(files: Vec<PathBuf>, target_files: Option<&[String]>) -> (Vec<PathBuf>, Vec<String>) {
+    let Some(targets) = target_files else {
+        return (files, Vec::new());
+    };
+
+    let mut selected = Vec::new();
+    let mut unselected = Vec::new();
+    for file in files {
+        let path_str = file.to_string_lossy().into_owned();
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let is_selected = targets.iter().any(|target| {
+            target == &name || Path::new(target) == file.as_path()
+        });
+        if is_selected {
+            selected.push(file);
+        } else {
+            unselected.push(path_str);
+        }
+    }
+    (selected, unselected)
+}
@@ -0,0 +1,131 @@
+//! 扫描压缩包，解压到工作目录后重新打包
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    archive, backup, collect_theme_files, editor::validate_json_content, file_io, file_mapping_from_text,
+    finish_operation, preflight, settings::AppSettings, Error, OperationLog, OperationType, ScanResult,
+};
+
+/// 直接读取 zip / apk 包中的 theme 文件（任意目录层级），不解压到磁盘
+///
+/// 结果中的文件路径为 `<包路径>!/<包内路径>`，只用于展示，不能直接用于修改；
+/// 修改时先用 [`open_archive_workspace`] 解出到工作目录。
+pub fn scan_archive(settings: &AppSettings, archive_path: String) -> Result<ScanResult, Error> {
+    let path = PathBuf::from(&archive_path);
+    if !path.is_file() {
+        return Err(Error::ArchiveNotFound { path: archive_path });
+    }
+    let files = archive::read_theme_entries(&path)?
+        .into_iter()
+        .map(|(entry_name, content)| {
+            let file_path = format!("{}{}{}", archive_path, archive::ENTRY_SEPARATOR, entry_name);
+            let (raw, format) = file_io::decode(&content).map_err(|err| format!("{}: {}", file_path, err))?;
+            file_mapping_from_text(file_path, &raw, format, settings)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(ScanResult {
+        files,
+        ignored_files: Vec::new(),
+        retries: Vec::new(),
+        timings: None,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveWorkspace {
+    pub archive_path: String,
+    /// 解出的 theme 文件所在目录，可直接用于扫描、新增、导入等操作
+    pub workspace_dir: String,
+    pub entries: Vec<archive::WorkspaceEntry>,
+}
+
+/// 把压缩包中的 theme 文件解出到工作目录，修改完成后用 [`repack_archive`] 回写
+///
+/// 未指定工作目录时使用系统临时目录下按包路径区分的目录。工作目录会先被清空。
+pub fn open_archive_workspace(archive_path: String, workspace_dir: Option<String>) -> Result<ArchiveWorkspace, Error> {
+    let path = PathBuf::from(&archive_path);
+    if !path.is_file() {
+        return Err(Error::ArchiveNotFound { path: archive_path });
+    }
+    let workspace = workspace_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| archive::default_workspace(&path));
+    let entries = archive::extract_to_workspace(&path, &workspace)?;
+    Ok(ArchiveWorkspace {
+        archive_path,
+        workspace_dir: workspace.to_string_lossy().into_owned(),
+        entries,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepackResult {
+    pub archive_path: String,
+    /// 内容有变化、已写回的包内路径
+    pub updated_entries: Vec<String>,
+    /// 原压缩包的备份，没有变化时为 None
+    pub backup_dir: Option<String>,
+}
+
+/// 用工作目录中修改后的 theme 文件重新打包，打包前备份原压缩包
+///
+/// 其他条目原样保留；theme 文件都没有变化时不备份也不写文件。apk 回写后需要重新签名。
+pub fn repack_archive(
+    settings: &AppSettings,
+    archive_path: String,
+    workspace_dir: Option<String>,
+) -> Result<RepackResult, Error> {
+    let path = PathBuf::from(&archive_path);
+    if !path.is_file() {
+        return Err(Error::ArchiveNotFound { path: archive_path });
+    }
+    let workspace = workspace_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| archive::default_workspace(&path));
+    if !workspace.is_dir() {
+        return Err("工作目录不存在，请先解出压缩包".into());
+    }
+
+    // 内容校验失败的文件不打包，避免把损坏的 theme 文件部署出去
+    for file in collect_theme_files(&workspace)? {
+        let (raw, _) = file_io::read_text(&file).map_err(|err| Error::read_failed(&file, err))?;
+        validate_json_content(&raw).map_err(|err| format!("{} 内容校验失败，未打包：{}", file.display(), err))?;
+    }
+
+    let updated_entries = archive::changed_entries(&path, &workspace)?;
+    if updated_entries.is_empty() {
+        return Ok(RepackResult {
+            archive_path,
+            updated_entries,
+            backup_dir: None,
+        });
+    }
+
+    let archive_dir = path.parent().ok_or("无法获取压缩包所在目录")?;
+    preflight::ensure_writable(archive_dir, std::slice::from_ref(&path), settings)?;
+    let backup_dir = backup::create_backup(archive_dir, std::slice::from_ref(&path), settings)?;
+    archive::repack_from_workspace(&path, &workspace)?;
+
+    let backup_dir = backup_dir.to_string_lossy().into_owned();
+    let info = format!("压缩包: {}\n更新条目: {}", archive_path, updated_entries.join(", "));
+    finish_operation(
+        settings,
+        &backup::storage_dir(archive_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&archive_path),
+            backup_dir: Some(&backup_dir),
+            additional_info: Some(&info),
+            ..OperationLog::new(OperationType::RepackArchive)
+        },
+    );
+
+    Ok(RepackResult {
+        archive_path,
+        updated_entries,
+        backup_dir: Some(backup_dir),
+    })
+}
@@ -0,0 +1,192 @@
+//! 备份、恢复、清理备份和查看操作历史
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup, collect_theme_files, diff, editor::parse_mappings, file_io, finish_operation, history, preflight,
+    settings::AppSettings, BackupResult, Error, OperationLog, OperationType,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    pub restored_files: Vec<String>,
+    /// 恢复前为被覆盖的当前文件创建的备份
+    pub backup_dir: Option<String>,
+}
+
+/// 从备份（目录或 zip 压缩包）恢复文件到目标目录，恢复前先备份将被覆盖的文件
+pub fn restore_backup(settings: &AppSettings, target_dir: String, backup_path: String) -> Result<RestoreResult, Error> {
+    restore_from_backup(settings, target_dir, backup_path, None)
+}
+
+/// 只从备份中恢复指定文件名的文件，其他文件之后的修改保持不变
+pub fn restore_backup_files(
+    settings: &AppSettings,
+    target_dir: String,
+    backup_path: String,
+    file_names: Vec<String>,
+) -> Result<RestoreResult, Error> {
+    if file_names.is_empty() {
+        return Err("请选择要恢复的文件".into());
+    }
+    restore_from_backup(settings, target_dir, backup_path, Some(&file_names))
+}
+
+fn restore_from_backup(
+    settings: &AppSettings,
+    target_dir: String,
+    backup_path: String,
+    file_names: Option<&[String]>,
+) -> Result<RestoreResult, Error> {
+    let dir = PathBuf::from(&target_dir);
+    if !dir.is_dir() {
+        return Err(Error::DirNotFound { path: dir.to_string_lossy().into_owned() });
+    }
+    backup::ensure_intact(Path::new(&backup_path))?;
+    let mut files = backup::read_backup(Path::new(&backup_path))?;
+    if let Some(names) = file_names {
+        let missing: Vec<&str> = names
+            .iter()
+            .filter(|name| !files.iter().any(|(backup_name, _)| backup_name == *name))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("备份中不存在文件: {}", missing.join("、")).into());
+        }
+        files.retain(|(name, _)| names.contains(name));
+    }
+    if files.is_empty() {
+        return Err("备份中没有可恢复的文件".into());
+    }
+
+    let existing: Vec<PathBuf> = files
+        .iter()
+        .map(|(name, _)| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    let backup_dir_path = if existing.is_empty() {
+        None
+    } else {
+        preflight::ensure_writable(&dir, &existing, settings)?;
+        let backup_dir = backup::create_backup(&dir, &existing, settings)?;
+        Some(backup_dir.to_string_lossy().into_owned())
+    };
+
+    let mut restored_files = Vec::new();
+    for (name, content) in files {
+        let path = dir.join(&name);
+        file_io::write_atomic(&path, &content).map_err(|err| format!("恢复 {} 失败: {}", name, err))?;
+        restored_files.push(path.to_string_lossy().into_owned());
+    }
+
+    let info = match file_names {
+        Some(names) => format!("从备份恢复 {}: {}", names.join("、"), backup_path),
+        None => format!("从备份恢复: {}", backup_path),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(&dir, settings),
+        OperationLog {
+            updated_files: &restored_files,
+            backup_dir: backup_dir_path.as_ref(),
+            additional_info: Some(&info),
+            ..OperationLog::new(OperationType::Restore)
+        },
+    );
+
+    Ok(RestoreResult {
+        restored_files,
+        backup_dir: backup_dir_path,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneBackupsResult {
+    #[serde(flatten)]
+    pub plan: backup::PrunePlan,
+    /// 为 true 时只预览，未实际删除
+    pub dry_run: bool,
+}
+
+/// 按设置中的保留策略清理目录下的旧备份，dry_run 为 true 时只返回将被删除的备份
+pub fn prune_backups(settings: &AppSettings, target_dir: String, dry_run: Option<bool>) -> Result<PruneBackupsResult, Error> {
+    let dir = backup::storage_dir(Path::new(&target_dir), settings);
+    let dry_run = dry_run.unwrap_or(false);
+    let plan = if dry_run {
+        backup::plan_prune(&dir, &settings.backup_retention)?
+    } else {
+        backup::prune_backups(&dir, &settings.backup_retention)?
+    };
+    Ok(PruneBackupsResult { plan, dry_run })
+}
+
+/// 读取目录下的操作历史（最新的在前）
+pub fn get_operation_history(
+    settings: &AppSettings,
+    target_dir: String,
+    limit: Option<usize>,
+) -> Result<Vec<history::HistoryEntry>, Error> {
+    let storage_dir = backup::storage_dir(Path::new(&target_dir), settings);
+    Ok(history::load_history(&history::prepare_log_dir(&storage_dir, settings)?, limit)?)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDiffResult {
+    pub file_path: String,
+    pub backup_file: String,
+    /// 映射级差异：A 为备份，B 为当前文件
+    pub diff: diff::MappingDiff,
+    /// 统一格式的文本差异，内容相同时为空
+    pub unified_diff: String,
+}
+
+pub fn diff_with_backup(settings: &AppSettings, file_path: String, backup_dir: String) -> Result<BackupDiffResult, Error> {
+    let current_path = PathBuf::from(&file_path);
+    if !current_path.exists() {
+        return Err(Error::FileNotFound { file: file_path });
+    }
+    let file_name = current_path
+        .file_name()
+        .ok_or("无法获取文件名")?
+        .to_string_lossy()
+        .into_owned();
+    let backup_raw = backup::read_backup_file(Path::new(&backup_dir), &file_name)?
+        .ok_or_else(|| format!("备份中不存在该文件: {}", file_name))?;
+
+    let (current_raw, _) = file_io::read_text(&current_path).map_err(|err| Error::read_failed(&current_path, err))?;
+    let prefixes = settings.prefixes();
+    let backup_mappings = parse_mappings(&backup_raw, &prefixes)?;
+    let current_mappings = parse_mappings(&current_raw, &prefixes)?;
+
+    let backup_file = Path::new(&backup_dir).join(&file_name).to_string_lossy().into_owned();
+    Ok(BackupDiffResult {
+        diff: diff::diff_mappings(&backup_mappings, &current_mappings),
+        unified_diff: diff::unified_diff(&backup_raw, &current_raw, &backup_file, &file_path),
+        file_path,
+        backup_file,
+    })
+}
+
+pub fn backup_theme_files(settings: &AppSettings, target_dir: String) -> Result<BackupResult, Error> {
+    let dir = PathBuf::from(&target_dir);
+    let files = collect_theme_files(&dir)?;
+
+    if files.is_empty() {
+        return Err(Error::NoThemeFiles);
+    }
+
+    let backup_dir = backup::create_backup_of_kind(&dir, &files, settings, backup::BackupKind::Manual)?;
+
+    Ok(BackupResult {
+        backup_dir: backup_dir.to_string_lossy().into_owned(),
+    })
+}
+
+/// 列出目录的所有备份（最新的在前），包括修改前自动创建、手动创建和定时创建的备份
+pub fn list_backups(settings: &AppSettings, target_dir: String) -> Result<Vec<backup::BackupInfo>, Error> {
+    Ok(backup::list_source_backups(Path::new(&target_dir), settings)?)
+}
@@ -0,0 +1,249 @@
+//! 被注释掉的映射的恢复和清除
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup, editor,
+    editor::{extract_gw_id, locate_entries},
+    ensure_safe_to_modify, file_io, finish_operation,
+    i18n::{Locale, Message, MessageCode},
+    settings::AppSettings,
+    write_single_file, AddedMapping, DeletedMapping, EditedFile, Error, FileOutcome, OperationLog, OperationType,
+    SkippedFile, SkippedMapping,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentedMappingsResult {
+    pub file_path: String,
+    /// 已取消注释或已清除的本地栏目ID
+    pub local_ids: Vec<String>,
+    /// 未处理的映射（如段落中已有同一本地栏目ID的有效映射）
+    pub skipped: Vec<SkippedMapping>,
+    pub backup_dir: Option<String>,
+}
+
+/// 取消注释指定的映射，条目移到原所在 sExtOptions 段落的末尾
+///
+/// prefix 为映射的前缀，None 表示映射前缀（portalPrefix）。
+/// 同一段落中已有该前缀和本地栏目ID的有效映射时跳过，避免产生重复。
+pub fn uncomment_mappings(
+    settings: &AppSettings,
+    file_path: String,
+    local_ids: Vec<String>,
+    prefix: Option<String>,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+) -> Result<CommentedMappingsResult, Error> {
+    let prefix = settings.key_prefix(prefix.as_deref())?;
+    let (raw, format, mut commented) = read_commented(settings, &file_path, expected_hash.as_deref())?;
+    commented.retain(|span| span.prefix == prefix);
+    let active = locate_entries(&raw, &settings.prefixes()).map_err(Error::in_file(&file_path))?;
+    let mut skipped = missing_commented(&file_path, &local_ids, &commented);
+    let mut targets: Vec<&editor::CommentedSpan> = Vec::new();
+    let same_mapping = |block: usize, prefix: &str, local_id: &str, span: &editor::CommentedSpan| {
+        block == span.block && prefix == span.prefix && local_id == span.local_id
+    };
+    for span in commented.iter().filter(|span| local_ids.contains(&span.local_id)) {
+        let exists = active.iter().any(|entry| same_mapping(entry.block, &entry.prefix, &entry.local_id, span))
+            || targets.iter().any(|target| same_mapping(target.block, &target.prefix, &target.local_id, span));
+        if exists {
+            skipped.push(SkippedMapping {
+                file_path: file_path.clone(),
+                local_id: span.local_id.clone(),
+                reason: "段落中已有该本地栏目ID的映射".to_string(),
+            });
+        } else {
+            targets.push(span);
+        }
+    }
+
+    let updated = editor::uncomment_spans(&raw, &targets)?;
+    let added: Vec<AddedMapping> = targets
+        .iter()
+        .map(|span| AddedMapping {
+            file_path: file_path.clone(),
+            local_id: span.local_id.clone(),
+            gw_id: extract_gw_id(&span.raw_value).unwrap_or_default(),
+            block: Some(span.block),
+            prefix: Some(span.prefix.clone()),
+            ..Default::default()
+        })
+        .collect();
+    write_commented_edit(
+        settings,
+        file_path,
+        &raw,
+        format,
+        updated,
+        &targets,
+        skipped,
+        auto_increment_version,
+        OperationType::Uncomment,
+        Some(&added),
+        None,
+    )
+}
+
+/// 删除被注释掉的映射，local_ids 为 None 时删除文件中所有被注释的映射
+///
+/// prefix 为映射的前缀；为 None 时按本地栏目ID删除的是映射前缀（portalPrefix）的条目，全部删除时不限前缀。
+pub fn purge_commented_mappings(
+    settings: &AppSettings,
+    file_path: String,
+    local_ids: Option<Vec<String>>,
+    prefix: Option<String>,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+) -> Result<CommentedMappingsResult, Error> {
+    let (raw, format, mut commented) = read_commented(settings, &file_path, expected_hash.as_deref())?;
+    if local_ids.is_some() || prefix.is_some() {
+        let prefix = settings.key_prefix(prefix.as_deref())?;
+        commented.retain(|span| span.prefix == prefix);
+    }
+    let skipped = match &local_ids {
+        Some(local_ids) => missing_commented(&file_path, local_ids, &commented),
+        None => Vec::new(),
+    };
+    let targets: Vec<&editor::CommentedSpan> = commented
+        .iter()
+        .filter(|span| match &local_ids {
+            Some(ids) => ids.contains(&span.local_id),
+            None => true,
+        })
+        .collect();
+
+    let updated = editor::remove_commented_spans(&raw, &targets);
+    let deleted: Vec<DeletedMapping> = targets
+        .iter()
+        .map(|span| DeletedMapping {
+            file_path: file_path.clone(),
+            local_id: span.local_id.clone(),
+            gw_id: extract_gw_id(&span.raw_value),
+            block: Some(span.block),
+            prefix: Some(span.prefix.clone()),
+            ..Default::default()
+        })
+        .collect();
+    write_commented_edit(
+        settings,
+        file_path,
+        &raw,
+        format,
+        updated,
+        &targets,
+        skipped,
+        auto_increment_version,
+        OperationType::PurgeCommented,
+        None,
+        Some(&deleted),
+    )
+}
+
+/// 读取文件并定位所有前缀下被注释的映射，文件在扫描后被修改时拒绝操作
+fn read_commented(
+    settings: &AppSettings,
+    file_path: &str,
+    expected_hash: Option<&str>,
+) -> Result<(String, file_io::TextFormat, Vec<editor::CommentedSpan>), Error> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+    let (raw, format) = file_io::read_text(path).map_err(|err| Error::read_failed(path, err))?;
+    if expected_hash.is_some_and(|expected| file_io::content_hash(raw.as_bytes()) != expected) {
+        return Err(Error::StaleConflict { file: file_path.to_string() });
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    let commented = editor::locate_commented_entries(&raw, &settings.prefixes()).map_err(Error::in_file(file_path))?;
+    Ok((raw, format, commented))
+}
+
+/// 请求中没有对应注释行的本地栏目ID
+fn missing_commented(file_path: &str, local_ids: &[String], commented: &[editor::CommentedSpan]) -> Vec<SkippedMapping> {
+    local_ids
+        .iter()
+        .filter(|local_id| !commented.iter().any(|span| &span.local_id == *local_id))
+        .map(|local_id| SkippedMapping {
+            file_path: file_path.to_string(),
+            local_id: local_id.clone(),
+            reason: "未找到被注释的该映射".to_string(),
+        })
+        .collect()
+}
+
+/// 校验、备份并写入取消注释或清除注释后的内容，记录操作日志
+#[allow(clippy::too_many_arguments)]
+fn write_commented_edit(
+    settings: &AppSettings,
+    file_path: String,
+    raw: &str,
+    format: file_io::TextFormat,
+    updated: String,
+    targets: &[&editor::CommentedSpan],
+    skipped: Vec<SkippedMapping>,
+    auto_increment_version: bool,
+    operation_type: OperationType,
+    added: Option<&[AddedMapping]>,
+    deleted: Option<&[DeletedMapping]>,
+) -> Result<CommentedMappingsResult, Error> {
+    let local_ids: Vec<String> = targets.iter().map(|span| span.local_id.clone()).collect();
+    if targets.is_empty() {
+        return Ok(CommentedMappingsResult {
+            file_path,
+            local_ids,
+            skipped,
+            backup_dir: None,
+        });
+    }
+
+    let path = PathBuf::from(&file_path);
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+    let backup_dir = backup_dir.to_string_lossy().into_owned();
+
+    let skipped_files: Vec<SkippedFile> = if skipped.is_empty() {
+        Vec::new()
+    } else {
+        vec![SkippedFile::new(
+            file_path.clone(),
+            Message::new(MessageCode::PartiallyProcessed),
+            skipped.iter().map(|item| item.local_id.clone()).collect(),
+            Locale::of(settings),
+        )]
+    };
+    let info = match operation_type {
+        OperationType::Uncomment => format!("取消注释 {} 条映射：{}", local_ids.len(), local_ids.join("、")),
+        _ => format!("清除 {} 条被注释的映射：{}", local_ids.len(), local_ids.join("、")),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&file_path),
+            skipped_files: &skipped_files,
+            backup_dir: Some(&backup_dir),
+            additional_info: Some(&info),
+            deleted: deleted.unwrap_or_default(),
+            added: added.unwrap_or_default(),
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(operation_type)
+        },
+    );
+
+    Ok(CommentedMappingsResult {
+        file_path,
+        local_ids,
+        skipped,
+        backup_dir: Some(backup_dir),
+    })
+}
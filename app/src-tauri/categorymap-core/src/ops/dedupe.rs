@@ -0,0 +1,153 @@
+//! 删除同一段落中的重复映射
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    backup, duplicates,
+    editor::{extract_gw_id, locate_entries, remove_entry_spans, EntrySpan},
+    ensure_safe_to_modify, file_io, finish_operation,
+    i18n::Locale,
+    settings::AppSettings,
+    write_single_file, DeletedMapping, EditedFile, Error, FileOutcome, OperationLog, OperationType,
+};
+
+/// 去重时保留哪一条
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepPolicy {
+    /// 保留第一次出现的条目
+    #[default]
+    First,
+    /// 保留最后一次出现的条目
+    Last,
+}
+
+impl KeepPolicy {
+    /// 日志中记录的保留方式
+    pub(crate) fn label_in(self, locale: Locale) -> &'static str {
+        match self {
+            KeepPolicy::First => locale.pick("保留第一条", "kept the first"),
+            KeepPolicy::Last => locale.pick("保留最后一条", "kept the last"),
+        }
+    }
+}
+
+/// 去重或按条件删除时被删除的条目
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedDuplicate {
+    pub local_id: String,
+    pub gw_id: Option<String>,
+    pub raw_value: String,
+    /// 条目的映射前缀
+    pub prefix: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupeResult {
+    pub file_path: String,
+    pub removed: Vec<DroppedDuplicate>,
+    pub backup_dir: Option<String>,
+}
+
+pub fn dedupe_mappings(
+    settings: &AppSettings,
+    file_path: String,
+    keep: Option<KeepPolicy>,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+) -> Result<DedupeResult, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+
+    let keep = keep.unwrap_or_default();
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    let spans = locate_entries(&raw, &settings.prefixes()).map_err(Error::in_file(&file_path))?;
+
+    // 同一段落中每个前缀下的每个本地栏目ID只保留一条，其余重复项全部删除
+    let to_remove: Vec<&EntrySpan> = duplicates::redundant_entries(&raw, &spans, duplicates::DuplicateScope::Local, keep)
+        .into_iter()
+        .map(|idx| &spans[idx])
+        .collect();
+
+    if to_remove.is_empty() {
+        return Ok(DedupeResult {
+            file_path,
+            removed: Vec::new(),
+            backup_dir: None,
+        });
+    }
+
+    let removed: Vec<DroppedDuplicate> = to_remove
+        .iter()
+        .map(|span| {
+            let raw_value = &raw[span.value_start..span.value_end];
+            DroppedDuplicate {
+                local_id: span.local_id.clone(),
+                gw_id: extract_gw_id(raw_value),
+                raw_value: raw_value.to_string(),
+                prefix: span.prefix.clone(),
+            }
+        })
+        .collect();
+
+    let updated = remove_entry_spans(&raw, &to_remove)?;
+
+    // 校验通过后再创建备份
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+
+    // 写入操作日志
+    let locale = Locale::of(settings);
+    let dedupe_info = match locale {
+        Locale::ZhCn => format!("去除 {} 条重复映射（{}）", removed.len(), keep.label_in(locale)),
+        Locale::EnUs => format!("Removed {} duplicate mappings ({})", removed.len(), keep.label_in(locale)),
+    };
+    let deleted_mappings: Vec<DeletedMapping> = removed
+        .iter()
+        .map(|item| DeletedMapping {
+            file_path: file_path.clone(),
+            local_id: item.local_id.clone(),
+            gw_id: item.gw_id.clone(),
+            prefix: Some(item.prefix.clone()),
+            ..Default::default()
+        })
+        .collect();
+
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&file_path),
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(&dedupe_info),
+            deleted: &deleted_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::Dedupe)
+        },
+    );
+
+    Ok(DedupeResult {
+        file_path,
+        removed,
+        backup_dir: Some(backup_dir.to_string_lossy().into_owned()),
+    })
+}
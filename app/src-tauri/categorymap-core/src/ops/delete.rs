@@ -0,0 +1,400 @@
+//! 批量删除映射
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    backup, editor,
+    editor::{parse_mappings, remove_mapping_from_block, EditError},
+    file_io, finish_operation,
+    i18n::{Locale, Message, MessageCode},
+    is_stale, preflight, safe_mode_skipped_file,
+    settings::AppSettings,
+    stale_skipped_file, write_edited_file, BulkInsertResult, DeletedMapping, EditedFile, Error, FileOutcome, FileResult,
+    OperationLog, OperationType, SkippedFile,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteMappingRequest {
+    pub file_path: String,
+    pub local_id: String,
+    /// 只从该序号的 sExtOptions 段落中删除，None 表示所有段落
+    #[serde(default)]
+    pub block: Option<usize>,
+    /// 映射 key 的前缀，None 表示设置中的映射前缀
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// 删除映射：soft 为 true 时注释掉条目（软删除），否则直接删除
+pub(crate) fn take_out_mapping(raw: &str, local_id: &str, prefix: &str, block: Option<usize>, soft: bool) -> Result<String, EditError> {
+    if soft {
+        editor::comment_out_mapping_from_block(raw, local_id, prefix, block)
+    } else {
+        remove_mapping_from_block(raw, local_id, prefix, block)
+    }
+}
+
+/// 日志中记录的删除方式
+pub(crate) fn delete_mode_label(soft: bool, locale: Locale) -> &'static str {
+    if soft {
+        locale.pick("软删除，已注释掉原条目", "soft delete, original entries commented out")
+    } else {
+        locale.pick("直接删除", "deleted")
+    }
+}
+
+/// 批量删除映射；soft 为 true 时把条目注释掉而不是删除，便于在设备上手动恢复
+pub fn batch_delete_mappings(
+    settings: &AppSettings,
+    requests: Vec<DeleteMappingRequest>,
+    auto_increment_version: bool,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    soft: bool,
+    on_file_done: &(dyn Fn(&FileResult) + Sync),
+) -> Result<BulkInsertResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    if requests.is_empty() {
+        return Err("删除列表为空".into());
+    }
+    for req in &requests {
+        settings.key_prefix(req.prefix.as_deref())?;
+    }
+
+
+    // 按文件路径分组（保持请求中文件首次出现的顺序），提高效率
+    let mut file_groups: Vec<(String, Vec<&DeleteMappingRequest>)> = Vec::new();
+    for req in &requests {
+        match file_groups.iter_mut().find(|(file_path, _)| *file_path == req.file_path) {
+            Some((_, targets)) => targets.push(req),
+            None => file_groups.push((req.file_path.clone(), vec![req])),
+        }
+    }
+
+    // 先并行读取、检查并在内存中删除，确定需要写入的文件后再备份
+    let retry_context = file_io::RetryContext::current();
+    let planned: Vec<(FileOutcome, Option<PendingDelete>)> = file_groups
+        .into_par_iter()
+        .map_init(|| retry_context.enter(), |_, (file_path, targets)| {
+            plan_file_delete(settings, file_path, &targets, expected_hashes.as_ref(), soft)
+        })
+        .collect();
+
+    // 只备份需要写入的文件；文件在不同目录时使用第一个文件的目录
+    let files_to_backup: Vec<&PathBuf> = planned
+        .iter()
+        .filter_map(|(_, pending)| pending.as_ref().map(|pending| &pending.path))
+        .collect();
+    let mut backup_dir_path: Option<String> = None;
+    let mut log_target_dir: Option<PathBuf> = None;
+    if let Some(first_file) = files_to_backup.first() {
+        let file_dir = first_file.parent().ok_or("无法获取文件所在目录")?;
+        preflight::ensure_writable(file_dir, &files_to_backup, settings)?;
+        let backup_dir = backup::create_backup(file_dir, &files_to_backup, settings)?;
+        backup_dir_path = Some(backup_dir.to_string_lossy().into_owned());
+        log_target_dir = Some(file_dir.to_path_buf());
+    }
+
+    // 并行写入，结果按文件顺序合并
+    let outcomes: Vec<FileOutcome> = planned
+        .into_par_iter()
+        .map_init(|| retry_context.enter(), |_, (mut outcome, pending)| {
+            if let Some(pending) = pending {
+                write_file_delete(settings, pending, auto_increment_version, &mut outcome);
+            }
+            outcome
+        })
+        .inspect(|outcome| {
+            if let Some(file_result) = outcome.file_result() {
+                on_file_done(&file_result);
+            }
+        })
+        .collect();
+    let FileOutcome {
+        updated_files,
+        skipped_files,
+        stale_files,
+        validation_errors,
+        version_changes,
+        deleted_mappings,
+        ..
+    } = FileOutcome::merge(outcomes);
+
+    // 没有写入任何文件时不记录日志
+    let Some(log_target_dir) = log_target_dir else {
+        return Ok(BulkInsertResult {
+            updated_files,
+            skipped_files,
+            validation_errors,
+            stale_files,
+            backup_dir: backup_dir_path,
+            retries: retries.finish(),
+            ..Default::default()
+        });
+    };
+
+    let locale = Locale::of(settings);
+    let delete_info = match locale {
+        Locale::ZhCn => format!("批量删除 {} 条映射（{}）", requests.len(), delete_mode_label(soft, locale)),
+        Locale::EnUs => format!("Deleted {} mappings ({})", requests.len(), delete_mode_label(soft, locale)),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(&log_target_dir, settings),
+        OperationLog {
+            updated_files: &updated_files,
+            skipped_files: &skipped_files,
+            backup_dir: backup_dir_path.as_ref(),
+            additional_info: Some(&delete_info),
+            deleted: &deleted_mappings,
+            version_changes: &version_changes,
+            ..OperationLog::new(OperationType::BatchDelete)
+        },
+    );
+
+    Ok(BulkInsertResult {
+        updated_files,
+        skipped_files,
+        validation_errors,
+        stale_files,
+        backup_dir: backup_dir_path,
+        retries: retries.finish(),
+        ..Default::default()
+    })
+}
+
+/// 已在内存中删除、等待写入的文件
+struct PendingDelete {
+    path: PathBuf,
+    file_path: String,
+    raw: String,
+    format: file_io::TextFormat,
+    updated: String,
+    deleted_ids: Vec<String>,
+    /// 未能删除的本地栏目ID
+    failed_ids: Vec<String>,
+}
+
+/// 读取并检查单个文件，在内存中删除请求的映射；无法处理的文件记入跳过记录，不返回待写入的内容
+fn plan_file_delete(
+    settings: &AppSettings,
+    file_path: String,
+    targets: &[&DeleteMappingRequest],
+    expected_hashes: Option<&std::collections::HashMap<String, String>>,
+    soft: bool,
+) -> (FileOutcome, Option<PendingDelete>) {
+    let mut outcome = FileOutcome::default();
+    let local_ids: Vec<String> = targets.iter().map(|req| req.local_id.clone()).collect();
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        outcome.skipped_files.push(SkippedFile::new(
+            file_path,
+            Message::new(MessageCode::FileNotFound),
+            local_ids,
+            Locale::of(settings),
+        ));
+        return (outcome, None);
+    }
+
+    let (raw, format) = match file_io::read_text(&path) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            outcome.skipped_files.push(SkippedFile::new(
+                file_path,
+                Message::new(MessageCode::ReadFailed).with("error", e),
+                local_ids,
+                Locale::of(settings),
+            ));
+            return (outcome, None);
+        }
+    };
+
+    if is_stale(expected_hashes, &file_path, &raw) {
+        outcome.skipped_files.push(stale_skipped_file(&file_path, Locale::of(settings)));
+        outcome.stale_files.push(file_path);
+        return (outcome, None);
+    }
+    if let Some(skipped) = safe_mode_skipped_file(settings, &file_path, &raw) {
+        outcome.skipped_files.push(skipped);
+        return (outcome, None);
+    }
+
+    // 先解析文件获取映射信息（用于记录日志）
+    let parsed_mappings = parse_mappings(&raw, &settings.prefixes()).unwrap_or_default();
+    let mut mapping_map: std::collections::HashMap<(String, String), Option<String>> =
+        std::collections::HashMap::new();
+    for entry in parsed_mappings {
+        mapping_map.entry((entry.prefix, entry.local_id)).or_insert(entry.gw_id);
+    }
+
+    // 逐个删除
+    let mut current_content = raw.clone();
+    let mut deleted_ids = Vec::new();
+    let mut failed_ids = Vec::new();
+    for req in targets {
+        let local_id = &req.local_id;
+        let prefix = req.prefix.as_deref().unwrap_or(&settings.portal_prefix);
+        // 在删除前记录映射信息
+        let gw_id = mapping_map.get(&(prefix.to_string(), local_id.clone())).cloned().flatten();
+
+        match take_out_mapping(&current_content, local_id, prefix, req.block, soft) {
+            Ok(updated) => {
+                current_content = updated;
+                deleted_ids.push(local_id.clone());
+                outcome.deleted_mappings.push(DeletedMapping {
+                    file_path: file_path.clone(),
+                    local_id: local_id.clone(),
+                    gw_id,
+                    block: req.block,
+                    prefix: req.prefix.clone(),
+                    soft,
+                });
+            }
+            // 记录错误但不中断处理
+            Err(_) => failed_ids.push(local_id.clone()),
+        }
+    }
+
+    if deleted_ids.is_empty() {
+        // 所有ID都删除失败
+        outcome.skipped_files.push(SkippedFile::new(
+            file_path,
+            Message::new(MessageCode::DeleteFailed).with("ids", failed_ids.join("、")),
+            failed_ids,
+            Locale::of(settings),
+        ));
+        return (outcome, None);
+    }
+    let pending = PendingDelete {
+        path,
+        file_path,
+        raw,
+        format,
+        updated: current_content,
+        deleted_ids,
+        failed_ids,
+    };
+    (outcome, Some(pending))
+}
+
+/// 校验并写入删除后的内容，未能写入时整个文件保留原样
+fn write_file_delete(
+    settings: &AppSettings,
+    pending: PendingDelete,
+    auto_increment_version: bool,
+    outcome: &mut FileOutcome,
+) {
+    let edit = EditedFile {
+        path: &pending.path,
+        file_path: &pending.file_path,
+        raw: &pending.raw,
+        format: pending.format,
+        updated: pending.updated,
+    };
+    if let Err(refused) = write_edited_file(settings, edit, auto_increment_version, outcome) {
+        outcome.refuse(
+            settings,
+            &pending.file_path,
+            refused,
+            MessageCode::DeleteValidationFailed,
+            pending.deleted_ids,
+        );
+        outcome.deleted_mappings.clear();
+        return;
+    }
+    // 如果有部分失败，记录跳过的文件
+    if !pending.failed_ids.is_empty() {
+        outcome.skipped_files.push(SkippedFile::new(
+            pending.file_path,
+            Message::new(MessageCode::PartialDeleteFailed).with("ids", pending.failed_ids.join("、")),
+            pending.failed_ids,
+            Locale::of(settings),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(local_ids: &[&str]) -> String {
+        let body: Vec<String> = local_ids
+            .iter()
+            .map(|local_id| format!("    \"portal_frag_{}\": \"a.B|intent://?es_tabId=9{}&es_title=\"", local_id, local_id))
+            .collect();
+        format!("{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", body.join(",\n"))
+    }
+
+    fn request(path: &std::path::Path, local_id: &str) -> DeleteMappingRequest {
+        DeleteMappingRequest {
+            file_path: path.to_string_lossy().into_owned(),
+            local_id: local_id.to_string(),
+            block: None,
+            prefix: None,
+        }
+    }
+
+    #[test]
+    fn backs_up_only_files_that_pass_the_checks() {
+        let dir = std::env::temp_dir().join(format!("catmap-batch-delete-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fresh = dir.join("theme1.json");
+        let stale = dir.join("theme2.json");
+        std::fs::write(&fresh, theme(&["101", "102"])).unwrap();
+        std::fs::write(&stale, theme(&["101"])).unwrap();
+        let settings = AppSettings::default();
+        let stale_path = stale.to_string_lossy().into_owned();
+        let expected = std::collections::HashMap::from([(stale_path.clone(), "outdated".to_string())]);
+
+        // 只有过期的文件时不创建备份
+        let result = batch_delete_mappings(
+            &settings,
+            vec![request(&stale, "101")],
+            false,
+            Some(expected.clone()),
+            false,
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(result.stale_files, [stale_path.as_str()]);
+        assert!(result.backup_dir.is_none());
+
+        let result = batch_delete_mappings(
+            &settings,
+            vec![request(&fresh, "101"), request(&stale, "101")],
+            false,
+            Some(expected),
+            false,
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(result.updated_files, [fresh.to_string_lossy()]);
+        let backup = backup::read_backup(std::path::Path::new(&result.backup_dir.unwrap())).unwrap();
+        let names: Vec<&str> = backup.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["theme1.json"]);
+        assert_eq!(std::fs::read_to_string(&stale).unwrap(), theme(&["101"]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn logs_the_summary_in_the_configured_locale() {
+        let dir = std::env::temp_dir().join(format!("catmap-delete-locale-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("theme1.json");
+        std::fs::write(&file, theme(&["101", "102"])).unwrap();
+        let settings = AppSettings {
+            locale: "en-US".to_string(),
+            ..AppSettings::default()
+        };
+
+        batch_delete_mappings(&settings, vec![request(&file, "101")], false, None, true, &|_| {}).unwrap();
+        let history = crate::get_operation_history(&settings, dir.to_string_lossy().into_owned(), None).unwrap();
+        let log = std::fs::read_to_string(&history[0].log_file).unwrap();
+        assert!(log.contains("Deleted 1 mappings (soft delete, original entries commented out)"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
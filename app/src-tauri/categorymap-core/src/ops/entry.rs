@@ -0,0 +1,264 @@
+//! 单条映射的删除、修改原始值和恢复
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup, delete_mode_label, editor,
+    editor::{extract_gw_id, parse_mappings},
+    ensure_safe_to_modify, file_io, finish_operation,
+    i18n::Locale,
+    settings::AppSettings,
+    take_out_mapping, write_single_file, AddedMapping, DeletedMapping, EditedFile, Error, FileOutcome, MappingEntry,
+    OperationLog, OperationType,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn delete_mapping(
+    settings: &AppSettings,
+    file_path: String,
+    local_id: String,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+    block: Option<usize>,
+    soft: bool,
+    prefix: Option<String>,
+) -> Result<Option<String>, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+    let prefix = settings.key_prefix(prefix.as_deref())?;
+
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    
+    // 先解析文件获取国网ID（用于日志记录）
+    let parsed_mappings = parse_mappings(&raw, &settings.prefixes()).unwrap_or_default();
+    let gw_id = parsed_mappings.iter()
+        .find(|e| e.local_id == local_id && e.prefix == prefix && (block.is_none() || block == e.block_index))
+        .and_then(|e| e.gw_id.clone());
+    
+    let updated = take_out_mapping(&raw, &local_id, prefix, block, soft)?;
+
+    // 检查和校验都通过后再创建备份，被拒绝的删除不留下多余的备份
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+
+    // 写入操作日志
+    let locale = Locale::of(settings);
+    let delete_info = match locale {
+        Locale::ZhCn => format!("删除本地栏目ID: {}（{}）", local_id, delete_mode_label(soft, locale)),
+        Locale::EnUs => format!("Deleted local ID: {} ({})", local_id, delete_mode_label(soft, locale)),
+    };
+    let deleted_mappings = vec![DeletedMapping {
+        file_path: file_path.clone(),
+        local_id: local_id.clone(),
+        gw_id,
+        block,
+        prefix: Some(prefix.to_string()),
+        soft,
+    }];
+    
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&file_path),
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(&delete_info),
+            deleted: &deleted_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::SingleDelete)
+        },
+    );
+
+    Ok(Some(backup_dir.to_string_lossy().into_owned()))
+}
+
+/// 直接替换映射的整个值（用于模板无法表达的自定义参数），返回备份目录
+///
+/// 新值需是 `栏目类名|intent://...?key=value` 形式；block 为 None 时修改所有段落中的该映射。
+#[allow(clippy::too_many_arguments)]
+pub fn set_raw_value(
+    settings: &AppSettings,
+    file_path: String,
+    local_id: String,
+    raw_value: String,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+    block: Option<usize>,
+    prefix: Option<String>,
+) -> Result<Option<String>, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+    let prefix = settings.key_prefix(prefix.as_deref())?;
+    let raw_value = raw_value.trim();
+    editor::check_raw_value(raw_value)?;
+
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    let (updated, old_values) = editor::set_raw_value_in_file(&raw, &local_id, prefix, block, raw_value)?;
+    if old_values.iter().all(|old| old == raw_value) {
+        return Err("映射值未变化".into());
+    }
+
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+
+    // 写入操作日志，原值记为删除、新值记为新增
+    let set_info = match Locale::of(settings) {
+        Locale::ZhCn => format!("修改本地栏目ID {} 的映射值：{} → {}", local_id, old_values.join("、"), raw_value),
+        Locale::EnUs => format!("Changed local ID {}: {} → {}", local_id, old_values.join(", "), raw_value),
+    };
+    let deleted_mappings: Vec<DeletedMapping> = old_values
+        .iter()
+        .map(|old| DeletedMapping {
+            file_path: file_path.clone(),
+            local_id: local_id.clone(),
+            gw_id: extract_gw_id(old),
+            ..Default::default()
+        })
+        .collect();
+    let added_mappings: Vec<AddedMapping> = extract_gw_id(raw_value)
+        .map(|gw_id| AddedMapping {
+            file_path: file_path.clone(),
+            local_id: local_id.clone(),
+            gw_id,
+            ..Default::default()
+        })
+        .into_iter()
+        .collect();
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: &outcome.updated_files,
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(&set_info),
+            deleted: &deleted_mappings,
+            added: &added_mappings,
+            modified: &outcome.modified_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::SetRawValue)
+        },
+    );
+
+    Ok(Some(backup_dir.to_string_lossy().into_owned()))
+}
+
+/// 从备份中取回一条映射，重新写入当前文件中相同序号的 sExtOptions 段落，返回本次修改前的备份目录
+///
+/// 用于误删单条映射后的恢复，不会回退文件中的其他修改；当前文件中该映射仍存在时拒绝恢复。
+pub fn restore_mapping(
+    settings: &AppSettings,
+    target_dir: String,
+    backup_dir: String,
+    file_name: String,
+    local_id: String,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+) -> Result<Option<String>, Error> {
+    let dir = PathBuf::from(&target_dir);
+    let path = dir.join(&file_name);
+    if !path.is_file() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+    let file_path = path.to_string_lossy().into_owned();
+    backup::ensure_intact(Path::new(&backup_dir))?;
+    let backup_raw = backup::read_backup_file(Path::new(&backup_dir), &file_name)?
+        .ok_or_else(|| format!("备份中不存在该文件: {}", file_name))?;
+    let prefixes = settings.prefixes();
+    let backed_up: Vec<MappingEntry> = parse_mappings(&backup_raw, &prefixes)?
+        .into_iter()
+        .filter(|entry| entry.local_id == local_id && entry.is_active())
+        .collect();
+    if backed_up.is_empty() {
+        return Err(format!("备份的 {} 中没有本地栏目ID {} 的映射", file_name, local_id).into());
+    }
+
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    let current = parse_mappings(&raw, &prefixes).map_err(Error::in_file(&file_path))?;
+    let mut lines = Vec::new();
+    for entry in &backed_up {
+        let block = entry.block_index.unwrap_or(0);
+        // 当前文件同一段落中已有该映射（可能是之后修改过的值）时不覆盖
+        if current
+            .iter()
+            .any(|existing| existing.local_id == local_id && existing.is_active() && existing.block_index == Some(block))
+        {
+            return Err(format!("当前文件中已存在本地栏目ID {} 的映射，无需恢复", local_id).into());
+        }
+        lines.push((block, format!("\"{}{}\":\"{}\"", entry.prefix, entry.local_id, entry.raw_value)));
+    }
+    let updated = editor::insert_lines_into_blocks(&raw, &lines)?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir_path = write_single_file(settings, &dir, edit, auto_increment_version, &mut outcome)?;
+
+    let info = format!("从备份 {} 恢复 {} 中本地栏目ID {} 的映射", backup_dir, file_name, local_id);
+    let added_mappings: Vec<AddedMapping> = backed_up
+        .iter()
+        .filter_map(|entry| {
+            entry.gw_id.clone().map(|gw_id| AddedMapping {
+                file_path: file_path.clone(),
+                local_id: local_id.clone(),
+                gw_id,
+                ..Default::default()
+            })
+        })
+        .collect();
+    finish_operation(
+        settings,
+        &backup::storage_dir(&dir, settings),
+        OperationLog {
+            updated_files: &outcome.updated_files,
+            backup_dir: Some(&backup_dir_path.to_string_lossy().into_owned()),
+            additional_info: Some(&info),
+            added: &added_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::RestoreMapping)
+        },
+    );
+
+    Ok(Some(backup_dir_path.to_string_lossy().into_owned()))
+}
@@ -0,0 +1,592 @@
+//! 批量新增映射
+
+use rayon::prelude::*;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    backup, cancel, catalog, collect_theme_files,
+    editor::{insert_entries, parse_mappings, set_gw_ids_in_file, EditError},
+    file_io, finish_operation,
+    i18n::{Locale, Message, MessageCode},
+    is_stale, preflight, profile, rollback_cancelled, safe_mode_skipped_file, select_target_files,
+    settings::AppSettings,
+    stale_skipped_file, validation, write_edited_file, AddedMapping, BulkInsertResult, ConflictStrategy, EditedFile,
+    EntryConflict, EntryFileResult, EntryFileStatus, Error, FileOutcome, FileResult, MappingInput, NormalizedInput,
+    OperationLog, OperationType, SkippedFile, WriteRefused,
+};
+
+/// 批量新增映射，写入每个文件前发送 write-progress 事件
+///
+/// 中途被取消时，已写入的文件会从本次备份中恢复。
+/// 指定配置目录且设置要求校验本地栏目目录时，只接受本地栏目目录中的本地栏目ID（按规范后的ID检查）。
+#[allow(clippy::too_many_arguments)]
+pub fn run_bulk_insert(
+    settings: &AppSettings,
+    config_dir: Option<&Path>,
+    target_dir: String,
+    entries: Vec<MappingInput>,
+    auto_increment_version: bool,
+    target_files: Option<Vec<String>>,
+    conflict_strategy: Option<ConflictStrategy>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    profile: bool,
+    cancel_token: &cancel::CancelToken,
+    on_progress: &(dyn Fn(&Path, usize, usize) + Sync),
+    on_file_done: &(dyn Fn(&FileResult) + Sync),
+) -> Result<BulkInsertResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let profile = profile::ProfileScope::begin(profile);
+    if entries.is_empty() {
+        return Err(Error::NoEntries);
+    }
+    // 按设置规范输入的ID，之后的校验和写入都使用规范后的值
+    let mut entries = entries;
+    let mut normalized_inputs = Vec::new();
+    for entry in &mut entries {
+        let local_id = settings.id_normalization.local_id(&entry.local_id);
+        let gw_id = settings.id_normalization.gw_id(&entry.gw_id);
+        if local_id != entry.local_id || gw_id != entry.gw_id {
+            normalized_inputs.push(NormalizedInput {
+                raw_local_id: std::mem::replace(&mut entry.local_id, local_id.clone()),
+                raw_gw_id: std::mem::replace(&mut entry.gw_id, gw_id.clone()),
+                local_id,
+                gw_id,
+            });
+        }
+    }
+    if let Some(entry) = entries.iter().find(|entry| settings.template(entry.template.as_deref()).is_none()) {
+        return Err(EditError::UnknownTemplate { name: entry.template.clone().unwrap_or_default() }.into());
+    }
+    for entry in &entries {
+        settings.key_prefix(entry.prefix.as_deref())?;
+    }
+    if let Some(config_dir) = config_dir {
+        catalog::ensure_local_ids_known(settings, config_dir, entries.iter().map(|entry| entry.local_id.as_str()))?;
+    }
+
+    // 在修改任何文件之前校验国网栏目ID，reject 模式下剔除不符合规则的映射
+    let invalid_entries = validation::check_entries(
+        settings,
+        entries.iter().map(|entry| (entry.local_id.as_str(), entry.gw_id.as_str())),
+    )?;
+    let entry_order: Vec<String> = entries.iter().map(|entry| entry.local_id.clone()).collect();
+    entries.retain(|entry| {
+        !invalid_entries
+            .iter()
+            .any(|invalid| invalid.rejected && invalid.local_id == entry.local_id && invalid.gw_id == entry.gw_id)
+    });
+    if entries.is_empty() {
+        return Err(Error::InvalidGwIds { ids: format_invalid_entries(&invalid_entries) });
+    }
+
+    let dir = PathBuf::from(&target_dir);
+    let all_files = collect_theme_files(&dir)?;
+
+    // 开启国网栏目ID占用检查时，在目录的所有文件中查找已使用这些国网栏目ID的其他本地栏目ID
+    let pairs: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|entry| (entry.local_id.as_str(), entry.gw_id.as_str()))
+        .collect();
+    let gw_id_reuses = validation::check_gw_id_reuse(settings, &all_files, &pairs)?;
+    if gw_id_reuses.iter().any(|reuse| reuse.rejected) {
+        let details: Vec<String> = gw_id_reuses
+            .iter()
+            .map(|reuse| {
+                let used_by: Vec<&str> = reuse.used_by.iter().map(|used| used.local_id.as_str()).collect();
+                format!("{}（{}，已被 {} 使用）", reuse.local_id, reuse.gw_id, used_by.join("、"))
+            })
+            .collect();
+        return Err(Error::GwIdsReused { ids: details.join("、") });
+    }
+
+    // 仅处理用户选中的文件（未指定时处理全部文件）
+    let (files, unselected_files) = select_target_files(all_files, target_files.as_deref());
+    if files.is_empty() {
+        return Err(Error::NoSelectedFiles);
+    }
+    let conflict_strategy = conflict_strategy.unwrap_or_default();
+    let mut skipped_files = Vec::new();
+    let mut conflicts: Vec<EntryConflict> = Vec::new();
+    let mut entry_results: Vec<EntryFileResult> = Vec::new();
+    // (文件, 待新增的映射, 待覆盖国网ID的映射)
+    let mut files_to_update: Vec<(PathBuf, Vec<MappingInput>, Vec<MappingInput>)> = Vec::new();
+
+    // 先检查哪些文件需要更新
+    for file in &files {
+        let file_path_str = file.to_string_lossy().into_owned();
+        let (raw, _) = file_io::read_text(file).map_err(|err| Error::read_failed(file, err))?;
+        let parsed = profile::measure(file, profile::Stage::Parse, || parse_mappings(&raw, &settings.prefixes()))?;
+        // 按（前缀, 本地栏目ID）判断是否已存在，不同前缀下的同名ID互不冲突
+        let existing: std::collections::HashMap<(&str, &str), Option<&String>> = parsed
+            .iter()
+            .map(|item| ((item.prefix.as_str(), item.local_id.as_str()), item.gw_id.as_ref()))
+            .collect();
+        
+        // 找出重复的ID和需要添加的ID
+        let mut duplicate_ids = Vec::new();
+        let mut pending = Vec::new();
+        let mut overwrites = Vec::new();
+        
+        for entry in &entries {
+            let prefix = entry.prefix.as_deref().unwrap_or(&settings.portal_prefix);
+            let Some(existing_gw_id) = existing.get(&(prefix, entry.local_id.as_str())) else {
+                pending.push(entry.clone());
+                continue;
+            };
+
+            let unchanged = existing_gw_id.map(|gw| gw == &entry.gw_id).unwrap_or(false);
+            let resolution = if conflict_strategy == ConflictStrategy::Overwrite && !unchanged {
+                overwrites.push(entry.clone());
+                "overwritten"
+            } else {
+                duplicate_ids.push(entry.local_id.clone());
+                entry_results.push(EntryFileResult::new(
+                    &entry.local_id,
+                    &file_path_str,
+                    EntryFileStatus::Duplicate,
+                    None,
+                ));
+                "skipped"
+            };
+            conflicts.push(EntryConflict {
+                file_path: file_path_str.clone(),
+                local_id: entry.local_id.clone(),
+                gw_id: entry.gw_id.clone(),
+                existing_gw_id: existing_gw_id.cloned(),
+                resolution: resolution.to_string(),
+            });
+        }
+
+        if pending.is_empty() && overwrites.is_empty() {
+            let message = if duplicate_ids.is_empty() {
+                Message::new(MessageCode::AllMappingsExist)
+            } else {
+                Message::new(MessageCode::DuplicateIds).with("ids", duplicate_ids.join("、"))
+            };
+            skipped_files.push(SkippedFile::new(file_path_str, message, duplicate_ids, Locale::of(settings)));
+        } else {
+            // 如果有部分重复，也要记录
+            if !duplicate_ids.is_empty() {
+                skipped_files.push(SkippedFile::new(
+                    file_path_str,
+                    Message::new(MessageCode::PartialDuplicate).with("ids", duplicate_ids.join("、")),
+                    duplicate_ids.clone(),
+                    Locale::of(settings),
+                ));
+            }
+            files_to_update.push((file.clone(), pending, overwrites));
+        }
+    }
+
+    // error 策略：存在任何冲突时整批取消，不修改任何文件
+    if conflict_strategy == ConflictStrategy::Error && !conflicts.is_empty() {
+        let details: Vec<String> = conflicts
+            .iter()
+            .map(|c| format!("{}（{}）", c.local_id, c.file_path))
+            .collect();
+        return Err(Error::LocalIdsExist { ids: details.join("、") });
+    }
+
+    // 只有在有文件需要更新时才备份
+    let mut backup_dir_path: Option<String> = None;
+    if !files_to_update.is_empty() {
+        let update_paths: Vec<&PathBuf> = files_to_update.iter().map(|(file, _, _)| file).collect();
+        preflight::ensure_writable(&dir, &update_paths, settings)?;
+        let backup_dir = backup::create_backup(&dir, &files, settings)?;
+        backup_dir_path = Some(backup_dir.to_string_lossy().into_owned());
+    }
+
+    // 并行执行更新，结果按文件顺序合并
+    let total = files_to_update.len();
+    let started = AtomicUsize::new(0);
+    let retry_context = file_io::RetryContext::current();
+    let outcomes = files_to_update
+        .par_iter()
+        .map_init(|| retry_context.enter(), |_, (file, pending, overwrites)| {
+            if cancel_token.is_cancelled() {
+                return FileOutcome::default();
+            }
+            on_progress(file, started.fetch_add(1, Ordering::SeqCst) + 1, total);
+            let options = InsertOptions {
+                expected_hashes: expected_hashes.as_ref(),
+                auto_increment_version,
+            };
+            // 读写单个文件出错时只跳过该文件（文件保持原样），其他文件照常写入
+            insert_into_file(settings, file, pending, overwrites, options).unwrap_or_else(|err| {
+                let file_path_str = file.to_string_lossy().into_owned();
+                let skipped = SkippedFile::new(
+                    file_path_str.clone(),
+                    Message::new(MessageCode::WriteFailed).with("error", &err),
+                    Vec::new(),
+                    Locale::of(settings),
+                );
+                FileOutcome {
+                    entry_results: failed_entry_results(&file_path_str, pending.iter().chain(overwrites), &skipped.reason),
+                    skipped_files: vec![skipped],
+                    ..Default::default()
+                }
+            })
+        })
+        .inspect(|outcome| {
+            if let Some(file_result) = outcome.file_result() {
+                on_file_done(&file_result);
+            }
+        })
+        .collect::<Vec<_>>();
+    let outcome = FileOutcome::merge(outcomes);
+    if cancel_token.is_cancelled() {
+        return Err(rollback_cancelled(backup_dir_path.as_deref(), &outcome.updated_files).into());
+    }
+    skipped_files.extend(outcome.skipped_files);
+    let FileOutcome {
+        updated_files,
+        stale_files,
+        validation_errors,
+        version_changes,
+        added_mappings,
+        modified_mappings,
+        overwritten_mappings,
+        entry_results: written_results,
+        ..
+    } = outcome;
+    entry_results.extend(written_results);
+
+    // 被校验规则拒绝的映射在每个选中文件中都记为失败
+    for file in &files {
+        let file_path_str = file.to_string_lossy();
+        for invalid in invalid_entries.iter().filter(|invalid| invalid.rejected) {
+            entry_results.push(EntryFileResult::new(
+                &invalid.local_id,
+                &file_path_str,
+                EntryFileStatus::Error,
+                Some("国网栏目ID不符合校验规则".to_string()),
+            ));
+        }
+    }
+    let file_order: std::collections::HashMap<String, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (file.to_string_lossy().into_owned(), index))
+        .collect();
+    entry_results.sort_by_key(|result| {
+        (
+            file_order.get(&result.file_path).copied(),
+            entry_order.iter().position(|local_id| *local_id == result.local_id),
+        )
+    });
+
+    // 写入操作日志，只统计实际写入文件的映射（不含跳过或写入失败的文件）
+    let added_count = added_mappings
+        .iter()
+        .map(|added| added.local_id.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    let overwritten_count = entry_results
+        .iter()
+        .filter(|result| result.status == EntryFileStatus::Overwritten)
+        .count();
+    let locale = Locale::of(settings);
+    let entries_info = match (locale, overwritten_count) {
+        (Locale::ZhCn, 0) => format!("新增 {} 条映射", added_count),
+        (Locale::ZhCn, _) => format!("新增 {} 条映射，覆盖已存在映射 {} 处", added_count, overwritten_count),
+        (Locale::EnUs, 0) => format!("Added {} mappings", added_count),
+        (Locale::EnUs, _) => format!("Added {} mappings, overwrote {} existing ones", added_count, overwritten_count),
+    };
+    let entries_info = if normalized_inputs.is_empty() {
+        entries_info
+    } else {
+        let details: Vec<String> = normalized_inputs
+            .iter()
+            .map(|input| {
+                format!(
+                    "\"{}\"=\"{}\" → {}={}",
+                    input.raw_local_id, input.raw_gw_id, input.local_id, input.gw_id
+                )
+            })
+            .collect();
+        match locale {
+            Locale::ZhCn => format!(
+                "{}；按ID规范规则改写输入 {} 条：{}",
+                entries_info,
+                normalized_inputs.len(),
+                details.join("、")
+            ),
+            Locale::EnUs => format!(
+                "{}; rewrote {} inputs by the ID normalization rules: {}",
+                entries_info,
+                normalized_inputs.len(),
+                details.join(", ")
+            ),
+        }
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(&dir, settings),
+        OperationLog {
+            updated_files: &updated_files,
+            skipped_files: &skipped_files,
+            backup_dir: backup_dir_path.as_ref(),
+            additional_info: Some(&entries_info),
+            added: &added_mappings,
+            modified: &modified_mappings,
+            version_changes: &version_changes,
+            entry_results: &entry_results,
+            conflict_strategy: Some(conflict_strategy),
+            overwritten: &overwritten_mappings,
+            ..OperationLog::new(OperationType::BulkInsert)
+        },
+    );
+
+    Ok(BulkInsertResult {
+        updated_files,
+        skipped_files,
+        unselected_files,
+        conflicts,
+        validation_errors,
+        stale_files,
+        invalid_entries,
+        gw_id_reuses,
+        entry_results,
+        normalized_inputs,
+        backup_dir: backup_dir_path,
+        retries: retries.finish(),
+        timings: profile.map(profile::ProfileScope::finish),
+    })
+}
+
+/// 批量新增中每个文件共用的选项
+#[derive(Clone, Copy)]
+struct InsertOptions<'a> {
+    expected_hashes: Option<&'a std::collections::HashMap<String, String>>,
+    auto_increment_version: bool,
+}
+
+/// 在单个文件中新增 pending、覆盖 overwrites 的国网栏目ID并写入；
+/// 文件已被修改、安全模式拒绝或校验失败时跳过该文件，读写出错时返回错误（文件保持原样）
+fn insert_into_file(
+    settings: &AppSettings,
+    file: &Path,
+    pending: &[MappingInput],
+    overwrites: &[MappingInput],
+    options: InsertOptions,
+) -> Result<FileOutcome, Error> {
+    let mut outcome = FileOutcome::default();
+    let file_path_str = file.to_string_lossy().into_owned();
+    let (raw, format) = file_io::read_text(file).map_err(|err| Error::read_failed(file, err))?;
+    // 本文件未能写入时，其中待新增和待覆盖的映射都记为失败
+    let failed_entries =
+        |message: &str| failed_entry_results(&file_path_str, pending.iter().chain(overwrites), message);
+    if is_stale(options.expected_hashes, &file_path_str, &raw) {
+        let skipped = stale_skipped_file(&file_path_str, Locale::of(settings));
+        outcome.entry_results = failed_entries(&skipped.reason);
+        outcome.skipped_files.push(skipped);
+        outcome.stale_files.push(file_path_str);
+        return Ok(outcome);
+    }
+    if let Some(skipped) = safe_mode_skipped_file(settings, &file_path_str, &raw) {
+        outcome.entry_results = failed_entries(&skipped.reason);
+        outcome.skipped_files.push(skipped);
+        return Ok(outcome);
+    }
+    let mut updated = set_gw_ids_in_file(&raw, overwrites, settings)?;
+    if !pending.is_empty() {
+        updated = insert_entries(&updated, pending, settings)?;
+    }
+
+    let edit = EditedFile {
+        path: file,
+        file_path: &file_path_str,
+        raw: &raw,
+        format,
+        updated,
+    };
+    match write_edited_file(settings, edit, options.auto_increment_version, &mut outcome) {
+        Ok(()) => {}
+        Err(WriteRefused::Failed(err)) => return Err(err),
+        Err(refused) => {
+            outcome.refuse(settings, &file_path_str, refused, MessageCode::ValidationFailed, Vec::new());
+            let reason = outcome.skipped_files.last().map(|skipped| skipped.reason.clone()).unwrap_or_default();
+            outcome.entry_results = failed_entries(&reason);
+            return Ok(outcome);
+        }
+    }
+
+    // 记录新增和覆盖的映射详情
+    for entry in pending {
+        outcome.added_mappings.push(recorded_mapping(&file_path_str, entry));
+        outcome.entry_results.push(EntryFileResult::new(
+            &entry.local_id,
+            &file_path_str,
+            EntryFileStatus::Inserted,
+            None,
+        ));
+    }
+    for entry in overwrites {
+        outcome.overwritten_mappings.push(recorded_mapping(&file_path_str, entry));
+        outcome.entry_results.push(EntryFileResult::new(
+            &entry.local_id,
+            &file_path_str,
+            EntryFileStatus::Overwritten,
+            None,
+        ));
+    }
+    Ok(outcome)
+}
+
+/// 写入文件的一条映射，连同模板、参数、段落和前缀记入操作日志，供重放时还原输入
+fn recorded_mapping(file_path: &str, entry: &MappingInput) -> AddedMapping {
+    AddedMapping {
+        file_path: file_path.to_string(),
+        local_id: entry.local_id.clone(),
+        gw_id: entry.gw_id.clone(),
+        template: entry.template.clone(),
+        params: entry.params.clone(),
+        block: entry.block,
+        prefix: entry.prefix.clone(),
+    }
+}
+
+/// 文件未能写入时其中每条映射的结果
+fn failed_entry_results<'a>(
+    file_path: &str,
+    entries: impl Iterator<Item = &'a MappingInput>,
+    message: &str,
+) -> Vec<EntryFileResult> {
+    entries
+        .map(|entry| EntryFileResult::new(&entry.local_id, file_path, EntryFileStatus::Error, Some(message.to_string())))
+        .collect()
+}
+
+/// 把不符合校验规则的映射拼成提示文字
+pub(crate) fn format_invalid_entries(invalid_entries: &[validation::InvalidGwId]) -> String {
+    invalid_entries
+        .iter()
+        .map(|invalid| format!("{}（{}）", invalid.local_id, invalid.gw_id))
+        .collect::<Vec<_>>()
+        .join("、")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str =
+        "{\n  \"version\": 3,\n  \"sExtOptions\": {\n    \"portal_frag_101\": \"a.B|intent://?es_tabId=900&es_title=\"\n  }\n}\n";
+
+    fn input(local_id: &str, gw_id: &str) -> MappingInput {
+        MappingInput {
+            local_id: local_id.to_string(),
+            gw_id: gw_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn insert(
+        settings: &AppSettings,
+        dir: &Path,
+        entries: Vec<MappingInput>,
+        conflict_strategy: ConflictStrategy,
+        expected_hashes: Option<std::collections::HashMap<String, String>>,
+        cancel_token: &cancel::CancelToken,
+    ) -> Result<BulkInsertResult, Error> {
+        run_bulk_insert(
+            settings,
+            None,
+            dir.to_string_lossy().into_owned(),
+            entries,
+            false,
+            None,
+            Some(conflict_strategy),
+            expected_hashes,
+            false,
+            cancel_token,
+            &|_, _, _| {},
+            &|_| {},
+        )
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("catmap-insert-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("theme1.json"), THEME).unwrap();
+        std::fs::write(dir.join("theme2.json"), THEME).unwrap();
+        dir
+    }
+
+    #[test]
+    fn skips_existing_ids_and_stale_files() {
+        let dir = temp_dir("skip");
+        let settings = AppSettings::default();
+        let stale = dir.join("theme2.json").to_string_lossy().into_owned();
+        let expected = std::collections::HashMap::from([(stale.clone(), "outdated".to_string())]);
+
+        let result = insert(
+            &settings,
+            &dir,
+            vec![input("101", "901"), input("102", "902")],
+            ConflictStrategy::Skip,
+            Some(expected),
+            &cancel::CancelToken::default(),
+        )
+        .unwrap();
+        let fresh = dir.join("theme1.json").to_string_lossy().into_owned();
+        assert_eq!(result.updated_files, [fresh.as_str()]);
+        assert_eq!(result.stale_files, [stale.as_str()]);
+        assert!(result.conflicts.iter().all(|conflict| conflict.local_id == "101" && conflict.resolution == "skipped"));
+        let statuses: Vec<(&str, EntryFileStatus)> = result
+            .entry_results
+            .iter()
+            .filter(|entry| entry.file_path == fresh)
+            .map(|entry| (entry.local_id.as_str(), entry.status))
+            .collect();
+        assert_eq!(statuses, [("101", EntryFileStatus::Duplicate), ("102", EntryFileStatus::Inserted)]);
+
+        let content = std::fs::read_to_string(dir.join("theme1.json")).unwrap();
+        assert!(content.contains("es_tabId=900") && content.contains("portal_frag_102"));
+        assert_eq!(std::fs::read_to_string(dir.join("theme2.json")).unwrap(), THEME);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn error_strategy_and_cancelling_leave_files_unchanged() {
+        let dir = temp_dir("error");
+        let settings = AppSettings::default();
+        let token = cancel::CancelToken::default();
+
+        let result = insert(&settings, &dir, vec![input("101", "901")], ConflictStrategy::Error, None, &token);
+        assert!(matches!(result, Err(Error::LocalIdsExist { .. })));
+
+        token.cancel();
+        assert!(insert(&settings, &dir, vec![input("102", "902")], ConflictStrategy::Skip, None, &token).is_err());
+        for name in ["theme1.json", "theme2.json"] {
+            assert_eq!(std::fs::read_to_string(dir.join(name)).unwrap(), THEME);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn same_local_id_under_another_prefix_is_not_a_conflict() {
+        let dir = temp_dir("prefix");
+        let settings = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..AppSettings::default()
+        };
+        let entry = MappingInput {
+            prefix: Some("vod_frag_".to_string()),
+            ..input("101", "901")
+        };
+
+        let token = cancel::CancelToken::default();
+        let result = insert(&settings, &dir, vec![entry], ConflictStrategy::Error, None, &token).unwrap();
+        assert_eq!(result.updated_files.len(), 2);
+        assert!(result.entry_results.iter().all(|entry| entry.status == EntryFileStatus::Inserted));
+        let content = std::fs::read_to_string(dir.join("theme1.json")).unwrap();
+        assert!(content.contains("\"portal_frag_101\": \"a.B|intent://?es_tabId=900"));
+        assert!(content.contains("\"vod_frag_101\""));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,398 @@
+//! 只读的检查：搜索、统计、报告、一致性、主表校验、目录对比、lint 和文件校验
+
+use chrono::Local;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backup, collect_theme_files, diff, editor::find_ext_options_blocks, file_io, lint, master, preflight, report,
+    scan_directory, search, settings::AppSettings, stats, Error, FileMapping,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub files: Vec<search::FileSearchHits>,
+    pub total_hits: usize,
+}
+
+/// 在目录下所有 theme 文件中搜索映射，结果按文件分组
+///
+/// `fields` 未指定时搜索本地栏目ID、国网栏目ID和映射值，`mode` 默认为子串匹配。
+pub fn search_mappings(
+    settings: &AppSettings,
+    target_dir: String,
+    query: String,
+    fields: Option<Vec<search::SearchField>>,
+    mode: Option<search::MatchMode>,
+) -> Result<SearchResult, Error> {
+    if query.trim().is_empty() {
+        return Err("搜索内容不能为空".into());
+    }
+    let files = scan_directory(Path::new(&target_dir), settings)?;
+    let fields = fields
+        .filter(|fields| !fields.is_empty())
+        .unwrap_or_else(|| {
+            vec![
+                search::SearchField::LocalId,
+                search::SearchField::GwId,
+                search::SearchField::RawValue,
+            ]
+        });
+    let files = search::search(&files, &query, &fields, mode.unwrap_or_default());
+    let total_hits = files.iter().map(|file| file.hits.len()).sum();
+    Ok(SearchResult { files, total_hits })
+}
+
+/// 统计目录下各 theme 文件的映射数量、重复情况，以及映射数量与多数文件不一致的文件
+pub fn get_statistics(settings: &AppSettings, target_dir: String) -> Result<stats::Statistics, Error> {
+    let files = scan_directory(Path::new(&target_dir), settings)?;
+    Ok(stats::compute(&files))
+}
+
+/// 默认的审计报告目录（位于存储目录下）
+const REPORT_DIR_NAME: &str = "reports";
+
+/// 生成审计报告（HTML 或 Markdown），包含扫描结果、重复项分析和一致性检查，返回报告路径
+///
+/// 未指定 output_path 时写入存储目录下的 `reports/mapping_report_<时间戳>.<扩展名>`，不放进会被部署的目标目录。
+pub fn generate_report(
+    settings: &AppSettings,
+    target_dir: String,
+    format: Option<report::ReportFormat>,
+    output_path: Option<String>,
+) -> Result<String, Error> {
+    let format = format.unwrap_or_default();
+    let files = scan_directory(Path::new(&target_dir), settings)?;
+    let statistics = stats::compute(&files);
+    let consistency = build_consistency_report(&files);
+
+    let now = Local::now();
+    let content = report::render(
+        format,
+        &target_dir,
+        &now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        &files,
+        &statistics,
+        &consistency,
+    );
+    let output_path = match output_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let report_dir = backup::storage_dir(Path::new(&target_dir), settings).join(REPORT_DIR_NAME);
+            fs::create_dir_all(&report_dir).map_err(|e| format!("创建报告目录失败: {}", e))?;
+            report_dir.join(format!(
+                "mapping_report_{}.{}",
+                now.format("%Y%m%d-%H%M%S"),
+                format.extension()
+            ))
+        }
+    };
+    file_io::write_atomic(&output_path, content).map_err(|e| format!("写入报告失败: {}", e))?;
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+/// 在部分文件中缺失的映射
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingMapping {
+    pub local_id: String,
+    pub present_in: Vec<String>,
+    pub missing_in: Vec<String>,
+}
+
+/// 同一国网栏目ID及使用它的文件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GwIdVariant {
+    pub gw_id: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// 在不同文件中映射到不同国网栏目ID的本地栏目ID
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GwIdMismatch {
+    pub local_id: String,
+    pub variants: Vec<GwIdVariant>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyReport {
+    pub files: Vec<String>,
+    pub missing: Vec<MissingMapping>,
+    pub mismatched: Vec<GwIdMismatch>,
+    pub consistent: bool,
+}
+
+pub fn check_consistency(settings: &AppSettings, target_dir: String) -> Result<ConsistencyReport, Error> {
+    let files = scan_directory(Path::new(&target_dir), settings)?;
+    Ok(build_consistency_report(&files))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterValidationResult {
+    pub master_path: String,
+    /// 主映射表中的映射数
+    pub master_count: usize,
+    pub files: Vec<master::MasterFileReport>,
+    /// 所有文件都与主映射表一致
+    pub passed: bool,
+}
+
+/// 用主映射表（CSV 或 JSON）验收目录下的 theme 文件，逐个文件报告缺失、多余和国网栏目ID不一致的映射
+pub fn validate_against_master(
+    settings: &AppSettings,
+    target_dir: String,
+    master_path: String,
+) -> Result<MasterValidationResult, Error> {
+    let master = master::load(Path::new(&master_path))?;
+    let files = scan_directory(Path::new(&target_dir), settings)?;
+    let reports = master::validate(&files, &master);
+    Ok(MasterValidationResult {
+        master_path,
+        master_count: master.len(),
+        passed: reports.iter().all(|report| report.passed),
+        files: reports,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryDiffResult {
+    pub dir_a: String,
+    pub dir_b: String,
+    pub files: Vec<diff::FileDiff>,
+    pub identical: bool,
+}
+
+pub fn diff_directories(settings: &AppSettings, dir_a: String, dir_b: String) -> Result<DirectoryDiffResult, Error> {
+    let files_a = scan_directory(Path::new(&dir_a), settings).map_err(|e| format!("扫描目录 A 失败: {}", e))?;
+    let files_b = scan_directory(Path::new(&dir_b), settings).map_err(|e| format!("扫描目录 B 失败: {}", e))?;
+
+    let files = diff::diff_file_sets(&files_a, &files_b);
+    let identical = files.iter().all(|f| f.presence == "both" && f.diff.is_empty());
+    Ok(DirectoryDiffResult {
+        dir_a,
+        dir_b,
+        files,
+        identical,
+    })
+}
+
+/// 比较多个文件的映射集合，找出缺失和国网栏目ID不一致的映射
+pub fn build_consistency_report(files: &[FileMapping]) -> ConsistencyReport {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    let file_paths: Vec<String> = files.iter().map(|f| f.file_path.clone()).collect();
+
+    // 本地栏目ID -> 国网栏目ID -> 使用该映射的文件
+    let mut by_local: BTreeMap<&str, BTreeMap<Option<&str>, BTreeSet<&str>>> = BTreeMap::new();
+    for file in files {
+        for entry in file.mappings.iter().filter(|entry| entry.is_active()) {
+            by_local
+                .entry(entry.local_id.as_str())
+                .or_default()
+                .entry(entry.gw_id.as_deref())
+                .or_default()
+                .insert(file.file_path.as_str());
+        }
+    }
+
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    for (local_id, variants) in &by_local {
+        let present: BTreeSet<&str> = variants.values().flatten().copied().collect();
+        if present.len() < files.len() {
+            missing.push(MissingMapping {
+                local_id: local_id.to_string(),
+                present_in: present.iter().map(|f| f.to_string()).collect(),
+                missing_in: file_paths
+                    .iter()
+                    .filter(|f| !present.contains(f.as_str()))
+                    .cloned()
+                    .collect(),
+            });
+        }
+        if variants.len() > 1 {
+            mismatched.push(GwIdMismatch {
+                local_id: local_id.to_string(),
+                variants: variants
+                    .iter()
+                    .map(|(gw_id, files)| GwIdVariant {
+                        gw_id: gw_id.map(|gw| gw.to_string()),
+                        files: files.iter().map(|f| f.to_string()).collect(),
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    let consistent = missing.is_empty() && mismatched.is_empty();
+    ConsistencyReport {
+        files: file_paths,
+        missing,
+        mismatched,
+        consistent,
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    pub file_path: String,
+    pub issues: Vec<lint::LintIssue>,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// 检查单个 theme 文件中解析时被容忍或忽略的问题，不修改文件
+pub fn lint_theme_file(settings: &AppSettings, file_path: String) -> Result<LintReport, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+
+    let (raw, _) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    let issues = lint::lint(&raw, &settings.prefixes());
+    let errors = issues.iter().filter(|issue| issue.severity == lint::LintSeverity::Error).count();
+    Ok(LintReport {
+        file_path,
+        warnings: issues.len() - errors,
+        errors,
+        issues,
+    })
+}
+
+/// 单个文件的语法检查结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileValidation {
+    pub file_path: String,
+    /// 没有 error 级别的问题（文件可以正常扫描和修改）
+    pub valid: bool,
+    pub issues: Vec<lint::SyntaxIssue>,
+}
+
+/// 检查单个文件的语法，给出出错的行列号和附近的源码，不修改文件
+pub fn validate_file(file_path: String) -> Result<FileValidation, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+
+    let (raw, _) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    let issues = lint::validate_syntax(&raw);
+    Ok(FileValidation {
+        file_path,
+        valid: issues.iter().all(|issue| issue.issue.severity != lint::LintSeverity::Error),
+        issues,
+    })
+}
+
+/// 目录中单个 theme 文件的检查结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReadiness {
+    pub file_path: String,
+    /// 找到了可用的 sExtOptions 段落
+    pub has_ext_options: bool,
+    /// 文件无法读取或 sExtOptions 段落无法解析时的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 目标目录的就绪检查报告
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetReadiness {
+    pub path: String,
+    /// 路径存在且是目录
+    pub exists: bool,
+    /// 匹配的 theme 文件数（不含被 .catmapignore 排除的文件）
+    pub theme_file_count: usize,
+    pub files: Vec<FileReadiness>,
+    /// 无法写入的文件、目录或备份目录
+    pub blocked: Vec<preflight::BlockedPath>,
+    /// 备份空间估算，没有文件或无法统计文件大小时为 None
+    pub backup_space: Option<backup::SpaceEstimate>,
+    /// 所有检查都通过，可以进行修改操作
+    pub ready: bool,
+    /// 未通过的检查，按检查顺序排列
+    pub problems: Vec<String>,
+}
+
+/// 打开或拖入目录时检查目录是否可以操作：是否存在、theme 文件数、是否可写、每个文件是否有 sExtOptions
+/// 段落、备份空间是否足够，一次给出所有问题，不修改任何文件
+pub fn validate_target_dir(settings: &AppSettings, path: String) -> Result<TargetReadiness, Error> {
+    let dir = PathBuf::from(&path);
+    let mut report = TargetReadiness {
+        path,
+        exists: dir.is_dir(),
+        theme_file_count: 0,
+        files: Vec::new(),
+        blocked: Vec::new(),
+        backup_space: None,
+        ready: false,
+        problems: Vec::new(),
+    };
+    if !report.exists {
+        let problem = if dir.exists() { "路径不是目录" } else { "目录不存在" };
+        report.problems.push(problem.into());
+        return Ok(report);
+    }
+
+    let files = collect_theme_files(&dir)?;
+    report.theme_file_count = files.len();
+    if files.is_empty() {
+        report.problems.push("目录中没有 theme 文件".into());
+    }
+    for file in &files {
+        let (has_ext_options, error) = match file_io::read_text(file) {
+            Ok((raw, _)) => match find_ext_options_blocks(&raw) {
+                Ok(blocks) => (!blocks.is_empty(), None),
+                Err(err) => (false, Some(err.to_string())),
+            },
+            Err(err) => (false, Some(format!("读取失败: {}", err))),
+        };
+        report.files.push(FileReadiness {
+            file_path: file.to_string_lossy().into_owned(),
+            has_ext_options,
+            error,
+        });
+    }
+    let missing = report.files.iter().filter(|file| !file.has_ext_options).count();
+    if missing > 0 {
+        report.problems.push(format!("{} 个文件没有可用的 sExtOptions 段落", missing));
+    }
+
+    report.blocked = preflight::check(&dir, &files, settings).blocked;
+    if !report.blocked.is_empty() {
+        report.problems.push(format!("{} 个文件或目录无法写入", report.blocked.len()));
+    }
+
+    if !files.is_empty() {
+        match backup::estimate_space(&dir, &files, settings) {
+            Ok(estimate) => {
+                if !estimate.is_sufficient() {
+                    report.problems.push(format!(
+                        "备份目录 {} 所在磁盘空间不足：备份需要 {} 字节，剩余 {} 字节",
+                        estimate.backup_root,
+                        estimate.required,
+                        estimate.available.unwrap_or_default()
+                    ));
+                }
+                report.backup_space = Some(estimate);
+            }
+            Err(err) => report.problems.push(format!("无法估算备份空间: {}", err)),
+        }
+    }
+
+    report.ready = report.problems.is_empty();
+    Ok(report)
+}
@@ -0,0 +1,140 @@
+//! 迁移映射前缀
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup, editor, file_io, finish_operation, i18n::Locale, plan_and_apply, settings::AppSettings, ApplyOptions,
+    BulkInsertResult, Error, FilePlan, OperationLog, OperationType, PlannedFile,
+};
+
+/// 迁移映射前缀时单个文件的变更计划
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixMigrationPlan {
+    pub file_path: String,
+    /// key 将被修改的本地栏目ID
+    pub local_ids: Vec<String>,
+    /// 修改后会与已存在的 key 重名的本地栏目ID
+    pub collisions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigratePrefixResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<PrefixMigrationPlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+/// 在目录下所有 theme 文件中把映射 key 的前缀 old_prefix 改为 new_prefix，dry_run 为 true 时只返回变更计划
+///
+/// 任一映射改名后会与已存在的 key 重名时不修改任何文件。迁移完成后需要在设置中把映射前缀改为新前缀。
+pub fn migrate_prefix(
+    settings: &AppSettings,
+    target_dir: String,
+    old_prefix: String,
+    new_prefix: String,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<MigratePrefixResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let old_prefix = old_prefix.trim().to_string();
+    let new_prefix = new_prefix.trim().to_string();
+    if old_prefix.is_empty() || new_prefix.is_empty() {
+        return Err("请输入原映射前缀和新映射前缀".into());
+    }
+    if old_prefix.contains('"') || new_prefix.contains('"') {
+        return Err("映射前缀不能包含引号".into());
+    }
+    if old_prefix == new_prefix {
+        return Err("新旧映射前缀相同，无需迁移".into());
+    }
+
+    let dir = PathBuf::from(&target_dir);
+    let dry_run = dry_run.unwrap_or(false);
+    // 任一映射改名后会重名时不修改任何文件
+    let reject_collisions = |plans: &[PrefixMigrationPlan]| {
+        let collisions: Vec<String> = plans
+            .iter()
+            .flat_map(|plan| {
+                let new_prefix = &new_prefix;
+                plan.collisions
+                    .iter()
+                    .map(move |local_id| format!("{}{}（{}）", new_prefix, local_id, plan.file_path))
+            })
+            .collect();
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("以下映射迁移后会与已存在的 key 重名，未修改任何文件：{}", collisions.join("、")).into())
+        }
+    };
+    let options = ApplyOptions {
+        check: Some(&reject_collisions),
+        ..ApplyOptions::new(auto_increment_version, dry_run)
+    };
+    let applied = plan_and_apply(settings, &dir, expected_hashes.as_ref(), options, |file_path, raw| {
+        let renamed = editor::rename_prefix_in_file(raw, &old_prefix, &new_prefix)?;
+        if renamed.local_ids.is_empty() && renamed.collisions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(PlannedFile {
+            plan: PrefixMigrationPlan {
+                file_path: file_path.to_string(),
+                local_ids: renamed.local_ids,
+                collisions: renamed.collisions,
+            },
+            updated: Some(renamed.content),
+        }))
+    })?;
+    if applied.plans.is_empty() {
+        return Err(format!("未找到前缀为 {} 的映射", old_prefix).into());
+    }
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        // 写入操作日志
+        let migrated: usize = applied.written().map(|plan| plan.local_ids.len()).sum();
+        let outcome = &applied.outcome;
+        let migrate_info = match Locale::of(settings) {
+            Locale::ZhCn => format!(
+                "将映射前缀 {} 改为 {}，共 {} 个文件 {} 处",
+                old_prefix,
+                new_prefix,
+                outcome.updated_files.len(),
+                migrated
+            ),
+            Locale::EnUs => format!(
+                "Changed mapping prefix {} to {}: {} entries in {} files",
+                old_prefix,
+                new_prefix,
+                migrated,
+                outcome.updated_files.len()
+            ),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(&dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&migrate_info),
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(OperationType::MigratePrefix)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(MigratePrefixResult { result, plans, dry_run })
+}
+
+impl FilePlan for PrefixMigrationPlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
@@ -0,0 +1,45 @@
+//! 各项查询和修改操作，每个模块一类操作；修改文件的公共流程见 [`apply`]
+
+mod apply;
+mod archive;
+mod backups;
+mod commented;
+mod dedupe;
+mod delete;
+mod entry;
+mod insert;
+mod inspect;
+mod migrate;
+mod normalize;
+mod remote;
+mod removal;
+mod repair;
+mod replace;
+mod replay;
+mod scan;
+mod sort;
+mod sync;
+mod transfer;
+mod update;
+
+pub use apply::*;
+pub use archive::*;
+pub use backups::*;
+pub use commented::*;
+pub use dedupe::*;
+pub use delete::*;
+pub use entry::*;
+pub use insert::*;
+pub use inspect::*;
+pub use migrate::*;
+pub use normalize::*;
+pub use remote::*;
+pub use removal::*;
+pub use repair::*;
+pub use replace::*;
+pub use replay::*;
+pub use scan::*;
+pub use sort::*;
+pub use sync::*;
+pub use transfer::*;
+pub use update::*;
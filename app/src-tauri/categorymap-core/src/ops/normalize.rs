@@ -0,0 +1,180 @@
+//! 规范映射值和疑似重复的ID
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup, editor, file_io, finish_operation, i18n::Locale, plan_and_apply, settings::AppSettings, ApplyOptions,
+    BulkInsertResult, Error, FilePlan, MappingKey, OperationLog, OperationType, PlannedFile,
+};
+
+/// 规范映射值时单个文件的变更计划
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizePlan {
+    pub file_path: String,
+    /// 映射值将被改写的映射
+    pub mappings: Vec<MappingKey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizeValuesResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<NormalizePlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+/// 把目录下所有 theme 文件中的映射值改写为规范形式（见 [`crate::intent`]），dry_run 为 true 时只返回变更计划
+///
+/// 无法解析的映射值保持不变，可在扫描结果中按 nonStandard 找出后手工修改。
+pub fn normalize_values(
+    settings: &AppSettings,
+    target_dir: String,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<NormalizeValuesResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let dir = PathBuf::from(&target_dir);
+    let dry_run = dry_run.unwrap_or(false);
+    let options = ApplyOptions::new(auto_increment_version, dry_run);
+    let applied = plan_and_apply(settings, &dir, expected_hashes.as_ref(), options, |file_path, raw| {
+        let (updated, mappings) = editor::normalize_values_in_file(raw, &settings.prefixes())?;
+        Ok((!mappings.is_empty()).then(|| PlannedFile {
+            plan: NormalizePlan {
+                file_path: file_path.to_string(),
+                mappings,
+            },
+            updated: Some(updated),
+        }))
+    })?;
+    if applied.plans.is_empty() {
+        return Err("所有映射值均已是规范形式".into());
+    }
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        // 写入操作日志
+        let normalized: usize = applied.written().map(|plan| plan.mappings.len()).sum();
+        let outcome = &applied.outcome;
+        let normalize_info = match Locale::of(settings) {
+            Locale::ZhCn => format!("规范映射值，共 {} 个文件 {} 处", outcome.updated_files.len(), normalized),
+            Locale::EnUs => format!("Normalized {} values in {} files", normalized, outcome.updated_files.len()),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(&dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&normalize_info),
+                modified: &outcome.modified_mappings,
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(OperationType::NormalizeValues)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(NormalizeValuesResult { result, plans, dry_run })
+}
+
+impl FilePlan for NormalizePlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
+
+/// 规范疑似重复ID时单个文件的变更计划
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdNormalizePlan {
+    pub file_path: String,
+    pub changes: Vec<editor::IdChange>,
+    /// 规范后会与已存在的 key 重名、未修改 key 的映射，需按重复映射手工处理
+    pub collisions: Vec<MappingKey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizeIdsResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<IdNormalizePlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+/// 把目录下所有 theme 文件中疑似重复（suspicious_duplicate）的ID改为规范形式，dry_run 为 true 时只返回变更计划
+///
+/// 规范形式见 [`crate::duplicates::canonical_id`]。只修改同一段落中存在规范形式相同的其他ID的条目，
+/// 单独出现的带前导零的ID保持不变。
+pub fn normalize_ids(
+    settings: &AppSettings,
+    target_dir: String,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<NormalizeIdsResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let dir = PathBuf::from(&target_dir);
+    let dry_run = dry_run.unwrap_or(false);
+    let options = ApplyOptions::new(auto_increment_version, dry_run);
+    let applied = plan_and_apply(settings, &dir, expected_hashes.as_ref(), options, |file_path, raw| {
+        let editor::IdNormalization {
+            content: updated,
+            changes,
+            collisions,
+        } = editor::normalize_ids_in_file(raw, &settings.prefixes())?;
+        if changes.is_empty() && collisions.is_empty() {
+            return Ok(None);
+        }
+        // 只有重名、没有可规范的ID时只列入预览
+        let updated = (!changes.is_empty()).then_some(updated);
+        Ok(Some(PlannedFile {
+            plan: IdNormalizePlan {
+                file_path: file_path.to_string(),
+                changes,
+                collisions,
+            },
+            updated,
+        }))
+    })?;
+    if applied.plans.is_empty() {
+        return Err("没有疑似重复的ID".into());
+    }
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        // 写入操作日志
+        let normalized: usize = applied.written().map(|plan| plan.changes.len()).sum();
+        let outcome = &applied.outcome;
+        let normalize_info = match Locale::of(settings) {
+            Locale::ZhCn => format!("规范疑似重复ID，共 {} 个文件 {} 处", outcome.updated_files.len(), normalized),
+            Locale::EnUs => format!("Normalized {} similar IDs in {} files", normalized, outcome.updated_files.len()),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(&dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&normalize_info),
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(OperationType::NormalizeIds)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(NormalizeIdsResult { result, plans, dry_run })
+}
+
+impl FilePlan for IdNormalizePlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
@@ -0,0 +1,239 @@
+//! 扫描远程目录（设备或 HTTP 服务器），把修改后的文件推送回去
+
+use serde::Serialize;
+use std::{fs, path::PathBuf};
+
+use crate::{
+    collect_theme_files,
+    editor::validate_json_content,
+    file_io,
+    i18n::{Locale, Message, MessageCode},
+    load_file_mapping, remote,
+    settings::AppSettings,
+    webdav, webhook, write_operation_log, Error, FileMapping, OperationLog, OperationType, SkippedFile,
+};
+
+/// 远程工作目录中保存扫描时下载的原始文件的子目录，用于判断哪些文件被修改过
+const REMOTE_BASE_DIR: &str = ".remote-base";
+
+/// 上传前下载远程当前文件做对比的子目录
+const REMOTE_CHECK_DIR: &str = ".remote-check";
+
+/// 上传操作日志前临时存放日志的子目录
+const REMOTE_LOG_DIR: &str = ".remote-log";
+
+/// 远程目录对应的默认工作目录（系统临时目录下，按连接和目录区分）
+fn remote_workspace(source: &dyn remote::RemoteSource, workspace_dir: Option<String>) -> PathBuf {
+    workspace_dir.map(PathBuf::from).unwrap_or_else(|| {
+        let hash = file_io::content_hash(source.describe().as_bytes());
+        std::env::temp_dir().join(format!("categorymap-remote-{}", &hash[..12]))
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteScanResult {
+    /// 下载到的本地工作目录，可直接用于新增、导入、删除等操作，完成后用 [`apply_remote`] 上传
+    pub workspace_dir: String,
+    pub files: Vec<FileMapping>,
+}
+
+/// 通过 SSH 下载远程目录中的 theme 文件到本地工作目录并扫描（工作目录会先被清空）
+pub fn scan_remote(
+    settings: &AppSettings,
+    target: remote::RemoteTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteScanResult, Error> {
+    scan_remote_source(settings, &remote::Ssh::new(settings, target)?, workspace_dir)
+}
+
+/// 通过 HTTP/WebDAV 下载远程目录中的 theme 文件到本地工作目录并扫描（工作目录会先被清空）
+pub fn scan_http(
+    settings: &AppSettings,
+    target: webdav::HttpTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteScanResult, Error> {
+    scan_remote_source(settings, &webdav::Http::new(target)?, workspace_dir)
+}
+
+fn scan_remote_source(
+    settings: &AppSettings,
+    source: &dyn remote::RemoteSource,
+    workspace_dir: Option<String>,
+) -> Result<RemoteScanResult, Error> {
+    let workspace = remote_workspace(source, workspace_dir);
+    let names = source.list_theme_files()?;
+    if names.is_empty() {
+        return Err("远程目录中没有 theme 文件".into());
+    }
+
+    if workspace.exists() {
+        fs::remove_dir_all(&workspace).map_err(|e| format!("清空工作目录失败: {}", e))?;
+    }
+    let base_dir = workspace.join(REMOTE_BASE_DIR);
+    fs::create_dir_all(&base_dir).map_err(|e| format!("创建工作目录失败: {}", e))?;
+    source.download(&names, &base_dir)?;
+
+    let mut files = Vec::new();
+    for name in &names {
+        let file = workspace.join(name);
+        fs::copy(base_dir.join(name), &file).map_err(|e| e.to_string())?;
+        files.push(load_file_mapping(&file, settings)?);
+    }
+    Ok(RemoteScanResult {
+        workspace_dir: workspace.to_string_lossy().into_owned(),
+        files,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteApplyResult {
+    /// 已上传的远程文件路径
+    pub uploaded_files: Vec<String>,
+    pub skipped_files: Vec<SkippedFile>,
+    /// 远程备份目录，没有覆盖远程文件时为 None
+    pub backup_dir: Option<String>,
+}
+
+/// 把工作目录中修改过的 theme 文件上传回远程目录
+///
+/// 上传前在远程目录下备份将被覆盖的文件；扫描后远程文件又被他人修改的，跳过不上传。
+/// 操作日志写在远程目录中，与本地目录的日志位置一致。
+pub fn apply_remote(
+    settings: &AppSettings,
+    target: remote::RemoteTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteApplyResult, Error> {
+    apply_remote_source(settings, &remote::Ssh::new(settings, target)?, workspace_dir)
+}
+
+/// 把工作目录中修改过的 theme 文件通过 HTTP PUT 上传回远程目录，流程与 [`apply_remote`] 相同
+pub fn apply_http(
+    settings: &AppSettings,
+    target: webdav::HttpTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteApplyResult, Error> {
+    apply_remote_source(settings, &webdav::Http::new(target)?, workspace_dir)
+}
+
+fn apply_remote_source(
+    settings: &AppSettings,
+    source: &dyn remote::RemoteSource,
+    workspace_dir: Option<String>,
+) -> Result<RemoteApplyResult, Error> {
+    let workspace = remote_workspace(source, workspace_dir);
+    let base_dir = workspace.join(REMOTE_BASE_DIR);
+    if !base_dir.is_dir() {
+        return Err("工作目录不存在，请先扫描远程目录".into());
+    }
+
+    // 找出相对扫描时有变化的文件，内容校验失败时整体中止
+    let mut changed = Vec::new();
+    for file in collect_theme_files(&workspace)? {
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let content = fs::read(&file).map_err(|e| e.to_string())?;
+        if fs::read(base_dir.join(&name)).ok().as_deref() == Some(content.as_slice()) {
+            continue;
+        }
+        let (raw, _) = file_io::decode(&content).map_err(|e| format!("{}: {}", name, e))?;
+        validate_json_content(&raw).map_err(|err| format!("{} 内容校验失败，未上传：{}", name, err))?;
+        changed.push((name, file));
+    }
+    if changed.is_empty() {
+        return Ok(RemoteApplyResult {
+            uploaded_files: Vec::new(),
+            skipped_files: Vec::new(),
+            backup_dir: None,
+        });
+    }
+
+    // 下载远程当前的文件，与扫描时的内容对比
+    let check_dir = workspace.join(REMOTE_CHECK_DIR);
+    let _ = fs::remove_dir_all(&check_dir);
+    fs::create_dir_all(&check_dir).map_err(|e| e.to_string())?;
+    let remote_names = source.list_theme_files()?;
+    let existing: Vec<String> = changed
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| remote_names.contains(name))
+        .collect();
+    if let Err(e) = source.download(&existing, &check_dir) {
+        let _ = fs::remove_dir_all(&check_dir);
+        return Err(e.into());
+    }
+    let mut skipped_files = Vec::new();
+    let mut to_upload = Vec::new();
+    for (name, file) in changed {
+        let current = existing
+            .contains(&name)
+            .then(|| fs::read(check_dir.join(&name)).ok())
+            .flatten();
+        if current != fs::read(base_dir.join(&name)).ok() {
+            skipped_files.push(SkippedFile::new(
+                source.file_path(&name),
+                Message::new(MessageCode::RemoteModified),
+                Vec::new(),
+                Locale::of(settings),
+            ));
+        } else {
+            to_upload.push((name, file));
+        }
+    }
+    let _ = fs::remove_dir_all(&check_dir);
+    if to_upload.is_empty() {
+        return Ok(RemoteApplyResult {
+            uploaded_files: Vec::new(),
+            skipped_files,
+            backup_dir: None,
+        });
+    }
+
+    // 远程备份将被覆盖的文件，再上传
+    let overwritten: Vec<String> = to_upload
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| existing.contains(name))
+        .collect();
+    let backup_dir = if overwritten.is_empty() { None } else { Some(source.backup(&overwritten)?) };
+    let paths: Vec<PathBuf> = to_upload.iter().map(|(_, file)| file.clone()).collect();
+    source.upload(&paths)?;
+
+    // 上传后的内容作为新的对比基准
+    for (name, file) in &to_upload {
+        let _ = fs::copy(file, base_dir.join(name));
+    }
+    let uploaded_files: Vec<String> = to_upload.iter().map(|(name, _)| source.file_path(name)).collect();
+
+    let log_dir = workspace.join(REMOTE_LOG_DIR);
+    let _ = fs::remove_dir_all(&log_dir);
+    let info = format!("远程目录: {}", source.describe());
+    let mut record = OperationLog {
+        updated_files: &uploaded_files,
+        skipped_files: &skipped_files,
+        backup_dir: backup_dir.as_ref(),
+        additional_info: Some(&info),
+        ..OperationLog::new(OperationType::RemoteApply)
+    }
+    .record(settings);
+    let logged = write_operation_log(settings, &log_dir, &mut record).and_then(|_| {
+        let logs: Vec<PathBuf> = fs::read_dir(&log_dir)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        source.put_files(&logs)
+    });
+    let _ = fs::remove_dir_all(&log_dir);
+    if let Err(e) = logged {
+        // 日志写入失败不影响主操作，只打印错误
+        eprintln!("写入操作日志失败: {}", e);
+    }
+    // 远程文件不在本地 git 工作区中，只推送 webhook
+    webhook::notify(settings, webhook::OperationSummary::from_record(&record, &workspace));
+
+    Ok(RemoteApplyResult {
+        uploaded_files,
+        skipped_files,
+        backup_dir,
+    })
+}
@@ -0,0 +1,335 @@
+//! 按条件删除映射：按国网栏目ID删除和清空映射
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup, collect_theme_files, delete_mode_label,
+    editor::{comment_out_entry_spans, extract_gw_id, locate_entries, remove_entry_spans, EntrySpan},
+    file_io, finish_operation,
+    i18n::{Locale, MessageCode},
+    plan_and_apply,
+    settings::AppSettings,
+    ApplyOptions, BulkInsertResult, DeletedMapping, DroppedDuplicate, Error, FilePlan, OperationLog, OperationType,
+    PlannedFile,
+};
+
+/// 按条件删除映射时单个文件的变更计划
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovalPlan {
+    pub file_path: String,
+    /// 将被删除的条目
+    pub removed: Vec<DroppedDuplicate>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveMappingsResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<RemovalPlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+/// 删除目录下所有 theme 文件中（所有映射前缀）国网栏目ID为 gw_ids 之一的映射，dry_run 为 true 时只返回变更计划
+pub fn delete_by_gw_id(
+    settings: &AppSettings,
+    target_dir: String,
+    gw_ids: Vec<String>,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, Error> {
+    let mut unique_ids: Vec<String> = Vec::new();
+    for gw_id in gw_ids.iter().map(|gw_id| gw_id.trim()).filter(|gw_id| !gw_id.is_empty()) {
+        if !unique_ids.iter().any(|known| known == gw_id) {
+            unique_ids.push(gw_id.to_string());
+        }
+    }
+    let gw_ids = unique_ids;
+    if gw_ids.is_empty() {
+        return Err("请输入要删除的国网栏目ID".into());
+    }
+
+    let request = RemovalRequest {
+        operation_type: OperationType::DeleteByGwId,
+        summary: match Locale::of(settings) {
+            Locale::ZhCn => format!("按国网栏目ID {} 删除映射", gw_ids.join("、")),
+            Locale::EnUs => format!("Delete mappings by GW ID {}", gw_ids.join(", ")),
+        },
+        soft: false,
+        transactional: false,
+        files: None,
+        prefixes: settings.prefixes().into_iter().map(String::from).collect(),
+        auto_increment_version,
+        dry_run: dry_run.unwrap_or(false),
+        expected_hashes,
+    };
+    let result = remove_matching_mappings(settings, &target_dir, request, |raw, spans| {
+        spans
+            .iter()
+            .enumerate()
+            .filter(|(_, span)| {
+                extract_gw_id(&raw[span.value_start..span.value_end]).is_some_and(|gw_id| gw_ids.contains(&gw_id))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    })?;
+    if result.plans.is_empty() {
+        return Err(format!("未找到国网栏目ID为 {} 的映射", gw_ids.join("、")).into());
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearMappingsResult {
+    #[serde(flatten)]
+    pub result: RemoveMappingsResult,
+    /// 执行清空时需要传回的确认码，由将删除的条目计算得到，文件内容变化后失效
+    pub confirm_token: String,
+}
+
+/// 清空目录（或单个文件）中所有映射前缀的条目，sExtOptions 中的其他键保持不变
+///
+/// 未提供确认码时只预览，返回将删除的条目和确认码；确认码与当前内容一致时才备份并写入，任一文件失败时不保留部分修改。
+pub fn clear_mappings(
+    settings: &AppSettings,
+    target_path: String,
+    confirm_token: Option<String>,
+    auto_increment_version: bool,
+) -> Result<ClearMappingsResult, Error> {
+    let path = PathBuf::from(&target_path);
+    let (dir, files) = if path.is_dir() {
+        let files = collect_theme_files(&path)?;
+        (path, files)
+    } else if path.is_file() {
+        let dir = path.parent().ok_or("无法获取文件所在目录")?.to_path_buf();
+        (dir, vec![path])
+    } else {
+        return Err(format!("路径不存在: {}", target_path).into());
+    };
+    let dir = dir.to_string_lossy().into_owned();
+    let request = |dry_run: bool| RemovalRequest {
+        operation_type: OperationType::ClearMappings,
+        summary: match Locale::of(settings) {
+            Locale::ZhCn => format!("清空 {} 中的所有映射", target_path),
+            Locale::EnUs => format!("Clear all mappings in {}", target_path),
+        },
+        soft: false,
+        transactional: true,
+        files: Some(files.clone()),
+        prefixes: settings.prefixes().into_iter().map(String::from).collect(),
+        auto_increment_version,
+        dry_run,
+        expected_hashes: None,
+    };
+    let select_all = |_: &str, spans: &[EntrySpan]| (0..spans.len()).collect();
+
+    let preview = remove_matching_mappings(settings, &dir, request(true), select_all)?;
+    if preview.plans.is_empty() {
+        return Err("没有需要清空的映射".into());
+    }
+    let token = clear_token(&preview.plans);
+    let Some(confirm_token) = confirm_token else {
+        return Ok(ClearMappingsResult {
+            result: preview,
+            confirm_token: token,
+        });
+    };
+    if confirm_token.trim() != token {
+        return Err("确认码不正确或文件在预览后已被修改，请重新预览后再清空".into());
+    }
+    Ok(ClearMappingsResult {
+        result: remove_matching_mappings(settings, &dir, request(false), select_all)?,
+        confirm_token: token,
+    })
+}
+
+/// 清空映射的确认码：将删除的条目内容的哈希前 8 位
+fn clear_token(plans: &[RemovalPlan]) -> String {
+    let mut content = String::new();
+    for plan in plans {
+        for item in &plan.removed {
+            content.push_str(&format!("{}\t{}\t{}\n", plan.file_path, item.local_id, item.raw_value));
+        }
+    }
+    file_io::content_hash(content.as_bytes())[..8].to_string()
+}
+
+/// 在目录范围内删除映射的操作参数
+pub(crate) struct RemovalRequest {
+    pub(crate) operation_type: OperationType,
+    /// 操作日志中的说明
+    pub(crate) summary: String,
+    /// 注释掉条目而不是删除
+    pub(crate) soft: bool,
+    /// 任一文件无法处理时不修改任何文件，写入中途失败时从备份恢复已写入的文件
+    pub(crate) transactional: bool,
+    /// 只处理这些文件，None 表示目录下所有 theme 文件
+    pub(crate) files: Option<Vec<PathBuf>>,
+    /// 在这些映射前缀的条目中查找，key 匹配多个前缀时归入最长的前缀
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) auto_increment_version: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) expected_hashes: Option<std::collections::HashMap<String, String>>,
+}
+
+/// 在目录下所有 theme 文件中删除（或注释掉）select 选出的条目（返回条目在 spans 中的序号），备份后写入并记录日志
+///
+/// 没有需要删除的条目时返回空的计划，不创建备份。
+pub(crate) fn remove_matching_mappings(
+    settings: &AppSettings,
+    target_dir: &str,
+    request: RemovalRequest,
+    select: impl Fn(&str, &[EntrySpan]) -> Vec<usize>,
+) -> Result<RemoveMappingsResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let dir = PathBuf::from(target_dir);
+    let options = ApplyOptions {
+        files: request.files,
+        validation_code: MessageCode::DeleteValidationFailed,
+        transactional: request.transactional,
+        ..ApplyOptions::new(request.auto_increment_version, request.dry_run)
+    };
+    let applied = plan_and_apply(settings, &dir, request.expected_hashes.as_ref(), options, |file_path, raw| {
+        let prefixes: Vec<&str> = request.prefixes.iter().map(String::as_str).collect();
+        let located = locate_entries(raw, &prefixes)?;
+        let spans: Vec<&EntrySpan> = select(raw, &located).into_iter().map(|idx| &located[idx]).collect();
+        if spans.is_empty() {
+            return Ok(None);
+        }
+        let updated = if request.soft {
+            comment_out_entry_spans(raw, &spans)?
+        } else {
+            remove_entry_spans(raw, &spans)?
+        };
+        let removed = spans
+            .iter()
+            .map(|span| {
+                let raw_value = &raw[span.value_start..span.value_end];
+                DroppedDuplicate {
+                    local_id: span.local_id.clone(),
+                    gw_id: extract_gw_id(raw_value),
+                    raw_value: raw_value.to_string(),
+                    prefix: span.prefix.clone(),
+                }
+            })
+            .collect();
+        Ok(Some(PlannedFile {
+            plan: RemovalPlan {
+                file_path: file_path.to_string(),
+                removed,
+            },
+            updated: Some(updated),
+        }))
+    })?;
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        let deleted: Vec<DeletedMapping> = applied
+            .written()
+            .flat_map(|plan| {
+                plan.removed.iter().map(|item| DeletedMapping {
+                    file_path: plan.file_path.clone(),
+                    local_id: item.local_id.clone(),
+                    gw_id: item.gw_id.clone(),
+                    prefix: Some(item.prefix.clone()),
+                    soft: request.soft,
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        // 写入操作日志
+        let outcome = &applied.outcome;
+        let locale = Locale::of(settings);
+        let remove_info = match locale {
+            Locale::ZhCn => format!(
+                "{}，共 {} 个文件 {} 条（{}）",
+                request.summary,
+                outcome.updated_files.len(),
+                deleted.len(),
+                delete_mode_label(request.soft, locale)
+            ),
+            Locale::EnUs => format!(
+                "{}: {} mappings in {} files ({})",
+                request.summary,
+                deleted.len(),
+                outcome.updated_files.len(),
+                delete_mode_label(request.soft, locale)
+            ),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(&dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&remove_info),
+                deleted: &deleted,
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(request.operation_type)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(RemoveMappingsResult {
+        result,
+        plans,
+        dry_run: request.dry_run,
+    })
+}
+
+impl FilePlan for RemovalPlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    fn local_ids(&self) -> Vec<String> {
+        self.removed.iter().map(|item| item.local_id.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str = concat!(
+        "{\n  \"version\": 3,\n  \"sExtOptions\": {\n",
+        "    \"portal_frag_101\": \"a.B|intent://?es_tabId=900&es_title=\",\n",
+        "    \"vod_frag_102\": \"a.B|intent://?es_tabId=901&es_title=\",\n",
+        "    \"other\": \"keep\"\n  }\n}\n"
+    );
+
+    #[test]
+    fn clears_only_with_the_previewed_token() {
+        let dir = std::env::temp_dir().join(format!("catmap-clear-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("theme1.json"), THEME).unwrap();
+        let settings = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..AppSettings::default()
+        };
+        let target = dir.to_string_lossy().into_owned();
+
+        let preview = clear_mappings(&settings, target.clone(), None, false).unwrap();
+        assert!(preview.result.dry_run);
+        assert_eq!(preview.result.plans[0].removed.len(), 2);
+        assert_eq!(std::fs::read_to_string(dir.join("theme1.json")).unwrap(), THEME);
+
+        assert!(clear_mappings(&settings, target.clone(), Some("00000000".into()), false).is_err());
+        assert_eq!(std::fs::read_to_string(dir.join("theme1.json")).unwrap(), THEME);
+
+        let cleared = clear_mappings(&settings, target, Some(preview.confirm_token), false).unwrap();
+        assert!(!cleared.result.dry_run);
+        let content = std::fs::read_to_string(dir.join("theme1.json")).unwrap();
+        assert!(!content.contains("portal_frag_101") && !content.contains("vod_frag_102"));
+        assert!(content.contains("\"other\": \"keep\""));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,102 @@
+//! 修复 sExtOptions 段落中的格式问题
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup, diff, editor::validate_json_content, file_io, finish_operation, i18n::Locale, repair, settings::AppSettings,
+    write_single_file, EditedFile, Error, FileOutcome, OperationLog, OperationType,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairResult {
+    pub file_path: String,
+    /// 修复的问题，为空时文件没有需要修复的地方（不写文件）
+    pub fixes: Vec<repair::RepairFix>,
+    /// 修复前后的统一格式差异，供写入前预览
+    pub unified_diff: String,
+    pub dry_run: bool,
+    pub backup_dir: Option<String>,
+}
+
+/// 修复 sExtOptions 段落中的末尾逗号、多余逗号、缺少的逗号和空行，其他内容保持不变
+///
+/// dry_run 为 true 时只返回差异预览，不修改文件。
+pub fn repair_ext_options(
+    settings: &AppSettings,
+    file_path: String,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<RepairResult, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    let (updated, fixes) = repair::repair(&raw)?;
+    if fixes.is_empty() {
+        return Ok(RepairResult {
+            file_path,
+            fixes,
+            unified_diff: String::new(),
+            dry_run,
+            backup_dir: None,
+        });
+    }
+    validate_json_content(&updated).map_err(|err| format!("修复后仍有语法错误，已保留原文件：{}", err))?;
+    let unified_diff = diff::unified_diff(&raw, &updated, &file_path, &file_path);
+    if dry_run {
+        return Ok(RepairResult {
+            file_path,
+            fixes,
+            unified_diff,
+            dry_run,
+            backup_dir: None,
+        });
+    }
+
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+
+    // 写入操作日志
+    let repair_info = match Locale::of(settings) {
+        Locale::ZhCn => format!("修复 sExtOptions 段落中的 {} 处格式问题", fixes.len()),
+        Locale::EnUs => format!("Fixed {} formatting problems in sExtOptions", fixes.len()),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&file_path),
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(&repair_info),
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::RepairExtOptions)
+        },
+    );
+
+    Ok(RepairResult {
+        file_path,
+        fixes,
+        unified_diff,
+        dry_run,
+        backup_dir: Some(backup_dir.to_string_lossy().into_owned()),
+    })
+}
@@ -0,0 +1,144 @@
+//! 替换国网栏目ID
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup, editor::replace_gw_id_in_file, file_io, finish_operation, i18n::{Locale, MessageCode}, plan_and_apply,
+    settings::AppSettings, validation, AddedMapping, ApplyOptions, BulkInsertResult, DeletedMapping, Error, FilePlan,
+    MappingKey, OperationLog, OperationType, PlannedFile,
+};
+
+/// 替换国网栏目ID时单个文件的变更计划
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GwIdReplacePlan {
+    pub file_path: String,
+    /// 国网栏目ID将被替换的映射
+    pub mappings: Vec<MappingKey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceGwIdResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<GwIdReplacePlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+/// 在目录下所有 theme 文件中把国网栏目ID old_gw_id 替换为 new_gw_id，dry_run 为 true 时只返回变更计划
+pub fn replace_gw_id(
+    settings: &AppSettings,
+    target_dir: String,
+    old_gw_id: String,
+    new_gw_id: String,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<ReplaceGwIdResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let old_gw_id = old_gw_id.trim().to_string();
+    let new_gw_id = new_gw_id.trim().to_string();
+    if old_gw_id.is_empty() || new_gw_id.is_empty() {
+        return Err("请输入原国网栏目ID和新国网栏目ID".into());
+    }
+    if old_gw_id == new_gw_id {
+        return Err("新旧国网栏目ID相同，无需替换".into());
+    }
+    if let Some(validator) = validation::GwIdValidator::from_settings(settings)? {
+        if let Some(invalid) = validator.check("", &new_gw_id) {
+            if invalid.rejected {
+                return Err(format!("新国网栏目ID {} {}", new_gw_id, invalid.reason).into());
+            }
+        }
+    }
+
+    let dir = PathBuf::from(&target_dir);
+    let dry_run = dry_run.unwrap_or(false);
+    let options = ApplyOptions {
+        validation_code: MessageCode::ReplaceValidationFailed,
+        ..ApplyOptions::new(auto_increment_version, dry_run)
+    };
+    let applied = plan_and_apply(settings, &dir, expected_hashes.as_ref(), options, |file_path, raw| {
+        let (updated, mappings) = replace_gw_id_in_file(raw, &old_gw_id, &new_gw_id, &settings.prefixes())?;
+        Ok((!mappings.is_empty()).then(|| PlannedFile {
+            plan: GwIdReplacePlan {
+                file_path: file_path.to_string(),
+                mappings,
+            },
+            updated: Some(updated),
+        }))
+    })?;
+    if applied.plans.is_empty() {
+        return Err(format!("未找到国网栏目ID为 {} 的映射", old_gw_id).into());
+    }
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        // 旧值记为删除、新值记为新增，便于在日志中对照
+        let mut deleted = Vec::new();
+        let mut added = Vec::new();
+        for plan in applied.written() {
+            for key in &plan.mappings {
+                deleted.push(DeletedMapping {
+                    file_path: plan.file_path.clone(),
+                    local_id: key.local_id.clone(),
+                    gw_id: Some(old_gw_id.clone()),
+                    prefix: Some(key.prefix.clone()),
+                    ..Default::default()
+                });
+                added.push(AddedMapping {
+                    file_path: plan.file_path.clone(),
+                    local_id: key.local_id.clone(),
+                    gw_id: new_gw_id.clone(),
+                    prefix: Some(key.prefix.clone()),
+                    ..Default::default()
+                });
+            }
+        }
+
+        // 写入操作日志
+        let outcome = &applied.outcome;
+        let replace_info = match Locale::of(settings) {
+            Locale::ZhCn => format!(
+                "将国网栏目ID {} 替换为 {}，共 {} 个文件 {} 处",
+                old_gw_id,
+                new_gw_id,
+                outcome.updated_files.len(),
+                added.len()
+            ),
+            Locale::EnUs => format!(
+                "Replaced GW ID {} with {}: {} entries in {} files",
+                old_gw_id,
+                new_gw_id,
+                added.len(),
+                outcome.updated_files.len()
+            ),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(&dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&replace_info),
+                deleted: &deleted,
+                added: &added,
+                modified: &outcome.modified_mappings,
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(OperationType::ReplaceGwId)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(ReplaceGwIdResult { result, plans, dry_run })
+}
+
+impl FilePlan for GwIdReplacePlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
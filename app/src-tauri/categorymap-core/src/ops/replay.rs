@@ -0,0 +1,148 @@
+//! 按操作日志在其他目录重放批量新增
+
+use std::path::Path;
+
+use crate::{
+    backup, batch_delete_mappings, cancel, history, run_bulk_insert, settings::AppSettings, BulkInsertResult,
+    DeleteMappingRequest, Error, MappingInput, OperationType,
+};
+
+/// 按操作日志把一次批量新增或删除重新应用到当前文件
+///
+/// 重放走正常的命令流程，同样会做重复检查、备份和记录日志；
+/// 指定配置目录时与批量新增一样按设置校验本地栏目目录。
+pub fn replay_operation(
+    settings: &AppSettings,
+    config_dir: Option<&Path>,
+    target_dir: String,
+    operation_id: String,
+    auto_increment_version: bool,
+) -> Result<BulkInsertResult, Error> {
+    let storage_dir = backup::storage_dir(Path::new(&target_dir), settings);
+    let record = history::find_record(&history::prepare_log_dir(&storage_dir, settings)?, &operation_id)?;
+    match record.operation_type {
+        OperationType::BulkInsert => {
+            if record.added.is_empty() && record.overwritten.is_empty() {
+                return Err("该操作没有新增任何映射，无需重放".into());
+            }
+            // 原操作对所有选中文件写入同一批映射，这里按记录的模板、参数、段落和前缀还原出映射列表和目标文件；
+            // 被覆盖的映射同样还原，并沿用原操作的冲突处理策略，使其在重放时再次覆盖
+            let mut entries: Vec<MappingInput> = Vec::new();
+            let mut target_files: Vec<String> = Vec::new();
+            for added in record.added.iter().chain(&record.overwritten) {
+                if !entries.iter().any(|e| e.local_id == added.local_id && e.prefix == added.prefix) {
+                    entries.push(MappingInput {
+                        local_id: added.local_id.clone(),
+                        gw_id: added.gw_id.clone(),
+                        params: added.params.clone(),
+                        template: added.template.clone(),
+                        block: added.block,
+                        prefix: added.prefix.clone(),
+                    });
+                }
+                if !target_files.contains(&added.file_path) {
+                    target_files.push(added.file_path.clone());
+                }
+            }
+            // 按原操作的输入顺序排列，旧版本的日志中没有处理结果时保持记录顺序
+            entries.sort_by_key(|entry| {
+                record.entry_results.iter().position(|result| result.local_id == entry.local_id)
+            });
+            run_bulk_insert(
+                settings,
+                config_dir,
+                target_dir,
+                entries,
+                auto_increment_version,
+                Some(target_files),
+                record.conflict_strategy,
+                None,
+                false,
+                &cancel::CancelToken::default(),
+                &|_, _, _| {},
+                &|_| {},
+            )
+        }
+        OperationType::BatchDelete | OperationType::SingleDelete => {
+            if record.deleted.is_empty() {
+                return Err("该操作没有删除任何映射，无需重放".into());
+            }
+            let requests = record
+                .deleted
+                .iter()
+                .map(|deleted| DeleteMappingRequest {
+                    file_path: deleted.file_path.clone(),
+                    local_id: deleted.local_id.clone(),
+                    block: deleted.block,
+                    prefix: deleted.prefix.clone(),
+                })
+                .collect();
+            // 同一次操作中的删除方式相同
+            let soft = record.deleted.iter().any(|deleted| deleted.soft);
+            batch_delete_mappings(settings, requests, auto_increment_version, None, soft, &|_| {})
+        }
+        other => Err(format!("暂不支持重放该类型的操作: {}", other.label()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_operation_history, ConflictStrategy, EntryFileStatus};
+
+    const THEME: &str =
+        "{\n  \"version\": 3,\n  \"sExtOptions\": {\n    \"portal_frag_101\": \"a.B|intent://?es_tabId=900&es_title=\"\n  }\n}\n";
+
+    fn input(local_id: &str, gw_id: &str) -> MappingInput {
+        MappingInput {
+            local_id: local_id.to_string(),
+            gw_id: gw_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn replays_overwritten_mappings_with_the_recorded_strategy() {
+        let dir = std::env::temp_dir().join(format!("catmap-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("theme1.json");
+        std::fs::write(&file, THEME).unwrap();
+        let settings = AppSettings::default();
+        let target_dir = dir.to_string_lossy().into_owned();
+
+        run_bulk_insert(
+            &settings,
+            None,
+            target_dir.clone(),
+            vec![input("101", "901"), input("102", "902")],
+            false,
+            None,
+            Some(ConflictStrategy::Overwrite),
+            None,
+            false,
+            &cancel::CancelToken::default(),
+            &|_, _, _| {},
+            &|_| {},
+        )
+        .unwrap();
+        let history = get_operation_history(&settings, target_dir.clone(), None).unwrap();
+        let storage_dir = backup::storage_dir(&dir, &settings);
+        let record = history::find_record(&history::prepare_log_dir(&storage_dir, &settings).unwrap(), &history[0].id)
+            .unwrap();
+        assert_eq!(record.conflict_strategy, Some(ConflictStrategy::Overwrite));
+        assert_eq!(record.overwritten.len(), 1);
+        assert_eq!(record.overwritten[0].gw_id, "901");
+
+        // 恢复原文件后重放：已存在的映射按原策略再次覆盖
+        std::fs::write(&file, THEME).unwrap();
+        let result = replay_operation(&settings, None, target_dir, history[0].id.clone(), false).unwrap();
+        let statuses: Vec<(&str, EntryFileStatus)> =
+            result.entry_results.iter().map(|r| (r.local_id.as_str(), r.status)).collect();
+        assert_eq!(statuses, [("101", EntryFileStatus::Overwritten), ("102", EntryFileStatus::Inserted)]);
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert!(content.contains("es_tabId=901"));
+        assert!(!content.contains("es_tabId=900"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,197 @@
+//! 扫描目录和单个 theme 文件，列出目录中的 theme 文件
+
+use chrono::Local;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    editor::{find_commented_mappings, find_misplaced_mappings, parse_mappings},
+    file_io, ignore, parse_cache, preflight, profile, select_target_files,
+    settings::AppSettings,
+    Error, FileMapping, MappingEntry,
+};
+
+/// 扫描目录下所有 theme 文件并解析映射
+pub fn scan_directory(dir: &Path, settings: &AppSettings) -> Result<Vec<FileMapping>, Error> {
+    scan_directory_with_progress(dir, settings, &|_, _, _| Ok(()))
+}
+
+/// 并行扫描目录，每开始处理一个文件时回调（文件, 已开始的文件数, 总数），回调返回错误时中止扫描
+///
+/// 结果顺序与文件排序一致，不受并行处理的影响。
+pub fn scan_directory_with_progress(
+    dir: &Path,
+    settings: &AppSettings,
+    on_progress: &(dyn Fn(&Path, usize, usize) -> Result<(), Error> + Sync),
+) -> Result<Vec<FileMapping>, Error> {
+    let files = collect_theme_files(dir)?;
+
+    let total = files.len();
+    let started = AtomicUsize::new(0);
+    let retry_context = file_io::RetryContext::current();
+    files
+        .par_iter()
+        .map_init(|| retry_context.enter(), |_, file| {
+            on_progress(file, started.fetch_add(1, Ordering::SeqCst) + 1, total)?;
+            load_file_mapping(file, settings)
+        })
+        .collect()
+}
+
+/// 读取并解析单个 theme 文件
+pub fn load_file_mapping(file: &Path, settings: &AppSettings) -> Result<FileMapping, Error> {
+    let (raw, format) = file_io::read_text(file).map_err(|err| Error::read_failed(file, err))?;
+    file_mapping_from_text(file.to_string_lossy().into_owned(), &raw, format, settings)
+}
+
+pub(crate) fn file_mapping_from_text(
+    file_path: String,
+    raw: &str,
+    format: file_io::TextFormat,
+    settings: &AppSettings,
+) -> Result<FileMapping, Error> {
+    let content_hash = file_io::content_hash(raw.as_bytes());
+    let mappings = profile::measure(Path::new(&file_path), profile::Stage::Parse, || {
+        parse_cache::get_or_parse(&content_hash, settings, || parse_file_entries(raw, settings))
+    })?;
+    Ok(FileMapping {
+        file_path,
+        encoding: format.encoding,
+        has_bom: format.has_bom,
+        line_ending: file_io::detect_line_ending(raw),
+        content_hash,
+        mappings,
+    })
+}
+
+/// 解析文件中的所有条目：sExtOptions 中的映射、被注释的映射和不在 sExtOptions 中的映射
+pub(crate) fn parse_file_entries(raw: &str, settings: &AppSettings) -> Result<Vec<MappingEntry>, String> {
+    let mut mappings = parse_mappings(raw, &settings.prefixes())?;
+    mappings.extend(find_commented_mappings(raw, &settings.prefixes()));
+    mappings.extend(find_misplaced_mappings(raw, &settings.prefixes()));
+    Ok(mappings)
+}
+
+/// 文件列表中显示的文件信息
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub file_path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 最后修改时间（YYYY-MM-DD HH:MM:SS），文件系统不支持时为 None
+    pub modified_at: Option<String>,
+    pub encoding: file_io::TextEncoding,
+    pub has_bom: bool,
+    pub line_ending: file_io::LineEnding,
+    /// sExtOptions 中的映射数量，文件无法解析时为 None
+    pub mapping_count: Option<usize>,
+    pub read_only: bool,
+}
+
+/// 读取单个文件的大小、修改时间、编码、换行符、映射数量和只读标记
+pub fn get_file_info(settings: &AppSettings, file_path: String) -> Result<FileInfo, Error> {
+    let path = PathBuf::from(&file_path);
+    let metadata = fs::metadata(file_io::long_path(&path)).map_err(|_| Error::FileNotFound { file: file_path.clone() })?;
+    if !metadata.is_file() {
+        return Err(format!("不是文件: {}", file_path).into());
+    }
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    let modified_at = metadata
+        .modified()
+        .ok()
+        .map(|time| chrono::DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string());
+    Ok(FileInfo {
+        size: metadata.len(),
+        modified_at,
+        encoding: format.encoding,
+        has_bom: format.has_bom,
+        line_ending: file_io::detect_line_ending(&raw),
+        mapping_count: parse_mappings(&raw, &settings.prefixes()).ok().map(|mappings| mappings.len()),
+        read_only: metadata.permissions().readonly(),
+        file_path,
+    })
+}
+
+/// 修改前预检目录中的 theme 文件（或选中的文件）和备份目录是否可写，不修改任何文件
+pub fn check_write_access(
+    settings: &AppSettings,
+    target_dir: String,
+    target_files: Option<Vec<String>>,
+) -> Result<preflight::PreflightReport, Error> {
+    let dir = PathBuf::from(&target_dir);
+    let (files, _) = select_target_files(collect_theme_files(&dir)?, target_files.as_deref());
+    Ok(preflight::check(&dir, &files, settings))
+}
+
+/// 文件名是否为 theme*.json
+///
+/// 名称需是单个普通的路径段：远程目录、设备或 HTTP 服务器返回的名称会直接拼到本地目录后，
+/// 含路径分隔符或 `..` 时可能写到目录之外。
+pub fn is_theme_file_name(name: &str) -> bool {
+    name.starts_with("theme") && name.ends_with(".json") && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// 目录中的 theme 文件（不含被 .catmapignore 排除的文件），按路径排序
+pub fn collect_theme_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let rules = ignore::IgnoreRules::load(dir)?;
+    let mut files = list_theme_files(dir)?;
+    if !rules.is_empty() {
+        files.retain(|file| rules.matched(&file_name_lossy(file)).is_none());
+    }
+    Ok(files)
+}
+
+/// 目录中被 .catmapignore 排除的 theme 文件及命中的规则
+pub fn ignored_theme_files(dir: &Path) -> Result<Vec<ignore::IgnoredFile>, Error> {
+    let rules = ignore::IgnoreRules::load(dir)?;
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(list_theme_files(dir)?
+        .into_iter()
+        .filter_map(|file| {
+            let (pattern, line) = rules.matched(&file_name_lossy(&file))?;
+            Some(ignore::IgnoredFile {
+                file_path: file.to_string_lossy().into_owned(),
+                pattern: pattern.to_string(),
+                line,
+            })
+        })
+        .collect())
+}
+
+pub(crate) fn file_name_lossy(file: &Path) -> String {
+    file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn list_theme_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let long_dir = file_io::long_path(dir);
+    if !long_dir.exists() {
+        return Err(Error::DirNotFound { path: dir.to_string_lossy().into_owned() });
+    }
+    // 按扩展长度路径遍历，返回的仍是原目录下的路径
+    let mut files = file_io::retry_io(dir, || {
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&long_dir).min_depth(1).max_depth(1) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                if let Some(name) = entry.file_name().to_str() {
+                    if is_theme_file_name(name) {
+                        files.push(dir.join(name));
+                    }
+                }
+            }
+        }
+        Ok(files)
+    })
+    .map_err(|err| err.to_string())?;
+    files.sort();
+    Ok(files)
+}
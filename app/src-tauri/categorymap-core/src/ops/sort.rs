@@ -0,0 +1,96 @@
+//! 按本地栏目ID排序映射
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    backup, editor::sort_entries, ensure_safe_to_modify, file_io, finish_operation, i18n::Locale, settings::AppSettings,
+    write_single_file, EditedFile, Error, FileOutcome, OperationLog, OperationType,
+};
+
+/// 映射条目的排序方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// 按本地栏目ID排序
+    #[default]
+    ByLocalId,
+    /// 按国网栏目ID排序，没有国网栏目ID的条目排在最后
+    ByGwId,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortResult {
+    pub file_path: String,
+    /// 顺序是否有变化（没有变化时不写文件）
+    pub changed: bool,
+    pub backup_dir: Option<String>,
+}
+
+/// 只调整 sExtOptions 中映射条目的顺序，不同前缀的条目按设置中前缀的顺序分组，其他 key 和注释保持原位
+pub fn sort_mappings(
+    settings: &AppSettings,
+    file_path: String,
+    order: Option<SortOrder>,
+    auto_increment_version: bool,
+    expected_hash: Option<String>,
+) -> Result<SortResult, Error> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(Error::FileNotFound { file: path.to_string_lossy().into_owned() });
+    }
+
+    let order = order.unwrap_or_default();
+    let (raw, format) = file_io::read_text(&path).map_err(|err| Error::read_failed(&path, err))?;
+    if let Some(expected) = &expected_hash {
+        if &file_io::content_hash(raw.as_bytes()) != expected {
+            return Err(Error::StaleConflict { file: file_path.to_string() });
+        }
+    }
+    ensure_safe_to_modify(settings, &raw)?;
+    let updated = sort_entries(&raw, &settings.prefixes(), order).map_err(Error::in_file(&file_path))?;
+    if updated == raw {
+        return Ok(SortResult {
+            file_path,
+            changed: false,
+            backup_dir: None,
+        });
+    }
+
+    // 校验通过后再创建备份
+    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
+    let mut outcome = FileOutcome::default();
+    let edit = EditedFile {
+        path: &path,
+        file_path: &file_path,
+        raw: &raw,
+        format,
+        updated,
+    };
+    let backup_dir = write_single_file(settings, file_dir, edit, auto_increment_version, &mut outcome)?;
+
+    // 写入操作日志
+    let locale = Locale::of(settings);
+    let sort_info = match order {
+        SortOrder::ByLocalId => locale.pick("按本地栏目ID排序", "Sorted by local ID"),
+        SortOrder::ByGwId => locale.pick("按国网栏目ID排序", "Sorted by GW ID"),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(file_dir, settings),
+        OperationLog {
+            updated_files: std::slice::from_ref(&file_path),
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(sort_info),
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::Sort)
+        },
+    );
+
+    Ok(SortResult {
+        file_path,
+        changed: true,
+        backup_dir: Some(backup_dir.to_string_lossy().into_owned()),
+    })
+}
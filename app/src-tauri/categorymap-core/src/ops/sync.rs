@@ -0,0 +1,254 @@
+//! 以一个文件为准同步其他文件的映射
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{
+    backup,
+    editor::{apply_sync, extract_gw_id, parse_mappings, plan_sync},
+    file_io, finish_operation,
+    i18n::{Locale, Message, MessageCode},
+    plan_and_apply,
+    settings::AppSettings,
+    AddedMapping, ApplyOptions, BulkInsertResult, DeletedMapping, Error, FilePlan, MappingKey, OperationLog, OperationType,
+    PlannedFile, SkippedFile,
+};
+
+/// 同步映射时单个目标文件的变更计划
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFilePlan {
+    pub file_path: String,
+    /// 需要新增的映射
+    pub added: Vec<MappingKey>,
+    /// 需要删除的映射
+    pub removed: Vec<MappingKey>,
+    /// 映射值需要更新的映射
+    pub changed: Vec<MappingKey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub plans: Vec<SyncFilePlan>,
+    /// 是否仅预览（未写入文件）
+    pub dry_run: bool,
+}
+
+pub fn sync_mappings(
+    settings: &AppSettings,
+    source_file: String,
+    target_files: Vec<String>,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let source_path = PathBuf::from(&source_file);
+    if !source_path.exists() {
+        return Err("源文件不存在".into());
+    }
+    let target_paths: Vec<PathBuf> = target_files
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| p != &source_path)
+        .collect();
+    if target_paths.is_empty() {
+        return Err(Error::NoTargetFiles);
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    let (source_raw, _) = file_io::read_text(&source_path).map_err(|err| Error::read_failed(&source_path, err))?;
+
+    // 源文件中所有前缀的映射（同一映射重复时以第一条为准）
+    let prefixes = settings.prefixes();
+    let mut source_mappings: std::collections::HashMap<MappingKey, String> = std::collections::HashMap::new();
+    for entry in parse_mappings(&source_raw, &prefixes)? {
+        let key = MappingKey {
+            prefix: entry.prefix,
+            local_id: entry.local_id,
+        };
+        source_mappings.entry(key).or_insert(entry.raw_value);
+    }
+
+    // 备份放在源文件所在目录；每个文件的变更在写入前算好，无法处理的文件单独跳过
+    let file_dir = source_path.parent().ok_or("无法获取文件所在目录")?;
+    let options = ApplyOptions {
+        files: Some(target_paths),
+        validation_code: MessageCode::SyncValidationFailed,
+        ..ApplyOptions::new(auto_increment_version, dry_run)
+    };
+    let mut deleted_mappings: Vec<DeletedMapping> = Vec::new();
+    let mut applied = plan_and_apply(settings, file_dir, expected_hashes.as_ref(), options, |file_path, raw| {
+        let mut plan = plan_sync(raw, &source_mappings, &prefixes)?;
+        plan.file_path = file_path.to_string();
+        if plan.added.is_empty() && plan.removed.is_empty() && plan.changed.is_empty() {
+            return Ok(Some(PlannedFile { plan, updated: None }));
+        }
+        let updated = apply_sync(raw, &source_mappings, &prefixes)?;
+        let existing = parse_mappings(raw, &prefixes).unwrap_or_default();
+        for key in &plan.removed {
+            deleted_mappings.push(DeletedMapping {
+                file_path: file_path.to_string(),
+                local_id: key.local_id.clone(),
+                gw_id: existing
+                    .iter()
+                    .find(|e| e.prefix == key.prefix && e.local_id == key.local_id)
+                    .and_then(|e| e.gw_id.clone()),
+                prefix: Some(key.prefix.clone()),
+                ..Default::default()
+            });
+        }
+        Ok(Some(PlannedFile {
+            plan,
+            updated: Some(updated),
+        }))
+    })?;
+
+    let in_sync: Vec<String> = applied
+        .plans
+        .iter()
+        .filter(|plan| plan.added.is_empty() && plan.removed.is_empty() && plan.changed.is_empty())
+        .map(|plan| plan.file_path.clone())
+        .collect();
+    for file_path in in_sync {
+        applied.outcome.skipped_files.push(SkippedFile::new(
+            file_path,
+            Message::new(MessageCode::AlreadyInSync),
+            Vec::new(),
+            Locale::of(settings),
+        ));
+    }
+
+    if let Some(backup_dir) = &applied.backup_dir {
+        // 记录已写入文件中新增和删除的映射详情
+        let written: Vec<&SyncFilePlan> = applied.written().collect();
+        let added: Vec<AddedMapping> = written
+            .iter()
+            .flat_map(|plan| {
+                plan.added.iter().map(|key| AddedMapping {
+                    file_path: plan.file_path.clone(),
+                    local_id: key.local_id.clone(),
+                    gw_id: source_mappings
+                        .get(key)
+                        .and_then(|value| extract_gw_id(value))
+                        .unwrap_or_default(),
+                    prefix: Some(key.prefix.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let deleted: Vec<DeletedMapping> = deleted_mappings
+            .into_iter()
+            .filter(|item| written.iter().any(|plan| plan.file_path == item.file_path))
+            .collect();
+
+        // 写入操作日志
+        let outcome = &applied.outcome;
+        let changed_count: usize = written.iter().map(|p| p.changed.len()).sum();
+        let sync_info = match Locale::of(settings) {
+            Locale::ZhCn => format!(
+                "以 {} 为源同步 {} 个文件，更新映射值 {} 处",
+                source_file,
+                outcome.updated_files.len(),
+                changed_count
+            ),
+            Locale::EnUs => format!(
+                "Synced {} files from {}, updated {} values",
+                outcome.updated_files.len(),
+                source_file,
+                changed_count
+            ),
+        };
+        finish_operation(
+            settings,
+            &backup::storage_dir(file_dir, settings),
+            OperationLog {
+                updated_files: &outcome.updated_files,
+                skipped_files: &outcome.skipped_files,
+                backup_dir: Some(backup_dir),
+                additional_info: Some(&sync_info),
+                deleted: &deleted,
+                added: &added,
+                modified: &outcome.modified_mappings,
+                version_changes: &outcome.version_changes,
+                ..OperationLog::new(OperationType::Sync)
+            },
+        );
+    }
+
+    let (result, plans) = applied.into_result(retries);
+    Ok(SyncResult { result, plans, dry_run })
+}
+
+impl FilePlan for SyncFilePlan {
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(keys: &[(&str, &str)]) -> String {
+        let body: Vec<String> = keys
+            .iter()
+            .map(|(key, gw_id)| {
+                format!(
+                    "    \"{}\": \"com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=\"",
+                    key, gw_id
+                )
+            })
+            .collect();
+        format!("{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", body.join(",\n"))
+    }
+
+    #[test]
+    fn syncs_every_prefix_and_skips_files_that_cannot_be_synced() {
+        let dir = std::env::temp_dir().join(format!("catmap-sync-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.json");
+        let broken = dir.join("broken.json");
+        let target = dir.join("target.json");
+        std::fs::write(&source, theme(&[("portal_frag_101", "1001"), ("portal_frag_x_201", "2001")])).unwrap();
+        std::fs::write(&broken, "{\n  \"version\": 3\n}\n").unwrap();
+        std::fs::write(&target, theme(&[("portal_frag_101", "1001"), ("portal_frag_x_202", "2002")])).unwrap();
+
+        let settings = AppSettings {
+            extra_prefixes: vec!["portal_frag_x_".to_string()],
+            ..AppSettings::default()
+        };
+        let targets = [&broken, &target].map(|path| path.to_string_lossy().into_owned());
+        let result = sync_mappings(
+            &settings,
+            source.to_string_lossy().into_owned(),
+            targets.to_vec(),
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(result.result.updated_files, [targets[1].clone()]);
+        let skipped: Vec<&str> = result.result.skipped_files.iter().map(|s| s.file_path.as_str()).collect();
+        assert_eq!(skipped, [targets[0].as_str()]);
+        let written = std::fs::read_to_string(&target).unwrap();
+        let keys: Vec<(String, String)> = parse_mappings(&written, &settings.prefixes())
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.prefix, entry.local_id))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("portal_frag_".to_string(), "101".to_string()),
+                ("portal_frag_x_".to_string(), "201".to_string())
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
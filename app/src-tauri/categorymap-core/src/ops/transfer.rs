@@ -0,0 +1,436 @@
+//! 映射的导入和导出
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backup, cancel, collect_theme_files,
+    editor::{matching_prefix, merge_mappings_in_file, replace_mappings_in_file},
+    export, file_io, file_name_lossy, finish_operation, format_invalid_entries,
+    i18n::{Locale, Message, MessageCode},
+    is_stale, preflight, rollback_cancelled, safe_mode_skipped_file, scan_directory,
+    settings::AppSettings,
+    stale_skipped_file, validation, write_edited_file, BulkInsertResult, EditedFile, Error, FileOutcome, MappingKey,
+    OperationLog, OperationType, SkippedFile,
+};
+
+/// 导入模式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// 替换模式：清空现有映射后写入导入的映射
+    #[default]
+    Replace,
+    /// 合并模式：覆盖同名映射、追加缺失映射，其余保持不变
+    Merge,
+}
+
+/// 导入的映射来源
+#[derive(Debug, Clone)]
+pub enum ImportSource {
+    /// 同一张映射表写入所有文件，key 为本地栏目ID或带前缀的完整 key
+    Table(std::collections::HashMap<String, String>),
+    /// 自描述导出文件，按文件名把映射写回同名文件
+    Export(export::MappingExport),
+}
+
+/// 单个文件的导入统计
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFileStats {
+    pub file_path: String,
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    /// 每个文件的新增/更新/未变化数量（仅合并模式）
+    pub file_stats: Vec<ImportFileStats>,
+    /// 导入导出文件时的兼容性提示
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub output_path: String,
+    pub file_count: usize,
+    pub mapping_count: usize,
+}
+
+/// 把目录中各 theme 文件的映射导出为带元数据的 JSON 文件
+pub fn export_mappings(settings: &AppSettings, target_dir: String, output_path: String) -> Result<ExportResult, Error> {
+    let dir = PathBuf::from(&target_dir);
+    if !dir.is_dir() {
+        return Err(Error::DirNotFound { path: target_dir });
+    }
+    let export = export::MappingExport::build(settings, &dir)?;
+    export::write(&export, Path::new(&output_path))?;
+    Ok(ExportResult {
+        output_path,
+        file_count: export.files.len(),
+        mapping_count: export.mapping_count(),
+    })
+}
+
+/// 未指定输出路径时问题清单所在的目录名
+const PROBLEM_EXPORT_DIR_NAME: &str = "exports";
+
+/// 只导出状态不是 normal、本地栏目ID与国网栏目ID相同或映射值不规范的条目，交给运营方核对
+///
+/// 未指定输出路径时写到备份和日志存放目录（见 [`backup::storage_dir`]）下的 exports 目录中，
+/// 不放进会被部署的目标目录；file_count 为含有问题条目的文件数。
+pub fn export_problems(
+    settings: &AppSettings,
+    target_dir: String,
+    format: Option<export::ProblemFormat>,
+    output_path: Option<String>,
+) -> Result<ExportResult, Error> {
+    let dir = PathBuf::from(&target_dir);
+    if !dir.is_dir() {
+        return Err(Error::DirNotFound { path: target_dir });
+    }
+    let format = format.unwrap_or_default();
+    let entries = export::problem_entries(&scan_directory(&dir, settings)?);
+    let content = export::render_problems(&entries, format)?;
+    let output_path = match output_path {
+        Some(path) => path,
+        None => {
+            let export_dir = backup::storage_dir(&dir, settings).join(PROBLEM_EXPORT_DIR_NAME);
+            fs::create_dir_all(&export_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            export_dir
+                .join(format!(
+                    "mapping_problems_{}.{}",
+                    Local::now().format("%Y%m%d-%H%M%S"),
+                    format.extension()
+                ))
+                .to_string_lossy()
+                .into_owned()
+        }
+    };
+    file_io::write_atomic(Path::new(&output_path), content).map_err(|e| format!("写入问题清单失败: {}", e))?;
+    let files: std::collections::HashSet<&str> = entries.iter().map(|entry| entry.file_name.as_str()).collect();
+    Ok(ExportResult {
+        output_path,
+        file_count: files.len(),
+        mapping_count: entries.len(),
+    })
+}
+
+/// 按（前缀, 本地栏目ID）区分的导入映射表
+type KeyedTable = std::collections::HashMap<MappingKey, String>;
+
+/// 把映射表的 key 拆成（前缀, 本地栏目ID）：以已配置的前缀开头的 key 归入最长的前缀，其余按映射前缀处理
+fn keyed_table(settings: &AppSettings, table: std::collections::HashMap<String, String>) -> KeyedTable {
+    let prefixes = settings.prefixes();
+    table
+        .into_iter()
+        .map(|(key, gw_id)| {
+            let key = match matching_prefix(&key, &prefixes) {
+                Some(prefix) => MappingKey {
+                    prefix: prefix.to_string(),
+                    local_id: key[prefix.len()..].to_string(),
+                },
+                None => MappingKey {
+                    prefix: settings.portal_prefix.clone(),
+                    local_id: key,
+                },
+            };
+            (key, gw_id)
+        })
+        .collect()
+}
+
+/// 导入映射，写入每个文件前发送 write-progress 事件
+///
+/// 来源是导出文件时，每个文件只写入导出文件中同名文件的映射，没有对应映射的文件跳过。
+/// 映射表的 key 带有已配置的映射前缀时写入该前缀下，替换模式清空所有前缀下的现有映射。
+/// 中途被取消时，已写入的文件会从本次备份中恢复。
+#[allow(clippy::too_many_arguments)]
+pub fn run_import(
+    settings: &AppSettings,
+    target_dir: String,
+    source: ImportSource,
+    auto_increment_version: bool,
+    mode: Option<ImportMode>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    cancel_token: &cancel::CancelToken,
+    on_progress: &(dyn Fn(&Path, usize, usize) + Sync),
+) -> Result<ImportResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    let mode = mode.unwrap_or_default();
+    let dir = PathBuf::from(&target_dir);
+    let files = collect_theme_files(&dir)?;
+
+    // 映射表对所有文件生效；导出文件按文件名（小写）分表
+    let (mut shared, mut per_file, warnings, source_info) = match source {
+        ImportSource::Table(mappings) => {
+            (Some(keyed_table(settings, mappings)), std::collections::HashMap::new(), Vec::new(), None)
+        }
+        ImportSource::Export(export) => {
+            let warnings = export.compatibility_warnings(settings, &dir, &files);
+            let info = format!("来源: 导出文件（{}，导出于 {}）", export.source_dir, export.exported_at);
+            (None, export.tables(settings), warnings, Some(info))
+        }
+    };
+    let mapping_count = |shared: &Option<KeyedTable>, per_file: &std::collections::HashMap<String, KeyedTable>| {
+        shared.as_ref().map_or(0, |mappings| mappings.len()) + per_file.values().map(|table| table.len()).sum::<usize>()
+    };
+    if mapping_count(&shared, &per_file) == 0 {
+        return Err(Error::EmptyImport);
+    }
+
+    // 在修改任何文件之前校验国网栏目ID，reject 模式下剔除不符合规则的映射
+    let mut invalid_entries = validation::check_entries(
+        settings,
+        shared
+            .iter()
+            .chain(per_file.values())
+            .flatten()
+            .map(|(key, gw_id)| (key.local_id.as_str(), gw_id.as_str())),
+    )?;
+    invalid_entries.sort_by(|a, b| a.local_id.cmp(&b.local_id));
+    invalid_entries.dedup_by(|a, b| a.local_id == b.local_id && a.gw_id == b.gw_id);
+    for mappings in shared.iter_mut().chain(per_file.values_mut()) {
+        mappings.retain(|key, _| {
+            !invalid_entries
+                .iter()
+                .any(|invalid| invalid.rejected && invalid.local_id == key.local_id)
+        });
+    }
+    let total_mappings = mapping_count(&shared, &per_file);
+    if total_mappings == 0 {
+        return Err(Error::InvalidGwIds { ids: format_invalid_entries(&invalid_entries) });
+    }
+
+    let mut outcome = FileOutcome::default();
+    let mut file_stats = Vec::new();
+
+    // 先确认所有文件可写，再备份
+    preflight::ensure_writable(&dir, &files, settings)?;
+    let backup_dir = backup::create_backup(&dir, &files, settings)?;
+
+    // 对每个文件执行导入，单个文件读取、修改或写入失败时跳过该文件，不影响其他文件
+    let total = files.len();
+    for (index, file) in files.into_iter().enumerate() {
+        if cancel_token.is_cancelled() {
+            return Err(rollback_cancelled(backup_dir.to_str(), &outcome.updated_files).into());
+        }
+        on_progress(&file, index + 1, total);
+        let file_path_str = file.to_string_lossy().into_owned();
+        let skip = |reason: Message| SkippedFile::new(file_path_str.clone(), reason, Vec::new(), Locale::of(settings));
+        let mappings = match &shared {
+            Some(mappings) => mappings,
+            None => match per_file.get(&file_name_lossy(&file).to_lowercase()) {
+                Some(mappings) => mappings,
+                None => {
+                    outcome.skipped_files.push(skip(Message::new(MessageCode::NotInExport)));
+                    continue;
+                }
+            },
+        };
+        let (raw, format) = match file_io::read_text(&file) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                outcome
+                    .skipped_files
+                    .push(skip(Message::new(MessageCode::ReadFailed).with("error", e)));
+                continue;
+            }
+        };
+        if is_stale(expected_hashes.as_ref(), &file_path_str, &raw) {
+            outcome.skipped_files.push(stale_skipped_file(&file_path_str, Locale::of(settings)));
+            outcome.stale_files.push(file_path_str);
+            continue;
+        }
+        if let Some(skipped) = safe_mode_skipped_file(settings, &file_path_str, &raw) {
+            outcome.skipped_files.push(skipped);
+            continue;
+        }
+        // 合并模式下的统计数，没有新增或更新任何映射时不写入
+        let prefixes = settings.prefixes();
+        let edited = match mode {
+            ImportMode::Replace => replace_mappings_in_file(&raw, mappings, &prefixes).map(|updated| (updated, None)),
+            ImportMode::Merge => merge_mappings_in_file(&raw, mappings, &prefixes).map(|(merged, mut stats)| {
+                stats.file_path = file_path_str.clone();
+                (merged, Some(stats))
+            }),
+        };
+        let (updated, stats) = match edited {
+            Ok((_, Some(stats))) if stats.added + stats.updated == 0 => {
+                outcome.skipped_files.push(skip(Message::new(MessageCode::NothingChanged)));
+                continue;
+            }
+            Ok(edited) => edited,
+            Err(e) => {
+                outcome.skipped_files.push(skip(Message::other(e)));
+                continue;
+            }
+        };
+
+        let edit = EditedFile {
+            path: &file,
+            file_path: &file_path_str,
+            raw: &raw,
+            format,
+            updated,
+        };
+        match write_edited_file(settings, edit, auto_increment_version, &mut outcome) {
+            // 统计数只计入已写入的文件
+            Ok(()) => file_stats.extend(stats),
+            Err(refused) => {
+                let local_ids = mappings.keys().map(|key| key.local_id.clone()).collect();
+                outcome.refuse(settings, &file_path_str, refused, MessageCode::ValidationFailed, local_ids);
+            }
+        }
+    }
+
+    // 写入操作日志
+    let (operation_type, mut mappings_info) = match mode {
+        ImportMode::Replace => (OperationType::Import, format!("导入 {} 条映射（替换模式）", total_mappings)),
+        ImportMode::Merge => {
+            let added: usize = file_stats.iter().map(|s| s.added).sum();
+            let updated: usize = file_stats.iter().map(|s| s.updated).sum();
+            (
+                OperationType::ImportMerge,
+                format!("导入 {} 条映射（合并模式），共新增 {} 条、更新 {} 条", total_mappings, added, updated),
+            )
+        }
+    };
+    if let Some(info) = source_info {
+        mappings_info.push_str(&format!("\n{}", info));
+    }
+    finish_operation(
+        settings,
+        &backup::storage_dir(&dir, settings),
+        OperationLog {
+            updated_files: &outcome.updated_files,
+            skipped_files: &outcome.skipped_files,
+            backup_dir: Some(&backup_dir.to_string_lossy().into_owned()),
+            additional_info: Some(&mappings_info),
+            modified: &outcome.modified_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(operation_type)
+        },
+    );
+
+    Ok(ImportResult {
+        result: BulkInsertResult {
+            updated_files: outcome.updated_files,
+            skipped_files: outcome.skipped_files,
+            validation_errors: outcome.validation_errors,
+            stale_files: outcome.stale_files,
+            invalid_entries,
+            backup_dir: Some(backup_dir.to_string_lossy().into_owned()),
+            retries: retries.finish(),
+            ..Default::default()
+        },
+        file_stats,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(keys: &[(&str, &str)]) -> String {
+        let body: Vec<String> = keys
+            .iter()
+            .map(|(key, gw_id)| format!("    \"{}\": \"{}\"", key, gw_id))
+            .collect();
+        format!("{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", body.join(",\n"))
+    }
+
+    fn exported(file_name: &str, mappings: &[(&str, &str, Option<&str>)]) -> export::ExportedFile {
+        export::ExportedFile {
+            file_name: file_name.to_string(),
+            file_hash: String::new(),
+            version: None,
+            mappings: mappings
+                .iter()
+                .map(|(local_id, gw_id, prefix)| export::ExportedMapping {
+                    local_id: local_id.to_string(),
+                    gw_id: gw_id.to_string(),
+                    prefix: prefix.map(str::to_string),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn merge_counts_only_written_files_and_keeps_secondary_prefixes() {
+        let dir = std::env::temp_dir().join(format!("catmap-import-merge-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("theme1.json"), theme(&[("portal_frag_101", "1001"), ("portal_frag_x_201", "2001")]))
+            .unwrap();
+        std::fs::write(dir.join("theme2.json"), theme(&[("portal_frag_101", "1001")])).unwrap();
+
+        let settings = AppSettings {
+            extra_prefixes: vec!["portal_frag_x_".to_string()],
+            ..AppSettings::default()
+        };
+        // theme2 的映射值会让文件无法通过校验，只有 theme1 被写入
+        let export = export::MappingExport {
+            format: export::EXPORT_FORMAT.to_string(),
+            version: export::EXPORT_VERSION,
+            tool_version: String::new(),
+            exported_at: String::new(),
+            source_dir: String::new(),
+            portal_prefix: settings.portal_prefix.clone(),
+            files: vec![
+                exported("theme1.json", &[("201", "2002", Some("portal_frag_x_")), ("102", "1002", None)]),
+                exported("theme2.json", &[("102", "1002\"", None)]),
+            ],
+        };
+        let result = run_import(
+            &settings,
+            dir.to_string_lossy().into_owned(),
+            ImportSource::Export(export),
+            false,
+            Some(ImportMode::Merge),
+            None,
+            &cancel::CancelToken::default(),
+            &|_, _, _| {},
+        )
+        .unwrap();
+
+        let theme1 = dir.join("theme1.json").to_string_lossy().into_owned();
+        let theme2 = dir.join("theme2.json").to_string_lossy().into_owned();
+        assert_eq!(result.result.updated_files, [theme1.as_str()]);
+        let skipped: Vec<&str> = result.result.skipped_files.iter().map(|s| s.file_path.as_str()).collect();
+        assert_eq!(skipped, [theme2.as_str()]);
+        let stats: Vec<(&str, usize, usize)> = result
+            .file_stats
+            .iter()
+            .map(|stats| (stats.file_path.as_str(), stats.added, stats.updated))
+            .collect();
+        assert_eq!(stats, [(theme1.as_str(), 1, 1)]);
+
+        let written = std::fs::read_to_string(&theme1).unwrap();
+        let entries = crate::parse_mappings(&written, &settings.prefixes()).unwrap();
+        let keys: Vec<(&str, &str, &str)> = entries
+            .iter()
+            .map(|entry| (entry.prefix.as_str(), entry.local_id.as_str(), entry.raw_value.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("portal_frag_", "101", "1001"),
+                ("portal_frag_x_", "201", "2002"),
+                ("portal_frag_", "102", "1002"),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,388 @@
+//! 批量修改映射的国网栏目ID
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    backup, collect_theme_files,
+    editor::{parse_mappings, set_gw_ids_in_file, IntentParams},
+    file_io, finish_operation, format_invalid_entries,
+    i18n::{Locale, Message, MessageCode},
+    is_stale, preflight, safe_mode_skipped_file,
+    settings::AppSettings,
+    stale_skipped_file, validation, write_edited_file, AddedMapping, BulkInsertResult, DeletedMapping, EditedFile,
+    Error, FileOutcome, MappingInput, OperationLog, OperationType, SkippedFile,
+};
+
+/// 批量修改时的单条修改请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingUpdate {
+    pub local_id: String,
+    pub new_gw_id: String,
+    /// 同时修改的附加参数
+    #[serde(flatten)]
+    pub params: IntentParams,
+    /// 只修改该序号的 sExtOptions 段落，None 表示所有段落
+    #[serde(default)]
+    pub block: Option<usize>,
+    /// 只修改当前国网栏目ID等于该值的映射，None 表示不限制
+    #[serde(default)]
+    pub expected_gw_id: Option<String>,
+    /// 映射的前缀，None 表示映射前缀（portalPrefix）
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// 已修改的映射（旧值 → 新值）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatedMapping {
+    pub file_path: String,
+    pub local_id: String,
+    /// 映射的前缀
+    pub prefix: String,
+    /// 条目所在 sExtOptions 段落的序号
+    pub block: Option<usize>,
+    pub old_gw_id: Option<String>,
+    pub new_gw_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdateResult {
+    #[serde(flatten)]
+    pub result: BulkInsertResult,
+    pub updated: Vec<UpdatedMapping>,
+    /// 在所有文件中都不存在（或当前国网栏目ID与 expectedGwId 不符）的本地栏目ID
+    pub not_found: Vec<String>,
+}
+
+/// 一次性修改多个本地栏目ID的国网栏目ID
+///
+/// 每个文件的修改要么全部写入要么整体保留原样，所有文件共用一个备份目录并只记录一条操作日志。
+pub fn batch_update_mappings(
+    settings: &AppSettings,
+    target_dir: String,
+    updates: Vec<MappingUpdate>,
+    auto_increment_version: bool,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<BatchUpdateResult, Error> {
+    let retries = file_io::RetryScope::begin();
+    // 映射按（前缀, 本地栏目ID）区分
+    let mut expected_gw_ids: std::collections::HashMap<(String, String), String> = std::collections::HashMap::new();
+    let mut inputs: Vec<MappingInput> = Vec::new();
+    for update in updates {
+        let prefix = settings.key_prefix(update.prefix.as_deref())?.to_string();
+        let local_id = update.local_id.trim().to_string();
+        if let Some(expected) = &update.expected_gw_id {
+            expected_gw_ids.insert((prefix.clone(), local_id.clone()), expected.trim().to_string());
+        }
+        inputs.push(MappingInput {
+            local_id,
+            gw_id: update.new_gw_id.trim().to_string(),
+            params: update.params,
+            template: None,
+            block: update.block,
+            prefix: Some(prefix),
+        });
+    }
+    let mut updates: Vec<MappingInput> = inputs
+        .into_iter()
+        .filter(|update| !update.local_id.is_empty() && !update.gw_id.is_empty())
+        .collect();
+    if updates.is_empty() {
+        return Err("请至少输入一条修改。".into());
+    }
+    let mut seen = std::collections::HashSet::new();
+    let repeated: Vec<&str> = updates
+        .iter()
+        .filter(|update| !seen.insert((update.prefix.as_deref(), update.local_id.as_str())))
+        .map(|update| update.local_id.as_str())
+        .collect();
+    if !repeated.is_empty() {
+        return Err(format!("同一本地栏目ID只能修改一次：{}", repeated.join(", ")).into());
+    }
+
+    // 在修改任何文件之前校验新的国网栏目ID，reject 模式下剔除不符合规则的修改
+    let invalid_entries = validation::check_entries(
+        settings,
+        updates.iter().map(|update| (update.local_id.as_str(), update.gw_id.as_str())),
+    )?;
+    updates.retain(|update| {
+        !invalid_entries
+            .iter()
+            .any(|invalid| invalid.rejected && invalid.local_id == update.local_id)
+    });
+    if updates.is_empty() {
+        return Err(Error::InvalidGwIds { ids: format_invalid_entries(&invalid_entries) });
+    }
+
+    let dir = PathBuf::from(&target_dir);
+    let files = collect_theme_files(&dir)?;
+
+    let mut skipped_files = Vec::new();
+    let mut stale_files: Vec<String> = Vec::new();
+    let mut found: std::collections::HashSet<(String, String)> = std::collections::HashSet::new();
+    // (文件, 原内容, 编码格式, 本文件需要的修改)
+    let mut pending: Vec<(PathBuf, String, file_io::TextFormat, Vec<MappingInput>)> = Vec::new();
+
+    // 先找出每个文件中需要修改的映射
+    for path in &files {
+        let file_path_str = path.to_string_lossy().into_owned();
+        let (raw, format) = match file_io::read_text(path) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                skipped_files.push(SkippedFile::new(
+                    file_path_str,
+                    Message::new(MessageCode::ReadFailed).with("error", e),
+                    Vec::new(),
+                    Locale::of(settings),
+                ));
+                continue;
+            }
+        };
+        let parsed = match parse_mappings(&raw, &settings.prefixes()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                skipped_files.push(SkippedFile::new(
+                    file_path_str,
+                    Message::other(e),
+                    Vec::new(),
+                    Locale::of(settings),
+                ));
+                continue;
+            }
+        };
+
+        let mut file_updates = Vec::new();
+        for update in &updates {
+            let key = update_key(update);
+            let expected = expected_gw_ids.get(&key);
+            let Some(existing) = parsed.iter().find(|item| {
+                item.local_id == update.local_id
+                    && Some(item.prefix.as_str()) == update.prefix.as_deref()
+                    && match expected {
+                        Some(expected) => item.gw_id.as_deref() == Some(expected.as_str()),
+                        None => true,
+                    }
+            }) else {
+                continue;
+            };
+            found.insert(key);
+            if existing.gw_id.as_deref() != Some(update.gw_id.as_str()) || !update.params.matches(&existing.raw_value) {
+                let mut update = update.clone();
+                // 限定了原值时只改命中的段落，不连带修改其他段落中的同名映射
+                if expected.is_some() && update.block.is_none() {
+                    update.block = existing.block_index;
+                }
+                file_updates.push(update);
+            }
+        }
+        if file_updates.is_empty() {
+            continue;
+        }
+        if is_stale(expected_hashes.as_ref(), &file_path_str, &raw) {
+            skipped_files.push(stale_skipped_file(&file_path_str, Locale::of(settings)));
+            stale_files.push(file_path_str);
+            continue;
+        }
+        if let Some(skipped) = safe_mode_skipped_file(settings, &file_path_str, &raw) {
+            skipped_files.push(skipped);
+            continue;
+        }
+        pending.push((path.clone(), raw, format, file_updates));
+    }
+
+    let not_found: Vec<String> = updates
+        .iter()
+        .filter(|update| !found.contains(&update_key(update)))
+        .map(|update| update.local_id.clone())
+        .collect();
+
+    if pending.is_empty() {
+        return Ok(BatchUpdateResult {
+            result: BulkInsertResult {
+                skipped_files,
+                stale_files,
+                invalid_entries,
+                retries: retries.finish(),
+                ..Default::default()
+            },
+            updated: Vec::new(),
+            not_found,
+        });
+    }
+
+    // 所有需要修改的文件共用一个备份目录
+    let files_to_backup: Vec<&PathBuf> = pending.iter().map(|(path, _, _, _)| path).collect();
+    preflight::ensure_writable(&dir, &files_to_backup, settings)?;
+    let backup_dir = backup::create_backup(&dir, &files_to_backup, settings)?;
+    let backup_dir_path = backup_dir.to_string_lossy().into_owned();
+
+    let mut outcome = FileOutcome {
+        skipped_files,
+        ..Default::default()
+    };
+    let mut updated: Vec<UpdatedMapping> = Vec::new();
+
+    for (path, raw, format, file_updates) in pending {
+        let file_path_str = path.to_string_lossy().into_owned();
+        let content = match set_gw_ids_in_file(&raw, &file_updates, settings) {
+            Ok(content) => content,
+            Err(e) => {
+                outcome.skipped_files.push(SkippedFile::new(
+                    file_path_str,
+                    Message::other(e),
+                    Vec::new(),
+                    Locale::of(settings),
+                ));
+                continue;
+            }
+        };
+
+        // 校验或写入失败时整个文件保留原样
+        let edit = EditedFile {
+            path: &path,
+            file_path: &file_path_str,
+            raw: &raw,
+            format,
+            updated: content,
+        };
+        if let Err(refused) = write_edited_file(settings, edit, auto_increment_version, &mut outcome) {
+            outcome.refuse(settings, &file_path_str, refused, MessageCode::ValidationFailed, Vec::new());
+            continue;
+        }
+        // 每个被修改的条目各记一条，旧值取自同一段落、同一前缀的条目
+        let existing = parse_mappings(&raw, &settings.prefixes()).unwrap_or_default();
+        for update in &file_updates {
+            let prefix = update.prefix.as_deref().unwrap_or_default();
+            for entry in existing.iter().filter(|e| {
+                e.local_id == update.local_id
+                    && e.prefix == prefix
+                    && (update.block.is_none() || e.block_index == update.block)
+            }) {
+                updated.push(UpdatedMapping {
+                    file_path: file_path_str.clone(),
+                    local_id: update.local_id.clone(),
+                    prefix: prefix.to_string(),
+                    block: entry.block_index,
+                    old_gw_id: entry.gw_id.clone(),
+                    new_gw_id: update.gw_id.clone(),
+                });
+            }
+        }
+    }
+
+    // 写入操作日志：旧值记为删除、新值记为新增，便于在日志中对照
+    let deleted_mappings: Vec<DeletedMapping> = updated
+        .iter()
+        .map(|item| DeletedMapping {
+            file_path: item.file_path.clone(),
+            local_id: item.local_id.clone(),
+            gw_id: item.old_gw_id.clone(),
+            block: item.block,
+            prefix: Some(item.prefix.clone()),
+            ..Default::default()
+        })
+        .collect();
+    let added_mappings: Vec<AddedMapping> = updated
+        .iter()
+        .map(|item| AddedMapping {
+            file_path: item.file_path.clone(),
+            local_id: item.local_id.clone(),
+            gw_id: item.new_gw_id.clone(),
+            block: item.block,
+            prefix: Some(item.prefix.clone()),
+            ..Default::default()
+        })
+        .collect();
+    let update_info = match Locale::of(settings) {
+        Locale::ZhCn => format!("修改 {} 个文件中的 {} 处国网栏目ID", outcome.updated_files.len(), updated.len()),
+        Locale::EnUs => format!("Changed {} GW IDs in {} files", updated.len(), outcome.updated_files.len()),
+    };
+    finish_operation(
+        settings,
+        &backup::storage_dir(&dir, settings),
+        OperationLog {
+            updated_files: &outcome.updated_files,
+            skipped_files: &outcome.skipped_files,
+            backup_dir: Some(&backup_dir_path),
+            additional_info: Some(&update_info),
+            deleted: &deleted_mappings,
+            added: &added_mappings,
+            modified: &outcome.modified_mappings,
+            version_changes: &outcome.version_changes,
+            ..OperationLog::new(OperationType::BatchUpdate)
+        },
+    );
+
+    Ok(BatchUpdateResult {
+        result: BulkInsertResult {
+            updated_files: outcome.updated_files,
+            skipped_files: outcome.skipped_files,
+            validation_errors: outcome.validation_errors,
+            stale_files,
+            invalid_entries,
+            backup_dir: Some(backup_dir_path),
+            retries: retries.finish(),
+            ..Default::default()
+        },
+        updated,
+        not_found,
+    })
+}
+
+/// 修改请求对应的映射（前缀, 本地栏目ID），前缀已在请求开始时解析
+fn update_key(update: &MappingInput) -> (String, String) {
+    (update.prefix.clone().unwrap_or_default(), update.local_id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(gw_id: &str) -> String {
+        format!("com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=", gw_id)
+    }
+
+    #[test]
+    fn records_the_old_gw_id_of_the_entry_in_the_updated_block() {
+        let raw = format!(
+            "{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n    \"portal_frag_101\": \"{}\"\n  }},\n  \"themes\": [\n    {{\n      \"sExtOptions\": {{\n        \"portal_frag_101\": \"{}\"\n      }}\n    }}\n  ]\n}}\n",
+            value("1001"),
+            value("2001")
+        );
+        let dir = std::env::temp_dir().join(format!("catmap-batch-update-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("theme.json"), &raw).unwrap();
+
+        let update = MappingUpdate {
+            local_id: "101".to_string(),
+            new_gw_id: "3001".to_string(),
+            params: IntentParams::default(),
+            block: Some(1),
+            expected_gw_id: None,
+            prefix: None,
+        };
+        let result =
+            batch_update_mappings(&AppSettings::default(), dir.to_string_lossy().into_owned(), vec![update], false, None)
+                .unwrap();
+        let updated: Vec<(Option<usize>, Option<&str>, &str)> = result
+            .updated
+            .iter()
+            .map(|item| (item.block, item.old_gw_id.as_deref(), item.new_gw_id.as_str()))
+            .collect();
+        assert_eq!(updated, [(Some(1), Some("2001"), "3001")]);
+
+        let written = std::fs::read_to_string(dir.join("theme.json")).unwrap();
+        let gw_ids: Vec<Option<String>> = parse_mappings(&written, &["portal_frag_"])
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.gw_id)
+            .collect();
+        assert_eq!(gw_ids, [Some("1001".to_string()), Some("3001".to_string())]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
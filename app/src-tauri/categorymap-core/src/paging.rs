@@ -0,0 +1,181 @@
+//! 分页返回扫描结果
+//!
+//! 合并后的 theme 文件可能有上千条映射，一次返回整个扫描结果会让前端卡顿。这里把所有文件的映射按文件顺序拉平，
+//! 按条件过滤后分页返回，每条带上所在文件，前端可以只渲染当前页做虚拟滚动；文件本身的信息不分页，单独列出。
+
+use serde::{Deserialize, Serialize};
+
+use crate::{file_io, FileMapping, MappingEntry};
+
+/// 默认每页条数
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// 分页时的过滤条件，各条件同时满足才返回
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFilter {
+    /// 只返回这些状态的条目（如 duplicate_local、misplaced），为空时不按状态过滤
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    /// 只返回该文件中的条目
+    #[serde(default)]
+    pub file_path: Option<String>,
+    /// 本地栏目ID、国网栏目ID或映射值包含该字符串（不区分大小写）
+    #[serde(default)]
+    pub query: Option<String>,
+    /// 只返回本地栏目ID与国网栏目ID相同的条目
+    #[serde(default)]
+    pub same_id: bool,
+}
+
+impl ScanFilter {
+    fn matches(&self, file_path: &str, entry: &MappingEntry) -> bool {
+        if !self.statuses.is_empty() && !self.statuses.contains(&entry.status) {
+            return false;
+        }
+        if self.file_path.as_deref().is_some_and(|wanted| wanted != file_path) {
+            return false;
+        }
+        if self.same_id && !entry.same_id {
+            return false;
+        }
+        match self.query.as_deref().map(str::trim).filter(|query| !query.is_empty()) {
+            Some(query) => {
+                let query = query.to_lowercase();
+                [Some(entry.local_id.as_str()), entry.gw_id.as_deref(), Some(entry.raw_value.as_str())]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| value.to_lowercase().contains(&query))
+            }
+            None => true,
+        }
+    }
+}
+
+/// 分页结果中的一条映射
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagedEntry {
+    pub file_path: String,
+    #[serde(flatten)]
+    pub entry: MappingEntry,
+}
+
+/// 文件信息（不含映射）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSummary {
+    pub file_path: String,
+    pub encoding: file_io::TextEncoding,
+    pub has_bom: bool,
+    pub line_ending: file_io::LineEnding,
+    pub content_hash: String,
+    pub mapping_count: usize,
+    /// 符合过滤条件的条目数
+    pub matched_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanPage {
+    pub entries: Vec<PagedEntry>,
+    /// 当前页（从 0 开始）
+    pub page: usize,
+    pub page_size: usize,
+    /// 符合过滤条件的条目总数
+    pub total: usize,
+    pub total_pages: usize,
+    pub files: Vec<FileSummary>,
+}
+
+/// 取出第 page 页的条目；page_size 为 0 时使用 [`DEFAULT_PAGE_SIZE`]，超出范围的页返回空列表
+pub fn page(files: Vec<FileMapping>, page: usize, page_size: usize, filter: &ScanFilter) -> ScanPage {
+    let page_size = if page_size == 0 { DEFAULT_PAGE_SIZE } else { page_size };
+    let start = page.saturating_mul(page_size);
+    let mut entries = Vec::new();
+    let mut summaries = Vec::with_capacity(files.len());
+    let mut total = 0;
+    for file in files {
+        let mapping_count = file.mappings.len();
+        let mut matched_count = 0;
+        for entry in file.mappings {
+            if !filter.matches(&file.file_path, &entry) {
+                continue;
+            }
+            if total >= start && entries.len() < page_size {
+                entries.push(PagedEntry {
+                    file_path: file.file_path.clone(),
+                    entry,
+                });
+            }
+            total += 1;
+            matched_count += 1;
+        }
+        summaries.push(FileSummary {
+            file_path: file.file_path,
+            encoding: file.encoding,
+            has_bom: file.has_bom,
+            line_ending: file.line_ending,
+            content_hash: file.content_hash,
+            mapping_count,
+            matched_count,
+        });
+    }
+    ScanPage {
+        entries,
+        page,
+        page_size,
+        total,
+        total_pages: total.div_ceil(page_size),
+        files: summaries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_io::TextFormat, settings::AppSettings};
+
+    fn file(path: &str, entries: &[(&str, &str)]) -> FileMapping {
+        let body: Vec<String> = entries
+            .iter()
+            .map(|(local_id, gw_id)| {
+                format!(
+                    "    \"portal_frag_{}\": \"com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={}&es_title=\"",
+                    local_id, gw_id
+                )
+            })
+            .collect();
+        let raw = format!("{{\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", body.join(",\n"));
+        crate::file_mapping_from_text(path.to_string(), &raw, TextFormat::default(), &AppSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn pages_across_files_with_filter() {
+        let files = vec![
+            file("theme1.json", &[("101", "9001"), ("102", "9002"), ("103", "103")]),
+            file("theme2.json", &[("104", "9004"), ("105", "105")]),
+        ];
+        let ids = |page: &ScanPage| page.entries.iter().map(|e| e.entry.local_id.clone()).collect::<Vec<_>>();
+
+        let second = page(files.clone(), 1, 2, &ScanFilter::default());
+        assert_eq!((second.total, second.total_pages), (5, 3));
+        assert_eq!(ids(&second), ["103", "104"]);
+        assert_eq!(second.entries[1].file_path, "theme2.json");
+        assert!(page(files.clone(), 3, 2, &ScanFilter::default()).entries.is_empty());
+
+        let same_id = ScanFilter {
+            same_id: true,
+            ..Default::default()
+        };
+        let filtered = page(files.clone(), 0, 0, &same_id);
+        assert_eq!(ids(&filtered), ["103", "105"]);
+        assert_eq!(filtered.files.iter().map(|f| f.matched_count).collect::<Vec<_>>(), [1, 1]);
+
+        let query = ScanFilter {
+            query: Some("9002".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&page(files, 0, 10, &query)), ["102"]);
+    }
+}
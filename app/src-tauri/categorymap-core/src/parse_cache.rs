@@ -0,0 +1,142 @@
+//! 按文件内容哈希缓存解析结果
+//!
+//! 界面在每次操作后都会重新扫描目录，而大多数文件并没有变化。解析结果只取决于文件内容和映射前缀，
+//! 这里以二者的哈希为 key 缓存解析出的条目：内存缓存在进程内有效，设置了 parseCacheDir 时同时写到磁盘，
+//! 重启后第一次扫描也能直接使用。缓存的是标注栏目名称之前的条目，栏目目录变化不影响缓存。
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{file_io, settings::AppSettings, MappingEntry};
+
+/// 缓存格式版本，条目结构或解析规则变化时递增，使旧缓存失效
+const CACHE_VERSION: u32 = 2;
+/// 内存中最多缓存的文件数，超过时清空重来
+const MAX_MEMORY_ENTRIES: usize = 512;
+
+static MEMORY: Mutex<BTreeMap<String, Vec<MappingEntry>>> = Mutex::new(BTreeMap::new());
+
+fn memory() -> MutexGuard<'static, BTreeMap<String, Vec<MappingEntry>>> {
+    MEMORY.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 缓存 key：缓存版本、映射前缀（前缀不能包含引号，用引号分隔）和文件内容哈希
+fn cache_key(content_hash: &str, settings: &AppSettings) -> String {
+    let signature = format!("{}\"{}\"{}", CACHE_VERSION, settings.prefixes().join("\""), content_hash);
+    file_io::content_hash(signature.as_bytes())
+}
+
+fn disk_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+/// 取出内容哈希为 content_hash 的文件的解析结果，未缓存时调用 parse 解析并缓存
+///
+/// 磁盘缓存读写失败时当作未缓存处理，不影响扫描。
+pub fn get_or_parse(
+    content_hash: &str,
+    settings: &AppSettings,
+    parse: impl FnOnce() -> Result<Vec<MappingEntry>, String>,
+) -> Result<Vec<MappingEntry>, String> {
+    let key = cache_key(content_hash, settings);
+    if let Some(entries) = memory().get(&key) {
+        return Ok(entries.clone());
+    }
+    let disk_dir = settings.parse_cache_dir.as_deref().map(PathBuf::from);
+    let cached = disk_dir.as_deref().and_then(|dir| {
+        let content = fs::read(file_io::long_path(&disk_path(dir, &key))).ok()?;
+        serde_json::from_slice::<Vec<MappingEntry>>(&content).ok()
+    });
+    let entries = match cached {
+        Some(entries) => entries,
+        None => {
+            let entries = parse()?;
+            if let Some(dir) = disk_dir.as_deref() {
+                if let Err(err) = write_disk(dir, &key, &entries) {
+                    eprintln!("写入解析缓存失败: {}", err);
+                }
+            }
+            entries
+        }
+    };
+    let mut memory = memory();
+    if memory.len() >= MAX_MEMORY_ENTRIES {
+        memory.clear();
+    }
+    memory.insert(key, entries.clone());
+    Ok(entries)
+}
+
+fn write_disk(dir: &Path, key: &str, entries: &[MappingEntry]) -> Result<(), String> {
+    fs::create_dir_all(file_io::long_path(dir)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_vec(entries).map_err(|e| e.to_string())?;
+    fs::write(file_io::long_path(&disk_path(dir, key)), content).map_err(|e| e.to_string())
+}
+
+/// 清空内存缓存和设置中的磁盘缓存目录，返回删除的磁盘缓存文件数
+pub fn clear(settings: &AppSettings) -> Result<usize, String> {
+    memory().clear();
+    let Some(dir) = settings.parse_cache_dir.as_deref().map(PathBuf::from) else {
+        return Ok(0);
+    };
+    let Ok(read_dir) = fs::read_dir(file_io::long_path(&dir)) else {
+        return Ok(0);
+    };
+    let mut removed = 0;
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::remove_file(&path).map_err(|e| format!("删除解析缓存失败: {}", e))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn reuses_entries_for_same_content_and_prefixes() {
+        let raw = "{\n  \"sExtOptions\": {\n    \"portal_frag_101\": \"a.B|intent://?es_tabId=9001&es_title=\"\n  }\n}\n";
+        let dir = std::env::temp_dir().join(format!("catmap-parse-cache-{}", std::process::id()));
+        let settings = AppSettings {
+            parse_cache_dir: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let hash = file_io::content_hash(raw.as_bytes());
+        let parses = Cell::new(0);
+        let parse = || {
+            parses.set(parses.get() + 1);
+            crate::parse_file_entries(raw, &settings)
+        };
+
+        let first = get_or_parse(&hash, &settings, parse).unwrap();
+        assert_eq!(get_or_parse(&hash, &settings, parse).unwrap().len(), first.len());
+        assert_eq!(parses.get(), 1);
+
+        // 内存缓存清空后从磁盘读取，条目内容不变
+        memory().clear();
+        let from_disk = get_or_parse(&hash, &settings, parse).unwrap();
+        assert_eq!(parses.get(), 1);
+        assert_eq!(from_disk[0].local_id, "101");
+        assert_eq!(from_disk[0].gw_id.as_deref(), Some("9001"));
+
+        // 映射前缀不同时不能复用
+        let other = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..settings.clone()
+        };
+        get_or_parse(&hash, &other, parse).unwrap();
+        assert_eq!(parses.get(), 2);
+
+        assert_eq!(clear(&settings).unwrap(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
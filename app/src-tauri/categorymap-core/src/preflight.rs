@@ -0,0 +1,159 @@
+//! 修改文件前的写入权限预检
+//!
+//! 杀毒软件锁定文件、只读的网络共享等情况下，如果边写边发现，批量操作会停在一半：
+//! 部分文件已修改、其余没有。修改前先逐个探测目标文件、所在目录和备份目录，有任何一处不可写就整体中止。
+
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{backup, file_io::long_path, settings::AppSettings};
+
+/// 无法写入的文件或目录
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedPath {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    /// 检查过的文件数
+    pub checked: usize,
+    pub blocked: Vec<BlockedPath>,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        self.blocked.is_empty()
+    }
+}
+
+/// 检查文件本身、文件所在目录（原子写入要在同目录创建临时文件）和备份目录是否可写
+pub fn check<P: AsRef<Path>>(source_dir: &Path, files: &[P], settings: &AppSettings) -> PreflightReport {
+    let mut report = PreflightReport {
+        checked: files.len(),
+        ..Default::default()
+    };
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for file in files {
+        let file = file.as_ref();
+        if let Err(reason) = probe_file(file) {
+            report.blocked.push(BlockedPath {
+                path: file.to_string_lossy().into_owned(),
+                reason,
+            });
+        }
+        if let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if !dirs.iter().any(|seen| seen == dir) {
+                dirs.push(dir.to_path_buf());
+            }
+        }
+    }
+    dirs.push(backup::storage_dir(source_dir, settings).join("backups"));
+
+    for dir in dirs {
+        if let Err(reason) = probe_dir(&dir) {
+            report.blocked.push(BlockedPath {
+                path: dir.to_string_lossy().into_owned(),
+                reason,
+            });
+        }
+    }
+    report
+}
+
+/// 预检不通过时返回列出所有不可写路径的错误
+pub fn ensure_writable<P: AsRef<Path>>(source_dir: &Path, files: &[P], settings: &AppSettings) -> Result<(), String> {
+    let report = check(source_dir, files, settings);
+    if report.is_ok() {
+        return Ok(());
+    }
+    let details: Vec<String> = report
+        .blocked
+        .iter()
+        .map(|blocked| format!("{}（{}）", blocked.path, blocked.reason))
+        .collect();
+    Err(format!("以下文件或目录无法写入，未修改任何文件：{}", details.join("、")))
+}
+
+/// 以写入方式打开（不截断）已有文件，能发现只读属性和其他程序的独占锁
+fn probe_file(path: &Path) -> Result<(), String> {
+    let long = long_path(path);
+    let metadata = match fs::metadata(&long) {
+        Ok(metadata) => metadata,
+        // 尚不存在的文件由目录检查覆盖
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+    if metadata.permissions().readonly() {
+        return Err("文件为只读".into());
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&long)
+        .map(|_| ())
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::PermissionDenied => "没有写入权限或文件被其他程序占用".to_string(),
+            _ => e.to_string(),
+        })
+}
+
+/// 在目录中创建并删除一个探测文件；目录不存在时（备份目录首次使用前）改为探测最近的已存在上级目录，
+/// 预检本身不创建目录
+fn probe_dir(dir: &Path) -> Result<(), String> {
+    let long = long_path(&backup::existing_ancestor(dir));
+    if !long.is_dir() {
+        return Err("无法创建目录: 上级路径不是目录".into());
+    }
+    let probe = long.join(format!(".catmap-write-test-{}", std::process::id()));
+    fs::write(&probe, b"").map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => "目录不可写".to_string(),
+        _ => format!("目录不可写: {}", e),
+    })?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_read_only_files_without_creating_the_backup_dir() {
+        let dir = std::env::temp_dir().join(format!("catmap-preflight-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let writable = dir.join("theme1.json");
+        let read_only = dir.join("theme2.json");
+        fs::write(&writable, "{}").unwrap();
+        fs::write(&read_only, "{}").unwrap();
+        let mut permissions = fs::metadata(&read_only).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&read_only, permissions).unwrap();
+        let settings = AppSettings::default();
+
+        assert!(ensure_writable(&dir, &[&writable, &dir.join("theme3.json")], &settings).is_ok());
+        assert!(!dir.join("backups").exists());
+        let report = check(&dir, &[&writable, &read_only], &settings);
+        assert_eq!(report.checked, 2);
+        let blocked: Vec<&str> = report.blocked.iter().map(|blocked| blocked.path.as_str()).collect();
+        assert_eq!(blocked, [read_only.to_string_lossy()]);
+
+        let settings = AppSettings {
+            backup_root: Some(writable.to_string_lossy().into_owned()),
+            ..AppSettings::default()
+        };
+        assert!(ensure_writable(&dir, &[&writable], &settings).is_err());
+
+        let mut permissions = fs::metadata(&read_only).unwrap().permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&read_only, permissions).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
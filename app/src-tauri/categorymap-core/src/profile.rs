@@ -0,0 +1,171 @@
+//! 操作耗时统计
+//!
+//! 网络共享（SMB）上的操作变慢时，需要知道时间花在读取、解析还是写入上。开启统计的操作期间，
+//! 文件读写（见 [`crate::file_io`]）和解析都会记录耗时，操作结束时按文件汇总到结果的 timings 中。
+//! 与重试记录一样，同时进行的操作会看到彼此的记录。
+
+use serde::Serialize;
+use std::{
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// 记录耗时的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Read,
+    Parse,
+    Write,
+}
+
+/// 单个文件各阶段的耗时（毫秒），同一阶段多次执行时累加
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTiming {
+    pub file_path: String,
+    pub read_ms: f64,
+    pub parse_ms: f64,
+    pub write_ms: f64,
+}
+
+impl FileTiming {
+    fn add(&mut self, stage: Stage, ms: f64) {
+        match stage {
+            Stage::Read => self.read_ms += ms,
+            Stage::Parse => self.parse_ms += ms,
+            Stage::Write => self.write_ms += ms,
+        }
+    }
+}
+
+/// 一次操作的耗时统计
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Timings {
+    /// 按首次记录的顺序排列
+    pub files: Vec<FileTiming>,
+    /// 各文件读取耗时之和（并行处理时可能大于总耗时）
+    pub read_ms: f64,
+    pub parse_ms: f64,
+    pub write_ms: f64,
+    /// 操作开始到结束的总耗时
+    pub total_ms: f64,
+}
+
+struct ProfileLog {
+    /// 正在统计耗时的操作数，为 0 时不保存记录
+    scopes: usize,
+    records: Vec<(String, Stage, Duration)>,
+}
+
+static PROFILE_LOG: Mutex<ProfileLog> = Mutex::new(ProfileLog {
+    scopes: 0,
+    records: Vec::new(),
+});
+
+fn profile_log() -> MutexGuard<'static, ProfileLog> {
+    PROFILE_LOG.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 执行 op 并在有操作统计耗时时记录 path 在 stage 阶段的耗时
+pub fn measure<T>(path: &Path, stage: Stage, op: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = op();
+    let elapsed = started.elapsed();
+    let mut log = profile_log();
+    if log.scopes > 0 {
+        log.records.push((path.to_string_lossy().into_owned(), stage, elapsed));
+    }
+    result
+}
+
+/// 统计一次操作期间（包括其中的并行任务）的耗时
+pub struct ProfileScope {
+    start: usize,
+    started: Instant,
+}
+
+impl ProfileScope {
+    /// 开始统计；enabled 为 false 时返回 None，不产生任何开销
+    pub fn begin(enabled: bool) -> Option<Self> {
+        if !enabled {
+            return None;
+        }
+        let mut log = profile_log();
+        log.scopes += 1;
+        Some(Self {
+            start: log.records.len(),
+            started: Instant::now(),
+        })
+    }
+
+    /// 结束统计，按文件汇总开始以来的耗时
+    pub fn finish(self) -> Timings {
+        let mut timings = Timings {
+            total_ms: millis(self.started.elapsed()),
+            ..Default::default()
+        };
+        for (path, stage, elapsed) in &profile_log().records[self.start..] {
+            let ms = millis(*elapsed);
+            let index = match timings.files.iter().position(|file| file.file_path == *path) {
+                Some(index) => index,
+                None => {
+                    timings.files.push(FileTiming {
+                        file_path: path.clone(),
+                        ..Default::default()
+                    });
+                    timings.files.len() - 1
+                }
+            };
+            timings.files[index].add(*stage, ms);
+            match stage {
+                Stage::Read => timings.read_ms += ms,
+                Stage::Parse => timings.parse_ms += ms,
+                Stage::Write => timings.write_ms += ms,
+            }
+        }
+        timings
+    }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let mut log = profile_log();
+        log.scopes -= 1;
+        if log.scopes == 0 {
+            log.records.clear();
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_stages_per_file() {
+        assert!(ProfileScope::begin(false).is_none());
+        let scope = ProfileScope::begin(true).unwrap();
+        let sleep = || std::thread::sleep(Duration::from_millis(2));
+        measure(Path::new("profile-a.json"), Stage::Read, sleep);
+        measure(Path::new("profile-b.json"), Stage::Parse, sleep);
+        measure(Path::new("profile-a.json"), Stage::Write, sleep);
+        let timings = scope.finish();
+
+        let files: Vec<&FileTiming> = timings
+            .files
+            .iter()
+            .filter(|file| file.file_path.starts_with("profile-"))
+            .collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_path, "profile-a.json");
+        assert!(files[0].read_ms >= 2.0 && files[0].write_ms >= 2.0 && files[0].parse_ms == 0.0);
+        assert!(files[1].parse_ms >= 2.0);
+        assert!(timings.total_ms >= files[0].read_ms + files[0].write_ms + files[1].parse_ms);
+    }
+}
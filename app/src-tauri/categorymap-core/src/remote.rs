@@ -0,0 +1,349 @@
+//! 通过 SSH 访问远程服务器上的 theme 目录
+//!
+//! 调用系统自带的 OpenSSH 客户端（ssh、sftp），以批处理模式运行，只支持密钥或 ssh-agent 认证。
+//! 远程端需要是提供 POSIX shell 的 Linux 服务器。
+//!
+//! [`RemoteSource`] 抽象了远程目录的列出、下载、上传和备份，扫描和上传流程对 SSH 和 HTTP/WebDAV
+//! （见 [`crate::webdav`]）通用。
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use crate::settings::AppSettings;
+
+/// 上传时使用的临时文件后缀，上传完成后在远程端重命名覆盖原文件
+pub(crate) const UPLOAD_SUFFIX: &str = ".catmap-upload";
+
+/// 远程 theme 目录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTarget {
+    pub host: String,
+    /// SSH 端口，None 表示使用 ssh 配置中的端口（默认 22）
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    /// 私钥文件，None 表示使用 ssh 默认的密钥和 ssh-agent
+    pub identity_file: Option<String>,
+    /// 远程 theme 目录（绝对路径）
+    pub remote_dir: String,
+}
+
+impl RemoteTarget {
+    /// ssh 的连接目标：`user@host` 或 `host`
+    fn destination(&self) -> String {
+        match self.user.as_deref().map(str::trim) {
+            Some(user) if !user.is_empty() => format!("{}@{}", user, self.host.trim()),
+            _ => self.host.trim().to_string(),
+        }
+    }
+
+    /// 用于日志和工作目录区分的描述：`user@host:port:/dir`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.destination(),
+            self.port.unwrap_or(22),
+            self.remote_dir
+        )
+    }
+
+    /// 远程目录中文件的路径（远程端总是使用 / 分隔）
+    pub fn file_path(&self, name: &str) -> String {
+        format!("{}/{}", self.remote_dir.trim_end_matches('/'), name)
+    }
+}
+
+/// 可以扫描和上传的远程 theme 目录
+pub trait RemoteSource {
+    /// 用于日志和工作目录区分的描述，不含密码等凭据
+    fn describe(&self) -> String;
+    /// 远程目录中文件的路径，用于结果和日志展示
+    fn file_path(&self, name: &str) -> String;
+    /// 列出远程目录中的 theme 文件名（已排序）
+    fn list_theme_files(&self) -> Result<Vec<String>, String>;
+    /// 下载远程目录中的指定文件到本地目录
+    fn download(&self, names: &[String], local_dir: &Path) -> Result<(), String>;
+    /// 上传本地文件覆盖远程目录中的同名文件，尽量保证中途失败时原文件不变
+    fn upload(&self, files: &[PathBuf]) -> Result<(), String>;
+    /// 在远程目录下的 `backups/<时间戳>/` 中备份指定文件，返回备份目录的远程路径
+    fn backup(&self, names: &[String]) -> Result<String, String>;
+    /// 把本地文件原样上传到远程目录（用于操作日志）
+    fn put_files(&self, files: &[PathBuf]) -> Result<(), String>;
+}
+
+/// 检查主机名或用户名不会被 ssh 当作选项（以 - 开头）或拆成多个参数（含空白）
+fn check_destination_part(label: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.starts_with('-') || value.chars().any(char::is_whitespace) {
+        return Err(format!("{}不能以 - 开头或包含空白: {}", label, value));
+    }
+    Ok(())
+}
+
+/// ssh / sftp 命令封装
+pub struct Ssh {
+    ssh: PathBuf,
+    sftp: PathBuf,
+    target: RemoteTarget,
+}
+
+impl Ssh {
+    /// 使用设置中的 OpenSSH 目录，未设置时从 PATH 中查找 ssh 和 sftp
+    pub fn new(settings: &AppSettings, target: RemoteTarget) -> Result<Self, String> {
+        if target.host.trim().is_empty() {
+            return Err("远程主机不能为空".into());
+        }
+        check_destination_part("远程主机", &target.host)?;
+        if let Some(user) = target.user.as_deref() {
+            check_destination_part("用户名", user)?;
+        }
+        if !target.remote_dir.starts_with('/') {
+            return Err(format!("远程目录必须是绝对路径: {}", target.remote_dir));
+        }
+        let dir = settings
+            .ssh_dir
+            .as_deref()
+            .map(str::trim)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        Ok(Self {
+            ssh: dir.join("ssh"),
+            sftp: dir.join("sftp"),
+            target,
+        })
+    }
+
+    pub fn target(&self) -> &RemoteTarget {
+        &self.target
+    }
+
+    /// 带公共参数的命令：批处理模式（不询问密码），指定私钥
+    fn command(&self, program: &Path) -> Command {
+        let mut command = Command::new(program);
+        command.args(["-o", "BatchMode=yes"]);
+        if let Some(identity) = self.target.identity_file.as_deref().filter(|f| !f.trim().is_empty()) {
+            command.arg("-i").arg(identity);
+        }
+        // Windows 下不弹出控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        command
+    }
+
+    /// 在远程端执行 shell 命令，返回标准输出
+    fn exec(&self, remote_command: &str) -> Result<String, String> {
+        let mut command = self.command(&self.ssh);
+        if let Some(port) = self.target.port {
+            command.arg("-p").arg(port.to_string());
+        }
+        // -- 之后的参数不再按选项解析
+        command.arg("--").arg(self.target.destination()).arg(remote_command);
+        let output = command
+            .output()
+            .map_err(|e| format!("无法执行 ssh（{}）: {}", self.ssh.display(), e))?;
+        check_output("ssh", output)
+    }
+
+    /// 以批处理模式执行一组 sftp 命令，任一命令失败即中止
+    fn sftp_batch(&self, commands: &[String]) -> Result<(), String> {
+        let mut command = self.command(&self.sftp);
+        if let Some(port) = self.target.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        command
+            .args(["-b", "-", "--"])
+            .arg(self.target.destination())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("无法执行 sftp（{}）: {}", self.sftp.display(), e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(commands.join("\n").as_bytes())
+                .map_err(|e| format!("向 sftp 写入命令失败: {}", e))?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        check_output("sftp", output).map(|_| ())
+    }
+}
+
+impl RemoteSource for Ssh {
+    fn describe(&self) -> String {
+        self.target.describe()
+    }
+
+    fn file_path(&self, name: &str) -> String {
+        self.target.file_path(name)
+    }
+
+    fn list_theme_files(&self) -> Result<Vec<String>, String> {
+        let stdout = self
+            .exec(&format!("ls -1 -- {}", shell_quote(&self.target.remote_dir)))
+            .map_err(|e| format!("无法读取远程目录 {}: {}", self.target.remote_dir, e))?;
+        let mut names: Vec<String> = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|name| crate::is_theme_file_name(name))
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn download(&self, names: &[String], local_dir: &Path) -> Result<(), String> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let commands: Vec<String> = names
+            .iter()
+            .map(|name| {
+                format!(
+                    "get {} {}",
+                    sftp_quote(&self.target.file_path(name)),
+                    sftp_quote(&local_dir.join(name).to_string_lossy())
+                )
+            })
+            .collect();
+        self.sftp_batch(&commands).map_err(|e| format!("下载失败: {}", e))
+    }
+
+    /// 先全部上传为临时文件，再在远程端一次性重命名覆盖，中途失败时原文件保持不变。
+    fn upload(&self, files: &[PathBuf]) -> Result<(), String> {
+        let names: Vec<String> = files
+            .iter()
+            .filter_map(|file| file.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .collect();
+        if names.is_empty() {
+            return Ok(());
+        }
+        let commands: Vec<String> = files
+            .iter()
+            .zip(&names)
+            .map(|(file, name)| {
+                format!(
+                    "put {} {}",
+                    sftp_quote(&file.to_string_lossy()),
+                    sftp_quote(&format!("{}{}", self.target.file_path(name), UPLOAD_SUFFIX))
+                )
+            })
+            .collect();
+        self.sftp_batch(&commands).map_err(|e| format!("上传失败: {}", e))?;
+
+        let renames: Vec<String> = names
+            .iter()
+            .map(|name| {
+                let path = self.target.file_path(name);
+                format!(
+                    "mv -f -- {} {}",
+                    shell_quote(&format!("{}{}", path, UPLOAD_SUFFIX)),
+                    shell_quote(&path)
+                )
+            })
+            .collect();
+        self.exec(&renames.join(" && "))
+            .map(|_| ())
+            .map_err(|e| format!("替换远程文件失败: {}", e))
+    }
+
+    /// 目录结构与本地的目录格式备份相同；远程备份不会自动清理。
+    fn backup(&self, names: &[String]) -> Result<String, String> {
+        let backup_dir = self
+            .target
+            .file_path(&format!("backups/{}", Local::now().format("%Y%m%d-%H%M%S")));
+        let sources: Vec<String> = names
+            .iter()
+            .map(|name| shell_quote(&self.target.file_path(name)))
+            .collect();
+        self.exec(&format!(
+            "mkdir -p -- {dir} && cp -p -- {files} {dir}/",
+            dir = shell_quote(&backup_dir),
+            files = sources.join(" ")
+        ))
+        .map_err(|e| format!("创建远程备份失败: {}", e))?;
+        Ok(backup_dir)
+    }
+
+    fn put_files(&self, files: &[PathBuf]) -> Result<(), String> {
+        let commands: Vec<String> = files
+            .iter()
+            .filter_map(|file| {
+                let name = file.file_name()?.to_string_lossy();
+                Some(format!(
+                    "put {} {}",
+                    sftp_quote(&file.to_string_lossy()),
+                    sftp_quote(&self.target.file_path(&name))
+                ))
+            })
+            .collect();
+        self.sftp_batch(&commands)
+    }
+}
+
+fn check_output(program: &str, output: Output) -> Result<String, String> {
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() };
+    Err(format!("{} 执行失败: {}", program, message))
+}
+
+/// 远程 shell 的参数加单引号
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// sftp 批处理命令的参数加双引号，转义反斜杠（Windows 路径）和双引号
+fn sftp_quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(host: &str, user: Option<&str>, remote_dir: &str) -> RemoteTarget {
+        RemoteTarget {
+            host: host.to_string(),
+            port: None,
+            user: user.map(str::to_string),
+            identity_file: None,
+            remote_dir: remote_dir.to_string(),
+        }
+    }
+
+    #[test]
+    fn describes_targets_and_quotes_arguments() {
+        let remote = target(" stb-01 ", Some("deploy"), "/srv/theme/");
+        assert_eq!(remote.describe(), "deploy@stb-01:22:/srv/theme/");
+        assert_eq!(remote.file_path("theme1.json"), "/srv/theme/theme1.json");
+        assert_eq!(target("stb-01", Some(" "), "/srv").describe(), "stb-01:22:/srv");
+
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(sftp_quote(r#"C:\tmp\"a""#), r#""C:\\tmp\\\"a\"""#);
+    }
+
+    #[test]
+    fn rejects_targets_ssh_would_misread() {
+        let settings = AppSettings::default();
+        assert!(Ssh::new(&settings, target("-oProxyCommand=x", None, "/srv")).is_err());
+        assert!(Ssh::new(&settings, target("stb-01", Some("de ploy"), "/srv")).is_err());
+        assert!(Ssh::new(&settings, target("stb-01", None, "srv/theme")).is_err());
+        assert!(Ssh::new(&settings, target(" ", None, "/srv")).is_err());
+        let ssh = Ssh::new(&settings, target("stb-01", Some("deploy"), "/srv")).unwrap();
+        assert_eq!(ssh.ssh, PathBuf::from("ssh"));
+    }
+}
@@ -0,0 +1,187 @@
+//! 自动修复 sExtOptions 段落中手工编辑留下的格式问题
+//!
+//! 只处理段落内部成员之间的逗号和空行：删除末尾多余的逗号和连续的逗号，在缺少逗号的成员之间补上逗号，
+//! 删除成员之间的空行。成员本身、注释和缩进都保持原样，段落以外的内容不做改动。
+
+use serde::Serialize;
+
+use crate::{
+    cst::{parse_key, skip_trivia, skip_value},
+    editor::{find_ext_options_blocks, EditError},
+};
+
+/// 修复的一处问题
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairFix {
+    /// 问题在原文件中的行号（从 1 开始）
+    pub line: usize,
+    /// 问题类型：trailing_comma、extra_comma、missing_comma、blank_line
+    pub code: String,
+}
+
+/// 一处改动：把 start..end 替换为 replacement
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: &'static str,
+    fix: RepairFix,
+}
+
+/// 修复所有 sExtOptions 段落，返回修复后的内容和修复的问题（按位置排序）；没有问题时内容不变
+pub fn repair(raw: &str) -> Result<(String, Vec<RepairFix>), EditError> {
+    let mut edits = Vec::new();
+    for block in find_ext_options_blocks(raw)? {
+        repair_block(raw, block.start, &mut edits)?;
+    }
+    edits.sort_by_key(|edit| edit.start);
+    let mut repaired = raw.to_string();
+    for edit in edits.iter().rev() {
+        repaired.replace_range(edit.start..edit.end, edit.replacement);
+    }
+    Ok((repaired, edits.into_iter().map(|edit| edit.fix).collect()))
+}
+
+fn repair_block(raw: &str, open: usize, edits: &mut Vec<Edit>) -> Result<(), EditError> {
+    let bytes = raw.as_bytes();
+    let mut push = |start: usize, end: usize, replacement: &'static str, code: &str| {
+        edits.push(Edit {
+            start,
+            end,
+            replacement,
+            fix: RepairFix {
+                line: line_at(raw, start),
+                code: code.to_string(),
+            },
+        })
+    };
+    // 上一个成员之后的逗号（其后尚未出现下一个成员）
+    let mut last_comma: Option<usize> = None;
+    let mut i = open + 1;
+    loop {
+        let next = skip_trivia(raw, i);
+        for (start, end) in blank_lines(raw, i, next) {
+            push(start, end, "", "blank_line");
+        }
+        match bytes.get(next) {
+            None => return Err(EditError::UnterminatedExtOptions),
+            Some(b'}') => {
+                if let Some(comma) = last_comma {
+                    push(comma, comma + 1, "", "trailing_comma");
+                }
+                return Ok(());
+            }
+            Some(b',') => {
+                // 段落开头或另一个逗号之后的逗号
+                push(next, next + 1, "", "extra_comma");
+                i = next + 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let (_, key_end) = parse_key(raw, next)?;
+        let colon = skip_trivia(raw, key_end);
+        if bytes.get(colon) != Some(&b':') {
+            return Err(EditError::MalformedExtOptions);
+        }
+        let value_end = skip_value(raw, skip_trivia(raw, colon + 1))?;
+        let after = skip_trivia(raw, value_end);
+        last_comma = None;
+        match bytes.get(after) {
+            Some(b',') => {
+                for (start, end) in blank_lines(raw, value_end, after) {
+                    push(start, end, "", "blank_line");
+                }
+                last_comma = Some(after);
+                i = after + 1;
+            }
+            Some(b'}') | None => i = value_end,
+            Some(_) => {
+                push(value_end, value_end, ",", "missing_comma");
+                i = value_end;
+            }
+        }
+    }
+}
+
+/// start..end（只含空白和注释）中的空行，返回每个空行（含换行符）的范围；注释内部不做处理
+fn blank_lines(raw: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let bytes = raw.as_bytes();
+    let mut lines = Vec::new();
+    let mut i = start;
+    while i < end {
+        if raw[i..end].starts_with("//") {
+            i = raw[i..end].find('\n').map(|pos| i + pos).unwrap_or(end);
+        } else if raw[i..end].starts_with("/*") {
+            i = raw[i + 2..end].find("*/").map(|pos| i + pos + 4).unwrap_or(end);
+        } else if bytes[i] == b'\n' {
+            let line_start = i + 1;
+            let line_end = raw[line_start..end].find('\n').map(|pos| line_start + pos);
+            match line_end {
+                Some(line_end) if raw[line_start..line_end].trim().is_empty() => {
+                    lines.push((line_start, line_end + 1));
+                    i = line_end;
+                }
+                _ => i += 1,
+            }
+        } else {
+            i += 1;
+        }
+    }
+    lines
+}
+
+fn line_at(raw: &str, offset: usize) -> usize {
+    raw[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::validate_json_content;
+
+    fn codes(fixes: &[RepairFix]) -> Vec<(usize, &str)> {
+        fixes.iter().map(|fix| (fix.line, fix.code.as_str())).collect()
+    }
+
+    #[test]
+    fn fixes_commas_and_blank_lines_in_blocks() {
+        let raw = concat!(
+            "{\n",
+            "  \"list\": [1, 2,],\n",
+            "\n",
+            "  \"sExtOptions\": {\n",
+            "    \"portal_frag_1\": \"a\"\n",
+            "\n",
+            "    // 注释\n",
+            "    \"portal_frag_2\": \"b\",,\n",
+            "    \"portal_frag_3\": \"c\",\n",
+            "  }\n",
+            "}\n"
+        );
+        let (repaired, fixes) = repair(raw).unwrap();
+        assert_eq!(
+            codes(&fixes),
+            [(5, "missing_comma"), (6, "blank_line"), (8, "extra_comma"), (9, "trailing_comma")]
+        );
+        let expected = concat!(
+            "{\n",
+            "  \"list\": [1, 2,],\n",
+            "\n",
+            "  \"sExtOptions\": {\n",
+            "    \"portal_frag_1\": \"a\",\n",
+            "    // 注释\n",
+            "    \"portal_frag_2\": \"b\",\n",
+            "    \"portal_frag_3\": \"c\"\n",
+            "  }\n",
+            "}\n"
+        );
+        assert_eq!(repaired, expected);
+        assert!(validate_json_content(&repaired).is_ok());
+
+        let (again, fixes) = repair(&repaired).unwrap();
+        assert!(fixes.is_empty());
+        assert_eq!(again, repaired);
+    }
+}
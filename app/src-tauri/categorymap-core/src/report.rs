@@ -0,0 +1,304 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{stats::Statistics, ConsistencyReport, FileMapping};
+
+/// 报告格式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// 报告文件的默认扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// 报告内容块，先组装再按格式渲染
+enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    Table(Vec<&'static str>, Vec<Vec<String>>),
+}
+
+/// 渲染审计报告：扫描结果、重复项分析和一致性检查
+pub fn render(
+    format: ReportFormat,
+    target_dir: &str,
+    generated_at: &str,
+    files: &[FileMapping],
+    statistics: &Statistics,
+    consistency: &ConsistencyReport,
+) -> String {
+    let blocks = build_blocks(target_dir, generated_at, files, statistics, consistency);
+    match format {
+        ReportFormat::Html => render_html(&blocks),
+        ReportFormat::Markdown => render_markdown(&blocks),
+    }
+}
+
+fn build_blocks(
+    target_dir: &str,
+    generated_at: &str,
+    files: &[FileMapping],
+    statistics: &Statistics,
+    consistency: &ConsistencyReport,
+) -> Vec<Block> {
+    let mut blocks = vec![
+        Block::Heading(1, "栏目映射审计报告".to_string()),
+        Block::Paragraph(format!("生成时间：{}", generated_at)),
+        Block::Paragraph(format!("目标目录：{}", target_dir)),
+        Block::Paragraph(format!("theme 文件数：{}", statistics.file_count)),
+    ];
+
+    // 统计汇总
+    let total = &statistics.aggregate;
+    blocks.push(Block::Heading(2, "统计汇总".to_string()));
+    blocks.push(Block::Table(
+        vec!["映射总数", "本地ID重复", "国网ID重复", "疑似重复", "本地ID与国网ID相同", "缺少国网ID"],
+        vec![vec![
+            total.total.to_string(),
+            total.duplicate_local.to_string(),
+            total.duplicate_gw.to_string(),
+            total.suspicious_duplicate.to_string(),
+            total.same_id.to_string(),
+            total.empty_gw_id.to_string(),
+        ]],
+    ));
+
+    // 各文件统计
+    blocks.push(Block::Heading(2, "各文件统计".to_string()));
+    blocks.push(Block::Table(
+        vec!["文件", "映射数", "本地ID重复", "国网ID重复", "疑似重复", "本地ID与国网ID相同", "缺少国网ID"],
+        statistics
+            .files
+            .iter()
+            .map(|file| {
+                vec![
+                    file_name(&file.file_path),
+                    file.counts.total.to_string(),
+                    file.counts.duplicate_local.to_string(),
+                    file.counts.duplicate_gw.to_string(),
+                    file.counts.suspicious_duplicate.to_string(),
+                    file.counts.same_id.to_string(),
+                    file.counts.empty_gw_id.to_string(),
+                ]
+            })
+            .collect(),
+    ));
+    if !statistics.divergent_files.is_empty() {
+        blocks.push(Block::Paragraph(format!(
+            "以下文件的映射数量与多数文件（{} 条）不一致：",
+            statistics.majority_total.unwrap_or_default()
+        )));
+        blocks.push(Block::Table(
+            vec!["文件", "映射数"],
+            statistics
+                .divergent_files
+                .iter()
+                .map(|file| vec![file_name(&file.file_path), file.total.to_string()])
+                .collect(),
+        ));
+    }
+
+    // 重复项分析
+    blocks.push(Block::Heading(2, "重复项分析".to_string()));
+    let duplicates: Vec<Vec<String>> = files
+        .iter()
+        .flat_map(|file| {
+            file.mappings
+                .iter()
+                .filter(|entry| entry.status != "normal")
+                .map(move |entry| {
+                    let status = match entry.status.as_str() {
+                        "duplicate_local" => "本地ID重复",
+                        "duplicate_gw" => "国网ID重复",
+                        "suspicious_duplicate" => "疑似重复",
+                        "misplaced" => "不在 sExtOptions 中",
+                        "commented" => "已注释",
+                        other => other,
+                    };
+                    vec![
+                        file_name(&file.file_path),
+                        entry.local_id.clone(),
+                        entry.gw_id.clone().unwrap_or_default(),
+                        status.to_string(),
+                    ]
+                })
+        })
+        .collect();
+    if duplicates.is_empty() {
+        blocks.push(Block::Paragraph("未发现重复映射。".to_string()));
+    } else {
+        blocks.push(Block::Table(vec!["文件", "本地栏目ID", "国网栏目ID", "状态"], duplicates));
+    }
+
+    // 一致性检查
+    blocks.push(Block::Heading(2, "一致性检查".to_string()));
+    if consistency.consistent {
+        blocks.push(Block::Paragraph("所有文件的映射一致。".to_string()));
+    }
+    if !consistency.missing.is_empty() {
+        blocks.push(Block::Heading(3, format!("部分文件缺失的映射（{} 条）", consistency.missing.len())));
+        blocks.push(Block::Table(
+            vec!["本地栏目ID", "存在于", "缺失于"],
+            consistency
+                .missing
+                .iter()
+                .map(|missing| {
+                    vec![
+                        missing.local_id.clone(),
+                        file_names(&missing.present_in),
+                        file_names(&missing.missing_in),
+                    ]
+                })
+                .collect(),
+        ));
+    }
+    if !consistency.mismatched.is_empty() {
+        blocks.push(Block::Heading(3, format!("国网栏目ID不一致的映射（{} 条）", consistency.mismatched.len())));
+        blocks.push(Block::Table(
+            vec!["本地栏目ID", "国网栏目ID", "文件"],
+            consistency
+                .mismatched
+                .iter()
+                .flat_map(|mismatch| {
+                    mismatch.variants.iter().map(move |variant| {
+                        vec![
+                            mismatch.local_id.clone(),
+                            variant.gw_id.clone().unwrap_or_else(|| "(无)".to_string()),
+                            file_names(&variant.files),
+                        ]
+                    })
+                })
+                .collect(),
+        ));
+    }
+    blocks
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn file_names(paths: &[String]) -> String {
+    paths.iter().map(|path| file_name(path)).collect::<Vec<_>>().join("、")
+}
+
+fn render_html(blocks: &[Block]) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                body.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_html(text)));
+            }
+            Block::Paragraph(text) => body.push_str(&format!("<p>{}</p>\n", escape_html(text))),
+            Block::Table(headers, rows) => {
+                body.push_str("<table>\n<thead><tr>");
+                for header in headers {
+                    body.push_str(&format!("<th>{}</th>", escape_html(header)));
+                }
+                body.push_str("</tr></thead>\n<tbody>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        body.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</tbody>\n</table>\n");
+            }
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>栏目映射审计报告</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        HTML_STYLE, body
+    )
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: left; }
+th { background: #f3f3f3; }";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut output = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                output.push_str(&format!("{} {}\n\n", "#".repeat(*level as usize), text));
+            }
+            Block::Paragraph(text) => output.push_str(&format!("{}\n\n", text)),
+            Block::Table(headers, rows) => {
+                output.push_str(&format!("| {} |\n", headers.join(" | ")));
+                output.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|cell| escape_markdown_cell(cell)).collect();
+                    output.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_consistency_report, scan_directory, settings::AppSettings, stats};
+
+    #[test]
+    fn renders_findings_in_both_formats() {
+        let dir = std::env::temp_dir().join(format!("catmap-report-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = |gw_id: &str| {
+            format!(
+                "{{\n  \"sExtOptions\": {{\n    \"portal_frag_101\": \"a.B|intent://?es_tabId={}&es_title=\"\n  }}\n}}\n",
+                gw_id
+            )
+        };
+        std::fs::write(dir.join("theme1.json"), theme("900")).unwrap();
+        std::fs::write(dir.join("theme2.json"), theme("901")).unwrap();
+        let files = scan_directory(&dir, &AppSettings::default()).unwrap();
+        let statistics = stats::compute(&files);
+        let consistency = build_consistency_report(&files);
+        let target = "<share>|theme";
+
+        let html = render(ReportFormat::Html, target, "2024-01-01 12:00:00", &files, &statistics, &consistency);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("目标目录：&lt;share&gt;|theme"));
+        assert!(html.contains("国网栏目ID不一致的映射（1 条）"));
+        assert!(html.contains("<td>901</td><td>theme2.json</td>"));
+
+        let markdown = render(ReportFormat::Markdown, target, "2024-01-01 12:00:00", &files, &statistics, &consistency);
+        assert!(markdown.starts_with("# 栏目映射审计报告\n\n"));
+        assert!(markdown.contains("| 101 | 900 | theme1.json |"));
+        assert!(markdown.contains("未发现重复映射。"));
+        assert_eq!(escape_markdown_cell("a|b\nc"), "a\\|b c");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
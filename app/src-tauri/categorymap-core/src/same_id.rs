@@ -0,0 +1,206 @@
+//! 本地栏目ID与国网栏目ID相同（same_id）的映射
+//!
+//! 这类映射通常是等待填写真实国网栏目ID的占位条目。这里列出所有占位条目，
+//! 并按「本地栏目ID → 正确的国网栏目ID」对照表只修改仍是占位值的条目，修改后报告仍未处理的条目。
+//! 占位条目不能发布到正式环境，也可以一次全部删除或注释掉。
+
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use crate::{
+    batch_update_mappings, extract_gw_id, i18n::Locale, remove_matching_mappings, scan_directory, settings::AppSettings,
+    BatchUpdateResult, Error, FileMapping, IntentParams, MappingUpdate, OperationType, RemovalRequest,
+    RemoveMappingsResult,
+};
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SameIdEntry {
+    pub file_path: String,
+    pub local_id: String,
+    /// 映射的前缀
+    pub prefix: String,
+    pub block: Option<usize>,
+    pub block_path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SameIdAudit {
+    pub entries: Vec<SameIdEntry>,
+    /// 涉及的本地栏目ID（去重、排序）
+    pub local_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SameIdFixResult {
+    #[serde(flatten)]
+    pub result: BatchUpdateResult,
+    /// 修改后仍是占位值的条目（对照表中没有或未能修改）
+    pub remaining: Vec<SameIdEntry>,
+}
+
+/// 列出目录中所有本地栏目ID与国网栏目ID相同的映射（被注释和不在 sExtOptions 中的条目除外）
+pub fn audit(settings: &AppSettings, dir: &Path) -> Result<SameIdAudit, Error> {
+    if !dir.is_dir() {
+        return Err(Error::DirNotFound { path: dir.to_string_lossy().into_owned() });
+    }
+    Ok(audit_files(&scan_directory(dir, settings)?))
+}
+
+pub fn audit_files(files: &[FileMapping]) -> SameIdAudit {
+    let entries: Vec<SameIdEntry> = files
+        .iter()
+        .flat_map(|file| {
+            file.mappings
+                .iter()
+                .filter(|entry| entry.same_id && entry.is_active())
+                .map(|entry| SameIdEntry {
+                    file_path: file.file_path.clone(),
+                    local_id: entry.local_id.clone(),
+                    prefix: entry.prefix.clone(),
+                    block: entry.block_index,
+                    block_path: entry.block_path.clone(),
+                })
+        })
+        .collect();
+    let local_ids: BTreeSet<&str> = entries.iter().map(|entry| entry.local_id.as_str()).collect();
+    SameIdAudit {
+        local_ids: local_ids.into_iter().map(str::to_string).collect(),
+        entries,
+    }
+}
+
+/// 按对照表修正占位条目：只修改当前国网栏目ID仍等于本地栏目ID的映射，对照表中的本地栏目ID对所有前缀的占位条目生效
+pub fn fix(
+    settings: &AppSettings,
+    target_dir: String,
+    fixes: HashMap<String, String>,
+    auto_increment_version: bool,
+    expected_hashes: Option<HashMap<String, String>>,
+) -> Result<SameIdFixResult, Error> {
+    let dir = Path::new(&target_dir).to_path_buf();
+    // 对照表中的本地栏目ID在哪些前缀下有占位条目，就修改这些前缀的映射
+    let placeholders: BTreeSet<(String, String)> = audit(settings, &dir)?
+        .entries
+        .into_iter()
+        .map(|entry| (entry.prefix, entry.local_id))
+        .collect();
+    let mut updates = Vec::new();
+    for (local_id, gw_id) in fixes {
+        let local_id = local_id.trim().to_string();
+        let prefixes: Vec<String> = placeholders
+            .iter()
+            .filter(|(_, id)| *id == local_id)
+            .map(|(prefix, _)| prefix.clone())
+            .collect();
+        // 没有占位条目时仍交给批量修改，记入未找到的本地栏目ID
+        let prefixes = if prefixes.is_empty() {
+            vec![settings.portal_prefix.clone()]
+        } else {
+            prefixes
+        };
+        updates.extend(prefixes.into_iter().map(|prefix| MappingUpdate {
+            expected_gw_id: Some(local_id.clone()),
+            local_id: local_id.clone(),
+            new_gw_id: gw_id.clone(),
+            params: IntentParams::default(),
+            block: None,
+            prefix: Some(prefix),
+        }));
+    }
+    let result = batch_update_mappings(settings, target_dir, updates, auto_increment_version, expected_hashes)?;
+    Ok(SameIdFixResult {
+        result,
+        remaining: audit(settings, &dir)?.entries,
+    })
+}
+
+/// 删除（soft 为 true 时注释掉）目录中所有占位条目，dry_run 为 true 时只返回将处理的条目
+pub fn remove(
+    settings: &AppSettings,
+    target_dir: String,
+    soft: bool,
+    auto_increment_version: bool,
+    dry_run: Option<bool>,
+    expected_hashes: Option<HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, Error> {
+    if !Path::new(&target_dir).is_dir() {
+        return Err(Error::DirNotFound { path: target_dir });
+    }
+    let request = RemovalRequest {
+        operation_type: OperationType::RemoveSameId,
+        summary: Locale::of(settings)
+            .pick("清除本地栏目ID与国网栏目ID相同的占位映射", "Remove placeholder mappings whose local ID equals the GW ID")
+            .to_string(),
+        soft,
+        transactional: false,
+        files: None,
+        prefixes: settings.prefixes().into_iter().map(str::to_string).collect(),
+        auto_increment_version,
+        dry_run: dry_run.unwrap_or(false),
+        expected_hashes,
+    };
+    let result = remove_matching_mappings(settings, &target_dir, request, |raw, spans| {
+        spans
+            .iter()
+            .enumerate()
+            .filter(|(_, span)| {
+                extract_gw_id(&raw[span.value_start..span.value_end]).as_deref() == Some(span.local_id.as_str())
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    })?;
+    if result.plans.is_empty() {
+        return Err("目录中没有本地栏目ID与国网栏目ID相同的映射".into());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str = concat!(
+        "{\n  \"version\": 3,\n  \"sExtOptions\": {\n",
+        "    \"portal_frag_101\": \"a.B|intent://?es_tabId=101&es_title=\",\n",
+        "    \"vod_frag_101\": \"a.B|intent://?es_tabId=101&es_title=\",\n",
+        "    \"portal_frag_102\": \"a.B|intent://?es_tabId=102&es_title=\",\n",
+        "    \"portal_frag_103\": \"a.B|intent://?es_tabId=903&es_title=\"\n  }\n}\n"
+    );
+
+    #[test]
+    fn fixes_placeholders_under_every_prefix_and_reports_the_rest() {
+        let dir = std::env::temp_dir().join(format!("catmap-same-id-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("theme1.json"), THEME).unwrap();
+        let settings = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..AppSettings::default()
+        };
+
+        let found = audit(&settings, &dir).unwrap();
+        assert_eq!(found.entries.len(), 3);
+        assert_eq!(found.local_ids, ["101", "102"]);
+
+        let target = dir.to_string_lossy().into_owned();
+        let preview = remove(&settings, target.clone(), true, false, Some(true), None).unwrap();
+        assert_eq!(preview.plans[0].removed.len(), 3);
+        assert_eq!(std::fs::read_to_string(dir.join("theme1.json")).unwrap(), THEME);
+
+        let fixes = HashMap::from([("101".to_string(), "901".to_string())]);
+        let fixed = fix(&settings, target, fixes, false, None).unwrap();
+        let remaining: Vec<&str> = fixed.remaining.iter().map(|entry| entry.local_id.as_str()).collect();
+        assert_eq!(remaining, ["102"]);
+        let content = std::fs::read_to_string(dir.join("theme1.json")).unwrap();
+        assert_eq!(content.matches("es_tabId=901").count(), 2);
+        assert!(content.contains("es_tabId=903"));
+        assert!(audit(&settings, &dir.join("missing")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{FileMapping, MappingEntry};
+
+/// 可搜索的字段
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchField {
+    LocalId,
+    GwId,
+    RawValue,
+}
+
+/// 匹配方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// 完全相等
+    Exact,
+    /// 包含查询字符串（不区分大小写）
+    #[default]
+    Substring,
+    /// 查询字符串中的字符按顺序出现即可（不区分大小写）
+    Fuzzy,
+}
+
+/// 一条命中的映射
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub entry: MappingEntry,
+    pub matched_fields: Vec<SearchField>,
+}
+
+/// 单个文件中的命中结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSearchHits {
+    pub file_path: String,
+    pub hits: Vec<SearchHit>,
+}
+
+/// 在扫描结果中搜索映射，只返回有命中的文件
+pub fn search(files: &[FileMapping], query: &str, fields: &[SearchField], mode: MatchMode) -> Vec<FileSearchHits> {
+    let query = query.trim();
+    files
+        .iter()
+        .filter_map(|file| {
+            let hits: Vec<SearchHit> = file
+                .mappings
+                .iter()
+                .filter_map(|entry| {
+                    let matched_fields: Vec<SearchField> = fields
+                        .iter()
+                        .copied()
+                        .filter(|field| field_value(entry, *field).is_some_and(|value| matches(value, query, mode)))
+                        .collect();
+                    if matched_fields.is_empty() {
+                        None
+                    } else {
+                        Some(SearchHit {
+                            entry: entry.clone(),
+                            matched_fields,
+                        })
+                    }
+                })
+                .collect();
+            if hits.is_empty() {
+                None
+            } else {
+                Some(FileSearchHits {
+                    file_path: file.file_path.clone(),
+                    hits,
+                })
+            }
+        })
+        .collect()
+}
+
+fn field_value(entry: &MappingEntry, field: SearchField) -> Option<&str> {
+    match field {
+        SearchField::LocalId => Some(entry.local_id.as_str()),
+        SearchField::GwId => entry.gw_id.as_deref(),
+        SearchField::RawValue => Some(entry.raw_value.as_str()),
+    }
+}
+
+fn matches(value: &str, query: &str, mode: MatchMode) -> bool {
+    match mode {
+        MatchMode::Exact => value == query,
+        MatchMode::Substring => value.to_lowercase().contains(&query.to_lowercase()),
+        MatchMode::Fuzzy => {
+            let value = value.to_lowercase();
+            let mut chars = value.chars();
+            query
+                .to_lowercase()
+                .chars()
+                .all(|wanted| chars.any(|c| c == wanted))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scan_directory, settings::AppSettings};
+
+    #[test]
+    fn matches_in_each_mode() {
+        assert!(matches("10086", "10086", MatchMode::Exact));
+        assert!(!matches("10086", "1008", MatchMode::Exact));
+        assert!(matches("a.VodFragment", "vodfrag", MatchMode::Substring));
+        assert!(matches("a.VodFragment", "vfmt", MatchMode::Fuzzy));
+        assert!(!matches("a.VodFragment", "tmfv", MatchMode::Fuzzy));
+    }
+
+    #[test]
+    fn returns_only_files_and_fields_with_hits() {
+        let dir = std::env::temp_dir().join(format!("catmap-search-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = |local_id: &str| {
+            format!(
+                "{{\n  \"sExtOptions\": {{\n    \"portal_frag_{}\": \"a.B|intent://?es_tabId=900&es_title=\"\n  }}\n}}\n",
+                local_id
+            )
+        };
+        std::fs::write(dir.join("theme1.json"), theme("900")).unwrap();
+        std::fs::write(dir.join("theme2.json"), theme("101")).unwrap();
+        let files = scan_directory(&dir, &AppSettings::default()).unwrap();
+
+        let hits = search(&files, " 900 ", &[SearchField::LocalId], MatchMode::Exact);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].file_path.ends_with("theme1.json"));
+        let hits = search(&files, "900", &[SearchField::LocalId, SearchField::GwId], MatchMode::Exact);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].hits[0].matched_fields, [SearchField::LocalId, SearchField::GwId]);
+        assert_eq!(hits[1].hits[0].matched_fields, [SearchField::GwId]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,497 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// 默认的映射 key 前缀
+pub const DEFAULT_PORTAL_PREFIX: &str = "portal_frag_";
+/// 默认的映射值模板，`{id}` 会被替换为国网栏目ID
+pub const DEFAULT_TEMPLATE_VALUE: &str =
+    "com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={id}&es_title=&es_focusStartColor=&es_focusEndColor=&es_focusImg=";
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+const RECENT_DIRS_FILE_NAME: &str = "recent_dirs.json";
+const PROFILES_FILE_NAME: &str = "profiles.json";
+/// 最近使用目录的最大保留数量
+const MAX_RECENT_DIRS: usize = 10;
+
+/// 备份保留策略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupRetention {
+    /// 最多保留最近 N 个备份，None 表示不限制
+    pub keep_last: Option<usize>,
+    /// 最多保留最近 M 天内的备份，None 表示不限制
+    pub max_age_days: Option<u32>,
+}
+
+/// 操作日志轮转策略，超出限制时删除最旧的日志
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogRetention {
+    /// 最多保留最近 N 次操作的日志，None 表示不限制
+    pub keep_last: Option<usize>,
+    /// 日志总大小上限（MB），None 表示不限制
+    pub max_total_mb: Option<u64>,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            keep_last: Some(500),
+            max_total_mb: Some(20),
+        }
+    }
+}
+
+/// 定时自动备份
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduledBackup {
+    /// 备份间隔（分钟），None 表示关闭定时备份
+    pub interval_minutes: Option<u32>,
+    /// 需要定时备份的目标目录
+    pub dirs: Vec<String>,
+}
+
+/// 定时审计：按间隔重新扫描登记的目录，与最近一个已确认的快照对比，发现工具之外的修改时提醒
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduledAudit {
+    /// 审计间隔（分钟），None 表示关闭定时审计
+    pub interval_minutes: Option<u32>,
+    /// 需要定时审计的目标目录
+    pub dirs: Vec<String>,
+}
+
+/// 新增映射前对输入ID的规范处理
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IdNormalizationRules {
+    /// 去掉两侧空白
+    pub trim: bool,
+    /// 全角数字、字母转为半角
+    pub full_width: bool,
+    /// 纯数字的本地栏目ID不足 N 位时在前面补零，None 表示不补零
+    pub pad_local_id: Option<usize>,
+    /// 纯数字的国网栏目ID不足 N 位时在前面补零，None 表示不补零
+    pub pad_gw_id: Option<usize>,
+}
+
+impl IdNormalizationRules {
+    pub fn local_id(&self, id: &str) -> String {
+        self.apply(id, self.pad_local_id)
+    }
+
+    pub fn gw_id(&self, id: &str) -> String {
+        self.apply(id, self.pad_gw_id)
+    }
+
+    fn apply(&self, id: &str, pad: Option<usize>) -> String {
+        let mut id = if self.full_width { crate::duplicates::half_width(id) } else { id.to_string() };
+        if self.trim {
+            id = id.trim().to_string();
+        }
+        match pad {
+            Some(width) if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => format!("{:0>width$}", id),
+            _ => id,
+        }
+    }
+}
+
+/// 备份的存储格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFormat {
+    /// 以时间戳命名的目录，内含文件副本
+    #[default]
+    Folder,
+    /// 单个 backup_<时间戳>.zip 压缩包
+    Zip,
+}
+
+/// 国网栏目ID不符合校验规则时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GwIdValidationMode {
+    /// 拒绝写入不符合规则的映射
+    #[default]
+    Reject,
+    /// 照常写入，只在结果中标记
+    Flag,
+}
+
+/// 具名的映射值模板，用于 GwPortalFragment 以外的栏目类型，`{id}` 会被替换为国网栏目ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueTemplate {
+    pub name: String,
+    pub value: String,
+}
+
+/// 应用设置，持久化在应用配置目录下的 settings.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    /// 映射 key 前缀
+    pub portal_prefix: String,
+    /// 其他模块使用的映射 key 前缀，扫描、新增和删除时与映射前缀一起处理
+    pub extra_prefixes: Vec<String>,
+    /// 新增映射时使用的值模板
+    pub template_value: String,
+    /// 其他可选的具名值模板，新增映射时按名称选择，未选择时使用 template_value
+    pub value_templates: Vec<ValueTemplate>,
+    /// 备份保留策略
+    pub backup_retention: BackupRetention,
+    /// 备份存储格式
+    pub backup_format: BackupFormat,
+    /// 备份和操作日志的存放根目录，None 表示放在目标目录下
+    pub backup_root: Option<String>,
+    /// 操作日志目录名，位于备份和日志的存放目录下
+    pub log_dir_name: String,
+    /// 操作日志轮转策略
+    pub log_retention: LogRetention,
+    /// 定时自动备份
+    pub scheduled_backup: ScheduledBackup,
+    /// 定时审计
+    pub scheduled_audit: ScheduledAudit,
+    /// 界面语言，同时决定跳过原因、错误消息和操作日志的语言（zh-CN、en-US）
+    pub locale: String,
+    /// 修改文件时是否自动递增版本号，命令未指定时使用
+    pub auto_increment_version: bool,
+    /// 国网栏目ID校验正则（需完整匹配，如 `\d{10}`），None 表示不校验
+    pub gw_id_pattern: Option<String>,
+    /// 国网栏目ID不符合校验规则时的处理方式
+    pub gw_id_validation: GwIdValidationMode,
+    /// 新增映射时检查国网栏目ID是否已被其他本地栏目ID使用：reject 取消整批写入，flag 只在结果中标记，None 表示不检查
+    pub gw_id_reuse_check: Option<GwIdValidationMode>,
+    /// 国网栏目目录的 HTTP 接口地址
+    pub gw_catalog_url: Option<String>,
+    /// 新增映射时要求本地栏目ID在已加载的本地栏目目录中
+    pub require_local_catalog: bool,
+    /// 新增映射前对输入的本地栏目ID和国网栏目ID做的规范处理
+    pub id_normalization: IdNormalizationRules,
+    /// adb 可执行文件路径，None 表示从 PATH 中查找
+    pub adb_path: Option<String>,
+    /// 设备上桌面应用的包名，推送 theme 文件后重启该应用
+    pub launcher_package: Option<String>,
+    /// OpenSSH 客户端（ssh、sftp）所在目录，None 表示从 PATH 中查找
+    pub ssh_dir: Option<String>,
+    /// 目标目录是 git 工作区时，每次修改后自动提交修改的 theme 文件
+    pub git_auto_commit: bool,
+    /// 操作完成或失败后推送摘要的 webhook 地址（企业微信、钉钉群机器人），None 表示不推送
+    pub webhook_url: Option<String>,
+    /// 解析结果的磁盘缓存目录，None 表示只在内存中缓存（重启后失效）
+    pub parse_cache_dir: Option<String>,
+    /// 安全模式：修改前检查目标文件，已有结构问题（见 lint）的文件一律不修改，避免改写已损坏的段落
+    pub safe_mode: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            portal_prefix: DEFAULT_PORTAL_PREFIX.to_string(),
+            extra_prefixes: Vec::new(),
+            template_value: DEFAULT_TEMPLATE_VALUE.to_string(),
+            value_templates: Vec::new(),
+            backup_retention: BackupRetention::default(),
+            backup_format: BackupFormat::default(),
+            backup_root: None,
+            log_dir_name: crate::history::DEFAULT_LOG_DIR_NAME.to_string(),
+            log_retention: LogRetention::default(),
+            scheduled_backup: ScheduledBackup::default(),
+            scheduled_audit: ScheduledAudit::default(),
+            locale: "zh-CN".to_string(),
+            auto_increment_version: true,
+            gw_id_pattern: None,
+            gw_id_validation: GwIdValidationMode::default(),
+            gw_id_reuse_check: None,
+            gw_catalog_url: None,
+            require_local_catalog: false,
+            id_normalization: IdNormalizationRules::default(),
+            adb_path: None,
+            launcher_package: None,
+            ssh_dir: None,
+            git_auto_commit: false,
+            webhook_url: None,
+            parse_cache_dir: None,
+            safe_mode: false,
+        }
+    }
+}
+
+impl AppSettings {
+    /// 检查设置是否可用
+    pub fn validate(&self) -> Result<(), String> {
+        if self.portal_prefix.trim().is_empty() {
+            return Err("映射前缀不能为空".into());
+        }
+        if self.portal_prefix.contains('"') {
+            return Err("映射前缀不能包含引号".into());
+        }
+        for (idx, prefix) in self.extra_prefixes.iter().enumerate() {
+            if prefix.trim().is_empty() || prefix.contains('"') {
+                return Err(format!("映射前缀无效: {}", prefix));
+            }
+            if *prefix == self.portal_prefix || self.extra_prefixes[..idx].contains(prefix) {
+                return Err(format!("映射前缀重复: {}", prefix));
+            }
+        }
+        if !self.template_value.contains("{id}") {
+            return Err("映射值模板必须包含 {id} 占位符".into());
+        }
+        for (idx, template) in self.value_templates.iter().enumerate() {
+            if template.name.trim().is_empty() {
+                return Err("映射值模板名称不能为空".into());
+            }
+            if self.value_templates[..idx].iter().any(|other| other.name == template.name) {
+                return Err(format!("映射值模板名称重复: {}", template.name));
+            }
+            if !template.value.contains("{id}") {
+                return Err(format!("映射值模板 {} 必须包含 {{id}} 占位符", template.name));
+            }
+        }
+        let rules = &self.id_normalization;
+        if rules.pad_local_id == Some(0) || rules.pad_gw_id == Some(0) {
+            return Err("补零位数必须大于 0".into());
+        }
+        let log_dir_name = self.log_dir_name.trim();
+        if log_dir_name.is_empty() || log_dir_name == "." || log_dir_name == ".." || log_dir_name.contains(['/', '\\']) {
+            return Err(format!("操作日志目录名无效: {}", self.log_dir_name));
+        }
+        if self.log_retention.keep_last == Some(0) || self.log_retention.max_total_mb == Some(0) {
+            return Err("操作日志保留数量和总大小上限必须大于 0".into());
+        }
+        if self.scheduled_backup.interval_minutes == Some(0) {
+            return Err("定时备份间隔必须大于 0 分钟".into());
+        }
+        if self.scheduled_audit.interval_minutes == Some(0) {
+            return Err("定时审计间隔必须大于 0 分钟".into());
+        }
+        if let Some(url) = self.webhook_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err("webhook 地址必须以 http:// 或 https:// 开头".into());
+            }
+        }
+        crate::validation::GwIdValidator::from_settings(self)?;
+        Ok(())
+    }
+
+    /// 所有需要处理的映射 key 前缀，映射前缀在前
+    pub fn prefixes(&self) -> Vec<&str> {
+        std::iter::once(self.portal_prefix.as_str())
+            .chain(self.extra_prefixes.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// 条目使用的映射 key 前缀，None 表示映射前缀；前缀未配置时返回错误
+    pub fn key_prefix<'a>(&'a self, prefix: Option<&'a str>) -> Result<&'a str, String> {
+        match prefix {
+            None => Ok(&self.portal_prefix),
+            Some(prefix) if self.prefixes().contains(&prefix) => Ok(prefix),
+            Some(prefix) => Err(format!("未配置的映射前缀: {}", prefix)),
+        }
+    }
+
+    /// 按名称查找值模板，name 为 None 时返回默认模板
+    pub fn template(&self, name: Option<&str>) -> Option<&str> {
+        match name {
+            None => Some(&self.template_value),
+            Some(name) => self
+                .value_templates
+                .iter()
+                .find(|template| template.name == name)
+                .map(|template| template.value.as_str()),
+        }
+    }
+}
+
+/// 读取设置，配置文件不存在时返回默认设置
+pub fn load_settings(config_dir: &Path) -> Result<AppSettings, String> {
+    let path = config_dir.join(SETTINGS_FILE_NAME);
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取设置文件失败: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("设置文件格式错误: {}", e))
+}
+
+/// 保存设置到配置目录
+pub fn save_settings(config_dir: &Path, settings: &AppSettings) -> Result<(), String> {
+    settings.validate()?;
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(config_dir.join(SETTINGS_FILE_NAME), content)
+        .map_err(|e| format!("写入设置文件失败: {}", e))
+}
+
+/// 命名的设置配置（不同项目、省份使用不同的前缀、模板和校验规则），以及各目标目录使用的配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SettingsProfiles {
+    pub profiles: BTreeMap<String, AppSettings>,
+    /// 目标目录 -> 使用的配置名称
+    pub dirs: BTreeMap<String, String>,
+    /// 最近一次应用的配置名称
+    pub active: Option<String>,
+}
+
+/// 读取设置配置，文件不存在时返回空列表
+pub fn load_profiles(config_dir: &Path) -> Result<SettingsProfiles, String> {
+    let path = config_dir.join(PROFILES_FILE_NAME);
+    if !path.exists() {
+        return Ok(SettingsProfiles::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取设置配置失败: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("设置配置文件格式错误: {}", e))
+}
+
+fn write_profiles(config_dir: &Path, profiles: &SettingsProfiles) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(config_dir.join(PROFILES_FILE_NAME), content).map_err(|e| format!("写入设置配置失败: {}", e))
+}
+
+/// 保存（新增或覆盖）命名的设置配置
+pub fn save_profile(config_dir: &Path, name: &str, settings: &AppSettings) -> Result<SettingsProfiles, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("配置名称不能为空".into());
+    }
+    settings.validate()?;
+    let mut profiles = load_profiles(config_dir)?;
+    profiles.profiles.insert(name.to_string(), settings.clone());
+    write_profiles(config_dir, &profiles)?;
+    Ok(profiles)
+}
+
+/// 删除设置配置，同时删除使用该配置的目录记录
+pub fn delete_profile(config_dir: &Path, name: &str) -> Result<SettingsProfiles, String> {
+    let mut profiles = load_profiles(config_dir)?;
+    if profiles.profiles.remove(name).is_none() {
+        return Err(format!("设置配置不存在: {}", name));
+    }
+    profiles.dirs.retain(|_, profile| profile != name);
+    if profiles.active.as_deref() == Some(name) {
+        profiles.active = None;
+    }
+    write_profiles(config_dir, &profiles)?;
+    Ok(profiles)
+}
+
+/// 应用设置配置：指定目录时只记住该目录使用这个配置，之后对该目录的操作都使用它（见 [`dir_profile`]），
+/// 保存的当前设置保持不变；未指定目录时把配置设为当前设置
+pub fn apply_profile(config_dir: &Path, name: &str, dir: Option<&str>) -> Result<AppSettings, String> {
+    let mut profiles = load_profiles(config_dir)?;
+    let settings = profiles
+        .profiles
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("设置配置不存在: {}", name))?;
+    match dir {
+        Some(dir) => {
+            profiles.dirs.insert(normalize_dir(dir), name.to_string());
+        }
+        None => {
+            save_settings(config_dir, &settings)?;
+            profiles.active = Some(name.to_string());
+        }
+    }
+    write_profiles(config_dir, &profiles)?;
+    Ok(settings)
+}
+
+/// 目录（或文件所在目录）记住的设置配置，没有记录或配置已被删除时返回 None
+///
+/// 配置只叠加在对该目录的操作上，不修改保存的当前设置。
+pub fn dir_profile(config_dir: &Path, path: &str) -> Result<Option<AppSettings>, String> {
+    let profiles = load_profiles(config_dir)?;
+    let parent = Path::new(path.trim()).parent().map(|parent| parent.to_string_lossy().into_owned());
+    Ok(std::iter::once(normalize_dir(path))
+        .chain(parent.as_deref().map(normalize_dir))
+        .find_map(|dir| profiles.dirs.get(&dir))
+        .and_then(|name| profiles.profiles.get(name))
+        .cloned())
+}
+
+/// 读取最近使用的目录列表（最近使用的在前），自动过滤已不存在的目录
+pub fn load_recent_dirs(config_dir: &Path) -> Result<Vec<String>, String> {
+    let path = config_dir.join(RECENT_DIRS_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取最近目录失败: {}", e))?;
+    // 文件损坏时视为空列表，不影响正常使用
+    let dirs: Vec<String> = serde_json::from_str(&raw).unwrap_or_default();
+    Ok(dirs.into_iter().filter(|dir| Path::new(dir).is_dir()).collect())
+}
+
+/// 将目录加入最近使用列表头部（去重），返回更新后的列表
+pub fn add_recent_dir(config_dir: &Path, dir: &str) -> Result<Vec<String>, String> {
+    let normalized = normalize_dir(dir);
+    if !Path::new(&normalized).is_dir() {
+        return Err(format!("目录不存在: {}", dir));
+    }
+
+    let mut dirs = load_recent_dirs(config_dir)?;
+    dirs.retain(|existing| normalize_dir(existing) != normalized);
+    dirs.insert(0, normalized);
+    dirs.truncate(MAX_RECENT_DIRS);
+
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(&dirs).map_err(|e| e.to_string())?;
+    fs::write(config_dir.join(RECENT_DIRS_FILE_NAME), content)
+        .map_err(|e| format!("写入最近目录失败: {}", e))?;
+    Ok(dirs)
+}
+
+/// 去掉目录路径末尾多余的分隔符，便于去重比较
+fn normalize_dir(dir: &str) -> String {
+    let trimmed = dir.trim();
+    let stripped = trimmed.trim_end_matches(['/', '\\']);
+    if stripped.is_empty() || stripped.ends_with(':') {
+        // 根目录（如 "/" 或 "C:\"）保持原样
+        trimmed.to_string()
+    } else {
+        stripped.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_input_ids() {
+        let rules = IdNormalizationRules {
+            trim: true,
+            full_width: true,
+            pad_local_id: Some(4),
+            pad_gw_id: None,
+        };
+        assert_eq!(rules.local_id(" １０１ "), "0101");
+        assert_eq!(rules.local_id("a1"), "a1");
+        assert_eq!(rules.gw_id("１００１"), "1001");
+        assert_eq!(IdNormalizationRules::default().local_id(" １０１"), " １０１");
+    }
+
+    #[test]
+    fn dir_profile_overlays_without_changing_current_settings() {
+        let config_dir = std::env::temp_dir().join(format!("catmap-dir-profile-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&config_dir);
+        let current = AppSettings::default();
+        save_settings(&config_dir, &current).unwrap();
+        let profile = AppSettings {
+            extra_prefixes: vec!["vod_frag_".to_string()],
+            ..Default::default()
+        };
+        save_profile(&config_dir, "四川", &profile).unwrap();
+        apply_profile(&config_dir, "四川", Some("/deploy/sichuan/")).unwrap();
+
+        let for_dir = dir_profile(&config_dir, "/deploy/sichuan").unwrap().unwrap();
+        assert_eq!(for_dir.extra_prefixes, ["vod_frag_"]);
+        let for_file = dir_profile(&config_dir, "/deploy/sichuan/theme.json").unwrap().unwrap();
+        assert_eq!(for_file.extra_prefixes, ["vod_frag_"]);
+        assert!(dir_profile(&config_dir, "/deploy/other").unwrap().is_none());
+        assert!(load_settings(&config_dir).unwrap().extra_prefixes.is_empty());
+        let _ = fs::remove_dir_all(&config_dir);
+    }
+}
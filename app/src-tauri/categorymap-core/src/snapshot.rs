@@ -0,0 +1,251 @@
+//! 映射快照：保存某一时刻目录中所有 theme 文件解析后的映射（不复制文件），之后可对比两个快照，
+//! 或对比快照和目录的当前状态，回答「上次发版以来改了哪些映射」。
+//!
+//! 确认（approve）过的快照作为基准，定时审计用 [`audit_drift`] 找出之后在工具之外发生的修改。
+//!
+//! 快照保存在备份存放目录（见 [`crate::backup::storage_dir`]）下的 snapshots 目录，每个快照一个 JSON 文件。
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backup,
+    diff::{self, DiffEntry, FileDiff},
+    scan_directory,
+    settings::AppSettings,
+    Error,
+};
+
+const SNAPSHOT_DIR_NAME: &str = "snapshots";
+/// 快照 ID（也是时间戳）的格式
+const SNAPSHOT_ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    #[serde(flatten)]
+    pub info: SnapshotInfo,
+    /// 文件名 -> 会被设备读取的映射
+    pub files: BTreeMap<String, Vec<DiffEntry>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub label: String,
+    pub target_dir: String,
+    /// 创建时间（YYYY-MM-DD HH:MM:SS）
+    pub taken_at: String,
+    pub file_count: usize,
+    pub mapping_count: usize,
+    /// 是否已确认为审计基准
+    #[serde(default)]
+    pub approved: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotComparison {
+    pub a: SnapshotInfo,
+    /// 与目录当前状态对比时为 None
+    pub b: Option<SnapshotInfo>,
+    pub files: Vec<FileDiff>,
+    pub identical: bool,
+}
+
+fn snapshot_dir(settings: &AppSettings, target_dir: &Path) -> PathBuf {
+    backup::storage_dir(target_dir, settings).join(SNAPSHOT_DIR_NAME)
+}
+
+fn current_state(settings: &AppSettings, target_dir: &Path) -> Result<BTreeMap<String, Vec<DiffEntry>>, Error> {
+    Ok(diff::by_file_name(&scan_directory(target_dir, settings)?))
+}
+
+/// 保存目录当前的映射状态为快照
+pub fn take(settings: &AppSettings, target_dir: &Path, label: &str) -> Result<SnapshotInfo, Error> {
+    let files = current_state(settings, target_dir)?;
+    let now = Local::now();
+    let dir = snapshot_dir(settings, target_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建快照目录失败: {}", e))?;
+    // 同一秒内多次创建时加序号
+    let base_id = now.format(SNAPSHOT_ID_FORMAT).to_string();
+    let mut id = base_id.clone();
+    let mut index = 1;
+    while dir.join(format!("{}.json", id)).exists() {
+        index += 1;
+        id = format!("{}-{}", base_id, index);
+    }
+    let snapshot = Snapshot {
+        info: SnapshotInfo {
+            id: id.clone(),
+            label: label.trim().to_string(),
+            target_dir: target_dir.to_string_lossy().into_owned(),
+            taken_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            file_count: files.len(),
+            mapping_count: files.values().map(Vec::len).sum(),
+            approved: false,
+        },
+        files,
+    };
+    write(&dir.join(format!("{}.json", id)), &snapshot)?;
+    Ok(snapshot.info)
+}
+
+fn write(path: &Path, snapshot: &Snapshot) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?;
+    crate::file_io::write_atomic(path, content).map_err(|e| format!("写入快照失败: {}", e))
+}
+
+/// 目录的所有快照，最新的在前
+pub fn list(settings: &AppSettings, target_dir: &Path) -> Result<Vec<SnapshotInfo>, Error> {
+    let dir = snapshot_dir(settings, target_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut infos = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| format!("读取快照目录失败: {}", e))? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        // 单个快照损坏不影响列出其他快照
+        match read(&path) {
+            Ok(snapshot) => infos.push(snapshot.info),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    infos.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(infos)
+}
+
+fn read(path: &Path) -> Result<Snapshot, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("读取快照失败: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("快照格式错误 {}: {}", path.display(), e))
+}
+
+fn snapshot_path(settings: &AppSettings, target_dir: &Path, id: &str) -> Result<PathBuf, Error> {
+    let path = snapshot_dir(settings, target_dir).join(format!("{}.json", id));
+    if id.contains(['/', '\\']) || !path.is_file() {
+        return Err(format!("快照不存在: {}", id).into());
+    }
+    Ok(path)
+}
+
+fn load(settings: &AppSettings, target_dir: &Path, id: &str) -> Result<Snapshot, Error> {
+    Ok(read(&snapshot_path(settings, target_dir, id)?)?)
+}
+
+/// 确认快照为审计基准，之后的定时审计与最近一个已确认的快照对比
+pub fn approve(settings: &AppSettings, target_dir: &Path, id: &str) -> Result<SnapshotInfo, Error> {
+    let path = snapshot_path(settings, target_dir, id)?;
+    let mut snapshot = read(&path)?;
+    snapshot.info.approved = true;
+    write(&path, &snapshot)?;
+    Ok(snapshot.info)
+}
+
+/// 对比快照 a 和快照 b，b 为 None 时与目录的当前状态对比
+pub fn compare(
+    settings: &AppSettings,
+    target_dir: &Path,
+    a: &str,
+    b: Option<&str>,
+) -> Result<SnapshotComparison, Error> {
+    let snapshot_a = load(settings, target_dir, a)?;
+    let (info_b, files_b) = match b {
+        Some(b) => {
+            let snapshot_b = load(settings, target_dir, b)?;
+            (Some(snapshot_b.info), snapshot_b.files)
+        }
+        None => (None, current_state(settings, target_dir)?),
+    };
+    let files = diff::diff_named_sets(&snapshot_a.files, &files_b);
+    let identical = files.iter().all(|f| f.presence == "both" && f.diff.is_empty());
+    Ok(SnapshotComparison {
+        a: snapshot_a.info,
+        b: info_b,
+        files,
+        identical,
+    })
+}
+
+/// 目录相对审计基准的变化
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub target_dir: String,
+    /// 对比的基准快照
+    pub baseline: SnapshotInfo,
+    /// 有变化的文件
+    pub files: Vec<FileDiff>,
+    /// 新增、删除和修改的映射总数
+    pub changed_mappings: usize,
+}
+
+/// 与最近一个已确认的快照对比，没有已确认的快照或没有变化时返回 None
+pub fn audit_drift(settings: &AppSettings, target_dir: &Path) -> Result<Option<DriftReport>, Error> {
+    let Some(baseline) = list(settings, target_dir)?.into_iter().find(|info| info.approved) else {
+        return Ok(None);
+    };
+    let comparison = compare(settings, target_dir, &baseline.id, None)?;
+    if comparison.identical {
+        return Ok(None);
+    }
+    let files: Vec<FileDiff> = comparison
+        .files
+        .into_iter()
+        .filter(|file| file.presence != "both" || !file.diff.is_empty())
+        .collect();
+    let changed_mappings = files
+        .iter()
+        .map(|file| file.diff.only_in_a.len() + file.diff.only_in_b.len() + file.diff.changed.len())
+        .sum();
+    Ok(Some(DriftReport {
+        target_dir: target_dir.to_string_lossy().into_owned(),
+        baseline,
+        files,
+        changed_mappings,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str =
+        "{\n  \"version\": 3,\n  \"sExtOptions\": {\n    \"portal_frag_101\": \"a.B|intent://?es_tabId=900&es_title=\"\n  }\n}\n";
+
+    #[test]
+    fn audits_drift_against_the_approved_snapshot() {
+        let dir = std::env::temp_dir().join(format!("catmap-snapshot-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("theme1.json"), THEME).unwrap();
+        let settings = AppSettings::default();
+
+        let first = take(&settings, &dir, " 发版 ").unwrap();
+        assert_eq!((first.label.as_str(), first.file_count, first.mapping_count), ("发版", 1, 1));
+        assert!(audit_drift(&settings, &dir).unwrap().is_none());
+        approve(&settings, &dir, &first.id).unwrap();
+        assert!(audit_drift(&settings, &dir).unwrap().is_none());
+
+        fs::write(dir.join("theme1.json"), THEME.replace("es_tabId=900", "es_tabId=901")).unwrap();
+        let drift = audit_drift(&settings, &dir).unwrap().unwrap();
+        assert_eq!(drift.baseline.id, first.id);
+        assert_eq!(drift.changed_mappings, 1);
+
+        let second = take(&settings, &dir, "").unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(list(&settings, &dir).unwrap().len(), 2);
+        assert!(!compare(&settings, &dir, &first.id, Some(&second.id)).unwrap().identical);
+        assert!(compare(&settings, &dir, &second.id, None).unwrap().identical);
+        assert!(compare(&settings, &dir, "../theme1", None).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{FileMapping, MappingEntry};
+
+/// 一组映射的计数
+#[derive(Debug, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingCounts {
+    pub total: usize,
+    pub duplicate_local: usize,
+    pub duplicate_gw: usize,
+    /// ID只差前导零、空白或全角/半角数字的疑似重复映射
+    pub suspicious_duplicate: usize,
+    pub same_id: usize,
+    /// 没有国网栏目ID（缺少 es_tabId 或为空）的映射
+    pub empty_gw_id: usize,
+    /// 不在 sExtOptions 中的 portal_frag_ 条目，不计入以上各项
+    pub misplaced: usize,
+    /// 被注释掉的条目，不计入以上各项
+    pub commented: usize,
+}
+
+impl MappingCounts {
+    fn from_entries(entries: &[MappingEntry]) -> Self {
+        let mut counts = MappingCounts::default();
+        for entry in entries {
+            if entry.is_misplaced() {
+                counts.misplaced += 1;
+                continue;
+            }
+            if entry.is_commented() {
+                counts.commented += 1;
+                continue;
+            }
+            counts.total += 1;
+            match entry.status.as_str() {
+                "duplicate_local" => counts.duplicate_local += 1,
+                "duplicate_gw" => counts.duplicate_gw += 1,
+                "suspicious_duplicate" => counts.suspicious_duplicate += 1,
+                _ => {}
+            }
+            if entry.same_id {
+                counts.same_id += 1;
+            }
+            if matches!(entry.gw_id.as_deref(), None | Some("")) {
+                counts.empty_gw_id += 1;
+            }
+        }
+        counts
+    }
+
+    fn add(&mut self, other: &MappingCounts) {
+        self.total += other.total;
+        self.duplicate_local += other.duplicate_local;
+        self.duplicate_gw += other.duplicate_gw;
+        self.suspicious_duplicate += other.suspicious_duplicate;
+        self.same_id += other.same_id;
+        self.empty_gw_id += other.empty_gw_id;
+        self.misplaced += other.misplaced;
+        self.commented += other.commented;
+    }
+}
+
+/// 单个文件的统计
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStatistics {
+    pub file_path: String,
+    #[serde(flatten)]
+    pub counts: MappingCounts,
+}
+
+/// 映射数量与多数文件不一致的文件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergentFile {
+    pub file_path: String,
+    pub total: usize,
+    /// 多数文件的映射数量
+    pub expected_total: usize,
+}
+
+/// 目录统计结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub file_count: usize,
+    pub files: Vec<FileStatistics>,
+    /// 所有文件的合计
+    pub aggregate: MappingCounts,
+    /// 多数文件的映射数量，没有文件时为 None
+    pub majority_total: Option<usize>,
+    pub divergent_files: Vec<DivergentFile>,
+}
+
+/// 统计各文件及全部文件的映射情况
+pub fn compute(files: &[FileMapping]) -> Statistics {
+    let file_stats: Vec<FileStatistics> = files
+        .iter()
+        .map(|file| FileStatistics {
+            file_path: file.file_path.clone(),
+            counts: MappingCounts::from_entries(&file.mappings),
+        })
+        .collect();
+
+    let mut aggregate = MappingCounts::default();
+    for stats in &file_stats {
+        aggregate.add(&stats.counts);
+    }
+
+    // 出现次数最多的映射数量视为多数；次数相同时取较大的数量
+    let mut frequency: BTreeMap<usize, usize> = BTreeMap::new();
+    for stats in &file_stats {
+        *frequency.entry(stats.counts.total).or_default() += 1;
+    }
+    let majority_total = frequency
+        .iter()
+        .max_by_key(|(total, count)| (**count, **total))
+        .map(|(total, _)| *total);
+
+    let divergent_files = match majority_total {
+        Some(expected_total) => file_stats
+            .iter()
+            .filter(|stats| stats.counts.total != expected_total)
+            .map(|stats| DivergentFile {
+                file_path: stats.file_path.clone(),
+                total: stats.counts.total,
+                expected_total,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Statistics {
+        file_count: file_stats.len(),
+        files: file_stats,
+        aggregate,
+        majority_total,
+        divergent_files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scan_directory, settings::AppSettings};
+
+    #[test]
+    fn counts_each_file_and_flags_divergent_ones() {
+        let dir = std::env::temp_dir().join(format!("catmap-stats-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = |options: &str| format!("{{\n  \"version\": 3,\n  \"sExtOptions\": {{\n{}\n  }}\n}}\n", options);
+        let entry = |local_id: &str, gw_id: &str| {
+            format!("    \"portal_frag_{}\": \"a.B|intent://?es_tabId={}&es_title=\"", local_id, gw_id)
+        };
+        for name in ["theme1.json", "theme2.json"] {
+            std::fs::write(dir.join(name), theme(&[entry("101", "900"), entry("102", "901")].join(",\n"))).unwrap();
+        }
+        let odd = [entry("101", "900"), entry("101", "902"), entry("103", "103"), entry("104", "")];
+        std::fs::write(dir.join("theme3.json"), theme(&odd.join(",\n"))).unwrap();
+
+        let statistics = compute(&scan_directory(&dir, &AppSettings::default()).unwrap());
+        assert_eq!(statistics.file_count, 3);
+        assert_eq!(statistics.majority_total, Some(2));
+        assert_eq!(statistics.divergent_files.len(), 1);
+        assert!(statistics.divergent_files[0].file_path.ends_with("theme3.json"));
+        let total = &statistics.aggregate;
+        assert_eq!((total.total, total.duplicate_local, total.same_id, total.empty_gw_id), (8, 2, 1, 1));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,504 @@
+//! 扫描结果、处理结果和操作日志中共用的数据类型
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::{IntentParams, JsonSyntaxError},
+    file_io,
+    i18n::{Locale, Message},
+    ignore, lint, profile, validation,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingEntry {
+    pub local_id: String,
+    /// 映射 key 的前缀（见设置中的 portalPrefix、extraPrefixes）
+    pub prefix: String,
+    pub gw_id: Option<String>,
+    /// 映射值中 intent 携带的附加参数
+    #[serde(flatten)]
+    pub params: IntentParams,
+    /// 映射值中 es_tabId 以外所有非空的 es_* 参数（含未单独列出的参数）
+    pub es_params: std::collections::BTreeMap<String, String>,
+    /// 映射值中的栏目类名，值中没有类名时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment_class: Option<String>,
+    pub raw_value: String,
+    pub same_id: bool,
+    /// 映射值是否偏离模板布局（无法解析、缺少栏目类名或 es_tabId、参数顺序或编码不规范）
+    pub non_standard: bool,
+    /// 状态：normal-正常, duplicate_local-本地ID重复, duplicate_gw-国网ID重复,
+    /// suspicious_duplicate-疑似重复（ID只差前导零、空白或全角/半角数字）,
+    /// misplaced-不在 sExtOptions 中（设备不会读取）, commented-已被注释掉
+    pub status: String,
+    /// 国网栏目在官方栏目目录中的名称（已缓存栏目目录时标注）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gw_name: Option<String>,
+    /// 本地栏目在本地栏目目录中的名称（已加载本地栏目目录时标注）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_name: Option<String>,
+    /// 国网栏目ID是否在官方栏目目录中，未缓存栏目目录时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_catalog: Option<bool>,
+    /// 所在 sExtOptions 段落的序号（从 0 开始），不在 sExtOptions 中的条目为 None
+    pub block_index: Option<usize>,
+    /// 所在 sExtOptions 段落所属对象的路径，如 `$`、`$.themes[1]`；
+    /// 不在 sExtOptions 中的条目为直接包含它的对象的路径
+    pub block_path: String,
+    /// 条目 key 所在的行号（从 1 开始），被注释的条目为注释所在的行
+    pub line: usize,
+    /// 条目 key 开始引号在文件内容中的字节位置
+    pub offset: usize,
+}
+
+impl MappingEntry {
+    /// 条目是否不在 sExtOptions 中（设备不会读取，不计入映射对比和统计）
+    pub fn is_misplaced(&self) -> bool {
+        self.status == "misplaced"
+    }
+
+    /// 条目是否被注释掉
+    pub fn is_commented(&self) -> bool {
+        self.status == "commented"
+    }
+
+    /// 条目是否会被设备读取（在 sExtOptions 中且未被注释），只有这些条目参与映射对比和统计
+    pub fn is_active(&self) -> bool {
+        !self.is_misplaced() && !self.is_commented()
+    }
+}
+
+/// 映射的标识：同一个本地栏目ID在不同前缀下是不同的映射
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingKey {
+    pub prefix: String,
+    pub local_id: String,
+}
+
+impl MappingKey {
+    pub fn of(span: &crate::EntrySpan) -> Self {
+        MappingKey {
+            prefix: span.prefix.clone(),
+            local_id: span.local_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMapping {
+    pub file_path: String,
+    /// 文件的文本编码，写回时保持不变
+    pub encoding: file_io::TextEncoding,
+    /// 是否以 UTF-8 BOM 开头，写回时保持不变
+    pub has_bom: bool,
+    pub line_ending: file_io::LineEnding,
+    /// 文件内容的 SHA-256，修改时用于检测文件是否已被他人改动
+    pub content_hash: String,
+    pub mappings: Vec<MappingEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    pub files: Vec<FileMapping>,
+    /// 被 .catmapignore 排除、未扫描的文件
+    pub ignored_files: Vec<ignore::IgnoredFile>,
+    /// 网络共享上因临时错误而重试的文件操作
+    pub retries: Vec<file_io::IoRetry>,
+    /// 各文件读取、解析、写入的耗时，仅在开启耗时统计（profile）时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<profile::Timings>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupResult {
+    pub backup_dir: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedFile {
+    pub file_path: String,
+    /// 按设置的语言渲染的跳过原因
+    pub reason: String,
+    pub duplicate_ids: Vec<String>,
+    /// 跳过原因的消息代码和参数，供前端自行翻译
+    #[serde(default)]
+    pub message: Option<Message>,
+    /// 安全模式下因文件已有结构问题而跳过时，检查发现的问题
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lint_issues: Vec<lint::LintIssue>,
+}
+
+impl SkippedFile {
+    pub fn new(file_path: String, message: Message, duplicate_ids: Vec<String>, locale: Locale) -> Self {
+        Self {
+            file_path,
+            reason: message.render(locale),
+            duplicate_ids,
+            message: Some(message),
+            lint_issues: Vec::new(),
+        }
+    }
+}
+
+/// 新增映射时本地栏目ID已存在的冲突记录
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryConflict {
+    pub file_path: String,
+    pub local_id: String,
+    pub gw_id: String,
+    pub existing_gw_id: Option<String>,
+    /// 处理结果：skipped-已跳过, overwritten-已覆盖
+    pub resolution: String,
+}
+
+/// 批量新增时一条映射在一个文件中的处理结果
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryFileStatus {
+    /// 已新增
+    Inserted,
+    /// 已覆盖原有的国网栏目ID
+    Overwritten,
+    /// 本地栏目ID已存在，已跳过
+    Duplicate,
+    /// 文件未能写入或映射未通过校验
+    Error,
+}
+
+impl EntryFileStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryFileStatus::Inserted => "已新增",
+            EntryFileStatus::Overwritten => "已覆盖",
+            EntryFileStatus::Duplicate => "已存在，已跳过",
+            EntryFileStatus::Error => "失败",
+        }
+    }
+
+    pub fn label_in(&self, locale: Locale) -> &'static str {
+        match (locale, self) {
+            (Locale::ZhCn, _) => self.label(),
+            (Locale::EnUs, EntryFileStatus::Inserted) => "inserted",
+            (Locale::EnUs, EntryFileStatus::Overwritten) => "overwritten",
+            (Locale::EnUs, EntryFileStatus::Duplicate) => "already exists, skipped",
+            (Locale::EnUs, EntryFileStatus::Error) => "failed",
+        }
+    }
+}
+
+/// 映射 × 文件结果矩阵中的一格
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryFileResult {
+    pub local_id: String,
+    pub file_path: String,
+    pub status: EntryFileStatus,
+    /// 失败原因
+    pub message: Option<String>,
+}
+
+impl EntryFileResult {
+    pub(crate) fn new(local_id: &str, file_path: &str, status: EntryFileStatus, message: Option<String>) -> Self {
+        Self {
+            local_id: local_id.to_string(),
+            file_path: file_path.to_string(),
+            status,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkInsertResult {
+    pub updated_files: Vec<String>,
+    pub skipped_files: Vec<SkippedFile>,
+    /// 未被选中而未处理的文件（区别于因冲突跳过的文件）
+    pub unselected_files: Vec<String>,
+    /// 本地栏目ID冲突及其处理结果（仅批量新增）
+    pub conflicts: Vec<EntryConflict>,
+    /// 修改后内容校验失败而保留原样的文件
+    pub validation_errors: Vec<FileValidationError>,
+    /// 扫描后已被其他人修改、因冲突（conflict）而拒绝写入的文件
+    pub stale_files: Vec<String>,
+    /// 国网栏目ID不符合校验规则的映射
+    pub invalid_entries: Vec<validation::InvalidGwId>,
+    /// 国网栏目ID已被其他本地栏目ID使用的映射（仅批量新增，flag 模式下照常写入）
+    pub gw_id_reuses: Vec<validation::GwIdReuse>,
+    /// 每条映射在每个选中文件中的处理结果（仅批量新增），按文件顺序排列
+    pub entry_results: Vec<EntryFileResult>,
+    /// 按设置中的ID规范规则改写过的输入（仅批量新增）
+    pub normalized_inputs: Vec<NormalizedInput>,
+    pub backup_dir: Option<String>,
+    /// 网络共享上因临时错误而重试的文件操作
+    pub retries: Vec<file_io::IoRetry>,
+    /// 各文件读取、解析、写入的耗时，仅在开启耗时统计（profile）时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<profile::Timings>,
+}
+
+/// 按ID规范规则改写过的一条输入映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizedInput {
+    pub raw_local_id: String,
+    pub raw_gw_id: String,
+    /// 实际写入的本地栏目ID
+    pub local_id: String,
+    /// 实际写入的国网栏目ID
+    pub gw_id: String,
+}
+
+/// 修改后内容校验失败的文件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileValidationError {
+    pub file_path: String,
+    #[serde(flatten)]
+    pub error: JsonSyntaxError,
+}
+
+impl FileValidationError {
+    pub(crate) fn new(file_path: String, error: JsonSyntaxError) -> Self {
+        Self { file_path, error }
+    }
+}
+
+/// 新增映射遇到已存在的本地栏目ID时的处理策略
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// 跳过已存在的映射
+    #[default]
+    Skip,
+    /// 用新的国网栏目ID覆盖已存在的映射
+    Overwrite,
+    /// 存在冲突时取消整批写入
+    Error,
+}
+
+/// 操作类型枚举
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationType {
+    BulkInsert,
+    Import,
+    ImportMerge,
+    BatchDelete,
+    SingleDelete,
+    Dedupe,
+    Sync,
+    Restore,
+    AdbPush,
+    ReplaceGwId,
+    BatchUpdate,
+    Sort,
+    RepackArchive,
+    RemoteApply,
+    Uncomment,
+    PurgeCommented,
+    MigratePrefix,
+    SetRawValue,
+    NormalizeValues,
+    NormalizeIds,
+    RestoreMapping,
+    DeleteByGwId,
+    RemoveSameId,
+    CleanupDuplicates,
+    ClearMappings,
+    RepairExtOptions,
+}
+
+impl OperationType {
+    /// 日志中显示的中文名称
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            OperationType::BulkInsert => "批量新增映射",
+            OperationType::Import => "导入映射（替换模式）",
+            OperationType::ImportMerge => "导入映射（合并模式）",
+            OperationType::BatchDelete => "批量删除映射",
+            OperationType::SingleDelete => "单个删除映射",
+            OperationType::Dedupe => "去除重复映射",
+            OperationType::Sync => "同步映射",
+            OperationType::Restore => "恢复备份",
+            OperationType::AdbPush => "推送到设备",
+            OperationType::ReplaceGwId => "替换国网栏目ID",
+            OperationType::BatchUpdate => "批量修改国网栏目ID",
+            OperationType::Sort => "排序映射",
+            OperationType::RepackArchive => "回写压缩包",
+            OperationType::RemoteApply => "上传到远程目录",
+            OperationType::Uncomment => "取消注释映射",
+            OperationType::PurgeCommented => "清除注释的映射",
+            OperationType::MigratePrefix => "迁移映射前缀",
+            OperationType::SetRawValue => "修改映射值",
+            OperationType::NormalizeValues => "规范映射值",
+            OperationType::NormalizeIds => "规范疑似重复ID",
+            OperationType::RestoreMapping => "从备份恢复映射",
+            OperationType::DeleteByGwId => "按国网栏目ID删除映射",
+            OperationType::RemoveSameId => "清除占位映射",
+            OperationType::CleanupDuplicates => "清理重复映射",
+            OperationType::ClearMappings => "清空映射",
+            OperationType::RepairExtOptions => "修复段落格式",
+        }
+    }
+
+    /// 按语言选择的名称
+    pub(crate) fn label_in(self, locale: Locale) -> &'static str {
+        if locale == Locale::ZhCn {
+            return self.label();
+        }
+        match self {
+            OperationType::BulkInsert => "Bulk insert mappings",
+            OperationType::Import => "Import mappings (replace)",
+            OperationType::ImportMerge => "Import mappings (merge)",
+            OperationType::BatchDelete => "Batch delete mappings",
+            OperationType::SingleDelete => "Delete mapping",
+            OperationType::Dedupe => "Remove duplicate mappings",
+            OperationType::Sync => "Sync mappings",
+            OperationType::Restore => "Restore backup",
+            OperationType::AdbPush => "Push to device",
+            OperationType::ReplaceGwId => "Replace GW ID",
+            OperationType::BatchUpdate => "Batch update GW IDs",
+            OperationType::Sort => "Sort mappings",
+            OperationType::RepackArchive => "Repack archive",
+            OperationType::RemoteApply => "Upload to remote directory",
+            OperationType::Uncomment => "Uncomment mappings",
+            OperationType::PurgeCommented => "Purge commented mappings",
+            OperationType::MigratePrefix => "Migrate mapping prefix",
+            OperationType::SetRawValue => "Edit mapping value",
+            OperationType::NormalizeValues => "Normalize mapping values",
+            OperationType::NormalizeIds => "Normalize suspicious duplicate IDs",
+            OperationType::RestoreMapping => "Restore mapping from backup",
+            OperationType::DeleteByGwId => "Delete mappings by GW ID",
+            OperationType::RemoveSameId => "Remove placeholder mappings",
+            OperationType::CleanupDuplicates => "Clean up duplicate mappings",
+            OperationType::ClearMappings => "Clear all mappings",
+            OperationType::RepairExtOptions => "Repair block formatting",
+        }
+    }
+
+    /// 根据中文或英文名称识别操作类型（用于解析文本日志）
+    pub(crate) fn from_label(label: &str) -> Option<Self> {
+        [
+            OperationType::BulkInsert,
+            OperationType::Import,
+            OperationType::ImportMerge,
+            OperationType::BatchDelete,
+            OperationType::SingleDelete,
+            OperationType::Dedupe,
+            OperationType::Sync,
+            OperationType::Restore,
+            OperationType::AdbPush,
+            OperationType::ReplaceGwId,
+            OperationType::BatchUpdate,
+            OperationType::Sort,
+            OperationType::RepackArchive,
+            OperationType::RemoteApply,
+            OperationType::Uncomment,
+            OperationType::PurgeCommented,
+            OperationType::MigratePrefix,
+            OperationType::SetRawValue,
+            OperationType::NormalizeValues,
+            OperationType::NormalizeIds,
+            OperationType::RestoreMapping,
+            OperationType::DeleteByGwId,
+            OperationType::RemoveSameId,
+            OperationType::CleanupDuplicates,
+            OperationType::ClearMappings,
+            OperationType::RepairExtOptions,
+        ]
+        .into_iter()
+        .find(|op| op.label() == label || op.label_in(Locale::EnUs) == label)
+    }
+}
+
+/// 删除的映射项信息
+///
+/// 段落、前缀和删除方式用于重放操作，旧版本的日志中没有，按默认值（所有段落、映射前缀、直接删除）处理。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedMapping {
+    pub file_path: String,
+    pub local_id: String,
+    pub gw_id: Option<String>,
+    #[serde(default)]
+    pub block: Option<usize>,
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// 是否为注释掉而不是删除
+    #[serde(default)]
+    pub soft: bool,
+}
+
+/// 新增的映射项信息
+///
+/// 模板、附加参数、段落和前缀与新增时的 [`MappingInput`] 相同，用于重放操作；旧版本的日志中没有，按默认值处理。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AddedMapping {
+    pub file_path: String,
+    pub local_id: String,
+    pub gw_id: String,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub params: IntentParams,
+    #[serde(default)]
+    pub block: Option<usize>,
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// 映射值被修改的映射（修改前后的完整映射值）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifiedMapping {
+    pub file_path: String,
+    pub local_id: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// 版本变化信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChange {
+    pub file_path: String,
+    pub old_version: u32,
+    pub new_version: u32,
+}
+
+/// 因文件被跳过而未处理的映射
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedMapping {
+    pub file_path: String,
+    pub local_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingInput {
+    pub local_id: String,
+    pub gw_id: String,
+    /// 需要写入的附加参数，未设置的参数保持模板或原值不变
+    #[serde(flatten)]
+    pub params: IntentParams,
+    /// 使用的值模板名称（见设置中的 valueTemplates），None 表示默认模板
+    #[serde(default)]
+    pub template: Option<String>,
+    /// 写入的 sExtOptions 段落序号，None 表示文件中的每个段落
+    #[serde(default)]
+    pub block: Option<usize>,
+    /// 映射 key 的前缀，None 表示设置中的映射前缀
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
@@ -0,0 +1,199 @@
+use regex::Regex;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    editor, file_io,
+    settings::{AppSettings, GwIdValidationMode},
+    MappingEntry,
+};
+
+/// 国网栏目ID不符合校验规则的映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidGwId {
+    pub local_id: String,
+    pub gw_id: String,
+    pub reason: String,
+    /// 是否因此被拒绝写入（flag 模式下仍会写入）
+    pub rejected: bool,
+}
+
+/// 按设置中的正则校验国网栏目ID
+pub struct GwIdValidator {
+    pattern: String,
+    regex: Regex,
+    mode: GwIdValidationMode,
+}
+
+impl GwIdValidator {
+    /// 根据设置创建校验器，未配置校验规则时返回 None
+    pub fn from_settings(settings: &AppSettings) -> Result<Option<Self>, String> {
+        let pattern = match settings.gw_id_pattern.as_deref().map(str::trim) {
+            Some(pattern) if !pattern.is_empty() => pattern,
+            _ => return Ok(None),
+        };
+        // 要求完整匹配，避免 \d{10} 匹配到更长ID中的一段
+        let regex = Regex::new(&format!("^(?:{})$", pattern))
+            .map_err(|e| format!("国网栏目ID校验规则不是有效的正则表达式: {}", e))?;
+        Ok(Some(Self {
+            pattern: pattern.to_string(),
+            regex,
+            mode: settings.gw_id_validation,
+        }))
+    }
+
+    /// 是否拒绝写入不符合规则的映射
+    pub fn rejects(&self) -> bool {
+        self.mode == GwIdValidationMode::Reject
+    }
+
+    /// 校验单条映射，符合规则时返回 None
+    pub fn check(&self, local_id: &str, gw_id: &str) -> Option<InvalidGwId> {
+        if self.regex.is_match(gw_id) {
+            return None;
+        }
+        Some(InvalidGwId {
+            local_id: local_id.to_string(),
+            gw_id: gw_id.to_string(),
+            reason: format!("国网栏目ID不符合校验规则 {}", self.pattern),
+            rejected: self.rejects(),
+        })
+    }
+}
+
+/// 按设置校验一批映射（本地栏目ID, 国网栏目ID），返回不符合规则的映射
+pub fn check_entries<'a>(
+    settings: &AppSettings,
+    entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<Vec<InvalidGwId>, String> {
+    let Some(validator) = GwIdValidator::from_settings(settings)? else {
+        return Ok(Vec::new());
+    };
+    Ok(entries
+        .into_iter()
+        .filter_map(|(local_id, gw_id)| validator.check(local_id, gw_id))
+        .collect())
+}
+
+/// 国网栏目ID已被其他本地栏目ID使用的映射
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GwIdReuse {
+    pub local_id: String,
+    pub gw_id: String,
+    /// 已使用该国网栏目ID的其他映射
+    pub used_by: Vec<GwIdUse>,
+    /// 是否因此取消写入（flag 模式下仍会写入）
+    pub rejected: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GwIdUse {
+    /// 所在文件，None 表示本次提交的其他映射
+    pub file_path: Option<String>,
+    pub local_id: String,
+}
+
+/// 按设置检查一批映射（本地栏目ID, 国网栏目ID）的国网栏目ID是否已被其他本地栏目ID使用，
+/// 检查范围为目录中的所有 theme 文件和本批中的其他映射；未开启检查时不读取文件
+pub fn check_gw_id_reuse(
+    settings: &AppSettings,
+    files: &[PathBuf],
+    entries: &[(&str, &str)],
+) -> Result<Vec<GwIdReuse>, String> {
+    let Some(mode) = settings.gw_id_reuse_check else {
+        return Ok(Vec::new());
+    };
+    let mut scanned = Vec::new();
+    for file in files {
+        let (raw, _) = file_io::read_text(file).map_err(|e| format!("读取文件失败: {}", e))?;
+        let mappings = editor::parse_mappings(&raw, &settings.prefixes())?;
+        scanned.push((file.to_string_lossy().into_owned(), mappings));
+    }
+    Ok(find_gw_id_reuses(&scanned, entries, mode == GwIdValidationMode::Reject))
+}
+
+/// 只统计会被设备读取的条目；本地栏目ID与国网栏目ID相同的占位条目不算占用
+fn find_gw_id_reuses(
+    scanned: &[(String, Vec<MappingEntry>)],
+    entries: &[(&str, &str)],
+    rejected: bool,
+) -> Vec<GwIdReuse> {
+    let mut users: BTreeMap<&str, Vec<GwIdUse>> = BTreeMap::new();
+    for (file_path, mappings) in scanned {
+        for entry in mappings.iter().filter(|entry| entry.is_active() && !entry.same_id) {
+            if let Some(gw_id) = entry.gw_id.as_deref() {
+                let uses = users.entry(gw_id).or_default();
+                let used = GwIdUse {
+                    file_path: Some(file_path.clone()),
+                    local_id: entry.local_id.clone(),
+                };
+                if !uses.contains(&used) {
+                    uses.push(used);
+                }
+            }
+        }
+    }
+    entries
+        .iter()
+        .filter_map(|&(local_id, gw_id)| {
+            let in_batch = entries
+                .iter()
+                .filter(|(other, other_gw)| *other != local_id && *other_gw == gw_id)
+                .map(|(other, _)| GwIdUse {
+                    file_path: None,
+                    local_id: other.to_string(),
+                });
+            let used_by: Vec<GwIdUse> = users
+                .get(gw_id)
+                .into_iter()
+                .flatten()
+                .filter(|used| used.local_id != local_id)
+                .cloned()
+                .chain(in_batch)
+                .collect();
+            (!used_by.is_empty()).then(|| GwIdReuse {
+                local_id: local_id.to_string(),
+                gw_id: gw_id.to_string(),
+                used_by,
+                rejected,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_gw_ids_used_by_other_local_ids() {
+        let theme = "{\n  \"sExtOptions\": {\n    \"portal_frag_101\": \"intent://?es_tabId=9001\",\n    \"portal_frag_9002\": \"intent://?es_tabId=9002\"\n  }\n}\n";
+        let scanned = vec![(
+            "theme1.json".to_string(),
+            editor::parse_mappings(theme, &[crate::settings::DEFAULT_PORTAL_PREFIX]).unwrap(),
+        )];
+        let reuses = find_gw_id_reuses(
+            &scanned,
+            &[("102", "9001"), ("103", "9002"), ("104", "9003"), ("105", "9003")],
+            true,
+        );
+        let found: Vec<(&str, Vec<&str>)> = reuses
+            .iter()
+            .map(|reuse| {
+                (
+                    reuse.local_id.as_str(),
+                    reuse.used_by.iter().map(|used| used.local_id.as_str()).collect(),
+                )
+            })
+            .collect();
+        // 9002 只被占位条目使用，不算重复；104、105 在本批中互相重复
+        assert_eq!(
+            found,
+            [("102", vec!["101"]), ("104", vec!["105"]), ("105", vec!["104"])]
+        );
+        assert!(reuses[0].used_by[0].file_path.is_some() && reuses[0].rejected);
+    }
+}
@@ -0,0 +1,234 @@
+//! 操作完成后向运维群机器人（企业微信、钉钉）的 webhook 推送摘要
+//!
+//! 两种机器人的文本消息格式相同：`{"msgtype": "text", "text": {"content": "..."}}`，
+//! 消息中另附结构化的 `operation` 字段，供自建的接收服务解析。消息正文使用设置中的语言。
+//! 推送在后台线程中进行（见 [`crate::background`]），失败不影响操作本身。
+
+use chrono::Local;
+use serde::Serialize;
+use serde_json::json;
+use std::{path::Path, time::Duration};
+
+use crate::{
+    background, cancel::CANCELLED_MESSAGE, i18n::Locale, settings::AppSettings, snapshot::DriftReport,
+    OperationLogRecord, OperationType,
+};
+
+/// 推送的超时时间
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 推送的操作摘要
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationSummary {
+    pub success: bool,
+    pub operation_type: OperationType,
+    pub operation_label: String,
+    pub target_dir: String,
+    pub operator: String,
+    pub updated_files: usize,
+    pub skipped_files: usize,
+    pub added: usize,
+    pub deleted: usize,
+    pub backup_dir: Option<String>,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 操作时间（RFC 3339）
+    pub timestamp: String,
+}
+
+impl OperationSummary {
+    /// 由操作日志生成成功摘要；目录取自第一个处理的文件，没有文件时用日志目录
+    pub fn from_record(record: &OperationLogRecord, log_dir: &Path) -> Self {
+        let target_dir = record
+            .updated_files
+            .iter()
+            .chain(record.skipped_files.iter().map(|skipped| &skipped.file_path))
+            .find_map(|file| Path::new(file).parent())
+            .unwrap_or(log_dir)
+            .to_string_lossy()
+            .into_owned();
+        Self {
+            success: true,
+            operation_type: record.operation_type,
+            operation_label: record.operation_label.clone(),
+            target_dir,
+            operator: record.operator.clone(),
+            updated_files: record.updated_files.len(),
+            skipped_files: record.skipped_files.len(),
+            added: record.added.len(),
+            deleted: record.deleted.len(),
+            backup_dir: record.backup_dir.clone(),
+            error: None,
+            timestamp: record.timestamp.clone(),
+        }
+    }
+
+    pub fn failure(operation_type: OperationType, target_dir: &str, error: &str, locale: Locale) -> Self {
+        Self {
+            success: false,
+            operation_type,
+            operation_label: operation_type.label_in(locale).to_string(),
+            target_dir: target_dir.to_string(),
+            operator: crate::current_operator(),
+            updated_files: 0,
+            skipped_files: 0,
+            added: 0,
+            deleted: 0,
+            backup_dir: None,
+            error: Some(error.to_string()),
+            timestamp: Local::now().to_rfc3339(),
+        }
+    }
+
+    /// 群消息正文
+    pub fn text(&self, locale: Locale) -> String {
+        let status = match (self.success, locale) {
+            (true, Locale::ZhCn) => "完成",
+            (false, Locale::ZhCn) => "失败",
+            (true, Locale::EnUs) => " succeeded",
+            (false, Locale::EnUs) => " failed",
+        };
+        let mut lines = vec![
+            format!("{}{}{}", locale.pick("【栏目映射】", "[CategoryMap] "), self.operation_label, status),
+            format!("{}: {}", locale.pick("目录", "Directory"), self.target_dir),
+            format!("{}: {}", locale.pick("操作人", "Operator"), self.operator),
+        ];
+        match &self.error {
+            Some(error) => lines.push(format!("{}: {}", locale.pick("失败原因", "Error"), error)),
+            None => {
+                lines.push(match locale {
+                    Locale::ZhCn => format!("修改 {} 个文件，跳过 {} 个文件", self.updated_files, self.skipped_files),
+                    Locale::EnUs => format!("{} files updated, {} skipped", self.updated_files, self.skipped_files),
+                });
+                if self.added > 0 || self.deleted > 0 {
+                    lines.push(match locale {
+                        Locale::ZhCn => format!("新增 {} 条映射，删除 {} 条映射", self.added, self.deleted),
+                        Locale::EnUs => format!("{} mappings added, {} deleted", self.added, self.deleted),
+                    });
+                }
+                lines.push(format!(
+                    "{}: {}",
+                    locale.pick("备份", "Backup"),
+                    self.backup_dir.as_deref().unwrap_or(locale.pick("无", "none"))
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// 向 webhook 地址 POST 摘要（在当前线程中等待结果）
+pub fn send(url: &str, summary: &OperationSummary, locale: Locale) -> Result<(), String> {
+    post(
+        url,
+        json!({
+            "msgtype": "text",
+            "text": { "content": summary.text(locale) },
+            "operation": summary,
+        }),
+    )
+}
+
+fn post(url: &str, body: serde_json::Value) -> Result<(), String> {
+    ureq::post(url)
+        .timeout(SEND_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map(|_| ())
+        .map_err(|e| format!("推送 webhook 通知失败: {}", e))
+}
+
+fn webhook_url(settings: &AppSettings) -> Option<&str> {
+    settings.webhook_url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+}
+
+/// 设置了 webhook 地址时在后台推送摘要，失败只打印错误
+pub fn notify(settings: &AppSettings, summary: OperationSummary) {
+    let Some(url) = webhook_url(settings).map(str::to_string) else {
+        return;
+    };
+    let locale = Locale::of(settings);
+    background::spawn(move || {
+        if let Err(e) = send(&url, &summary, locale) {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+/// 操作失败时推送通知；用户主动取消的操作不推送
+pub fn notify_failure(settings: &AppSettings, operation_type: OperationType, target_dir: &str, error: &str) {
+    if error == CANCELLED_MESSAGE {
+        return;
+    }
+    notify(
+        settings,
+        OperationSummary::failure(operation_type, target_dir, error, Locale::of(settings)),
+    );
+}
+
+/// 定时审计发现目录被工具之外修改时在后台推送提醒，消息中另附结构化的 `drift` 字段
+pub fn notify_drift(settings: &AppSettings, report: &DriftReport) {
+    let Some(url) = webhook_url(settings).map(str::to_string) else {
+        return;
+    };
+    let locale = Locale::of(settings);
+    let mut lines = vec![
+        locale
+            .pick("【栏目映射】定时审计发现工具之外的修改", "[CategoryMap] Scheduled audit found changes made outside the tool")
+            .to_string(),
+        format!("{}: {}", locale.pick("目录", "Directory"), report.target_dir),
+        format!(
+            "{}: {} {}",
+            locale.pick("基准快照", "Baseline snapshot"),
+            report.baseline.id,
+            report.baseline.label
+        ),
+        match locale {
+            Locale::ZhCn => format!("{} 个文件、{} 条映射有变化", report.files.len(), report.changed_mappings),
+            Locale::EnUs => format!("{} files, {} mappings changed", report.files.len(), report.changed_mappings),
+        },
+    ];
+    lines.extend(report.files.iter().map(|file| format!("- {}", file.file_name)));
+    let body = json!({
+        "msgtype": "text",
+        "text": { "content": lines.join("\n") },
+        "drift": report,
+    });
+    background::spawn(move || {
+        if let Err(e) = post(&url, body) {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_message_in_the_configured_locale() {
+        let failure = OperationSummary::failure(OperationType::Dedupe, "/srv/theme", "写入文件失败", Locale::EnUs);
+        assert_eq!(failure.operation_label, "Remove duplicate mappings");
+        let text = failure.text(Locale::EnUs);
+        assert!(text.starts_with("[CategoryMap] Remove duplicate mappings failed\nDirectory: /srv/theme\n"));
+        assert!(text.ends_with("\nError: 写入文件失败"));
+
+        let success = OperationSummary {
+            success: true,
+            operation_label: "去除重复映射".to_string(),
+            updated_files: 2,
+            deleted: 3,
+            error: None,
+            ..failure
+        };
+        let text = success.text(Locale::ZhCn);
+        assert!(text.starts_with("【栏目映射】去除重复映射完成\n目录: /srv/theme\n"));
+        assert!(text.ends_with("修改 2 个文件，跳过 0 个文件\n新增 0 条映射，删除 3 条映射\n备份: 无"));
+        assert!(webhook_url(&AppSettings {
+            webhook_url: Some("  ".to_string()),
+            ..AppSettings::default()
+        })
+        .is_none());
+    }
+}
@@ -0,0 +1,194 @@
+//! 工作区：把多个根目录（如按设备型号分开的部署目录）登记为一组，一次扫描全部根目录，
+//! 或对选中的部分根目录执行同一个批量操作。工作区保存在配置目录下的 workspaces.json。
+
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{file_io, ignored_theme_files, scan_directory, settings::AppSettings, Error, FileMapping, ScanResult};
+
+const WORKSPACES_FILE_NAME: &str = "workspaces.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub name: String,
+    pub roots: Vec<WorkspaceRoot>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceRoot {
+    /// 根目录在工作区中的名称，如设备型号
+    pub name: String,
+    pub dir: String,
+}
+
+/// 在一个根目录上执行操作的结果，失败不影响其他根目录
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootOutcome<T> {
+    pub root: String,
+    pub dir: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+impl Workspace {
+    /// 按名称选出根目录，None 表示全部根目录
+    pub fn select(&self, roots: Option<&[String]>) -> Result<Vec<&WorkspaceRoot>, String> {
+        let Some(names) = roots else {
+            return Ok(self.roots.iter().collect());
+        };
+        let selected: Vec<&WorkspaceRoot> = names
+            .iter()
+            .map(|name| {
+                self.roots
+                    .iter()
+                    .find(|root| root.name == *name)
+                    .ok_or_else(|| format!("工作区 {} 中没有根目录 {}", self.name, name))
+            })
+            .collect::<Result<_, String>>()?;
+        if selected.is_empty() {
+            return Err("请至少选择一个根目录".into());
+        }
+        Ok(selected)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("工作区名称不能为空".into());
+        }
+        if self.roots.is_empty() {
+            return Err("工作区至少需要一个根目录".into());
+        }
+        for (idx, root) in self.roots.iter().enumerate() {
+            if root.name.trim().is_empty() {
+                return Err("根目录名称不能为空".into());
+            }
+            if self.roots[..idx].iter().any(|other| other.name == root.name) {
+                return Err(format!("根目录名称重复: {}", root.name));
+            }
+            if !Path::new(&root.dir).is_dir() {
+                return Err(format!("目录不存在: {}", root.dir));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 依次在选中的根目录上执行操作，收集每个根目录的结果
+pub fn run_on_roots<T>(
+    roots: &[&WorkspaceRoot],
+    mut op: impl FnMut(&WorkspaceRoot) -> Result<T, Error>,
+) -> Vec<RootOutcome<T>> {
+    roots
+        .iter()
+        .map(|root| {
+            let (result, error) = match op(root) {
+                Ok(result) => (Some(result), None),
+                Err(e) => (None, Some(e)),
+            };
+            RootOutcome {
+                root: root.name.clone(),
+                dir: root.dir.clone(),
+                result,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// 扫描选中的根目录，结果按根目录分组；settings_for 返回扫描某个根目录时使用的设置，
+/// annotate 用于为每个根目录的结果标注栏目名称等信息
+pub fn scan(
+    roots: &[&WorkspaceRoot],
+    settings_for: &dyn Fn(&str) -> Result<AppSettings, String>,
+    annotate: &dyn Fn(&mut [FileMapping]),
+) -> Vec<RootOutcome<ScanResult>> {
+    run_on_roots(roots, |root| {
+        let retries = file_io::RetryScope::begin();
+        let settings = settings_for(&root.dir)?;
+        let dir = Path::new(&root.dir);
+        let mut files = scan_directory(dir, &settings)?;
+        annotate(&mut files);
+        Ok(ScanResult {
+            files,
+            ignored_files: ignored_theme_files(dir)?,
+            retries: retries.finish(),
+            timings: None,
+        })
+    })
+}
+
+pub fn load_workspaces(config_dir: &Path) -> Result<Vec<Workspace>, String> {
+    let path = config_dir.join(WORKSPACES_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取工作区失败: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("工作区文件格式错误: {}", e))
+}
+
+pub fn find_workspace(config_dir: &Path, name: &str) -> Result<Workspace, String> {
+    load_workspaces(config_dir)?
+        .into_iter()
+        .find(|workspace| workspace.name == name)
+        .ok_or_else(|| format!("工作区不存在: {}", name))
+}
+
+/// 保存（新增或覆盖同名）工作区，返回所有工作区
+pub fn save_workspace(config_dir: &Path, workspace: Workspace) -> Result<Vec<Workspace>, String> {
+    workspace.validate()?;
+    let mut workspaces = load_workspaces(config_dir)?;
+    match workspaces.iter_mut().find(|existing| existing.name == workspace.name) {
+        Some(existing) => *existing = workspace,
+        None => workspaces.push(workspace),
+    }
+    write_workspaces(config_dir, &workspaces)?;
+    Ok(workspaces)
+}
+
+pub fn delete_workspace(config_dir: &Path, name: &str) -> Result<Vec<Workspace>, String> {
+    let mut workspaces = load_workspaces(config_dir)?;
+    let count = workspaces.len();
+    workspaces.retain(|workspace| workspace.name != name);
+    if workspaces.len() == count {
+        return Err(format!("工作区不存在: {}", name));
+    }
+    write_workspaces(config_dir, &workspaces)?;
+    Ok(workspaces)
+}
+
+fn write_workspaces(config_dir: &Path, workspaces: &[Workspace]) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    let content = serde_json::to_string_pretty(workspaces).map_err(|e| e.to_string())?;
+    fs::write(config_dir.join(WORKSPACES_FILE_NAME), content).map_err(|e| format!("写入工作区失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_roots_by_name() {
+        let root = |name: &str| WorkspaceRoot {
+            name: name.to_string(),
+            dir: format!("/deploy/{}", name),
+        };
+        let workspace = Workspace {
+            name: "部署".to_string(),
+            roots: vec![root("A"), root("B"), root("C")],
+        };
+        assert_eq!(workspace.select(None).unwrap().len(), 3);
+        let selected = workspace.select(Some(&["C".to_string(), "A".to_string()])).unwrap();
+        let names: Vec<&str> = selected.iter().map(|root| root.name.as_str()).collect();
+        assert_eq!(names, ["C", "A"]);
+        assert!(workspace.select(Some(&["D".to_string()])).is_err());
+        assert!(workspace.select(Some(&[])).is_err());
+
+        let outcomes = run_on_roots(&selected, |root| if root.name == "A" { Err("失败".into()) } else { Ok(1) });
+        assert_eq!((outcomes[0].result, outcomes[1].error.clone()), (Some(1), Some(Error::from("失败"))));
+    }
+}
@@ -0,0 +1,60 @@
+use categorymap_core::cancel::CancelToken;
+use std::{collections::HashMap, sync::Mutex};
+use tauri::Manager;
+
+/// 正在进行中的可取消操作，由 Tauri 托管为全局状态
+#[derive(Debug, Default)]
+pub struct CancelRegistry {
+    tokens: Mutex<HashMap<String, CancelToken>>,
+}
+
+impl CancelRegistry {
+    /// 取消指定操作，操作不存在（或已结束）时返回 false
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.tokens.lock().unwrap().get(operation_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 操作期间持有的登记，结束（drop）时自动注销
+pub struct OperationGuard {
+    app: tauri::AppHandle,
+    operation_id: Option<String>,
+    token: CancelToken,
+}
+
+impl OperationGuard {
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Some(id) = &self.operation_id {
+            self.app.state::<CancelRegistry>().tokens.lock().unwrap().remove(id);
+        }
+    }
+}
+
+/// 登记一个可取消的操作；未提供操作ID时返回永不取消的标记
+pub fn start(app: &tauri::AppHandle, operation_id: Option<String>) -> OperationGuard {
+    let token = CancelToken::default();
+    if let Some(id) = &operation_id {
+        app.state::<CancelRegistry>()
+            .tokens
+            .lock()
+            .unwrap()
+            .insert(id.clone(), token.clone());
+    }
+    OperationGuard {
+        app: app.clone(),
+        operation_id,
+        token,
+    }
+}
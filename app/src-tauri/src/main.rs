@@ -1,1398 +1,1815 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use chrono::Local;
-use serde::{Deserialize, Serialize};
+use categorymap_core::{
+    adb, backup, catalog, collect_theme_files, devices, duplicates, editor, export, file_io, finish_operation,
+    generate, git, history,
+    i18n::{self, CommandError},
+    ignore, ignored_theme_files, paging, parse_cache, preflight, profile, remote, report, run_bulk_insert, run_import,
+    same_id, scan_directory, scan_directory_with_progress, search, settings, snapshot, stats, webdav, webhook,
+    workspace,
+    ArchiveWorkspace, BackupDiffResult, BackupResult, BatchUpdateResult, BulkInsertResult, ClearMappingsResult,
+    CommentedMappingsResult, ConflictStrategy, ConsistencyReport, DedupeResult, DeleteMappingRequest,
+    DirectoryDiffResult, Error, ExportResult, FileInfo, FileMapping, FileValidation, ImportMode, ImportResult,
+    ImportSource, KeepPolicy, LintReport, MappingInput, MappingUpdate, MasterValidationResult, MigratePrefixResult,
+    NormalizeIdsResult, NormalizeValuesResult, OperationLog, OperationType, PruneBackupsResult, RemoteApplyResult,
+    RemoteScanResult, RemoveMappingsResult, RepackResult, RepairResult, ReplaceGwIdResult, RestoreResult, ScanResult,
+    SearchResult, SkippedFile, SortOrder, SortResult, SyncResult, TargetReadiness,
+};
+use serde::Serialize;
 use std::{
-    collections::HashSet,
     fs,
-    io::Write,
     path::{Path, PathBuf},
     process::Command,
 };
-use walkdir::WalkDir;
 
-const PORTAL_PREFIX: &str = "portal_frag_";
-const TEMPLATE_VALUE: &str =
-    "com.ipanel.join.gw_ui_sdk.GwPortalFragment|intent://?es_tabId={id}&es_title=&es_focusStartColor=&es_focusEndColor=&es_focusImg=";
+mod cancel_registry;
+mod progress;
+mod scheduler;
+mod watcher;
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct MappingEntry {
-    local_id: String,
-    gw_id: Option<String>,
-    raw_value: String,
-    same_id: bool,
-    /// 状态：normal-正常, duplicate_local-本地ID重复, duplicate_gw-国网ID重复
-    status: String,
-}
+use settings::AppSettings;
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct FileMapping {
-    file_path: String,
-    mappings: Vec<MappingEntry>,
+/// 获取应用配置目录
+fn config_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path_resolver()
+        .app_config_dir()
+        .ok_or_else(|| "无法获取应用配置目录".to_string())
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ScanResult {
-    files: Vec<FileMapping>,
+/// 读取当前生效的应用设置
+fn current_settings(app: &tauri::AppHandle) -> Result<AppSettings, String> {
+    settings::load_settings(&config_dir(app)?)
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct BackupResult {
-    backup_dir: String,
+/// 操作 path（目录或其中的文件）时使用的设置：目录记住了设置配置时使用该配置，否则为当前设置
+fn dir_settings(app: &tauri::AppHandle, path: &str) -> Result<AppSettings, String> {
+    let config_dir = config_dir(app)?;
+    match settings::dir_profile(&config_dir, path)? {
+        Some(settings) => Ok(settings),
+        None => settings::load_settings(&config_dir),
+    }
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct SkippedFile {
-    file_path: String,
-    reason: String,
-    duplicate_ids: Vec<String>,
+/// 前端未指定时按设置决定是否自动递增版本号
+fn version_bump(settings: &AppSettings, requested: Option<bool>) -> bool {
+    requested.unwrap_or(settings.auto_increment_version)
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct BulkInsertResult {
-    updated_files: Vec<String>,
-    skipped_files: Vec<SkippedFile>,
-    backup_dir: Option<String>,
+/// 批量操作失败时推送 webhook 通知，原样返回错误
+fn report_failure(settings: &AppSettings, operation_type: OperationType, target_dir: &str, error: Error) -> Error {
+    webhook::notify_failure(settings, operation_type, target_dir, &error.to_string());
+    error
 }
 
-/// 操作类型枚举
-enum OperationType {
-    BulkInsert,
-    Import,
-    BatchDelete,
-    SingleDelete,
+/// 命令错误使用的语言：当前设置的语言，读取设置失败时为默认语言
+fn command_locale(app: &tauri::AppHandle) -> i18n::Locale {
+    current_settings(app).map(|settings| i18n::Locale::of(&settings)).unwrap_or_default()
 }
 
-/// 删除的映射项信息
-struct DeletedMapping {
-    file_path: String,
-    local_id: String,
-    gw_id: Option<String>,
+/// 执行命令，错误按当前设置的语言包装为结构化错误
+fn localized<T>(app: &tauri::AppHandle, command: impl FnOnce() -> Result<T, Error>) -> Result<T, CommandError> {
+    command().map_err(|error| CommandError::new(error, command_locale(app)))
 }
 
-/// 新增的映射项信息
-struct AddedMapping {
-    file_path: String,
-    local_id: String,
-    gw_id: String,
+/// 在后台线程执行耗时的命令，错误（包括线程异常）按当前设置的语言包装为结构化错误
+async fn run_blocking<T: Send + 'static>(
+    app: tauri::AppHandle,
+    task: impl FnOnce(&tauri::AppHandle) -> Result<T, Error> + Send + 'static,
+) -> Result<T, CommandError> {
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || task(&handle)).await;
+    localized(&app, || result.map_err(|err| err.to_string())?)
 }
 
-/// 版本变化信息
-#[derive(Debug)]
-struct VersionChange {
-    file_path: String,
-    old_version: u32,
-    new_version: u32,
-}
-
-/// 写入操作日志
-fn write_operation_log(
-    target_dir: &Path,
-    operation_type: OperationType,
-    updated_files: &[String],
-    skipped_files: &[SkippedFile],
-    backup_dir: Option<&String>,
-    additional_info: Option<&str>,
-    deleted_mappings: Option<&[DeletedMapping]>,
-    added_mappings: Option<&[AddedMapping]>,
-    version_changes: Option<&[VersionChange]>,
-) -> Result<(), String> {
-    let timestamp = Local::now();
-    let log_filename = format!("operation_{}.log", timestamp.format("%Y%m%d-%H%M%S"));
-    let log_path = target_dir.join(&log_filename);
-
-    let mut log_content = String::new();
-    
-    // 写入日志头
-    let separator = "=".repeat(80);
-    log_content.push_str(&separator);
-    log_content.push_str(&format!("\n操作日志 - {}\n", timestamp.format("%Y-%m-%d %H:%M:%S")));
-    log_content.push_str(&format!("{}\n", separator));
-    
-    // 操作类型
-    let op_type_str = match operation_type {
-        OperationType::BulkInsert => "批量新增映射",
-        OperationType::Import => "导入映射（替换模式）",
-        OperationType::BatchDelete => "批量删除映射",
-        OperationType::SingleDelete => "单个删除映射",
-    };
-    log_content.push_str(&format!("\n操作类型: {}\n", op_type_str));
-    
-    // 备份路径
-    if let Some(backup_path) = backup_dir {
-        log_content.push_str(&format!("备份路径: {}\n", backup_path));
-    } else {
-        log_content.push_str("备份路径: 无（未创建备份）\n");
-    }
-    
-    // 附加信息
-    if let Some(info) = additional_info {
-        log_content.push_str(&format!("附加信息: {}\n", info));
-    }
-    
-    // 版本变化信息
-    if let Some(changes) = version_changes {
-        if !changes.is_empty() {
-            log_content.push_str(&format!("版本变化: {} 个文件\n", changes.len()));
-            for change in changes {
-                log_content.push_str(&format!("  {} : {} → {}\n", 
-                    change.file_path, change.old_version, change.new_version));
-            }
-        }
-    }
-    
-    // 新增的映射详情（仅对新增操作）
-    if let Some(added) = added_mappings {
-        if !added.is_empty() {
-            log_content.push_str(&format!("\n新增的映射详情 ({} 条):\n", added.len()));
-            // 按文件路径分组显示
-            let mut file_groups: std::collections::HashMap<String, Vec<&AddedMapping>> = std::collections::HashMap::new();
-            for mapping in added {
-                file_groups.entry(mapping.file_path.clone()).or_insert_with(Vec::new).push(mapping);
-            }
-            
-            for (file_path, mappings) in file_groups {
-                log_content.push_str(&format!("  {}\n", file_path));
-                for mapping in mappings {
-                    log_content.push_str(&format!("    - 本地栏目ID: {} | 国网栏目ID: {}\n", mapping.local_id, mapping.gw_id));
-                }
-            }
-        }
-    }
-    
-    // 删除的映射详情（仅对删除操作）
-    if let Some(deleted) = deleted_mappings {
-        if !deleted.is_empty() {
-            log_content.push_str(&format!("\n删除的映射详情 ({} 条):\n", deleted.len()));
-            // 按文件路径分组显示
-            let mut file_groups: std::collections::HashMap<String, Vec<&DeletedMapping>> = std::collections::HashMap::new();
-            for mapping in deleted {
-                file_groups.entry(mapping.file_path.clone()).or_insert_with(Vec::new).push(mapping);
-            }
-            
-            for (file_path, mappings) in file_groups {
-                log_content.push_str(&format!("  {}\n", file_path));
-                for mapping in mappings {
-                    if let Some(gw_id) = &mapping.gw_id {
-                        log_content.push_str(&format!("    - 本地栏目ID: {} | 国网栏目ID: {}\n", mapping.local_id, gw_id));
-                    } else {
-                        log_content.push_str(&format!("    - 本地栏目ID: {} | 国网栏目ID: (无)\n", mapping.local_id));
-                    }
-                }
-            }
-        }
-    }
-    
-    // 成功处理的文件
-    log_content.push_str(&format!("\n成功处理的文件 ({} 个):\n", updated_files.len()));
-    if updated_files.is_empty() {
-        log_content.push_str("  无\n");
-    } else {
-        for file in updated_files {
-            log_content.push_str(&format!("  - {}\n", file));
-        }
-    }
-    
-    // 跳过的文件
-    if !skipped_files.is_empty() {
-        log_content.push_str(&format!("\n跳过的文件 ({} 个):\n", skipped_files.len()));
-        for skipped in skipped_files {
-            log_content.push_str(&format!("  - {}\n", skipped.file_path));
-            log_content.push_str(&format!("    原因: {}\n", skipped.reason));
-            if !skipped.duplicate_ids.is_empty() {
-                log_content.push_str(&format!("    相关ID: {}\n", skipped.duplicate_ids.join("、")));
-            }
-        }
-    }
-    
-    // 日志尾
-    let separator = "=".repeat(80);
-    log_content.push_str(&format!("\n{}\n", separator));
-    log_content.push_str("\n");
-    
-    // 写入文件
-    let mut file = fs::File::create(&log_path).map_err(|e| format!("创建日志文件失败: {}", e))?;
-    file.write_all(log_content.as_bytes())
-        .map_err(|e| format!("写入日志文件失败: {}", e))?;
-    
-    Ok(())
-}
-
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct MappingInput {
-    local_id: String,
-    gw_id: String,
+#[tauri::command]
+fn get_settings(app: tauri::AppHandle) -> Result<AppSettings, CommandError> {
+    localized(&app, || Ok(current_settings(&app)?))
 }
 
 #[tauri::command]
-fn scan_theme_files(target_dir: String) -> Result<ScanResult, String> {
-    let dir = PathBuf::from(&target_dir);
-    let files = collect_theme_files(&dir)?;
-
-    let mut results = Vec::with_capacity(files.len());
-    for file in files {
-        let raw = fs::read_to_string(&file).map_err(|err| err.to_string())?;
-        let mappings = parse_mappings(&raw)?;
-        results.push(FileMapping {
-            file_path: file.to_string_lossy().into_owned(),
-            mappings,
-        });
-    }
+fn set_settings(app: tauri::AppHandle, settings: AppSettings) -> Result<AppSettings, CommandError> {
+    localized(&app, || {
+        settings::save_settings(&config_dir(&app)?, &settings)?;
+        Ok(settings)
+    })
+}
 
-    Ok(ScanResult { files: results })
+/// 清空扫描的解析缓存（内存和设置中的磁盘缓存目录），返回删除的磁盘缓存文件数
+#[tauri::command]
+fn clear_parse_cache(app: tauri::AppHandle) -> Result<usize, CommandError> {
+    localized(&app, || Ok(parse_cache::clear(&current_settings(&app)?)?))
 }
 
+/// 从 HTTP 接口拉取国网栏目目录并缓存到本地，之后的扫描结果会标注栏目名称
+///
+/// 未指定 url 时使用设置中的栏目目录地址。
 #[tauri::command]
-fn backup_theme_files(target_dir: String) -> Result<BackupResult, String> {
-    let dir = PathBuf::from(&target_dir);
-    let files = collect_theme_files(&dir)?;
+async fn fetch_gw_catalog(app: tauri::AppHandle, url: Option<String>) -> Result<catalog::CatalogSummary, CommandError> {
+    run_blocking(app, move |app| {
+        let url = match url.filter(|url| !url.trim().is_empty()) {
+            Some(url) => url,
+            None => current_settings(app)?
+                .gw_catalog_url
+                .ok_or_else(|| "未配置栏目目录地址".to_string())?,
+        };
+        let catalog = catalog::fetch(url.trim())?;
+        catalog::save_cache(&config_dir(app)?, &catalog)?;
+        Ok(catalog.summary())
+    })
+    .await
+}
 
-    if files.is_empty() {
-        return Err("当前目录下未找到 theme*.json 文件".into());
-    }
+/// 加载本地栏目目录（CSV 或 JSON）并缓存，之后的扫描结果会标注本地栏目名称
+#[tauri::command]
+fn load_local_catalog(app: tauri::AppHandle, path: String) -> Result<catalog::LocalCatalogSummary, CommandError> {
+    localized(&app, || {
+        let catalog = catalog::load_local(Path::new(&path))?;
+        catalog::save_local_cache(&config_dir(&app)?, &catalog)?;
+        Ok(catalog.summary())
+    })
+}
 
-    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-    let backup_dir = dir.join("backups").join(timestamp);
-    fs::create_dir_all(&backup_dir).map_err(|err| err.to_string())?;
+#[tauri::command]
+fn get_recent_dirs(app: tauri::AppHandle) -> Result<Vec<String>, CommandError> {
+    localized(&app, || Ok(settings::load_recent_dirs(&config_dir(&app)?)?))
+}
 
-    for file in files {
-        if let Some(name) = file.file_name() {
-            let target = backup_dir.join(name);
-            fs::copy(&file, target).map_err(|err| err.to_string())?;
+#[tauri::command]
+fn add_recent_dir(app: tauri::AppHandle, dir: String) -> Result<Vec<String>, CommandError> {
+    localized(&app, || {
+        let config_dir = config_dir(&app)?;
+        let dirs = settings::add_recent_dir(&config_dir, &dir)?;
+        // 目录记住了设置配置时通知前端该目录使用的设置，保存的当前设置不变
+        if let Some(settings) = settings::dir_profile(&config_dir, &dir)? {
+            progress::emit_settings_changed(&app, &settings);
         }
-    }
-
-    Ok(BackupResult {
-        backup_dir: backup_dir.to_string_lossy().into_owned(),
+        Ok(dirs)
     })
 }
 
 #[tauri::command]
-fn bulk_insert_mappings(target_dir: String, entries: Vec<MappingInput>, auto_increment_version: bool) -> Result<BulkInsertResult, String> {
-    if entries.is_empty() {
-        return Err("请至少输入一条映射关系。".into());
-    }
+fn list_profiles(app: tauri::AppHandle) -> Result<settings::SettingsProfiles, CommandError> {
+    localized(&app, || Ok(settings::load_profiles(&config_dir(&app)?)?))
+}
 
-    let dir = PathBuf::from(&target_dir);
-    let files = collect_theme_files(&dir)?;
-    let mut updated_files = Vec::new();
-    let mut skipped_files = Vec::new();
-    let mut files_to_update: Vec<(PathBuf, Vec<MappingInput>)> = Vec::new();
-
-    // 先检查哪些文件需要更新
-    for file in &files {
-        let raw = fs::read_to_string(file).map_err(|err| err.to_string())?;
-        let parsed = parse_mappings(&raw)?;
-        let existing: HashSet<String> = parsed.iter().map(|item| item.local_id.clone()).collect();
-        
-        // 找出重复的ID和需要添加的ID
-        let mut duplicate_ids = Vec::new();
-        let mut pending = Vec::new();
-        
-        for entry in &entries {
-            if existing.contains(&entry.local_id) {
-                duplicate_ids.push(entry.local_id.clone());
-            } else {
-                pending.push(entry.clone());
-            }
-        }
+#[tauri::command]
+fn save_profile(
+    app: tauri::AppHandle,
+    name: String,
+    settings: AppSettings,
+) -> Result<settings::SettingsProfiles, CommandError> {
+    localized(&app, || Ok(settings::save_profile(&config_dir(&app)?, &name, &settings)?))
+}
 
-        if pending.is_empty() {
-            skipped_files.push(SkippedFile {
-                file_path: file.to_string_lossy().into_owned(),
-                reason: if duplicate_ids.is_empty() {
-                    "所有映射已存在".to_string()
-                } else {
-                    format!("本地栏目ID重复：{}", duplicate_ids.join("、"))
-                },
-                duplicate_ids,
-            });
-        } else {
-            // 如果有部分重复，也要记录
-            if !duplicate_ids.is_empty() {
-                skipped_files.push(SkippedFile {
-                    file_path: file.to_string_lossy().into_owned(),
-                    reason: format!("部分ID重复（已跳过）：{}", duplicate_ids.join("、")),
-                    duplicate_ids: duplicate_ids.clone(),
-                });
-            }
-            files_to_update.push((file.clone(), pending));
-        }
-    }
+#[tauri::command]
+fn delete_profile(app: tauri::AppHandle, name: String) -> Result<settings::SettingsProfiles, CommandError> {
+    localized(&app, || Ok(settings::delete_profile(&config_dir(&app)?, &name)?))
+}
 
-    // 只有在有文件需要更新时才备份
-    let mut backup_dir_path: Option<String> = None;
-    if !files_to_update.is_empty() {
-        let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-        let backup_dir = dir.join("backups").join(&timestamp);
-        fs::create_dir_all(&backup_dir).map_err(|err| err.to_string())?;
-
-        for file in &files {
-            if let Some(name) = file.file_name() {
-                let target = backup_dir.join(name);
-                fs::copy(file, target).map_err(|err| err.to_string())?;
-            }
-        }
-        backup_dir_path = Some(backup_dir.to_string_lossy().into_owned());
-    }
+/// 把设置配置设为当前设置，指定目录时记住该目录使用这个配置
+#[tauri::command]
+fn apply_profile(app: tauri::AppHandle, name: String, target_dir: Option<String>) -> Result<AppSettings, CommandError> {
+    localized(&app, || Ok(settings::apply_profile(&config_dir(&app)?, &name, target_dir.as_deref())?))
+}
 
-    // 收集新增的映射详情（用于日志记录）
-    let mut added_mappings: Vec<AddedMapping> = Vec::new();
-    let mut version_changes: Vec<VersionChange> = Vec::new();
-
-    // 执行更新
-    for (file, pending) in files_to_update {
-        let file_path_str = file.to_string_lossy().into_owned();
-        let raw = fs::read_to_string(&file).map_err(|err| err.to_string())?;
-        let mut updated = insert_entries(&raw, &pending)?;
-        
-        // 如果启用了自动递增版本号，则递增版本号
-        if auto_increment_version {
-            let old_version = extract_version(&raw);
-            updated = increment_version(&updated)?;
-            let new_version = extract_version(&updated);
-            
-            if let (Some(old_ver), Some(new_ver)) = (old_version, new_version) {
-                version_changes.push(VersionChange {
-                    file_path: file_path_str.clone(),
-                    old_version: old_ver,
-                    new_version: new_ver,
-                });
-            }
-        }
-        
-        fs::write(&file, updated).map_err(|err| err.to_string())?;
-        updated_files.push(file_path_str.clone());
-        
-        // 记录新增的映射详情
-        for entry in &pending {
-            added_mappings.push(AddedMapping {
-                file_path: file_path_str.clone(),
-                local_id: entry.local_id.clone(),
-                gw_id: entry.gw_id.clone(),
-            });
-        }
+/// 有缓存的栏目目录和本地栏目目录时标注栏目名称，缓存损坏不影响扫描
+fn annotate_catalogs(config_dir: &Path, files: &mut [FileMapping]) {
+    match catalog::load_cache(config_dir) {
+        Ok(Some(catalog)) => catalog.annotate(files),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
     }
-
-    // 写入操作日志
-    let entries_info = format!("新增 {} 条映射", entries.len());
-    if let Err(e) = write_operation_log(
-        &dir,
-        OperationType::BulkInsert,
-        &updated_files,
-        &skipped_files,
-        backup_dir_path.as_ref(),
-        Some(&entries_info),
-        None,
-        Some(&added_mappings),
-        if version_changes.is_empty() { None } else { Some(&version_changes) },
-    ) {
-        // 日志写入失败不影响主操作，只打印错误
-        eprintln!("写入操作日志失败: {}", e);
+    match catalog::load_local_cache(config_dir) {
+        Ok(Some(catalog)) => catalog.annotate(files),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
     }
+}
 
-    Ok(BulkInsertResult {
-        updated_files,
-        skipped_files,
-        backup_dir: backup_dir_path,
+/// 扫描目录并标注栏目名称，发送 scan-progress 事件，可通过 operation_id 取消
+fn scan_with_progress(
+    app: &tauri::AppHandle,
+    target_dir: &str,
+    operation_id: Option<String>,
+    profile: bool,
+) -> Result<ScanResult, Error> {
+    let settings = dir_settings(app, target_dir)?;
+    let guard = cancel_registry::start(app, operation_id);
+    let retries = file_io::RetryScope::begin();
+    let profile = profile::ProfileScope::begin(profile);
+    let mut files = scan_directory_with_progress(Path::new(target_dir), &settings, &|file, current, total| {
+        guard.token().check()?;
+        progress::emit(app, progress::SCAN_PROGRESS_EVENT, file, current, total);
+        Ok(())
+    })?;
+    annotate_catalogs(&config_dir(app)?, &mut files);
+    Ok(ScanResult {
+        files,
+        ignored_files: ignored_theme_files(Path::new(target_dir))?,
+        retries: retries.finish(),
+        timings: profile.map(profile::ProfileScope::finish),
     })
 }
 
+/// 扫描目录；profile 为 true 时在结果中返回各文件读取、解析的耗时
 #[tauri::command]
-fn import_mappings(
+async fn scan_theme_files(
+    app: tauri::AppHandle,
     target_dir: String,
-    mappings: std::collections::HashMap<String, String>,
-    auto_increment_version: bool,
-) -> Result<BulkInsertResult, String> {
-    if mappings.is_empty() {
-        return Err("导入的映射为空".into());
-    }
-
-    let dir = PathBuf::from(&target_dir);
-    let files = collect_theme_files(&dir)?;
-    let mut updated_files = Vec::new();
-    let mut version_changes: Vec<VersionChange> = Vec::new();
+    operation_id: Option<String>,
+    profile: Option<bool>,
+) -> Result<ScanResult, CommandError> {
+    run_blocking(app, move |app| {
+        scan_with_progress(app, &target_dir, operation_id, profile.unwrap_or(false))
+    })
+    .await
+}
 
-    // 先备份
-    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-    let backup_dir = dir.join("backups").join(timestamp);
-    fs::create_dir_all(&backup_dir).map_err(|err| err.to_string())?;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PagedScanResult {
+    #[serde(flatten)]
+    page: paging::ScanPage,
+    /// 被 .catmapignore 排除、未扫描的文件
+    ignored_files: Vec<ignore::IgnoredFile>,
+    retries: Vec<file_io::IoRetry>,
+}
 
-    for file in &files {
-        if let Some(name) = file.file_name() {
-            let target = backup_dir.join(name);
-            fs::copy(file, target).map_err(|err| err.to_string())?;
-        }
-    }
+/// 分页返回扫描结果（从第 0 页开始），文件中映射很多时前端按页加载
+#[tauri::command]
+async fn scan_theme_files_paged(
+    app: tauri::AppHandle,
+    target_dir: String,
+    page: usize,
+    page_size: usize,
+    filter: Option<paging::ScanFilter>,
+    operation_id: Option<String>,
+) -> Result<PagedScanResult, CommandError> {
+    run_blocking(app, move |app| {
+        let scanned = scan_with_progress(app, &target_dir, operation_id, false)?;
+        Ok(PagedScanResult {
+            page: paging::page(scanned.files, page, page_size, &filter.unwrap_or_default()),
+            ignored_files: scanned.ignored_files,
+            retries: scanned.retries,
+        })
+    })
+    .await
+}
 
-    // 对每个文件执行导入（替换模式）
-    for file in files {
-        let file_path_str = file.to_string_lossy().into_owned();
-        let raw = fs::read_to_string(&file).map_err(|err| err.to_string())?;
-        let mut updated = replace_mappings_in_file(&raw, &mappings)?;
-        
-        // 如果启用了自动递增版本号，则递增版本号
-        if auto_increment_version {
-            let old_version = extract_version(&raw);
-            updated = increment_version(&updated)?;
-            let new_version = extract_version(&updated);
-            
-            if let (Some(old_ver), Some(new_ver)) = (old_version, new_version) {
-                version_changes.push(VersionChange {
-                    file_path: file_path_str.clone(),
-                    old_version: old_ver,
-                    new_version: new_ver,
-                });
-            }
-        }
-        
-        fs::write(&file, updated).map_err(|err| err.to_string())?;
-        updated_files.push(file_path_str);
-    }
+#[tauri::command]
+fn search_mappings(
+    app: tauri::AppHandle,
+    target_dir: String,
+    query: String,
+    fields: Option<Vec<search::SearchField>>,
+    mode: Option<search::MatchMode>,
+) -> Result<SearchResult, CommandError> {
+    localized(&app, || {
+        categorymap_core::search_mappings(&dir_settings(&app, &target_dir)?, target_dir, query, fields, mode)
+    })
+}
 
-    // 写入操作日志
-    let mappings_info = format!("导入 {} 条映射（替换模式）", mappings.len());
-    if let Err(e) = write_operation_log(
-        &dir,
-        OperationType::Import,
-        &updated_files,
-        &[],
-        Some(&backup_dir.to_string_lossy().into_owned()),
-        Some(&mappings_info),
-        None,
-        None,
-        if version_changes.is_empty() { None } else { Some(&version_changes) },
-    ) {
-        // 日志写入失败不影响主操作，只打印错误
-        eprintln!("写入操作日志失败: {}", e);
-    }
+#[tauri::command]
+fn get_statistics(app: tauri::AppHandle, target_dir: String) -> Result<stats::Statistics, CommandError> {
+    localized(&app, || categorymap_core::get_statistics(&dir_settings(&app, &target_dir)?, target_dir))
+}
 
-    Ok(BulkInsertResult {
-        updated_files,
-        skipped_files: Vec::new(),
-        backup_dir: Some(backup_dir.to_string_lossy().into_owned()),
+#[tauri::command]
+fn generate_report(
+    app: tauri::AppHandle,
+    target_dir: String,
+    format: Option<report::ReportFormat>,
+    output_path: Option<String>,
+) -> Result<String, CommandError> {
+    localized(&app, || {
+        categorymap_core::generate_report(&dir_settings(&app, &target_dir)?, target_dir, format, output_path)
     })
 }
 
 #[tauri::command]
-fn delete_mapping(file_path: String, local_id: String, auto_increment_version: bool) -> Result<Option<String>, String> {
-    let path = PathBuf::from(&file_path);
-    if !path.exists() {
-        return Err("文件不存在".into());
-    }
+fn check_consistency(
+    app: tauri::AppHandle,
+    target_dir: String,
+) -> Result<ConsistencyReport, CommandError> {
+    localized(&app, || categorymap_core::check_consistency(&dir_settings(&app, &target_dir)?, target_dir))
+}
 
-    // 创建备份
-    let file_dir = path.parent().ok_or("无法获取文件所在目录")?;
-    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-    let backup_dir = file_dir.join("backups").join(&timestamp);
-    fs::create_dir_all(&backup_dir).map_err(|err| err.to_string())?;
+#[tauri::command]
+fn validate_against_master(
+    app: tauri::AppHandle,
+    target_dir: String,
+    master_path: String,
+) -> Result<MasterValidationResult, CommandError> {
+    localized(&app, || {
+        categorymap_core::validate_against_master(&dir_settings(&app, &target_dir)?, target_dir, master_path)
+    })
+}
 
-    if let Some(name) = path.file_name() {
-        let target = backup_dir.join(name);
-        fs::copy(&path, target).map_err(|err| err.to_string())?;
-    }
+/// 保存目录当前的映射状态为快照（只保存解析后的映射，不复制文件）
+#[tauri::command]
+fn take_snapshot(
+    app: tauri::AppHandle,
+    target_dir: String,
+    label: String,
+) -> Result<snapshot::SnapshotInfo, CommandError> {
+    localized(&app, || snapshot::take(&dir_settings(&app, &target_dir)?, Path::new(&target_dir), &label))
+}
 
-    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
-    
-    // 先解析文件获取国网ID（用于日志记录）
-    let parsed_mappings = parse_mappings(&raw).unwrap_or_default();
-    let gw_id = parsed_mappings.iter()
-        .find(|e| e.local_id == local_id)
-        .and_then(|e| e.gw_id.clone());
-    
-    let mut updated = remove_mapping_from_file(&raw, &local_id)?;
-    let mut version_changes: Vec<VersionChange> = Vec::new();
-    
-    // 如果启用了自动递增版本号，则递增版本号
-    if auto_increment_version {
-        let old_version = extract_version(&raw);
-        updated = increment_version(&updated)?;
-        let new_version = extract_version(&updated);
-        
-        if let (Some(old_ver), Some(new_ver)) = (old_version, new_version) {
-            version_changes.push(VersionChange {
-                file_path: file_path.clone(),
-                old_version: old_ver,
-                new_version: new_ver,
-            });
-        }
-    }
-    
-    fs::write(&path, updated).map_err(|err| err.to_string())?;
-
-    // 写入操作日志
-    let delete_info = format!("删除本地栏目ID: {}", local_id);
-    let deleted_mappings = vec![DeletedMapping {
-        file_path: file_path.clone(),
-        local_id: local_id.clone(),
-        gw_id,
-    }];
-    
-    if let Err(e) = write_operation_log(
-        file_dir,
-        OperationType::SingleDelete,
-        &[file_path.clone()],
-        &[],
-        Some(&backup_dir.to_string_lossy().into_owned()),
-        Some(&delete_info),
-        Some(&deleted_mappings),
-        None,
-        if version_changes.is_empty() { None } else { Some(&version_changes) },
-    ) {
-        // 日志写入失败不影响主操作，只打印错误
-        eprintln!("写入操作日志失败: {}", e);
-    }
+#[tauri::command]
+fn list_snapshots(app: tauri::AppHandle, target_dir: String) -> Result<Vec<snapshot::SnapshotInfo>, CommandError> {
+    localized(&app, || snapshot::list(&dir_settings(&app, &target_dir)?, Path::new(&target_dir)))
+}
 
-    Ok(Some(backup_dir.to_string_lossy().into_owned()))
+/// 对比两个快照，未指定 b 时与目录的当前状态对比
+#[tauri::command]
+fn compare_snapshots(
+    app: tauri::AppHandle,
+    target_dir: String,
+    a: String,
+    b: Option<String>,
+) -> Result<snapshot::SnapshotComparison, CommandError> {
+    localized(&app, || snapshot::compare(&dir_settings(&app, &target_dir)?, Path::new(&target_dir), &a, b.as_deref()))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DeleteMappingRequest {
-    file_path: String,
-    local_id: String,
+#[tauri::command]
+fn approve_snapshot(
+    app: tauri::AppHandle,
+    target_dir: String,
+    id: String,
+) -> Result<snapshot::SnapshotInfo, CommandError> {
+    localized(&app, || snapshot::approve(&dir_settings(&app, &target_dir)?, Path::new(&target_dir), &id))
 }
 
+/// 立即执行一次审计，与最近一个已确认的快照对比
 #[tauri::command]
-fn batch_delete_mappings(requests: Vec<DeleteMappingRequest>, auto_increment_version: bool) -> Result<BulkInsertResult, String> {
-    if requests.is_empty() {
-        return Err("删除列表为空".into());
-    }
+fn audit_drift(app: tauri::AppHandle, target_dir: String) -> Result<Option<snapshot::DriftReport>, CommandError> {
+    localized(&app, || snapshot::audit_drift(&dir_settings(&app, &target_dir)?, Path::new(&target_dir)))
+}
 
-    let mut updated_files = Vec::new();
-    let mut skipped_files = Vec::new();
+#[tauri::command]
+fn diff_directories(
+    app: tauri::AppHandle,
+    dir_a: String,
+    dir_b: String,
+) -> Result<DirectoryDiffResult, CommandError> {
+    localized(&app, || categorymap_core::diff_directories(&current_settings(&app)?, dir_a, dir_b))
+}
 
-    // 按文件路径分组，提高效率
-    let mut file_groups: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
-    for req in &requests {
-        file_groups
-            .entry(req.file_path.clone())
-            .or_insert_with(Vec::new)
-            .push(req.local_id.clone());
-    }
+/// 按备份清单校验备份是否完整
+#[tauri::command]
+fn verify_backup(app: tauri::AppHandle, backup_dir: String) -> Result<backup::BackupVerification, CommandError> {
+    localized(&app, || Ok(backup::verify_backup(Path::new(&backup_dir))?))
+}
 
-    // 收集所有需要备份的文件路径
-    let files_to_backup: Vec<PathBuf> = file_groups
-        .keys()
-        .map(|fp| PathBuf::from(fp))
-        .filter(|p| p.exists())
-        .collect();
-
-    // 创建备份（如果有文件需要更新）
-    let mut backup_dir_path: Option<String> = None;
-    if !files_to_backup.is_empty() {
-        // 找到所有文件的共同父目录（如果都在同一目录下）
-        // 如果文件在不同目录，则使用第一个文件的目录
-        let first_file = &files_to_backup[0];
-        let file_dir = first_file.parent().ok_or("无法获取文件所在目录")?;
-        
-        let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-        let backup_dir = file_dir.join("backups").join(&timestamp);
-        fs::create_dir_all(&backup_dir).map_err(|err| err.to_string())?;
-
-        // 备份所有涉及的文件
-        for file_path in &files_to_backup {
-            if let Some(name) = file_path.file_name() {
-                let target = backup_dir.join(name);
-                fs::copy(file_path, target).map_err(|err| err.to_string())?;
-            }
-        }
-        backup_dir_path = Some(backup_dir.to_string_lossy().into_owned());
-    }
+#[tauri::command]
+fn restore_backup(
+    app: tauri::AppHandle,
+    target_dir: String,
+    backup_path: String,
+) -> Result<RestoreResult, CommandError> {
+    localized(&app, || categorymap_core::restore_backup(&dir_settings(&app, &target_dir)?, target_dir, backup_path))
+}
 
-    // 收集删除的映射详情（用于日志记录）
-    let mut deleted_mappings: Vec<DeletedMapping> = Vec::new();
-    let mut version_changes: Vec<VersionChange> = Vec::new();
-
-    // 对每个文件批量删除
-    for (file_path, local_ids) in file_groups {
-        let path = PathBuf::from(&file_path);
-        if !path.exists() {
-            skipped_files.push(SkippedFile {
-                file_path: file_path.clone(),
-                reason: "文件不存在".to_string(),
-                duplicate_ids: local_ids,
-            });
-            continue;
-        }
+/// 只恢复备份中选中的文件
+#[tauri::command]
+fn restore_files(
+    app: tauri::AppHandle,
+    target_dir: String,
+    backup_dir: String,
+    file_names: Vec<String>,
+) -> Result<RestoreResult, CommandError> {
+    localized(&app, || {
+        categorymap_core::restore_backup_files(&dir_settings(&app, &target_dir)?, target_dir, backup_dir, file_names)
+    })
+}
 
-        let raw = match fs::read_to_string(&path) {
-            Ok(content) => content,
-            Err(e) => {
-                skipped_files.push(SkippedFile {
-                    file_path: file_path.clone(),
-                    reason: format!("读取文件失败: {}", e),
-                    duplicate_ids: local_ids,
-                });
-                continue;
-            }
-        };
+#[tauri::command]
+fn prune_backups(
+    app: tauri::AppHandle,
+    target_dir: String,
+    dry_run: Option<bool>,
+) -> Result<PruneBackupsResult, CommandError> {
+    localized(&app, || categorymap_core::prune_backups(&dir_settings(&app, &target_dir)?, target_dir, dry_run))
+}
 
-        // 先解析文件获取映射信息（用于记录日志）
-        let parsed_mappings = parse_mappings(&raw).unwrap_or_default();
-        let mut mapping_map: std::collections::HashMap<String, Option<String>> = std::collections::HashMap::new();
-        for entry in parsed_mappings {
-            mapping_map.insert(entry.local_id, entry.gw_id);
+/// 监听目录下 theme 文件的变化，文件变化时发送 theme-file-changed 事件
+#[tauri::command]
+fn watch_directory(
+    app: tauri::AppHandle,
+    registry: tauri::State<'_, watcher::WatcherRegistry>,
+    target_dir: String,
+) -> Result<(), CommandError> {
+    localized(&app, || {
+        let dir = PathBuf::from(&target_dir);
+        if !dir.is_dir() {
+            return Err("目标目录不存在".into());
         }
+        let settings = dir_settings(&app, &target_dir)?;
+        Ok(registry.watch(&app, &dir, settings)?)
+    })
+}
 
-        // 逐个删除
-        let mut current_content = raw.clone();
-        let mut successfully_deleted_ids = Vec::new();
-        let mut failed_to_delete_ids = Vec::new();
-        
-        for local_id in &local_ids {
-        // 在删除前记录映射信息
-        let gw_id = mapping_map.get(local_id).cloned().flatten();
-        
-        match remove_mapping_from_file(&current_content, local_id) {
-            Ok(updated) => {
-                current_content = updated;
-                successfully_deleted_ids.push(local_id.clone());
-                // 记录成功删除的映射详情
-                deleted_mappings.push(DeletedMapping {
-                    file_path: file_path.clone(),
-                    local_id: local_id.clone(),
-                    gw_id,
-                });
-            }
-                Err(_) => {
-                    failed_to_delete_ids.push(local_id.clone());
-                    // 记录错误但不中断处理
-                }
-            }
-        }
+/// 停止监听目录，返回此前是否在监听
+#[tauri::command]
+fn unwatch_directory(registry: tauri::State<'_, watcher::WatcherRegistry>, target_dir: String) -> bool {
+    registry.unwatch(Path::new(&target_dir))
+}
 
-        if !successfully_deleted_ids.is_empty() {
-            // 如果启用了自动递增版本号，则递增版本号
-            if auto_increment_version {
-                let old_version = extract_version(&raw);
-                current_content = increment_version(&current_content)?;
-                let new_version = extract_version(&current_content);
-                
-                if let (Some(old_ver), Some(new_ver)) = (old_version, new_version) {
-                    version_changes.push(VersionChange {
-                        file_path: file_path.clone(),
-                        old_version: old_ver,
-                        new_version: new_ver,
-                    });
-                }
-            }
-            
-            if let Err(err) = fs::write(&path, current_content) {
-                skipped_files.push(SkippedFile {
-                    file_path: file_path.clone(),
-                    reason: format!("写入文件失败: {}", err),
-                    duplicate_ids: successfully_deleted_ids,
-                });
-            } else {
-                updated_files.push(file_path.clone());
-                // 如果有部分失败，记录跳过的文件
-                if !failed_to_delete_ids.is_empty() {
-                    skipped_files.push(SkippedFile {
-                        file_path: file_path.clone(),
-                        reason: format!("部分ID未找到或删除失败：{}", failed_to_delete_ids.join("、")),
-                        duplicate_ids: failed_to_delete_ids,
-                    });
-                }
-            }
-        } else {
-            // 所有ID都删除失败
-            skipped_files.push(SkippedFile {
-                file_path: file_path.clone(),
-                reason: format!("所有ID删除失败：{}", failed_to_delete_ids.join("、")),
-                duplicate_ids: failed_to_delete_ids,
-            });
-        }
-    }
+/// 取消正在进行的扫描或批量写入，返回是否找到该操作
+#[tauri::command]
+fn cancel_operation(registry: tauri::State<'_, cancel_registry::CancelRegistry>, operation_id: String) -> bool {
+    registry.cancel(&operation_id)
+}
 
-    // 写入操作日志
-    // 确定目标目录（使用第一个文件的目录）
-    let log_target_dir = if let Some(first_file) = files_to_backup.first() {
-        first_file.parent()
-            .map(|p| p.to_path_buf())
-            .ok_or("无法获取文件所在目录")?
-    } else if let Some(first_updated) = updated_files.first() {
-        // 获取父目录路径并转换为 PathBuf 以拥有所有权
-        PathBuf::from(first_updated)
-            .parent()
-            .map(|p| p.to_path_buf())
-            .ok_or("无法获取文件所在目录")?
-    } else {
-        // 如果没有成功处理的文件，跳过日志记录
-        return Ok(BulkInsertResult {
-            updated_files,
-            skipped_files,
-            backup_dir: backup_dir_path,
-        });
-    };
-
-    let delete_info = format!("批量删除 {} 条映射", requests.len());
-    if let Err(e) = write_operation_log(
-        &log_target_dir,
-        OperationType::BatchDelete,
-        &updated_files,
-        &skipped_files,
-        backup_dir_path.as_ref(),
-        Some(&delete_info),
-        Some(&deleted_mappings),
-        None,
-        if version_changes.is_empty() { None } else { Some(&version_changes) },
-    ) {
-        // 日志写入失败不影响主操作，只打印错误
-        eprintln!("写入操作日志失败: {}", e);
-    }
+#[tauri::command]
+fn get_operation_history(
+    app: tauri::AppHandle,
+    target_dir: String,
+    limit: Option<usize>,
+) -> Result<Vec<history::HistoryEntry>, CommandError> {
+    localized(&app, || categorymap_core::get_operation_history(&dir_settings(&app, &target_dir)?, target_dir, limit))
+}
 
-    Ok(BulkInsertResult {
-        updated_files,
-        skipped_files,
-        backup_dir: backup_dir_path,
+#[tauri::command]
+fn replay_operation(
+    app: tauri::AppHandle,
+    target_dir: String,
+    operation_id: String,
+    auto_increment_version: Option<bool>,
+) -> Result<BulkInsertResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::replay_operation(
+            &settings,
+            Some(&config_dir(&app)?),
+            target_dir,
+            operation_id,
+            version_bump(&settings, auto_increment_version),
+        )
     })
 }
 
 #[tauri::command]
-fn open_folder(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
-    if !path_buf.exists() {
-        return Err(format!("路径不存在: {}", path));
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("explorer")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("无法打开文件夹: {}", e))?;
-    }
+fn diff_with_backup(
+    app: tauri::AppHandle,
+    file_path: String,
+    backup_dir: String,
+) -> Result<BackupDiffResult, CommandError> {
+    localized(&app, || categorymap_core::diff_with_backup(&dir_settings(&app, &file_path)?, file_path, backup_dir))
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("无法打开文件夹: {}", e))?;
-    }
+#[tauri::command]
+fn backup_theme_files(app: tauri::AppHandle, target_dir: String) -> Result<BackupResult, CommandError> {
+    localized(&app, || categorymap_core::backup_theme_files(&dir_settings(&app, &target_dir)?, target_dir))
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        Command::new("xdg-open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("无法打开文件夹: {}", e))?;
-    }
+#[tauri::command]
+fn list_backups(app: tauri::AppHandle, target_dir: String) -> Result<Vec<backup::BackupInfo>, CommandError> {
+    localized(&app, || categorymap_core::list_backups(&dir_settings(&app, &target_dir)?, target_dir))
+}
 
-    Ok(())
-}
-
-/// 提取 JSON 文件中的版本号
-fn extract_version(content: &str) -> Option<u32> {
-    let lines: Vec<&str> = content.lines().collect();
-    
-    for line in lines {
-        let trimmed = line.trim();
-        
-        // 查找包含 "version": 的行
-        if trimmed.starts_with("\"version\"") && trimmed.contains(':') {
-            // 提取版本号
-            if let Some(colon_pos) = trimmed.find(':') {
-                let value_part = &trimmed[colon_pos + 1..];
-                let value_part = value_part.trim().trim_end_matches(',');
-                
-                if let Ok(version) = value_part.parse::<u32>() {
-                    return Some(version);
-                }
-            }
-        }
-    }
-    None
-}
-
-/// 递增 JSON 文件中的版本号
-fn increment_version(content: &str) -> Result<String, String> {
-    // 查找 "version": 数字 的模式
-    let lines: Vec<&str> = content.lines().collect();
-    let mut updated_lines = Vec::new();
-    let mut version_updated = false;
-    
-    for line in lines {
-        let trimmed = line.trim();
-        
-        // 查找包含 "version": 的行
-        if trimmed.starts_with("\"version\"") && trimmed.contains(':') {
-            // 提取版本号
-            if let Some(colon_pos) = trimmed.find(':') {
-                let value_part = &trimmed[colon_pos + 1..];
-                let value_part = value_part.trim().trim_end_matches(',');
-                
-                if let Ok(current_version) = value_part.parse::<u32>() {
-                    let new_version = current_version + 1;
-                    
-                    // 保持原有的缩进和格式
-                    let indent = line.len() - line.trim_start().len();
-                    let indent_str = " ".repeat(indent);
-                    let comma = if trimmed.ends_with(',') { "," } else { "" };
-                    
-                    let new_line = format!("{}\"version\": {}{}", indent_str, new_version, comma);
-                    updated_lines.push(new_line);
-                    version_updated = true;
-                } else {
-                    updated_lines.push(line.to_string());
-                }
-            } else {
-                updated_lines.push(line.to_string());
-            }
-        } else {
-            updated_lines.push(line.to_string());
-        }
-    }
-    
-    if version_updated {
-        Ok(updated_lines.join("\n"))
-    } else {
-        // 如果没有找到版本号，返回原内容
-        Ok(content.to_string())
-    }
+/// 用设置页中正在编辑的模板为示例条目生成映射值，并返回解析器读回的结果
+#[tauri::command]
+fn render_template(
+    app: tauri::AppHandle,
+    template: String,
+    sample_entry: MappingInput,
+) -> Result<editor::TemplatePreview, CommandError> {
+    localized(&app, || Ok(editor::render_template(&template, &sample_entry, &current_settings(&app)?)))
 }
 
-fn main() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            scan_theme_files,
-            backup_theme_files,
-            bulk_insert_mappings,
-            import_mappings,
-            delete_mapping,
-            batch_delete_mappings,
-            open_folder
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+/// 按ID范围或模式生成待新增的映射，结果交给批量新增预览
+#[tauri::command]
+fn generate_mappings(
+    app: tauri::AppHandle,
+    range_spec: String,
+    gw_id_rule: generate::GwIdRule,
+) -> Result<Vec<MappingInput>, CommandError> {
+    localized(&app, || Ok(generate::generate(&range_spec, &gw_id_rule)?))
 }
 
-fn collect_theme_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
-    if !dir.exists() {
-        return Err("目标目录不存在".into());
-    }
-    let mut files = Vec::new();
-    for entry in WalkDir::new(dir).min_depth(1).max_depth(1) {
-        let entry = entry.map_err(|err| err.to_string())?;
-        if entry.file_type().is_file() {
-            if let Some(name) = entry.file_name().to_str() {
-                if name.starts_with("theme") && name.ends_with(".json") {
-                    files.push(entry.into_path());
-                }
-            }
-        }
-    }
-    files.sort();
-    Ok(files)
-}
-
-fn parse_mappings(raw: &str) -> Result<Vec<MappingEntry>, String> {
-    // 直接从文本中查找所有 portal_frag_* 条目，而不是从JSON对象中获取
-    // 因为JSON解析时重复的key会被覆盖，无法检测到重复
-    let mut result = Vec::new();
-    parse_portal_frag_from_text(raw, &mut result)?;
-    
-    // 检查重复：本地ID必须唯一，国网ID可以重复但需要提示
-    let mut local_id_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-    let mut gw_id_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-    
-    // 统计出现次数
-    for entry in &result {
-        *local_id_counts.entry(entry.local_id.clone()).or_insert(0) += 1;
-        if let Some(ref gw_id) = entry.gw_id {
-            *gw_id_counts.entry(gw_id.clone()).or_insert(0) += 1;
-        }
-    }
-    
-    // 标记状态
-    for entry in &mut result {
-        let local_count = local_id_counts.get(&entry.local_id).copied().unwrap_or(0);
-        let gw_count = entry.gw_id.as_ref()
-            .and_then(|gw| gw_id_counts.get(gw).copied())
-            .unwrap_or(0);
-        
-        if local_count > 1 {
-            entry.status = "duplicate_local".to_string();
-        } else if gw_count > 1 {
-            entry.status = "duplicate_gw".to_string();
-        } else {
-            entry.status = "normal".to_string();
-        }
-    }
-    
-    Ok(result)
-}
-
-/// 从原始文本中直接解析所有 portal_frag_* 条目，支持检测重复的key
-fn parse_portal_frag_from_text(raw: &str, acc: &mut Vec<MappingEntry>) -> Result<(), String> {
-    // 先找到 sExtOptions 块的位置
-    let (block_start, block_end) = find_ext_options_block(raw)?;
-    let block_content = &raw[block_start..=block_end];
-    
-    // 使用逐字符解析，查找所有 "portal_frag_xxx":"value" 的模式，同时跳过注释
-    let prefix = format!("\"{PORTAL_PREFIX}");
-    let bytes = block_content.as_bytes();
-    let mut i = 0;
-    let mut in_string = false;
-    let mut escape = false;
-    let mut in_line_comment = false;
-    let mut in_block_comment = false;
-    
-    while i < bytes.len() {
-        let ch = bytes[i];
-        
-        // 处理注释
-        if in_line_comment {
-            if ch == b'\n' {
-                in_line_comment = false;
-            }
-            i += 1;
-            continue;
-        }
-        
-        if in_block_comment {
-            if ch == b'*' && i + 1 < bytes.len() && bytes[i + 1] == b'/' {
-                in_block_comment = false;
-                i += 2;
-            } else {
-                i += 1;
-            }
-            continue;
-        }
-        
-        // 处理字符串
-        if in_string {
-            if escape {
-                escape = false;
-            } else if ch == b'\\' {
-                escape = true;
-            } else if ch == b'"' {
-                in_string = false;
-            }
-            i += 1;
-            continue;
-        }
-        
-        // 检查注释开始
-        if ch == b'/' && i + 1 < bytes.len() {
-            if bytes[i + 1] == b'/' {
-                in_line_comment = true;
-                i += 2;
-                continue;
-            }
-            if bytes[i + 1] == b'*' {
-                in_block_comment = true;
-                i += 2;
-                continue;
-            }
-        }
-        
-        // 检查字符串开始
-        if ch == b'"' {
-            in_string = true;
-            // 检查是否是 portal_frag_ 开头的key
-            if i + prefix.len() <= bytes.len() {
-                // 使用 get() 方法安全地获取字符串切片，避免字符边界问题
-                if let Some(candidate) = block_content.get(i..i + prefix.len()) {
-                    if candidate == prefix {
-                        // 找到了一个可能的 portal_frag_ 条目
-                        if let Some(entry) = parse_portal_entry_at(block_content, i, bytes) {
-                            acc.push(entry);
-                        }
-                    }
-                }
-            }
-        }
-        
-        i += 1;
-    }
-    
-    Ok(())
-}
-
-/// 在指定位置解析一个 portal_frag_ 条目
-fn parse_portal_entry_at(
-    content: &str,
-    start: usize,
-    bytes: &[u8],
-) -> Option<MappingEntry> {
-    // key 的开始引号在 start，跳过它
-    let key_start = start + 1;
-    let key_end = find_string_end(content, key_start, bytes)?;
-    
-    // 使用 get() 方法安全地获取字符串切片
-    let full_key = content.get(key_start..key_end)?;
-    
-    if !full_key.starts_with(PORTAL_PREFIX) {
-        return None;
-    }
-    
-    // 跳过冒号和空白
-    let mut value_start = key_end + 1;
-    while value_start < bytes.len()
-        && (bytes[value_start] == b':'
-            || bytes[value_start].is_ascii_whitespace())
-    {
-        value_start += 1;
-    }
-    
-    // 查找值的开始引号
-    if value_start >= bytes.len() || bytes[value_start] != b'"' {
-        return None;
-    }
-    
-    value_start += 1; // 跳过引号
-    let value_end = find_string_end(content, value_start, bytes)?;
-    
-    // 使用 get() 方法安全地获取字符串切片
-    let raw_value = content.get(value_start..value_end)?;
-    
-    let local_id = full_key.trim_start_matches(PORTAL_PREFIX).to_string();
-    let gw_id = extract_gw_id(raw_value);
-    let same_id = gw_id.as_ref().map(|gw| gw == &local_id).unwrap_or(false);
-    
-    Some(MappingEntry {
-        local_id,
-        gw_id,
-        raw_value: raw_value.to_string(),
-        same_id,
-        status: "normal".to_string(),
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn bulk_insert_mappings(
+    app: tauri::AppHandle,
+    target_dir: String,
+    entries: Vec<MappingInput>,
+    auto_increment_version: Option<bool>,
+    target_files: Option<Vec<String>>,
+    conflict_strategy: Option<ConflictStrategy>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    operation_id: Option<String>,
+    profile: Option<bool>,
+) -> Result<BulkInsertResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_dir)?;
+        let guard = cancel_registry::start(app, operation_id);
+        let dir = target_dir.clone();
+        run_bulk_insert(
+            &settings,
+            Some(&config_dir(app)?),
+            target_dir,
+            entries,
+            version_bump(&settings, auto_increment_version),
+            target_files,
+            conflict_strategy,
+            expected_hashes,
+            profile.unwrap_or(false),
+            guard.token(),
+            &|file, current, total| progress::emit(app, progress::WRITE_PROGRESS_EVENT, file, current, total),
+            &|result| progress::emit_file_result(app, result),
+        )
+        .map_err(|e| report_failure(&settings, OperationType::BulkInsert, &dir, e))
     })
+    .await
 }
 
-/// 查找字符串的结束位置（考虑转义）
-fn find_string_end(_content: &str, start: usize, bytes: &[u8]) -> Option<usize> {
-    let mut i = start;
-    let mut escaped = false;
-    
-    while i < bytes.len() {
-        let ch = bytes[i];
-        if escaped {
-            escaped = false;
-        } else if ch == b'\\' {
-            escaped = true;
-        } else if ch == b'"' {
-            return Some(i);
-        }
-        i += 1;
-    }
-    None
+#[tauri::command]
+fn list_workspaces(app: tauri::AppHandle) -> Result<Vec<workspace::Workspace>, CommandError> {
+    localized(&app, || Ok(workspace::load_workspaces(&config_dir(&app)?)?))
 }
 
+#[tauri::command]
+fn save_workspace(
+    app: tauri::AppHandle,
+    workspace: workspace::Workspace,
+) -> Result<Vec<workspace::Workspace>, CommandError> {
+    localized(&app, || Ok(workspace::save_workspace(&config_dir(&app)?, workspace)?))
+}
 
-fn extract_gw_id(raw_value: &str) -> Option<String> {
-    let marker = "es_tabId=";
-    let start = raw_value.find(marker)? + marker.len();
-    let remainder = &raw_value[start..];
-    let mut end = remainder.len();
-    for delimiter in ['&', '|', '"'] {
-        if let Some(pos) = remainder.find(delimiter) {
-            end = end.min(pos);
-        }
-    }
-    let gw_id = remainder[..end].trim();
-    if gw_id.is_empty() {
-        None
-    } else {
-        Some(gw_id.to_string())
-    }
+#[tauri::command]
+fn delete_workspace(app: tauri::AppHandle, name: String) -> Result<Vec<workspace::Workspace>, CommandError> {
+    localized(&app, || Ok(workspace::delete_workspace(&config_dir(&app)?, &name)?))
 }
 
-fn insert_entries(raw: &str, entries: &[MappingInput]) -> Result<String, String> {
-    let (block_start, block_end) = find_ext_options_block(raw)?;
-    let line_ending = if raw.contains("\r\n") { "\r\n" } else { "\n" };
-    let interior = &raw[block_start + 1..block_end];
-    let has_existing = interior.trim().is_empty() == false;
+/// 扫描工作区中选中的根目录（未指定时扫描全部），结果按根目录分组
+#[tauri::command]
+async fn scan_workspace(
+    app: tauri::AppHandle,
+    name: String,
+    roots: Option<Vec<String>>,
+) -> Result<Vec<workspace::RootOutcome<ScanResult>>, CommandError> {
+    run_blocking(app, move |app| {
+        let config_dir = config_dir(app)?;
+        let workspace = workspace::find_workspace(&config_dir, &name)?;
+        let selected = workspace.select(roots.as_deref())?;
+        Ok(workspace::scan(&selected, &|dir| dir_settings(app, dir), &|files| {
+            annotate_catalogs(&config_dir, files)
+        }))
+    })
+    .await
+}
 
-    let base_indent = detect_base_indent(raw, block_start);
-    let entry_indent = format!("{base_indent}  ");
-    let before_closing = &raw[..block_end];
-    let ws_start = trim_trailing_whitespace_start(before_closing);
+/// 在工作区中选中的根目录上分别执行批量新增，某个根目录失败不影响其他根目录
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn workspace_bulk_insert(
+    app: tauri::AppHandle,
+    name: String,
+    roots: Option<Vec<String>>,
+    entries: Vec<MappingInput>,
+    auto_increment_version: Option<bool>,
+    conflict_strategy: Option<ConflictStrategy>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    operation_id: Option<String>,
+) -> Result<Vec<workspace::RootOutcome<BulkInsertResult>>, CommandError> {
+    run_blocking(app, move |app| {
+        let config_dir = config_dir(app)?;
+        let workspace = workspace::find_workspace(&config_dir, &name)?;
+        let selected = workspace.select(roots.as_deref())?;
+        let guard = cancel_registry::start(app, operation_id);
+        Ok(workspace::run_on_roots(&selected, |root| {
+            guard.token().check()?;
+            let settings = dir_settings(app, &root.dir)?;
+            run_bulk_insert(
+                &settings,
+                Some(&config_dir),
+                root.dir.clone(),
+                entries.clone(),
+                version_bump(&settings, auto_increment_version),
+                None,
+                conflict_strategy,
+                expected_hashes.clone(),
+                false,
+                guard.token(),
+                &|file, current, total| progress::emit(app, progress::WRITE_PROGRESS_EVENT, file, current, total),
+                &|result| progress::emit_file_result(app, result),
+            )
+            .map_err(|e| report_failure(&settings, OperationType::BulkInsert, &root.dir, e))
+        }))
+    })
+    .await
+}
 
-    let mut insertion = String::new();
-    insertion.push_str(line_ending);
+#[tauri::command]
+fn list_devices(app: tauri::AppHandle) -> Result<Vec<devices::DeviceProfile>, CommandError> {
+    localized(&app, || Ok(devices::load_devices(&config_dir(&app)?)?))
+}
 
-    for (idx, entry) in entries.iter().enumerate() {
-        insertion.push_str(&entry_indent);
-        insertion.push_str(&format_entry(entry));
-        if idx < entries.len() - 1 {
-            insertion.push(',');
+#[tauri::command]
+fn save_device(
+    app: tauri::AppHandle,
+    device: devices::DeviceProfile,
+) -> Result<Vec<devices::DeviceProfile>, CommandError> {
+    localized(&app, || Ok(devices::save_device(&config_dir(&app)?, device)?))
+}
+
+#[tauri::command]
+fn delete_device(app: tauri::AppHandle, name: String) -> Result<Vec<devices::DeviceProfile>, CommandError> {
+    localized(&app, || Ok(devices::delete_device(&config_dir(&app)?, &name)?))
+}
+
+/// 按名称取出设备和操作它时使用的设置
+fn device_with_settings(
+    app: &tauri::AppHandle,
+    config_dir: &Path,
+    name: &str,
+) -> Result<(devices::DeviceProfile, AppSettings), String> {
+    let device = devices::find_device(config_dir, name)?;
+    let settings = device.settings(config_dir, &current_settings(app)?)?;
+    Ok((device, settings))
+}
+
+/// 用设备的设置配置扫描设备对应的本地目录
+#[tauri::command]
+async fn scan_device(app: tauri::AppHandle, name: String) -> Result<ScanResult, CommandError> {
+    run_blocking(app, move |app| {
+        let config_dir = config_dir(app)?;
+        let (device, settings) = device_with_settings(app, &config_dir, &name)?;
+        let dir = device.local_dir()?;
+        let retries = file_io::RetryScope::begin();
+        let mut files = scan_directory(dir, &settings)?;
+        annotate_catalogs(&config_dir, &mut files);
+        Ok(ScanResult {
+            files,
+            ignored_files: ignored_theme_files(dir)?,
+            retries: retries.finish(),
+            timings: None,
+        })
+    })
+    .await
+}
+
+/// 用设备的设置配置在设备对应的本地目录中批量新增映射
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn device_bulk_insert(
+    app: tauri::AppHandle,
+    name: String,
+    entries: Vec<MappingInput>,
+    auto_increment_version: Option<bool>,
+    target_files: Option<Vec<String>>,
+    conflict_strategy: Option<ConflictStrategy>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    operation_id: Option<String>,
+) -> Result<BulkInsertResult, CommandError> {
+    run_blocking(app, move |app| {
+        let config_dir = config_dir(app)?;
+        let (device, settings) = device_with_settings(app, &config_dir, &name)?;
+        device.local_dir()?;
+        let guard = cancel_registry::start(app, operation_id);
+        run_bulk_insert(
+            &settings,
+            Some(&config_dir),
+            device.dir.clone(),
+            entries,
+            version_bump(&settings, auto_increment_version),
+            target_files,
+            conflict_strategy,
+            expected_hashes,
+            false,
+            guard.token(),
+            &|file, current, total| progress::emit(app, progress::WRITE_PROGRESS_EVENT, file, current, total),
+            &|result| progress::emit_file_result(app, result),
+        )
+        .map_err(|e| report_failure(&settings, OperationType::BulkInsert, &device.dir, e))
+    })
+    .await
+}
+
+/// 把 source_file 中的映射同步到设备对应目录中的所有 theme 文件（不含 source_file 本身）
+#[tauri::command]
+async fn device_sync_mappings(
+    app: tauri::AppHandle,
+    name: String,
+    source_file: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncResult, CommandError> {
+    run_blocking(app, move |app| {
+        let config_dir = config_dir(app)?;
+        let (device, settings) = device_with_settings(app, &config_dir, &name)?;
+        let source = fs::canonicalize(&source_file).unwrap_or_else(|_| PathBuf::from(&source_file));
+        let target_files: Vec<String> = collect_theme_files(device.local_dir()?)?
+            .into_iter()
+            .filter(|file| fs::canonicalize(file).unwrap_or_else(|_| file.clone()) != source)
+            .map(|file| file.to_string_lossy().into_owned())
+            .collect();
+        if target_files.is_empty() {
+            return Err(format!("设备 {} 的目录中没有可同步的 theme 文件", device.name).into());
         }
-        insertion.push_str(line_ending);
-    }
-    insertion.push_str(&base_indent);
+        categorymap_core::sync_mappings(
+            &settings,
+            source_file,
+            target_files,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+    .await
+}
 
-    let mut updated = String::with_capacity(raw.len() + insertion.len());
-    updated.push_str(&before_closing[..ws_start]);
-    if has_existing {
-        updated.push(',');
-    }
-    updated.push_str(&insertion);
-    updated.push_str(&raw[block_end..]);
-    Ok(updated)
-}
-
-fn detect_base_indent(content: &str, block_start: usize) -> String {
-    let prefix = &content[..block_start];
-    if let Some((_, line)) = prefix.rsplit_once('\n') {
-        line.chars()
-            .take_while(|ch| ch.is_whitespace())
-            .collect::<String>()
-    } else {
-        String::new()
-    }
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn import_mappings(
+    app: tauri::AppHandle,
+    target_dir: String,
+    mappings: Option<std::collections::HashMap<String, String>>,
+    export_path: Option<String>,
+    auto_increment_version: Option<bool>,
+    mode: Option<ImportMode>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    operation_id: Option<String>,
+) -> Result<ImportResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_dir)?;
+        // 指定了导出文件时按文件导入，否则使用前端传入的映射表
+        let source = match (export_path, mappings) {
+            (Some(path), _) => ImportSource::Export(
+                export::detect(Path::new(&path))?.ok_or("所选文件不是映射导出文件")?,
+            ),
+            (None, Some(mappings)) => ImportSource::Table(mappings),
+            (None, None) => return Err("导入的映射为空".into()),
+        };
+        let guard = cancel_registry::start(app, operation_id);
+        let dir = target_dir.clone();
+        let operation_type = match mode {
+            Some(ImportMode::Merge) => OperationType::ImportMerge,
+            _ => OperationType::Import,
+        };
+        run_import(
+            &settings,
+            target_dir,
+            source,
+            version_bump(&settings, auto_increment_version),
+            mode,
+            expected_hashes,
+            guard.token(),
+            &|file, current, total| progress::emit(app, progress::WRITE_PROGRESS_EVENT, file, current, total),
+        )
+        .map_err(|e| report_failure(&settings, operation_type, &dir, e))
+    })
+    .await
+}
+
+#[tauri::command]
+fn export_mappings(
+    app: tauri::AppHandle,
+    target_dir: String,
+    output_path: String,
+) -> Result<ExportResult, CommandError> {
+    localized(&app, || categorymap_core::export_mappings(&dir_settings(&app, &target_dir)?, target_dir, output_path))
+}
+
+/// 只导出需要处理的问题条目（CSV 或 JSON）
+#[tauri::command]
+fn export_problems(
+    app: tauri::AppHandle,
+    target_dir: String,
+    format: Option<export::ProblemFormat>,
+    output_path: Option<String>,
+) -> Result<ExportResult, CommandError> {
+    localized(&app, || {
+        categorymap_core::export_problems(&dir_settings(&app, &target_dir)?, target_dir, format, output_path)
+    })
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn delete_mapping(
+    app: tauri::AppHandle,
+    file_path: String,
+    local_id: String,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+    block: Option<usize>,
+    soft: Option<bool>,
+    prefix: Option<String>,
+) -> Result<Option<String>, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::delete_mapping(
+            &settings,
+            file_path,
+            local_id,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+            block,
+            soft.unwrap_or(false),
+            prefix,
+        )
+    })
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn set_raw_value(
+    app: tauri::AppHandle,
+    file_path: String,
+    local_id: String,
+    raw_value: String,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+    block: Option<usize>,
+    prefix: Option<String>,
+) -> Result<Option<String>, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::set_raw_value(
+            &settings,
+            file_path,
+            local_id,
+            raw_value,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+            block,
+            prefix,
+        )
+    })
+}
+
+/// 从备份中恢复误删的单条映射
+#[tauri::command]
+fn restore_mapping(
+    app: tauri::AppHandle,
+    target_dir: String,
+    backup_dir: String,
+    file_name: String,
+    local_id: String,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<Option<String>, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::restore_mapping(
+            &settings,
+            target_dir,
+            backup_dir,
+            file_name,
+            local_id,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+        )
+    })
+}
+
+#[tauri::command]
+async fn batch_delete_mappings(
+    app: tauri::AppHandle,
+    requests: Vec<DeleteMappingRequest>,
+    auto_increment_version: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+    soft: Option<bool>,
+) -> Result<BulkInsertResult, CommandError> {
+    run_blocking(app, move |app| {
+        let dir = requests
+            .first()
+            .and_then(|request| Path::new(&request.file_path).parent())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let settings = dir_settings(app, &dir)?;
+        categorymap_core::batch_delete_mappings(
+            &settings,
+            requests,
+            version_bump(&settings, auto_increment_version),
+            expected_hashes,
+            soft.unwrap_or(false),
+            &|result| progress::emit_file_result(app, result),
+        )
+        .map_err(|e| report_failure(&settings, OperationType::BatchDelete, &dir, e))
+    })
+    .await
+}
+
+#[tauri::command]
+fn dedupe_mappings(
+    app: tauri::AppHandle,
+    file_path: String,
+    keep: Option<KeepPolicy>,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<DedupeResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::dedupe_mappings(
+            &settings,
+            file_path,
+            keep,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+        )
+    })
+}
+
+#[tauri::command]
+fn uncomment_mappings(
+    app: tauri::AppHandle,
+    file_path: String,
+    local_ids: Vec<String>,
+    prefix: Option<String>,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<CommentedMappingsResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::uncomment_mappings(
+            &settings,
+            file_path,
+            local_ids,
+            prefix,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+        )
+    })
+}
+
+#[tauri::command]
+fn purge_commented_mappings(
+    app: tauri::AppHandle,
+    file_path: String,
+    local_ids: Option<Vec<String>>,
+    prefix: Option<String>,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<CommentedMappingsResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::purge_commented_mappings(
+            &settings,
+            file_path,
+            local_ids,
+            prefix,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+        )
+    })
+}
+
+#[tauri::command]
+fn sync_mappings(
+    app: tauri::AppHandle,
+    source_file: String,
+    target_files: Vec<String>,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &source_file)?;
+        categorymap_core::sync_mappings(
+            &settings,
+            source_file,
+            target_files,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+#[tauri::command]
+fn replace_gw_id(
+    app: tauri::AppHandle,
+    target_dir: String,
+    old_gw_id: String,
+    new_gw_id: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<ReplaceGwIdResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::replace_gw_id(
+            &settings,
+            target_dir,
+            old_gw_id,
+            new_gw_id,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+/// 删除目录中国网栏目ID为 gw_ids 之一的所有映射，dry_run 时只返回将删除的条目
+#[tauri::command]
+fn delete_by_gw_id(
+    app: tauri::AppHandle,
+    target_dir: String,
+    gw_ids: Vec<String>,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::delete_by_gw_id(
+            &settings,
+            target_dir,
+            gw_ids,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+/// 清空目录或文件中的所有映射；未提供确认码时只预览并返回确认码
+#[tauri::command]
+async fn clear_mappings(
+    app: tauri::AppHandle,
+    target_path: String,
+    confirm_token: Option<String>,
+    auto_increment_version: Option<bool>,
+) -> Result<ClearMappingsResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_path)?;
+        categorymap_core::clear_mappings(
+            &settings,
+            target_path,
+            confirm_token,
+            version_bump(&settings, auto_increment_version),
+        )
+    })
+    .await
+}
+
+#[tauri::command]
+fn normalize_values(
+    app: tauri::AppHandle,
+    target_dir: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<NormalizeValuesResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::normalize_values(
+            &settings,
+            target_dir,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+#[tauri::command]
+fn normalize_ids(
+    app: tauri::AppHandle,
+    target_dir: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<NormalizeIdsResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::normalize_ids(
+            &settings,
+            target_dir,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+#[tauri::command]
+fn migrate_prefix(
+    app: tauri::AppHandle,
+    target_dir: String,
+    old_prefix: String,
+    new_prefix: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<MigratePrefixResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        categorymap_core::migrate_prefix(
+            &settings,
+            target_dir,
+            old_prefix,
+            new_prefix,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+    })
+}
+
+#[tauri::command]
+fn batch_update_mappings(
+    app: tauri::AppHandle,
+    target_dir: String,
+    updates: Vec<MappingUpdate>,
+    auto_increment_version: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<BatchUpdateResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &target_dir)?;
+        let dir = target_dir.clone();
+        let auto_increment_version = version_bump(&settings, auto_increment_version);
+        categorymap_core::batch_update_mappings(&settings, target_dir, updates, auto_increment_version, expected_hashes)
+            .map_err(|e| report_failure(&settings, OperationType::BatchUpdate, &dir, e))
+    })
+}
+
+#[tauri::command]
+fn sort_mappings(
+    app: tauri::AppHandle,
+    file_path: String,
+    order: Option<SortOrder>,
+    auto_increment_version: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<SortResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::sort_mappings(
+            &settings,
+            file_path,
+            order,
+            version_bump(&settings, auto_increment_version),
+            expected_hash,
+        )
+    })
+}
+
+/// 修复 sExtOptions 段落中的逗号和空行问题；dry_run 为 true 时只返回差异预览
+#[tauri::command]
+fn repair_ext_options(
+    app: tauri::AppHandle,
+    file_path: String,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hash: Option<String>,
+) -> Result<RepairResult, CommandError> {
+    localized(&app, || {
+        let settings = dir_settings(&app, &file_path)?;
+        categorymap_core::repair_ext_options(
+            &settings,
+            file_path,
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hash,
+        )
+    })
+}
+
+#[tauri::command]
+fn get_file_info(app: tauri::AppHandle, file_path: String) -> Result<FileInfo, CommandError> {
+    localized(&app, || categorymap_core::get_file_info(&dir_settings(&app, &file_path)?, file_path))
+}
+
+#[tauri::command]
+fn check_write_access(
+    app: tauri::AppHandle,
+    target_dir: String,
+    target_files: Option<Vec<String>>,
+) -> Result<preflight::PreflightReport, CommandError> {
+    localized(&app, || {
+        categorymap_core::check_write_access(&dir_settings(&app, &target_dir)?, target_dir, target_files)
+    })
+}
+
+#[tauri::command]
+fn lint_theme_file(app: tauri::AppHandle, file_path: String) -> Result<LintReport, CommandError> {
+    localized(&app, || categorymap_core::lint_theme_file(&dir_settings(&app, &file_path)?, file_path))
+}
+
+/// 检查手工编辑过的文件的语法，返回出错的行列号和附近的源码
+#[tauri::command]
+fn validate_file(app: tauri::AppHandle, file_path: String) -> Result<FileValidation, CommandError> {
+    localized(&app, || categorymap_core::validate_file(file_path))
+}
+
+/// 打开或拖入目录时检查目录是否可以操作，返回就绪检查报告
+#[tauri::command]
+async fn validate_target_dir(app: tauri::AppHandle, path: String) -> Result<TargetReadiness, CommandError> {
+    run_blocking(app, move |app| {
+        categorymap_core::validate_target_dir(&dir_settings(app, &path)?, path)
+    })
+    .await
+}
+
+#[tauri::command]
+fn scan_archive(app: tauri::AppHandle, archive_path: String) -> Result<ScanResult, CommandError> {
+    localized(&app, || categorymap_core::scan_archive(&current_settings(&app)?, archive_path))
+}
+
+#[tauri::command]
+fn open_archive_workspace(
+    app: tauri::AppHandle,
+    archive_path: String,
+    workspace_dir: Option<String>,
+) -> Result<ArchiveWorkspace, CommandError> {
+    localized(&app, || categorymap_core::open_archive_workspace(archive_path, workspace_dir))
+}
+
+#[tauri::command]
+fn repack_archive(
+    app: tauri::AppHandle,
+    archive_path: String,
+    workspace_dir: Option<String>,
+) -> Result<RepackResult, CommandError> {
+    localized(&app, || categorymap_core::repack_archive(&current_settings(&app)?, archive_path, workspace_dir))
+}
+
+/// 通过 SSH 下载远程目录中的 theme 文件到本地工作目录并扫描
+#[tauri::command]
+async fn scan_remote(
+    app: tauri::AppHandle,
+    target: remote::RemoteTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteScanResult, CommandError> {
+    run_blocking(app, move |app| {
+        categorymap_core::scan_remote(&current_settings(app)?, target, workspace_dir)
+    })
+    .await
+}
+
+/// 把工作目录中修改过的 theme 文件上传回远程目录
+#[tauri::command]
+async fn apply_remote(
+    app: tauri::AppHandle,
+    target: remote::RemoteTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteApplyResult, CommandError> {
+    run_blocking(app, move |app| {
+        categorymap_core::apply_remote(&current_settings(app)?, target, workspace_dir)
+    })
+    .await
+}
+
+/// 通过 HTTP/WebDAV 下载远程目录中的 theme 文件到本地工作目录并扫描
+#[tauri::command]
+async fn scan_http(
+    app: tauri::AppHandle,
+    target: webdav::HttpTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteScanResult, CommandError> {
+    run_blocking(app, move |app| {
+        categorymap_core::scan_http(&current_settings(app)?, target, workspace_dir)
+    })
+    .await
+}
+
+/// 把工作目录中修改过的 theme 文件通过 HTTP PUT 上传回远程目录
+#[tauri::command]
+async fn apply_http(
+    app: tauri::AppHandle,
+    target: webdav::HttpTarget,
+    workspace_dir: Option<String>,
+) -> Result<RemoteApplyResult, CommandError> {
+    run_blocking(app, move |app| {
+        categorymap_core::apply_http(&current_settings(app)?, target, workspace_dir)
+    })
+    .await
+}
+
+/// 列出本地栏目ID与国网栏目ID相同的占位映射
+#[tauri::command]
+async fn audit_same_id(app: tauri::AppHandle, target_dir: String) -> Result<same_id::SameIdAudit, CommandError> {
+    run_blocking(app, move |app| {
+        same_id::audit(&dir_settings(app, &target_dir)?, Path::new(&target_dir))
+    })
+    .await
+}
+
+/// 按对照表修正占位映射，只修改仍是占位值的条目
+#[tauri::command]
+async fn fix_same_id(
+    app: tauri::AppHandle,
+    target_dir: String,
+    fixes: std::collections::HashMap<String, String>,
+    auto_increment_version: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<same_id::SameIdFixResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_dir)?;
+        let dir = target_dir.clone();
+        let auto_increment_version = version_bump(&settings, auto_increment_version);
+        same_id::fix(&settings, target_dir, fixes, auto_increment_version, expected_hashes)
+            .map_err(|e| report_failure(&settings, OperationType::BatchUpdate, &dir, e))
+    })
+    .await
+}
+
+/// 删除（soft 时注释掉）目录中所有占位映射，dry_run 时只返回将处理的条目
+#[tauri::command]
+async fn remove_same_id(
+    app: tauri::AppHandle,
+    target_dir: String,
+    soft: Option<bool>,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_dir)?;
+        let dir = target_dir.clone();
+        same_id::remove(
+            &settings,
+            target_dir,
+            soft.unwrap_or(false),
+            version_bump(&settings, auto_increment_version),
+            dry_run,
+            expected_hashes,
+        )
+        .map_err(|e| report_failure(&settings, OperationType::RemoveSameId, &dir, e))
+    })
+    .await
+}
+
+/// 分析国网栏目ID重复的映射并给出处理建议
+#[tauri::command]
+async fn analyze_duplicates(
+    app: tauri::AppHandle,
+    target_dir: String,
+) -> Result<duplicates::DuplicateAnalysis, CommandError> {
+    run_blocking(app, move |app| {
+        duplicates::analyze(&dir_settings(app, &target_dir)?, Path::new(&target_dir))
+    })
+    .await
 }
 
-fn format_entry(entry: &MappingInput) -> String {
-    let value = TEMPLATE_VALUE.replace("{id}", &entry.gw_id);
-    format!("\"{PORTAL_PREFIX}{key}\":\"{value}\"", key = entry.local_id)
-}
-
-/// 替换文件中的映射项（导入模式）
-fn replace_mappings_in_file(
-    raw: &str,
-    mappings: &std::collections::HashMap<String, String>,
-) -> Result<String, String> {
-    let (block_start, block_end) = find_ext_options_block(raw)?;
-    let line_ending = if raw.contains("\r\n") { "\r\n" } else { "\n" };
-    let base_indent = detect_base_indent(raw, block_start);
-    let entry_indent = format!("{base_indent}  ");
-
-    // 解析现有内容，移除所有 portal_frag_* 条目
-    let interior = &raw[block_start + 1..block_end];
-    let lines: Vec<&str> = interior.split('\n').collect();
-    let mut filtered_lines = Vec::new();
-
-    for line in lines.iter() {
-        let trimmed = line.trim();
-        
-        // 跳过包含 portal_frag_ 的行（包括注释行）
-        if trimmed.contains(&format!("\"{PORTAL_PREFIX}")) || 
-           (trimmed.starts_with("//") && trimmed.contains("portal_frag_")) {
-            continue;
+/// 清理目录中所有文件的重复条目（按本地或国网栏目ID，保留第一条或最后一条），任一文件失败时不保留部分修改
+#[tauri::command]
+async fn cleanup_duplicates(
+    app: tauri::AppHandle,
+    target_dir: String,
+    scope: Option<duplicates::DuplicateScope>,
+    keep: Option<KeepPolicy>,
+    auto_increment_version: Option<bool>,
+    dry_run: Option<bool>,
+    expected_hashes: Option<std::collections::HashMap<String, String>>,
+) -> Result<RemoveMappingsResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &target_dir)?;
+        let dir = target_dir.clone();
+        let auto_increment_version = version_bump(&settings, auto_increment_version);
+        duplicates::cleanup(&settings, target_dir, scope, keep, auto_increment_version, dry_run, expected_hashes)
+            .map_err(|e| report_failure(&settings, OperationType::CleanupDuplicates, &dir, e))
+    })
+    .await
+}
+
+/// 查询目标目录的 git 工作区状态
+#[tauri::command]
+async fn git_status(app: tauri::AppHandle, target_dir: String) -> Result<git::GitStatus, CommandError> {
+    run_blocking(app, move |_| Ok(git::status(Path::new(&target_dir))?)).await
+}
+
+/// 列出通过 adb 连接的设备
+#[tauri::command]
+async fn adb_list_devices(app: tauri::AppHandle) -> Result<Vec<adb::AdbDevice>, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = current_settings(app)?;
+        Ok(adb::Adb::from_settings(&settings).list_devices()?)
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdbPullResult {
+    local_dir: String,
+    /// 已下载到本地的文件
+    pulled_files: Vec<String>,
+    /// 下载后的扫描结果
+    files: Vec<FileMapping>,
+}
+
+/// 通过 adb 下载设备上 remote_path 目录中的 theme 文件到 local_dir，然后扫描本地目录
+#[tauri::command]
+async fn adb_pull_themes(
+    app: tauri::AppHandle,
+    serial: String,
+    remote_path: String,
+    local_dir: String,
+) -> Result<AdbPullResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &local_dir)?;
+        let adb = adb::Adb::from_settings(&settings);
+        let names = adb.list_theme_files(&serial, &remote_path)?;
+        if names.is_empty() {
+            return Err(format!("设备目录 {} 中没有 theme 文件", remote_path).into());
         }
-        
-        // 保留非 portal_frag_ 的行
-        filtered_lines.push(*line);
-    }
 
-    // 构建新的内容
-    let mut new_content = String::new();
-    
-    // 添加过滤后的现有内容（如果有）
-    if !filtered_lines.is_empty() {
-        let filtered_text = filtered_lines.join("\n");
-        let trimmed_filtered = filtered_text.trim();
-        if !trimmed_filtered.is_empty() {
-            new_content.push_str(&trimmed_filtered);
-            if !trimmed_filtered.ends_with(',') {
-                new_content.push(',');
+        let dir = PathBuf::from(&local_dir);
+        fs::create_dir_all(&dir).map_err(|e| format!("创建本地目录失败: {}", e))?;
+        let mut pulled_files = Vec::new();
+        for (index, name) in names.iter().enumerate() {
+            let local_path = dir.join(name);
+            progress::emit(app, progress::WRITE_PROGRESS_EVENT, &local_path, index + 1, names.len());
+            adb.pull(&serial, &adb::remote_file_path(&remote_path, name), &local_path)?;
+            pulled_files.push(local_path.to_string_lossy().into_owned());
+        }
+
+        let files = scan_directory(&dir, &settings)?;
+        Ok(AdbPullResult {
+            local_dir,
+            pulled_files,
+            files,
+        })
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdbPushResult {
+    /// 已上传到设备的文件（设备上的路径）
+    pushed_files: Vec<String>,
+    /// 与设备上内容相同而未上传的文件
+    skipped_files: Vec<SkippedFile>,
+    /// 是否已重启桌面应用
+    restarted: bool,
+}
+
+/// 把 local_dir 中与设备上不同的 theme 文件通过 adb 上传到 remote_path，可选重启桌面应用，并记录操作日志
+#[tauri::command]
+async fn adb_push_themes(
+    app: tauri::AppHandle,
+    serial: String,
+    local_dir: String,
+    remote_path: String,
+    restart_app: bool,
+) -> Result<AdbPushResult, CommandError> {
+    run_blocking(app, move |app| {
+        let settings = dir_settings(app, &local_dir)?;
+        let launcher_package = match (restart_app, settings.launcher_package.as_deref().map(str::trim)) {
+            (false, _) => None,
+            (true, Some(package)) if !package.is_empty() => Some(package.to_string()),
+            (true, _) => return Err("未配置桌面应用包名，无法重启".into()),
+        };
+        let adb = adb::Adb::from_settings(&settings);
+        let dir = PathBuf::from(&local_dir);
+        let files = collect_theme_files(&dir)?;
+        if files.is_empty() {
+            return Err("本地目录中没有 theme 文件".into());
+        }
+        let remote_names = adb.list_theme_files(&serial, &remote_path)?;
+
+        // 先下载设备上的同名文件做对比，只上传内容有变化的文件
+        let locale = i18n::Locale::of(&settings);
+        let compare_dir = std::env::temp_dir().join(format!("catmap-adb-{}", std::process::id()));
+        fs::create_dir_all(&compare_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        let result = push_changed_files(
+            app,
+            &adb,
+            &serial,
+            &files,
+            &remote_path,
+            &remote_names,
+            &compare_dir,
+            locale,
+        );
+        let _ = fs::remove_dir_all(&compare_dir);
+        let (pushed_files, skipped_files) = result?;
+
+        let restarted = match &launcher_package {
+            Some(package) if !pushed_files.is_empty() => {
+                adb.restart_launcher(&serial, package)?;
+                true
+            }
+            _ => false,
+        };
+
+        let info = match locale {
+            i18n::Locale::ZhCn => format!(
+                "设备 {} 的 {}，上传 {} 个文件{}",
+                serial,
+                remote_path,
+                pushed_files.len(),
+                if restarted { "，已重启桌面应用" } else { "" }
+            ),
+            i18n::Locale::EnUs => format!(
+                "Uploaded {} files to {} on device {}{}",
+                pushed_files.len(),
+                remote_path,
+                serial,
+                if restarted { ", launcher restarted" } else { "" }
+            ),
+        };
+        finish_operation(
+            &settings,
+            &backup::storage_dir(&dir, &settings),
+            OperationLog {
+                updated_files: &pushed_files,
+                skipped_files: &skipped_files,
+                additional_info: Some(&info),
+                ..OperationLog::new(OperationType::AdbPush)
+            },
+        );
+
+        Ok(AdbPushResult {
+            pushed_files,
+            skipped_files,
+            restarted,
+        })
+    })
+    .await
+}
+
+/// 逐个上传与设备上内容不同（或设备上不存在）的文件，返回（已上传的设备路径, 跳过的文件）
+#[allow(clippy::too_many_arguments)]
+fn push_changed_files(
+    app: &tauri::AppHandle,
+    adb: &adb::Adb,
+    serial: &str,
+    files: &[PathBuf],
+    remote_dir: &str,
+    remote_names: &[String],
+    compare_dir: &Path,
+    locale: i18n::Locale,
+) -> Result<(Vec<String>, Vec<SkippedFile>), String> {
+    let mut pushed_files = Vec::new();
+    let mut skipped_files = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        progress::emit(app, progress::WRITE_PROGRESS_EVENT, file, index + 1, files.len());
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let remote_file = adb::remote_file_path(remote_dir, &name);
+        if remote_names.contains(&name) {
+            let device_copy = compare_dir.join(&name);
+            adb.pull(serial, &remote_file, &device_copy)?;
+            let local = fs::read(file).map_err(|e| e.to_string())?;
+            let remote = fs::read(&device_copy).map_err(|e| e.to_string())?;
+            if local == remote {
+                skipped_files.push(SkippedFile::new(
+                    file.to_string_lossy().into_owned(),
+                    i18n::Message::new(i18n::MessageCode::SameAsDevice),
+                    Vec::new(),
+                    locale,
+                ));
+                continue;
             }
-            new_content.push_str(line_ending);
         }
+        adb.push(serial, file, &remote_file)?;
+        pushed_files.push(remote_file);
     }
+    Ok((pushed_files, skipped_files))
+}
 
-    // 添加新的映射项
-    let mut mapping_vec: Vec<_> = mappings.iter().collect();
-    mapping_vec.sort_by_key(|(k, _)| *k);
+#[tauri::command]
+fn open_folder(app: tauri::AppHandle, path: String) -> Result<(), CommandError> {
+    localized(&app, || {
+        let path_buf = PathBuf::from(&path);
+        if !path_buf.exists() {
+            return Err(format!("路径不存在: {}", path).into());
+        }
 
-    for (idx, (local_id, raw_value)) in mapping_vec.iter().enumerate() {
-        new_content.push_str(&entry_indent);
-        new_content.push_str(&format!("\"{PORTAL_PREFIX}{key}\":\"{value}\"", key = local_id, value = raw_value));
-        if idx < mapping_vec.len() - 1 {
-            new_content.push(',');
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("explorer")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件夹: {}", e))?;
         }
-        new_content.push_str(line_ending);
-    }
 
-    // 构建最终结果
-    let mut result = String::with_capacity(raw.len() + new_content.len());
-    result.push_str(&raw[..block_start + 1]);
-    result.push_str(&new_content);
-    result.push_str(&base_indent);
-    result.push_str(&raw[block_end..]);
-    
-    Ok(result)
-}
-
-fn find_ext_options_block(content: &str) -> Result<(usize, usize), String> {
-    let key = "\"sExtOptions\"";
-    let key_index = content.find(key).ok_or("未找到 sExtOptions 段落")?;
-    let mut idx = key_index + key.len();
-    let bytes = content.as_bytes();
-    while idx < bytes.len() && bytes[idx].is_ascii_whitespace() {
-        idx += 1;
-    }
-    if idx >= bytes.len() || bytes[idx] != b':' {
-        return Err("sExtOptions 定义格式不正确".into());
-    }
-    idx += 1;
-    while idx < bytes.len() && bytes[idx].is_ascii_whitespace() {
-        idx += 1;
-    }
-    if idx >= bytes.len() || bytes[idx] != b'{' {
-        return Err("sExtOptions 不是对象类型".into());
-    }
-    let mut i = idx;
-    let mut depth = 0i32;
-    let mut in_string = false;
-    let mut escape = false;
-    let mut in_line_comment = false;
-    let mut in_block_comment = false;
-    let mut block_start = idx;
-
-    while i < bytes.len() {
-        let ch = bytes[i];
-        if in_line_comment {
-            if ch == b'\n' {
-                in_line_comment = false;
-            }
-            i += 1;
-            continue;
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("open")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件夹: {}", e))?;
         }
-        if in_block_comment {
-            if ch == b'*' && i + 1 < bytes.len() && bytes[i + 1] == b'/' {
-                in_block_comment = false;
-                i += 2;
-            } else {
-                i += 1;
-            }
-            continue;
+
+        #[cfg(target_os = "linux")]
+        {
+            Command::new("xdg-open")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件夹: {}", e))?;
         }
-        if in_string {
-            if escape {
-                escape = false;
-            } else if ch == b'\\' {
-                escape = true;
-            } else if ch == b'"' {
-                in_string = false;
-            }
-            i += 1;
-            continue;
+
+        Ok(())
+    })
+}
+
+/// 用系统默认程序打开文件（如 theme 文件或备份中的文件）
+#[tauri::command]
+fn open_file(app: tauri::AppHandle, path: String) -> Result<(), CommandError> {
+    localized(&app, || {
+        if !Path::new(&path).is_file() {
+            return Err(format!("文件不存在: {}", path).into());
         }
-        if ch == b'"' {
-            in_string = true;
-            i += 1;
-            continue;
+
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("explorer")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件: {}", e))?;
         }
-        if ch == b'/' && i + 1 < bytes.len() {
-            if bytes[i + 1] == b'/' {
-                in_line_comment = true;
-                i += 2;
-                continue;
-            }
-            if bytes[i + 1] == b'*' {
-                in_block_comment = true;
-                i += 2;
-                continue;
-            }
+
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("open")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件: {}", e))?;
         }
-        if ch == b'{' {
-            if depth == 0 {
-                block_start = i;
-            }
-            depth += 1;
-        } else if ch == b'}' {
-            depth -= 1;
-            if depth == 0 {
-                return Ok((block_start, i));
-            }
+
+        #[cfg(target_os = "linux")]
+        {
+            Command::new("xdg-open")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件: {}", e))?;
         }
-        i += 1;
-    }
-    Err("未能定位 sExtOptions 的结束位置".into())
+
+        Ok(())
+    })
 }
 
-fn trim_trailing_whitespace_start(content: &str) -> usize {
-    let mut idx = content.len();
-    while idx > 0 {
-        let ch = content.as_bytes()[idx - 1];
-        if ch == b' ' || ch == b'\t' || ch == b'\n' || ch == b'\r' {
-            idx -= 1;
-        } else {
-            break;
-        }
-    }
-    idx
-}
-
-/// 从文件中删除指定的映射项
-fn remove_mapping_from_file(raw: &str, local_id: &str) -> Result<String, String> {
-    let (block_start, block_end) = find_ext_options_block(raw)?;
-    let line_ending = if raw.contains("\r\n") { "\r\n" } else { "\n" };
-    let interior = &raw[block_start + 1..block_end];
-    
-    // 按行分割，过滤掉包含目标 local_id 的行
-    let lines: Vec<&str> = interior.split('\n').collect();
-    let mut filtered_lines = Vec::new();
-    let target_key = format!("\"{PORTAL_PREFIX}{local_id}\"");
-    let mut found_target = false;
-    
-    for (_idx, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        // 检查是否包含目标 key（包括注释行）
-        if trimmed.contains(&target_key) || 
-           (trimmed.starts_with("//") && trimmed.contains(&local_id)) {
-            found_target = true;
-            // 跳过这一行
-            continue;
+/// 在资源管理器 / 访达中显示并选中文件
+///
+/// Linux 下通过 FileManager1 D-Bus 接口选中文件，文件管理器不支持时退回到打开所在目录。
+#[tauri::command]
+fn reveal_in_folder(app: tauri::AppHandle, path: String) -> Result<(), CommandError> {
+    localized(&app, || {
+        let path_buf = PathBuf::from(&path);
+        if !path_buf.exists() {
+            return Err(format!("路径不存在: {}", path).into());
         }
-        // 保留其他行
-        filtered_lines.push(*line);
-    }
-    
-    if !found_target {
-        return Err(format!("未找到本地栏目ID: {}", local_id));
-    }
-    
-    // 清理末尾多余的逗号和空行
-    while let Some(last) = filtered_lines.last() {
-        let trimmed = last.trim();
-        if trimmed.is_empty() {
-            filtered_lines.pop();
-        } else {
-            break;
+
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("explorer")
+                .arg(format!("/select,{}", path))
+                .spawn()
+                .map_err(|e| format!("无法打开文件夹: {}", e))?;
         }
-    }
-    
-    // 处理逗号：确保 JSON 格式正确
-    // 移除所有行末尾的逗号，然后重新添加（除了最后一行）
-    let mut cleaned_lines = Vec::new();
-    for (idx, line) in filtered_lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("open")
+                .arg("-R")
+                .arg(&path)
+                .spawn()
+                .map_err(|e| format!("无法打开文件夹: {}", e))?;
         }
-        let mut cleaned = trimmed.trim_end_matches(',').trim_end().to_string();
-        // 如果不是最后一行，且下一行不是空行，添加逗号
-        if idx < filtered_lines.len() - 1 {
-            let next_trimmed = filtered_lines[idx + 1].trim();
-            if !next_trimmed.is_empty() {
-                cleaned.push(',');
+
+        #[cfg(target_os = "linux")]
+        {
+            let absolute = fs::canonicalize(&path_buf).unwrap_or_else(|_| path_buf.clone());
+            let shown = Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                ])
+                .arg(format!("array:string:{}", file_uri(&absolute)))
+                .arg("string:")
+                .status()
+                .is_ok_and(|status| status.success());
+            if !shown {
+                let dir = absolute.parent().unwrap_or(&absolute);
+                Command::new("xdg-open")
+                    .arg(dir)
+                    .spawn()
+                    .map_err(|e| format!("无法打开文件夹: {}", e))?;
             }
         }
-        // 恢复原始缩进
-        let indent = line.chars().take_while(|c| c.is_whitespace()).collect::<String>();
-        cleaned_lines.push(format!("{}{}", indent, cleaned));
-    }
-    
-    // 构建最终结果
-    let filtered_text = cleaned_lines.join("\n");
-    let mut result = String::with_capacity(raw.len());
-    result.push_str(&raw[..block_start + 1]);
-    if !filtered_text.trim().is_empty() {
-        result.push_str(&filtered_text);
-        result.push_str(line_ending);
+
+        Ok(())
+    })
+}
+
+/// 本地路径对应的 file:// URI（非 ASCII 字母数字和 `/-_.~` 之外的字节做百分号编码）
+#[cfg(target_os = "linux")]
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
     }
-    result.push_str(&raw[block_end..]);
-    
-    Ok(result)
+    uri
+}
+
+fn main() {
+    tauri::Builder::default()
+        .manage(cancel_registry::CancelRegistry::default())
+        .manage(watcher::WatcherRegistry::default())
+        .setup(|app| {
+            scheduler::start(app.handle());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_settings,
+            set_settings,
+            clear_parse_cache,
+            get_recent_dirs,
+            add_recent_dir,
+            list_profiles,
+            save_profile,
+            delete_profile,
+            apply_profile,
+            fetch_gw_catalog,
+            load_local_catalog,
+            scan_theme_files,
+            scan_theme_files_paged,
+            list_workspaces,
+            save_workspace,
+            delete_workspace,
+            scan_workspace,
+            workspace_bulk_insert,
+            list_devices,
+            save_device,
+            delete_device,
+            scan_device,
+            device_bulk_insert,
+            device_sync_mappings,
+            cancel_operation,
+            watch_directory,
+            unwatch_directory,
+            search_mappings,
+            get_statistics,
+            generate_report,
+            check_consistency,
+            validate_against_master,
+            diff_directories,
+            take_snapshot,
+            list_snapshots,
+            compare_snapshots,
+            approve_snapshot,
+            audit_drift,
+            diff_with_backup,
+            get_operation_history,
+            replay_operation,
+            backup_theme_files,
+            list_backups,
+            restore_backup,
+            restore_files,
+            verify_backup,
+            prune_backups,
+            render_template,
+            generate_mappings,
+            bulk_insert_mappings,
+            import_mappings,
+            export_mappings,
+            export_problems,
+            delete_mapping,
+            set_raw_value,
+            restore_mapping,
+            batch_delete_mappings,
+            dedupe_mappings,
+            analyze_duplicates,
+            cleanup_duplicates,
+            audit_same_id,
+            fix_same_id,
+            remove_same_id,
+            uncomment_mappings,
+            purge_commented_mappings,
+            sync_mappings,
+            replace_gw_id,
+            delete_by_gw_id,
+            clear_mappings,
+            migrate_prefix,
+            normalize_values,
+            normalize_ids,
+            batch_update_mappings,
+            sort_mappings,
+            repair_ext_options,
+            get_file_info,
+            check_write_access,
+            lint_theme_file,
+            validate_file,
+            validate_target_dir,
+            scan_archive,
+            open_archive_workspace,
+            repack_archive,
+            git_status,
+            adb_list_devices,
+            adb_pull_themes,
+            adb_push_themes,
+            scan_remote,
+            apply_remote,
+            scan_http,
+            apply_http,
+            open_folder,
+            open_file,
+            reveal_in_folder
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
 }
@@ -0,0 +1,50 @@
+use categorymap_core::{settings::AppSettings, FileResult};
+use serde::Serialize;
+use std::path::Path;
+use tauri::Manager;
+
+/// 扫描进度事件
+pub const SCAN_PROGRESS_EVENT: &str = "scan-progress";
+/// 写入进度事件
+pub const WRITE_PROGRESS_EVENT: &str = "write-progress";
+/// 批量操作中单个文件处理完成的事件
+pub const FILE_RESULT_EVENT: &str = "file-result";
+/// 打开的目录记住了设置配置的事件，负载为对该目录的操作使用的设置（保存的当前设置不变）
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// 进度事件的负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressPayload {
+    /// 当前正在处理的文件
+    pub current_file: String,
+    /// 当前是第几个文件（从 1 开始）
+    pub current: usize,
+    pub total: usize,
+}
+
+/// 向前端广播进度事件，发送失败只打印错误
+pub fn emit(app: &tauri::AppHandle, event: &str, current_file: &Path, current: usize, total: usize) {
+    let payload = ProgressPayload {
+        current_file: current_file.to_string_lossy().into_owned(),
+        current,
+        total,
+    };
+    if let Err(e) = app.emit_all(event, payload) {
+        eprintln!("发送进度事件失败: {}", e);
+    }
+}
+
+/// 推送单个文件的处理结果，发送失败只打印错误
+pub fn emit_file_result(app: &tauri::AppHandle, result: &FileResult) {
+    if let Err(e) = app.emit_all(FILE_RESULT_EVENT, result) {
+        eprintln!("发送文件处理结果事件失败: {}", e);
+    }
+}
+
+/// 通知前端打开的目录使用的设置，发送失败只打印错误
+pub fn emit_settings_changed(app: &tauri::AppHandle, settings: &AppSettings) {
+    if let Err(e) = app.emit_all(SETTINGS_CHANGED_EVENT, settings) {
+        eprintln!("发送设置变更事件失败: {}", e);
+    }
+}
@@ -0,0 +1,124 @@
+//! 定时自动备份：按设置中的间隔为登记的目录创建备份，文件在工具之外被修改时也能保留修改前的状态
+//!
+//! 定时审计：按设置中的间隔与最近一个已确认的快照对比，发现工具之外的修改时发送事件并推送 webhook 提醒
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::Manager;
+
+use categorymap_core::{backup, settings::AppSettings, snapshot, webhook};
+
+/// 定时备份完成事件
+pub const SCHEDULED_BACKUP_EVENT: &str = "scheduled-backup";
+/// 定时审计发现修改事件，负载为 [`snapshot::DriftReport`]
+pub const DRIFT_DETECTED_EVENT: &str = "drift-detected";
+
+/// 检查是否到期的间隔
+const TICK: Duration = Duration::from_secs(30);
+
+/// 定时备份完成事件的负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledBackupPayload {
+    pub target_dir: String,
+    pub backup_dir: String,
+}
+
+/// 启动后台线程；每次检查时重新读取设置，修改间隔或目录后无需重启
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let mut backup_runs: HashMap<String, Instant> = HashMap::new();
+        let mut audit_runs: HashMap<String, Instant> = HashMap::new();
+        // 每个目录最近一次提醒的修改内容，内容不变时不重复提醒
+        let mut reported_drift: HashMap<String, String> = HashMap::new();
+        loop {
+            thread::sleep(TICK);
+            let settings = match crate::current_settings(&app) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("定时任务读取设置失败: {}", e);
+                    continue;
+                }
+            };
+            run_backups(&app, &settings, &mut backup_runs);
+            run_audits(&app, &settings, &mut audit_runs, &mut reported_drift);
+        }
+    });
+}
+
+/// 到期的目录，返回前记录本次执行时间
+fn due_dirs(minutes: Option<u32>, dirs: &[String], last_run: &mut HashMap<String, Instant>) -> Vec<String> {
+    let Some(minutes) = minutes else {
+        return Vec::new();
+    };
+    let interval = Duration::from_secs(u64::from(minutes) * 60);
+    let due: Vec<String> = dirs
+        .iter()
+        .filter(|dir| match last_run.get(*dir) {
+            Some(last) => last.elapsed() >= interval,
+            None => true,
+        })
+        .cloned()
+        .collect();
+    for dir in &due {
+        last_run.insert(dir.clone(), Instant::now());
+    }
+    due
+}
+
+fn run_backups(app: &tauri::AppHandle, settings: &AppSettings, last_run: &mut HashMap<String, Instant>) {
+    let schedule = &settings.scheduled_backup;
+    for dir in due_dirs(schedule.interval_minutes, &schedule.dirs, last_run) {
+        match backup::create_scheduled_backup(Path::new(&dir), settings) {
+            Ok(Some(backup_dir)) => {
+                let payload = ScheduledBackupPayload {
+                    target_dir: dir,
+                    backup_dir: backup_dir.to_string_lossy().into_owned(),
+                };
+                if let Err(e) = app.emit_all(SCHEDULED_BACKUP_EVENT, payload) {
+                    eprintln!("发送定时备份事件失败: {}", e);
+                }
+            }
+            // 文件与最近一个备份相同，无需备份
+            Ok(None) => {}
+            Err(e) => eprintln!("定时备份 {} 失败: {}", dir, e),
+        }
+    }
+}
+
+fn run_audits(
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+    last_run: &mut HashMap<String, Instant>,
+    reported: &mut HashMap<String, String>,
+) {
+    let schedule = &settings.scheduled_audit;
+    for dir in due_dirs(schedule.interval_minutes, &schedule.dirs, last_run) {
+        let report = match snapshot::audit_drift(settings, Path::new(&dir)) {
+            Ok(Some(report)) => report,
+            // 没有已确认的快照或没有修改；修改被撤销或重新确认基准后，再次出现修改时重新提醒
+            Ok(None) => {
+                reported.remove(&dir);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("定时审计 {} 失败: {}", dir, e);
+                continue;
+            }
+        };
+        let fingerprint = format!("{:?}", report.files);
+        if reported.get(&dir) == Some(&fingerprint) {
+            continue;
+        }
+        reported.insert(dir, fingerprint);
+        if let Err(e) = app.emit_all(DRIFT_DETECTED_EVENT, report.clone()) {
+            eprintln!("发送审计提醒事件失败: {}", e);
+        }
+        webhook::notify_drift(settings, &report);
+    }
+}
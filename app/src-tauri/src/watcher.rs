@@ -0,0 +1,103 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::Manager;
+
+use categorymap_core::{is_theme_file_name, load_file_mapping, settings::AppSettings, FileMapping};
+
+/// theme 文件变化事件
+pub const THEME_FILE_CHANGED_EVENT: &str = "theme-file-changed";
+
+/// theme 文件变化事件的负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeFileChange {
+    pub file_path: String,
+    /// 变化类型：changed-新建或修改, removed-已删除
+    pub kind: String,
+    /// 变化后的映射，文件已删除或解析失败时为 None
+    pub file: Option<FileMapping>,
+    /// 解析失败时的错误信息
+    pub error: Option<String>,
+}
+
+/// 正在监听的目录，由 Tauri 托管为全局状态
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+}
+
+impl WatcherRegistry {
+    /// 开始监听目录下的 theme 文件，已在监听时直接返回
+    pub fn watch(&self, app: &tauri::AppHandle, dir: &Path, settings: AppSettings) -> Result<(), String> {
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(dir) {
+            return Ok(());
+        }
+
+        let app = app.clone();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => handle_event(&app, &event, &settings),
+            Err(e) => eprintln!("监听目录出错: {}", e),
+        })
+        .map_err(|e| format!("创建目录监听失败: {}", e))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("监听目录失败: {}", e))?;
+        watchers.insert(dir.to_path_buf(), watcher);
+        Ok(())
+    }
+
+    /// 停止监听目录，返回此前是否在监听
+    pub fn unwatch(&self, dir: &Path) -> bool {
+        // 丢弃 watcher 即停止监听
+        self.watchers.lock().unwrap().remove(dir).is_some()
+    }
+}
+
+fn handle_event(app: &tauri::AppHandle, event: &notify::Event, settings: &AppSettings) {
+    if event.kind.is_access() {
+        return;
+    }
+    for path in &event.paths {
+        let is_theme_file = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(is_theme_file_name);
+        if !is_theme_file {
+            continue;
+        }
+
+        let file_path = path.to_string_lossy().into_owned();
+        let change = if path.is_file() {
+            match load_file_mapping(path, settings) {
+                Ok(file) => ThemeFileChange {
+                    file_path,
+                    kind: "changed".to_string(),
+                    file: Some(file),
+                    error: None,
+                },
+                Err(e) => ThemeFileChange {
+                    file_path,
+                    kind: "changed".to_string(),
+                    file: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        } else {
+            ThemeFileChange {
+                file_path,
+                kind: "removed".to_string(),
+                file: None,
+                error: None,
+            }
+        };
+        if let Err(e) = app.emit_all(THEME_FILE_CHANGED_EVENT, change) {
+            eprintln!("发送文件变化事件失败: {}", e);
+        }
+    }
+}
@@ -8,6 +8,7 @@ import type {
   FileMapping,
   MappingInput
 } from "@/types/mapping";
+import { errorMessage } from "@/types/mapping";
 
 export interface ScanResult {
   files: FileMapping[];
@@ -66,7 +67,7 @@ export const useMappingStore = defineStore("mapping-store", () => {
       files.value = result.files;
       targetDir.value = pathToUse;
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
     } finally {
       loading.value = false;
     }
@@ -100,7 +101,7 @@ export const useMappingStore = defineStore("mapping-store", () => {
       }
       await scanDirectory(targetDir.value);
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
     }
   };
 
@@ -152,7 +153,7 @@ export const useMappingStore = defineStore("mapping-store", () => {
         error.value = null;
       }
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
     }
   };
 
@@ -254,13 +255,13 @@ export const useMappingStore = defineStore("mapping-store", () => {
           lastInsertReport.value = result;
           await scanDirectory(targetDir.value);
         } catch (err) {
-          error.value = err instanceof Error ? err.message : String(err);
+          error.value = errorMessage(err);
         } finally {
           loading.value = false;
         }
       }
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
     }
   };
 
@@ -286,7 +287,7 @@ export const useMappingStore = defineStore("mapping-store", () => {
       await scanDirectory(targetDir.value);
       error.value = null;
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
     }
   };
 
@@ -317,7 +318,7 @@ export const useMappingStore = defineStore("mapping-store", () => {
       error.value = null;
       return result;
     } catch (err) {
-      error.value = err instanceof Error ? err.message : String(err);
+      error.value = errorMessage(err);
       throw err;
     }
   };
@@ -8,6 +8,10 @@ export interface MappingEntry {
   sameId: boolean;
   /** 状态：normal-正常, duplicate_local-本地ID重复, duplicate_gw-国网ID重复 */
   status: string;
+  /** 条目 key 所在的行号（从 1 开始） */
+  line: number;
+  /** 条目 key 开始引号在文件内容中的字节位置 */
+  offset: number;
 }
 
 /**
@@ -51,3 +55,22 @@ export interface BulkInsertResult {
   backupDir?: string;
 }
 
+/**
+ * 命令返回的结构化错误，按 code 区分错误类型。
+ */
+export interface CommandError {
+  code: string;
+  params?: Record<string, string | number>;
+  message: string;
+}
+
+/**
+ * 取出 invoke 失败时可展示的错误文本。
+ */
+export const errorMessage = (err: unknown): string => {
+  if (err instanceof Error) return err.message;
+  if (err && typeof err === "object" && "message" in err) {
+    return String((err as CommandError).message);
+  }
+  return String(err);
+};